@@ -1,113 +1,269 @@
 use anyhow::{anyhow, Result};
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::types::{BucketLocationConstraint, CorsRule, CreateBucketConfiguration, LifecycleRule, LifecycleRuleFilter};
+use chrono::{TimeZone, Utc};
 use log::{debug, error, info};
 
 use super::auth::AwsAuth;
 
 /// S3 bucket operations
-#[allow(dead_code)] // Will be used in future implementations
 pub struct BucketManager {
     auth: AwsAuth,
 }
 
-/// S3 object information
-#[derive(Debug, Clone)]
-#[allow(dead_code)] // Will be used in future implementations
-pub struct S3ObjectInfo {
-    pub key: String,
-    pub size: i64,
-    pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
-    pub etag: Option<String>,
+/// A bucket lifecycle rule, summarized for display
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifecycleRuleInfo {
+    pub id: String,
+    pub enabled: bool,
+    /// Which objects the rule applies to
+    pub filter: String,
+    /// Storage class transitions, e.g. "GLACIER after 30 days"
+    pub transitions: Vec<String>,
+    /// When current versions expire
+    pub expiration: Option<String>,
+    /// Transitions and expiration of noncurrent versions
+    pub noncurrent_versions: Vec<String>,
+}
+
+/// A bucket CORS rule, for display
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsRuleInfo {
+    pub id: Option<String>,
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub expose_headers: Vec<String>,
+    pub max_age_seconds: Option<i32>,
+}
+
+/// Describe when a lifecycle action happens, from either a day count or a date
+fn lifecycle_timing(days: i32, date: Option<&aws_sdk_s3::primitives::DateTime>) -> String {
+    match date.and_then(|date| Utc.timestamp_opt(date.secs(), 0).single()) {
+        Some(date) => format!("on {}", date.format("%Y-%m-%d")),
+        None => format!("after {} days", days),
+    }
+}
+
+impl LifecycleRuleInfo {
+    /// Summarize an SDK lifecycle rule
+    pub fn from_rule(rule: &LifecycleRule) -> Self {
+        let filter = match rule.filter() {
+            Some(LifecycleRuleFilter::Prefix(prefix)) if !prefix.is_empty() => format!("Prefix \"{}\"", prefix),
+            Some(LifecycleRuleFilter::Tag(tag)) => format!("Tag {}={}", tag.key().unwrap_or_default(), tag.value().unwrap_or_default()),
+            Some(LifecycleRuleFilter::ObjectSizeGreaterThan(size)) => format!("Objects larger than {} bytes", size),
+            Some(LifecycleRuleFilter::ObjectSizeLessThan(size)) => format!("Objects smaller than {} bytes", size),
+            Some(LifecycleRuleFilter::And(and)) => {
+                let mut conditions = Vec::new();
+                if let Some(prefix) = and.prefix().filter(|prefix| !prefix.is_empty()) {
+                    conditions.push(format!("prefix \"{}\"", prefix));
+                }
+                for tag in and.tags().unwrap_or_default() {
+                    conditions.push(format!("tag {}={}", tag.key().unwrap_or_default(), tag.value().unwrap_or_default()));
+                }
+                if and.object_size_greater_than() > 0 {
+                    conditions.push(format!("larger than {} bytes", and.object_size_greater_than()));
+                }
+                if and.object_size_less_than() > 0 {
+                    conditions.push(format!("smaller than {} bytes", and.object_size_less_than()));
+                }
+                format!("Objects with {}", conditions.join(" and "))
+            },
+            _ => {
+                // Older rules set the prefix on the rule instead of a filter
+                #[allow(deprecated)]
+                let prefix = rule.prefix().filter(|prefix| !prefix.is_empty());
+                match prefix {
+                    Some(prefix) => format!("Prefix \"{}\"", prefix),
+                    None => "Whole bucket".to_string(),
+                }
+            },
+        };
+        
+        let transitions = rule.transitions()
+            .unwrap_or_default()
+            .iter()
+            .map(|transition| format!(
+                "{} {}",
+                transition.storage_class().map(|class| class.as_str()).unwrap_or("?"),
+                lifecycle_timing(transition.days(), transition.date())
+            ))
+            .collect();
+        
+        let expiration = rule.expiration().map(|expiration| {
+            if expiration.expired_object_delete_marker() {
+                "Remove expired delete markers".to_string()
+            } else {
+                format!("Expire {}", lifecycle_timing(expiration.days(), expiration.date()))
+            }
+        });
+        
+        let mut noncurrent_versions: Vec<String> = rule.noncurrent_version_transitions()
+            .unwrap_or_default()
+            .iter()
+            .map(|transition| format!(
+                "{} {} days after becoming noncurrent",
+                transition.storage_class().map(|class| class.as_str()).unwrap_or("?"),
+                transition.noncurrent_days()
+            ))
+            .collect();
+        if let Some(expiration) = rule.noncurrent_version_expiration() {
+            noncurrent_versions.push(format!("Expire {} days after becoming noncurrent", expiration.noncurrent_days()));
+        }
+        
+        Self {
+            id: rule.id().unwrap_or_default().to_string(),
+            enabled: rule.status().map_or(false, |status| status.as_str() == "Enabled"),
+            filter,
+            transitions,
+            expiration,
+            noncurrent_versions,
+        }
+    }
+}
+
+impl CorsRuleInfo {
+    /// Copy the fields of an SDK CORS rule
+    pub fn from_rule(rule: &CorsRule) -> Self {
+        let strings = |values: Option<&[String]>| values.unwrap_or_default().to_vec();
+        
+        Self {
+            id: rule.id().map(|id| id.to_string()),
+            allowed_origins: strings(rule.allowed_origins()),
+            allowed_methods: strings(rule.allowed_methods()),
+            allowed_headers: strings(rule.allowed_headers()),
+            expose_headers: strings(rule.expose_headers()),
+            max_age_seconds: Some(rule.max_age_seconds()).filter(|seconds| *seconds > 0),
+        }
+    }
+}
+
+/// Convert a bucket's location constraint to its region. Buckets in us-east-1 report an
+/// empty or missing constraint, and `EU` is the legacy name for eu-west-1.
+pub fn location_constraint_to_region(constraint: Option<&BucketLocationConstraint>) -> String {
+    match constraint.map(|constraint| constraint.as_str()) {
+        None | Some("") => "us-east-1".to_string(),
+        Some("EU") => "eu-west-1".to_string(),
+        Some(region) => region.to_string(),
+    }
+}
+
+/// Look up the region a bucket is in. Any client can ask, whatever region it is for.
+pub async fn get_bucket_region(client: &aws_sdk_s3::Client, bucket: &str) -> std::result::Result<String, String> {
+    match client.get_bucket_location().bucket(bucket).send().await {
+        Ok(resp) => Ok(location_constraint_to_region(resp.location_constraint())),
+        Err(err) => {
+            let sdk_error = err.into_service_error();
+            let error_code = sdk_error.code().unwrap_or("Unknown");
+            let error_message = sdk_error.message().unwrap_or("No error message");
+            
+            Err(format!("Failed to get bucket location: {} - {}", error_code, error_message))
+        }
+    }
+}
+
+/// Regions offered when creating a bucket
+pub const BUCKET_REGIONS: &[&str] = &[
+    "us-east-1",
+    "us-east-2",
+    "us-west-1",
+    "us-west-2",
+    "ca-central-1",
+    "sa-east-1",
+    "eu-west-1",
+    "eu-west-2",
+    "eu-west-3",
+    "eu-central-1",
+    "eu-north-1",
+    "ap-south-1",
+    "ap-northeast-1",
+    "ap-northeast-2",
+    "ap-southeast-1",
+    "ap-southeast-2",
+];
+
+/// Check a bucket name against the S3 naming rules, so an invalid name is reported
+/// before a request is sent: 3 to 63 characters of lowercase letters, digits, dots and
+/// hyphens, starting and ending with a letter or digit, without consecutive dots and
+/// not formatted like an IP address.
+pub fn validate_bucket_name(name: &str) -> std::result::Result<(), String> {
+    if name.len() < 3 || name.len() > 63 {
+        return Err(format!("Bucket names must be 3 to 63 characters long, this one has {}", name.len()));
+    }
+    
+    if let Some(c) = name.chars().find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '.' || *c == '-')) {
+        let reason = if c.is_ascii_uppercase() {
+            "uppercase letters aren't allowed".to_string()
+        } else if c == '_' {
+            "underscores aren't allowed".to_string()
+        } else {
+            format!("'{}' isn't allowed", c)
+        };
+        return Err(format!("Bucket names can only contain lowercase letters, digits, dots and hyphens: {}", reason));
+    }
+    
+    let starts_and_ends_alphanumeric = |c: Option<char>| c.map_or(false, |c| c.is_ascii_alphanumeric());
+    if !starts_and_ends_alphanumeric(name.chars().next()) || !starts_and_ends_alphanumeric(name.chars().last()) {
+        return Err("Bucket names must start and end with a letter or digit".to_string());
+    }
+    
+    if name.contains("..") {
+        return Err("Bucket names can't contain two dots in a row".to_string());
+    }
+    
+    if name.parse::<std::net::Ipv4Addr>().is_ok() {
+        return Err("Bucket names can't be formatted as an IP address".to_string());
+    }
+    
+    if name.starts_with("xn--") || name.ends_with("-s3alias") {
+        return Err("Bucket names can't start with xn-- or end with -s3alias".to_string());
+    }
+    
+    Ok(())
 }
 
 impl BucketManager {
     /// Create a new bucket manager with the given authentication
-    #[allow(dead_code)] // Will be used in future implementations
     pub fn new(auth: AwsAuth) -> Self {
         Self { auth }
     }
     
-    /// List all available buckets
-    #[allow(dead_code)] // Will be used in future implementations
-    pub async fn list_buckets(&mut self) -> Result<Vec<String>> {
-        let client = self.auth.get_client().await?;
+    /// Create a new bucket in the given region
+    pub async fn create_bucket(&mut self, bucket_name: &str, region: &str) -> Result<()> {
+        let client = self.auth.get_client_for_region(region).await?;
         
-        match client.list_buckets().send().await {
-            Ok(resp) => {
-                let buckets = resp.buckets().unwrap_or_default();
-                let bucket_names: Vec<String> = buckets
-                    .iter()
-                    .filter_map(|b| b.name().map(String::from))
-                    .collect();
-                    
-                info!("Listed {} S3 buckets", bucket_names.len());
-                Ok(bucket_names)
-            },
-            Err(err) => {
-                error!("Failed to list buckets: {}", err);
-                Err(anyhow!("Failed to list buckets: {}", err))
-            }
+        let mut request = client.create_bucket().bucket(bucket_name);
+        if let Some(configuration) = Self::bucket_configuration(region) {
+            request = request.create_bucket_configuration(configuration);
         }
-    }
-    
-    /// Create a new bucket
-    #[allow(dead_code)] // Will be used in future implementations
-    pub async fn create_bucket(&mut self, bucket_name: &str) -> Result<()> {
-        let client = self.auth.get_client().await?;
         
-        match client.create_bucket().bucket(bucket_name).send().await {
+        match request.send().await {
             Ok(_) => {
-                info!("Created bucket: {}", bucket_name);
+                info!("Created bucket {} in {}", bucket_name, region);
                 Ok(())
             },
             Err(err) => {
-                error!("Failed to create bucket {}: {}", bucket_name, err);
-                Err(anyhow!("Failed to create bucket {}: {}", bucket_name, err))
+                let err = err.into_service_error();
+                let details = err.message().unwrap_or_else(|| err.code().unwrap_or("unknown error")).to_string();
+                error!("Failed to create bucket {}: {}", bucket_name, details);
+                Err(anyhow!("Failed to create bucket {}: {}", bucket_name, details))
             }
         }
     }
     
-    /// List objects in a bucket with optional prefix
-    // pub async fn list_objects(&mut self, bucket: &str, prefix: Option<&str>) -> Result<Vec<S3ObjectInfo>> {
-    //     let client = self.auth.get_client().await?;
-        
-    //     let mut request = client.list_objects_v2().bucket(bucket);
-        
-    //     if let Some(prefix) = prefix {
-    //         request = request.prefix(prefix);
-    //     }
-        
-    //     match request.send().await {
-    //         Ok(resp) => {
-    //             let objects = resp.contents().unwrap_or_default();
-    //             let object_infos: Vec<S3ObjectInfo> = objects
-    //                 .iter()
-    //                 .map(|obj| {
-    //                     // Convert AWS DateTime to chrono DateTime
-    //                     let aws_dt = obj.last_modified();
-    //                     let chrono_dt = aws_dt.map(|dt| {
-    //                         let secs = dt.secs();
-    //                         let nanos = dt.subsec_nanos();
-    //                         chrono::DateTime::<chrono::Utc>::from_timestamp(secs as i64, nanos).unwrap_or_default()
-    //                     });
-                        
-    //                     S3ObjectInfo {
-    //                         key: obj.key().unwrap_or_default().to_string(),
-    //                         size: obj.size(),
-    //                         last_modified: chrono_dt,
-    //                         etag: obj.e_tag().map(String::from),
-    //                     }
-    //                 })
-    //                 .collect();
-                    
-    //             info!("Listed {} objects in bucket {}", object_infos.len(), bucket);
-    //             Ok(object_infos)
-    //         },
-    //         Err(err) => {
-    //             error!("Failed to list objects in bucket {}: {}", bucket, err);
-    //             Err(anyhow!("Failed to list objects in bucket {}: {}", bucket, err))
-    //         }
-    //     }
-    // }
+    /// Build the CreateBucket configuration for a region. S3 rejects an explicit
+    /// `us-east-1` location constraint, so that region must send no configuration at all.
+    fn bucket_configuration(region: &str) -> Option<CreateBucketConfiguration> {
+        if region.is_empty() || region == "us-east-1" {
+            return None;
+        }
+        
+        Some(
+            CreateBucketConfiguration::builder()
+                .location_constraint(BucketLocationConstraint::from(region))
+                .build()
+        )
+    }
     
     /// Delete an object from a bucket
     #[allow(dead_code)] // Will be used in future implementations
@@ -126,6 +282,66 @@ impl BucketManager {
         }
     }
     
+    /// Get the lifecycle rules of a bucket. A bucket without a lifecycle configuration has no rules.
+    pub async fn get_bucket_lifecycle_configuration(&mut self, bucket: &str, region: &str) -> Result<Vec<LifecycleRuleInfo>> {
+        let client = self.auth.get_client_for_region(region).await?;
+        
+        match client.get_bucket_lifecycle_configuration().bucket(bucket).send().await {
+            Ok(resp) => {
+                let rules = resp.rules()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(LifecycleRuleInfo::from_rule)
+                    .collect();
+                Ok(rules)
+            },
+            Err(err) => {
+                let service_error = err.into_service_error();
+                if service_error.code() == Some("NoSuchLifecycleConfiguration") {
+                    debug!("Bucket {} has no lifecycle configuration", bucket);
+                    return Ok(Vec::new());
+                }
+                
+                error!("Failed to get lifecycle configuration of {}: {}", bucket, service_error);
+                Err(anyhow!(
+                    "Failed to get lifecycle configuration: {} - {}",
+                    service_error.code().unwrap_or("Unknown"),
+                    service_error.message().unwrap_or("No error message")
+                ))
+            }
+        }
+    }
+    
+    /// Get the CORS rules of a bucket. A bucket without a CORS configuration has no rules.
+    pub async fn get_bucket_cors(&mut self, bucket: &str, region: &str) -> Result<Vec<CorsRuleInfo>> {
+        let client = self.auth.get_client_for_region(region).await?;
+        
+        match client.get_bucket_cors().bucket(bucket).send().await {
+            Ok(resp) => {
+                let rules = resp.cors_rules()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(CorsRuleInfo::from_rule)
+                    .collect();
+                Ok(rules)
+            },
+            Err(err) => {
+                let service_error = err.into_service_error();
+                if service_error.code() == Some("NoSuchCORSConfiguration") {
+                    debug!("Bucket {} has no CORS configuration", bucket);
+                    return Ok(Vec::new());
+                }
+                
+                error!("Failed to get CORS configuration of {}: {}", bucket, service_error);
+                Err(anyhow!(
+                    "Failed to get CORS configuration: {} - {}",
+                    service_error.code().unwrap_or("Unknown"),
+                    service_error.message().unwrap_or("No error message")
+                ))
+            }
+        }
+    }
+    
     /// Check if a bucket exists
     #[allow(dead_code)] // Will be used in future implementations
     pub async fn bucket_exists(&mut self, bucket: &str) -> Result<bool> {
@@ -143,3 +359,130 @@ impl BucketManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bucket_configuration() {
+        assert!(BucketManager::bucket_configuration("us-east-1").is_none());
+        assert!(BucketManager::bucket_configuration("").is_none());
+        
+        for region in ["us-west-2", "eu-west-1", "ap-northeast-1", "eu-central-2"] {
+            let configuration = BucketManager::bucket_configuration(region).unwrap();
+            let constraint = configuration.location_constraint().unwrap();
+            assert_eq!(constraint.as_str(), region);
+        }
+    }
+    
+    #[test]
+    fn test_validate_bucket_name() {
+        for name in ["my-bucket", "logs.example.com", "abc", "123bucket", "a".repeat(63).as_str()] {
+            assert_eq!(validate_bucket_name(name), Ok(()), "{}", name);
+        }
+        
+        for name in ["ab", "a".repeat(64).as_str(), "My-Bucket", "my_bucket", "-bucket", "bucket.", "my..bucket", "192.168.1.1", "xn--bucket", "bucket-s3alias", "my bucket"] {
+            assert!(validate_bucket_name(name).is_err(), "{}", name);
+        }
+    }
+    
+    #[test]
+    fn test_lifecycle_rule_info() {
+        use aws_sdk_s3::types::{ExpirationStatus, LifecycleExpiration, NoncurrentVersionExpiration, Transition, TransitionStorageClass};
+        
+        let rule = LifecycleRule::builder()
+            .id("archive-logs")
+            .status(ExpirationStatus::Enabled)
+            .filter(LifecycleRuleFilter::Prefix("logs/".to_string()))
+            .transitions(Transition::builder().days(30).storage_class(TransitionStorageClass::Glacier).build())
+            .expiration(LifecycleExpiration::builder().days(365).build())
+            .noncurrent_version_expiration(NoncurrentVersionExpiration::builder().noncurrent_days(7).build())
+            .build();
+        
+        let info = LifecycleRuleInfo::from_rule(&rule);
+        assert_eq!(info, LifecycleRuleInfo {
+            id: "archive-logs".to_string(),
+            enabled: true,
+            filter: "Prefix \"logs/\"".to_string(),
+            transitions: vec!["GLACIER after 30 days".to_string()],
+            expiration: Some("Expire after 365 days".to_string()),
+            noncurrent_versions: vec!["Expire 7 days after becoming noncurrent".to_string()],
+        });
+        
+        let rule = LifecycleRule::builder().status(ExpirationStatus::Disabled).build();
+        let info = LifecycleRuleInfo::from_rule(&rule);
+        assert!(!info.enabled);
+        assert_eq!(info.filter, "Whole bucket");
+        assert!(info.expiration.is_none());
+    }
+    
+    #[test]
+    fn test_cors_rule_info() {
+        let rule = CorsRule::builder()
+            .allowed_origins("https://example.com")
+            .allowed_methods("GET")
+            .allowed_methods("HEAD")
+            .allowed_headers("*")
+            .max_age_seconds(3000)
+            .build();
+        
+        let info = CorsRuleInfo::from_rule(&rule);
+        assert_eq!(info.allowed_origins, vec!["https://example.com"]);
+        assert_eq!(info.allowed_methods, vec!["GET", "HEAD"]);
+        assert_eq!(info.allowed_headers, vec!["*"]);
+        assert!(info.expose_headers.is_empty());
+        assert_eq!(info.max_age_seconds, Some(3000));
+    }
+    
+    #[test]
+    fn test_location_constraint_to_region() {
+        let region = |constraint: &str| location_constraint_to_region(Some(&BucketLocationConstraint::from(constraint)));
+        
+        // Missing and empty constraints mean us-east-1
+        assert_eq!(location_constraint_to_region(None), "us-east-1");
+        assert_eq!(region(""), "us-east-1");
+        
+        // Legacy name for eu-west-1
+        assert_eq!(region("EU"), "eu-west-1");
+        
+        // Regions the SDK doesn't know yet are passed through
+        assert_eq!(region("x"), "x");
+        assert_eq!(region("il-central-1"), "il-central-1");
+        
+        let mapped = [
+            (BucketLocationConstraint::AfSouth1, "af-south-1"),
+            (BucketLocationConstraint::ApEast1, "ap-east-1"),
+            (BucketLocationConstraint::ApNortheast1, "ap-northeast-1"),
+            (BucketLocationConstraint::ApNortheast2, "ap-northeast-2"),
+            (BucketLocationConstraint::ApNortheast3, "ap-northeast-3"),
+            (BucketLocationConstraint::ApSouth1, "ap-south-1"),
+            (BucketLocationConstraint::ApSoutheast1, "ap-southeast-1"),
+            (BucketLocationConstraint::ApSoutheast2, "ap-southeast-2"),
+            (BucketLocationConstraint::CaCentral1, "ca-central-1"),
+            (BucketLocationConstraint::CnNorth1, "cn-north-1"),
+            (BucketLocationConstraint::CnNorthwest1, "cn-northwest-1"),
+            (BucketLocationConstraint::EuCentral1, "eu-central-1"),
+            (BucketLocationConstraint::EuNorth1, "eu-north-1"),
+            (BucketLocationConstraint::EuSouth1, "eu-south-1"),
+            (BucketLocationConstraint::EuWest1, "eu-west-1"),
+            (BucketLocationConstraint::EuWest2, "eu-west-2"),
+            (BucketLocationConstraint::EuWest3, "eu-west-3"),
+            (BucketLocationConstraint::MeSouth1, "me-south-1"),
+            (BucketLocationConstraint::SaEast1, "sa-east-1"),
+            (BucketLocationConstraint::UsEast2, "us-east-2"),
+            (BucketLocationConstraint::UsGovEast1, "us-gov-east-1"),
+            (BucketLocationConstraint::UsGovWest1, "us-gov-west-1"),
+            (BucketLocationConstraint::UsWest1, "us-west-1"),
+            (BucketLocationConstraint::UsWest2, "us-west-2"),
+        ];
+        for (constraint, expected) in mapped {
+            assert_eq!(location_constraint_to_region(Some(&constraint)), expected);
+        }
+        
+        // Every value the SDK knows maps to a region name
+        for value in BucketLocationConstraint::values() {
+            assert!(region(value).contains('-'), "{} did not map to a region", value);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use flate2::write::{GzDecoder, ZlibDecoder};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Compression applied to an object's content, from its Content-Encoding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Parse a Content-Encoding header. Only a single gzip or deflate coding can be
+    /// decoded; anything else (including several stacked codings) is left as it is.
+    pub fn parse(header: &str) -> Option<Self> {
+        match header.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+            "deflate" => Some(ContentEncoding::Deflate),
+            _ => None,
+        }
+    }
+    
+    /// File extensions that mark content compressed with this encoding
+    fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ContentEncoding::Gzip => &["gz", "gzip"],
+            ContentEncoding::Deflate => &["zz", "deflate"],
+        }
+    }
+}
+
+/// Path for the decompressed content: `logs/app.log.gz` becomes `logs/app.log`.
+/// Paths without a matching extension are kept as they are.
+pub fn decoded_path(local_path: &Path, encoding: ContentEncoding) -> PathBuf {
+    let matches = local_path.extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .map_or(false, |extension| encoding.extensions().contains(&extension.as_str()));
+    
+    if matches {
+        local_path.with_extension("")
+    } else {
+        local_path.to_path_buf()
+    }
+}
+
+/// Streaming decompressor for downloaded chunks
+pub enum Decoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    Deflate(ZlibDecoder<Vec<u8>>),
+}
+
+impl Decoder {
+    /// Create a decoder for an encoding
+    pub fn new(encoding: ContentEncoding) -> Self {
+        match encoding {
+            ContentEncoding::Gzip => Decoder::Gzip(GzDecoder::new(Vec::new())),
+            ContentEncoding::Deflate => Decoder::Deflate(ZlibDecoder::new(Vec::new())),
+        }
+    }
+    
+    /// Decode a chunk of compressed data, returning the decompressed bytes produced so far
+    pub fn decode(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(std::mem::take(decoder.get_mut()))
+            },
+            Decoder::Deflate(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(std::mem::take(decoder.get_mut()))
+            },
+        }
+    }
+    
+    /// Finish decoding, returning any remaining decompressed bytes. Fails if a
+    /// gzip stream was truncated, since its trailing checksum is missing.
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.finish(),
+            Decoder::Deflate(decoder) => decoder.finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    
+    fn decode_in_chunks(encoding: ContentEncoding, compressed: &[u8]) -> Vec<u8> {
+        let mut decoder = Decoder::new(encoding);
+        let mut decoded = Vec::new();
+        for chunk in compressed.chunks(7) {
+            decoded.extend(decoder.decode(chunk).unwrap());
+        }
+        decoded.extend(decoder.finish().unwrap());
+        decoded
+    }
+    
+    #[test]
+    fn test_content_encoding_parse() {
+        assert_eq!(ContentEncoding::parse("gzip"), Some(ContentEncoding::Gzip));
+        assert_eq!(ContentEncoding::parse(" GZIP "), Some(ContentEncoding::Gzip));
+        assert_eq!(ContentEncoding::parse("x-gzip"), Some(ContentEncoding::Gzip));
+        assert_eq!(ContentEncoding::parse("deflate"), Some(ContentEncoding::Deflate));
+        assert_eq!(ContentEncoding::parse("br"), None);
+        assert_eq!(ContentEncoding::parse("gzip, br"), None);
+        assert_eq!(ContentEncoding::parse(""), None);
+    }
+    
+    #[test]
+    fn test_decoded_path() {
+        assert_eq!(decoded_path(Path::new("logs/app.log.gz"), ContentEncoding::Gzip), Path::new("logs/app.log"));
+        assert_eq!(decoded_path(Path::new("logs/app.log.GZ"), ContentEncoding::Gzip), Path::new("logs/app.log"));
+        assert_eq!(decoded_path(Path::new("data.json"), ContentEncoding::Gzip), Path::new("data.json"));
+        assert_eq!(decoded_path(Path::new("data.zz"), ContentEncoding::Deflate), Path::new("data"));
+        assert_eq!(decoded_path(Path::new("data.zz"), ContentEncoding::Gzip), Path::new("data.zz"));
+    }
+    
+    #[test]
+    fn test_decode_round_trip() {
+        let original = "hello world\n".repeat(1000).into_bytes();
+        
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&original).unwrap();
+        assert_eq!(decode_in_chunks(ContentEncoding::Gzip, &gzip.finish().unwrap()), original);
+        
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&original).unwrap();
+        assert_eq!(decode_in_chunks(ContentEncoding::Deflate, &zlib.finish().unwrap()), original);
+    }
+    
+    #[test]
+    fn test_decode_truncated_stream() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&"hello world\n".repeat(1000).into_bytes()).unwrap();
+        let compressed = gzip.finish().unwrap();
+        
+        let mut decoder = Decoder::new(ContentEncoding::Gzip);
+        decoder.decode(&compressed[..compressed.len() / 2]).unwrap();
+        assert!(decoder.finish().is_err());
+    }
+}
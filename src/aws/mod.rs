@@ -1,4 +1,8 @@
 pub mod auth;
 pub mod bucket;
+pub mod encoding;
+pub mod partial;
+pub mod profiles;
+pub mod throttle;
 pub mod transfer;
 pub mod s3;
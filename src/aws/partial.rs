@@ -0,0 +1,143 @@
+use anyhow::Result;
+use log::{debug, error};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extension of the sidecar files that record interrupted transfers
+pub const SIDECAR_EXTENSION: &str = "s3part";
+
+/// State of an interrupted download, stored next to the partial file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialDownload {
+    pub bucket: String,
+    pub key: String,
+    /// Expected size of the complete object
+    pub total_size: u64,
+    /// ETag of the object when the download started, to detect changes before resuming
+    pub e_tag: Option<String>,
+    /// Bytes written to the local file so far
+    pub bytes_written: u64,
+}
+
+/// State of an interrupted multipart upload, stored next to the source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialUpload {
+    pub bucket: String,
+    pub key: String,
+    pub upload_id: String,
+    pub part_size: u64,
+    /// Size and last modified time (seconds since the Unix epoch) of the source file, to
+    /// detect changes before resuming
+    pub total_size: u64,
+    pub modified: Option<u64>,
+    /// Part numbers and ETags of the parts already uploaded
+    pub completed_parts: Vec<(i32, String)>,
+}
+
+/// Get the sidecar path for a local file, e.g. `photo.jpg.s3part`
+pub fn sidecar_path(local_path: &Path) -> PathBuf {
+    let mut name = local_path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(SIDECAR_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Check whether a path is a transfer sidecar, so it isn't synced like a normal file
+pub fn is_sidecar(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| extension == SIDECAR_EXTENSION)
+}
+
+/// Check whether a local file is the partial file of an interrupted or running download,
+/// which shouldn't be uploaded as if it were complete
+pub fn is_partial_download(path: &Path) -> bool {
+    sidecar_path(path).exists() && load::<PartialDownload>(path).is_some()
+}
+
+/// Write the transfer state for a local file. The state is written to a temporary
+/// file first so an interruption never leaves a truncated sidecar behind.
+pub fn save<T: Serialize>(local_path: &Path, state: &T) -> Result<()> {
+    let path = sidecar_path(local_path);
+    let temp_path = path.with_extension(format!("{}.tmp", SIDECAR_EXTENSION));
+
+    fs::write(&temp_path, serde_json::to_string(state)?)?;
+    fs::rename(&temp_path, &path)?;
+
+    Ok(())
+}
+
+/// Read the transfer state for a local file, if a valid sidecar exists
+pub fn load<T: DeserializeOwned>(local_path: &Path) -> Option<T> {
+    let path = sidecar_path(local_path);
+    let contents = fs::read_to_string(&path).ok()?;
+
+    match serde_json::from_str(&contents) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            debug!("Ignoring unreadable sidecar {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Remove the sidecar for a local file after the transfer completes
+pub fn remove(local_path: &Path) {
+    let path = sidecar_path(local_path);
+
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            error!("Failed to remove sidecar {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir = tempdir().unwrap();
+        let local_path = dir.path().join("photo.jpg");
+
+        assert_eq!(sidecar_path(&local_path), dir.path().join("photo.jpg.s3part"));
+        assert!(is_sidecar(&sidecar_path(&local_path)));
+        assert!(!is_sidecar(&local_path));
+        assert!(load::<PartialDownload>(&local_path).is_none());
+
+        let state = PartialDownload {
+            bucket: "bucket".to_string(),
+            key: "photos/photo.jpg".to_string(),
+            total_size: 1000,
+            e_tag: Some("\"abc\"".to_string()),
+            bytes_written: 400,
+        };
+        save(&local_path, &state).unwrap();
+        assert_eq!(load::<PartialDownload>(&local_path), Some(state));
+        assert!(is_partial_download(&local_path));
+
+        remove(&local_path);
+        assert!(!sidecar_path(&local_path).exists());
+        assert!(!is_partial_download(&local_path));
+    }
+
+    #[test]
+    fn test_upload_sidecar_round_trip() {
+        let dir = tempdir().unwrap();
+        let local_path = dir.path().join("archive.zip");
+
+        let state = PartialUpload {
+            bucket: "bucket".to_string(),
+            key: "archive.zip".to_string(),
+            upload_id: "upload-1".to_string(),
+            part_size: 8 * 1024 * 1024,
+            total_size: 20 * 1024 * 1024,
+            modified: Some(1_700_000_000),
+            completed_parts: vec![(1, "\"etag1\"".to_string()), (2, "\"etag2\"".to_string())],
+        };
+        save(&local_path, &state).unwrap();
+        assert_eq!(load::<PartialUpload>(&local_path), Some(state));
+    }
+}
@@ -0,0 +1,75 @@
+use log::debug;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// List the named profiles in the shared AWS credentials and config files,
+/// sorted and without duplicates. Missing or unreadable files are skipped.
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles = BTreeSet::new();
+
+    let files = [
+        (shared_file("AWS_SHARED_CREDENTIALS_FILE", "credentials"), false),
+        (shared_file("AWS_CONFIG_FILE", "config"), true),
+    ];
+
+    for (path, is_config) in files.iter() {
+        let path = match path {
+            Some(path) => path,
+            None => continue,
+        };
+
+        match fs::read_to_string(path) {
+            Ok(contents) => profiles.extend(parse_profile_names(&contents, *is_config)),
+            Err(e) => debug!("Skipping AWS profile file {}: {}", path.display(), e),
+        }
+    }
+
+    profiles.into_iter().collect()
+}
+
+/// Get the path of a shared AWS file, from its environment variable or `~/.aws/<name>`
+fn shared_file(env_var: &str, name: &str) -> Option<PathBuf> {
+    match env::var_os(env_var) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dirs::home_dir().map(|home| home.join(".aws").join(name)),
+    }
+}
+
+/// Get the profile names from the section headers of a shared AWS file. In the config
+/// file named profiles are written `[profile name]`, except for `[default]`; `[sso-session ...]`
+/// and other sections aren't profiles.
+fn parse_profile_names(contents: &str, is_config: bool) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter_map(|line| line.strip_prefix('[')?.strip_suffix(']'))
+        .map(|section| section.trim())
+        .filter_map(|section| {
+            if !is_config || section == "default" {
+                Some(section)
+            } else {
+                section.strip_prefix("profile ").map(|name| name.trim())
+            }
+        })
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile_names() {
+        let credentials = "[default]\naws_access_key_id = 123\n\n[work]\naws_access_key_id = 456\n";
+        assert_eq!(parse_profile_names(credentials, false), vec!["default", "work"]);
+
+        let config = "[default]\nregion = us-east-1\n[profile dev]\nregion = eu-west-1\n[sso-session corp]\nsso_region = us-east-1\n";
+        assert_eq!(parse_profile_names(config, true), vec!["default", "dev"]);
+
+        assert!(parse_profile_names("# comment only\n[]\n", false).is_empty());
+    }
+}
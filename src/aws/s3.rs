@@ -1,10 +1,94 @@
 use anyhow::anyhow;
-use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
 use log::debug;
+use std::{fmt, io};
 
 /// Helper functions for S3 error handling
 pub struct S3ErrorHelper;
 
+/// Shown when an object can't be read because its SSE-KMS key denies kms:Decrypt
+pub const KMS_DECRYPT_MESSAGE: &str = "This object is encrypted with a KMS key you can't decrypt";
+
+/// Shown when an upload sets an ACL on a bucket whose Object Ownership setting disables ACLs
+pub const ACL_NOT_SUPPORTED_MESSAGE: &str = "The bucket has ACLs disabled, set Upload ACL to Bucket default";
+
+/// Error codes S3 answers with for failures that go away on their own
+const TRANSIENT_ERROR_CODES: &[&str] = &[
+    "SlowDown",
+    "RequestTimeout",
+    "RequestTimeTooSkewed",
+    "InternalError",
+    "ServiceUnavailable",
+    "Throttling",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+];
+
+/// A failed S3 request, with whether it is worth retrying decided from the SDK error when it
+/// was made rather than from the message
+#[derive(Debug)]
+pub struct S3Error {
+    message: String,
+    retryable: bool,
+}
+
+impl S3Error {
+    /// A transfer failure that is always worth retrying, such as a stalled connection
+    pub fn transient(message: String) -> Self {
+        Self { message, retryable: true }
+    }
+}
+
+impl fmt::Display for S3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for S3Error {}
+
+/// S3 operations performed by the app, used to explain permission failures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum S3Operation {
+    ListBuckets,
+    ListObjects,
+    ListObjectVersions,
+    GetObject,
+    PutObject,
+    DeleteObject,
+    DeleteObjectVersion,
+    CopyObject,
+}
+
+impl S3Operation {
+    /// IAM actions required by the operation
+    pub fn iam_actions(&self) -> &'static [&'static str] {
+        match self {
+            S3Operation::ListBuckets => &["s3:ListAllMyBuckets"],
+            S3Operation::ListObjects => &["s3:ListBucket"],
+            S3Operation::ListObjectVersions => &["s3:ListBucketVersions"],
+            S3Operation::GetObject => &["s3:GetObject"],
+            S3Operation::PutObject => &["s3:PutObject"],
+            S3Operation::DeleteObject => &["s3:DeleteObject"],
+            S3Operation::DeleteObjectVersion => &["s3:DeleteObjectVersion"],
+            S3Operation::CopyObject => &["s3:GetObject", "s3:PutObject"],
+        }
+    }
+    
+    /// Whether the operation's permissions apply to objects rather than the bucket
+    fn is_object_level(&self) -> bool {
+        !matches!(self, S3Operation::ListBuckets | S3Operation::ListObjects | S3Operation::ListObjectVersions)
+    }
+}
+
+/// The operation, bucket and key of a request that was denied
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionContext {
+    pub operation: S3Operation,
+    pub bucket: String,
+    pub key: Option<String>,
+}
+
 impl S3ErrorHelper {
     /// Extract detailed error information from an AWS SDK error
     pub fn extract_error_details<E>(error: &SdkError<E>) -> String 
@@ -17,9 +101,13 @@ impl S3ErrorHelper {
         // since code() and message() methods aren't directly available
         
         let error_string = format!("{:?}", error);
+        let status = error.raw_response().map(|response| response.http().status().as_u16());
         
-        // Try to extract error type from the debug output
-        let error_type = if error_string.contains("AccessDenied") {
+        // Try to extract error type from the debug output. KMS failures are also reported
+        // as AccessDenied, so they are checked first.
+        let error_type = if Self::is_kms_error(&error_string) {
+            "KMSAccessDenied"
+        } else if error_string.contains("AccessDenied") {
             "AccessDenied"
         } else if error_string.contains("NoSuchBucket") {
             "NoSuchBucket"
@@ -33,19 +121,38 @@ impl S3ErrorHelper {
             "InvalidToken"
         } else if error_string.contains("AuthorizationHeaderMalformed") {
             "AuthorizationHeaderMalformed"
+        } else if Self::is_region_error(error) {
+            "PermanentRedirect"
+        } else if error_string.contains("AccessControlListNotSupported") {
+            "AccessControlListNotSupported"
+        } else if matches!(error, SdkError::TimeoutError(_)) {
+            "Timeout"
+        } else if matches!(error, SdkError::DispatchFailure(_)) {
+            "NetworkError"
+        } else if error_string.contains("SlowDown") || status == Some(503) {
+            "SlowDown"
+        } else if status.map_or(false, |status| status >= 500) || error_string.contains("InternalError") {
+            "ServerError"
         } else {
             "Unknown"
         };
         
         // Check for specific error types and provide additional information
         let additional_info = match error_type {
+            "KMSAccessDenied" => " - This object is encrypted with a KMS key you can't decrypt, kms:Decrypt is required on the key",
             "AccessDenied" => " - Check your IAM permissions for this bucket",
             "NoSuchBucket" => " - The specified bucket does not exist",
             "InvalidAccessKeyId" => " - The AWS access key ID you provided does not exist",
             "SignatureDoesNotMatch" => " - The signature calculation is incorrect, check your secret key",
-            "ExpiredToken" => " - The provided token has expired, please refresh your credentials",
+            "ExpiredToken" => " - The session token has expired, please enter a new one in Settings",
             "InvalidToken" => " - The provided token is invalid, please check your credentials",
             "AuthorizationHeaderMalformed" => " - The authorization header is malformed, check region configuration",
+            "PermanentRedirect" => " - The bucket is in another region than the request was sent to",
+            "AccessControlListNotSupported" => " - The bucket has ACLs disabled (Object Ownership is bucket owner enforced), upload with the bucket default ACL or bucket-owner-full-control",
+            "Timeout" => " - The request timed out",
+            "NetworkError" => " - S3 could not be reached, check your network connection",
+            "SlowDown" => " - S3 is throttling requests, try again later or lower the number of concurrent transfers",
+            "ServerError" => " - S3 reported an internal error, try again later",
             _ => "",
         };
         
@@ -58,6 +165,169 @@ impl S3ErrorHelper {
         )
     }
     
+    /// Check whether an error indicates the request was sent to the wrong region for the bucket
+    pub fn is_region_error<E>(error: &SdkError<E>) -> bool
+    where
+        E: std::fmt::Debug
+    {
+        // S3 answers requests to the wrong regional endpoint with a 301 redirect
+        if let Some(response) = error.raw_response() {
+            if response.http().status().as_u16() == 301 {
+                return true;
+            }
+        }
+        
+        let error_string = format!("{:?}", error);
+        error_string.contains("PermanentRedirect") || error_string.contains("AuthorizationHeaderMalformed")
+    }
+    
+    /// Check whether an error message reports an AccessDenied failure on the bucket or object
+    pub fn is_access_denied(message: &str) -> bool {
+        message.contains("AccessDenied") && !Self::is_kms_error(message)
+    }
+    
+    /// Check whether an error message reports that the object's KMS key could not be used.
+    /// S3 returns these as AccessDenied on the object, but the missing permission is on the key.
+    pub fn is_kms_error(message: &str) -> bool {
+        message.contains("KMSAccessDenied")
+            || message.contains("kms:Decrypt")
+            || message.contains("KMS.")
+            || message.contains("arn:aws:kms:")
+    }
+    
+    /// Check whether an error message reports that the session token of temporary
+    /// credentials has expired or is no longer accepted
+    pub fn is_expired_token(message: &str) -> bool {
+        message.contains("ExpiredToken")
+            || message.contains("TokenRefreshRequired")
+            || message.contains("InvalidToken")
+    }
+    
+    /// Wrap an SDK error for the caller, with `context` ahead of its details and whether it
+    /// is worth retrying
+    pub fn error<E>(context: impl fmt::Display, error: &SdkError<E>) -> anyhow::Error
+    where
+        E: ProvideErrorMetadata + std::fmt::Debug + std::fmt::Display
+    {
+        anyhow::Error::new(S3Error {
+            message: format!("{}: {}", context, Self::extract_error_details(error)),
+            retryable: Self::is_transient(error),
+        })
+    }
+    
+    /// Check whether an SDK error is a transient failure: timeouts, network errors, throttling
+    /// and 5xx server errors. Client errors such as AccessDenied or NoSuchBucket fail the same
+    /// way every time.
+    pub fn is_transient<E: ProvideErrorMetadata>(error: &SdkError<E>) -> bool {
+        match error {
+            SdkError::TimeoutError(_) | SdkError::ResponseError(_) => true,
+            SdkError::DispatchFailure(failure) => !failure.is_user(),
+            SdkError::ServiceError(service) => {
+                let status = service.raw().http().status().as_u16();
+                service.err().code().map_or(false, |code| TRANSIENT_ERROR_CODES.contains(&code))
+                    || status == 429
+                    || status >= 500
+            },
+            _ => false,
+        }
+    }
+    
+    /// Check whether a failure is worth retrying: a transient S3 error, or a connection that
+    /// broke while the body was streaming
+    pub fn is_retryable(error: &anyhow::Error) -> bool {
+        error.chain().any(|cause| match cause.downcast_ref::<S3Error>() {
+            Some(s3_error) => s3_error.retryable,
+            None => cause.downcast_ref::<io::Error>().map_or(false, |e| matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::BrokenPipe | io::ErrorKind::TimedOut
+            )),
+        })
+    }
+    
+    /// Check whether an error message reports that S3 couldn't be reached at all, as opposed
+    /// to S3 answering with an error: SDK dispatch failures and timeouts, and the connection
+    /// failures `ErrorHandler` describes for network errors and I/O connection errors
+    pub fn is_network_error(message: &str) -> bool {
+        const NETWORK_TYPES: &[&str] = &["Type: NetworkError,", "Type: Timeout,"];
+        const NETWORK_MESSAGES: &[&str] = &[
+            "network error",
+            "dispatch failure",
+            "dns error",
+            "connection refused",
+            "connection reset",
+            "connection aborted",
+            "not connected",
+            "connection timed out",
+        ];
+        
+        let lowercase = message.to_ascii_lowercase();
+        NETWORK_TYPES.iter().any(|error_type| message.contains(error_type))
+            || NETWORK_MESSAGES.iter().any(|text| lowercase.contains(text))
+    }
+    
+    /// Check whether an error message reports that the bucket is in another region than
+    /// the request was sent to, i.e. the region it was thought to be in is wrong or out of date
+    pub fn is_redirect_error(message: &str) -> bool {
+        message.contains("PermanentRedirect") || message.contains("AuthorizationHeaderMalformed")
+    }
+    
+    /// Classify a transfer error into a short reason for display next to the file
+    pub fn failure_reason(message: &str) -> String {
+        let reason = if Self::is_kms_error(message) {
+            KMS_DECRYPT_MESSAGE
+        } else if Self::is_access_denied(message) {
+            "Access denied"
+        } else if message.contains("stalled") {
+            "Stalled: no data received"
+        } else if message.contains("NoSuchBucket") {
+            "Bucket not found"
+        } else if message.contains("NoSuchKey") || message.contains("NotFound") {
+            "Object not found"
+        } else if Self::is_expired_token(message) {
+            "Credentials expired"
+        } else if message.contains("AccessControlListNotSupported") {
+            ACL_NOT_SUPPORTED_MESSAGE
+        } else if message.starts_with("Disk full") {
+            "Disk full"
+        } else if message.starts_with("Permission denied writing") {
+            "Local file not writable"
+        } else if message.contains("No such file or directory") {
+            "Local file not found"
+        } else {
+            return message.lines().next().unwrap_or(message).to_string();
+        };
+        
+        reason.to_string()
+    }
+    
+    /// Explain which IAM action and resource a denied request most likely needed
+    pub fn explain_access_denied(context: &PermissionContext) -> String {
+        let resource = if context.operation == S3Operation::ListBuckets {
+            "*".to_string()
+        } else if context.operation.is_object_level() {
+            format!("arn:aws:s3:::{}/{}", context.bucket, context.key.as_deref().unwrap_or("*"))
+        } else {
+            format!("arn:aws:s3:::{}", context.bucket)
+        };
+        
+        let mut explanation = format!(
+            "Needs {} on {}.",
+            context.operation.iam_actions().join(" and "),
+            resource
+        );
+        
+        if context.operation == S3Operation::CopyObject {
+            explanation.push_str(" s3:GetObject applies to the source object and s3:PutObject to the destination.");
+        }
+        
+        if matches!(context.operation, S3Operation::GetObject | S3Operation::PutObject | S3Operation::CopyObject) {
+            explanation.push_str(" If the bucket uses SSE-KMS, the KMS key must also allow kms:Decrypt and kms:GenerateDataKey.");
+        }
+        
+        explanation.push_str(" Check your IAM policy, the bucket policy and any organization SCPs for an explicit Deny.");
+        explanation
+    }
+    
     /// Convert an AWS SDK error to an anyhow error with detailed information
     #[allow(dead_code)] // Will be used in future implementations
     pub fn convert_sdk_error<E>(error: SdkError<E>, operation: &str) -> anyhow::Error 
@@ -71,5 +341,94 @@ impl S3ErrorHelper {
 
 #[cfg(test)]
 mod tests {
-    // Tests would go here
+    use super::*;
+    use aws_sdk_s3::operation::get_object::GetObjectError;
+    
+    #[test]
+    fn test_explain_access_denied() {
+        let context = |operation, key: Option<&str>| PermissionContext {
+            operation,
+            bucket: "my-bucket".to_string(),
+            key: key.map(|k| k.to_string()),
+        };
+        
+        let explanation = S3ErrorHelper::explain_access_denied(&context(S3Operation::GetObject, Some("docs/a.txt")));
+        assert!(explanation.starts_with("Needs s3:GetObject on arn:aws:s3:::my-bucket/docs/a.txt."));
+        
+        let explanation = S3ErrorHelper::explain_access_denied(&context(S3Operation::ListObjects, None));
+        assert!(explanation.starts_with("Needs s3:ListBucket on arn:aws:s3:::my-bucket."));
+        
+        let explanation = S3ErrorHelper::explain_access_denied(&context(S3Operation::ListBuckets, None));
+        assert!(explanation.starts_with("Needs s3:ListAllMyBuckets on *."));
+        
+        let explanation = S3ErrorHelper::explain_access_denied(&context(S3Operation::PutObject, None));
+        assert!(explanation.starts_with("Needs s3:PutObject on arn:aws:s3:::my-bucket/*."));
+    }
+    
+    #[test]
+    fn test_is_access_denied() {
+        assert!(S3ErrorHelper::is_access_denied("AWS S3 error - Type: AccessDenied, Raw: service error"));
+        assert!(!S3ErrorHelper::is_access_denied("AWS S3 error - Type: NoSuchBucket, Raw: service error"));
+    }
+    
+    #[test]
+    fn test_failure_reason() {
+        assert_eq!(S3ErrorHelper::failure_reason("AWS S3 error - Type: AccessDenied, Raw: service error"), "Access denied");
+        assert_eq!(S3ErrorHelper::failure_reason("AWS S3 error - Type: KMSAccessDenied, Raw: service error"), KMS_DECRYPT_MESSAGE);
+        assert_eq!(S3ErrorHelper::failure_reason("stalled: no data received for 30 seconds"), "Stalled: no data received");
+        assert_eq!(S3ErrorHelper::failure_reason("Failed to get a.txt: NoSuchKey"), "Object not found");
+        assert_eq!(S3ErrorHelper::failure_reason("Disk full while writing a.txt"), "Disk full");
+        assert_eq!(S3ErrorHelper::failure_reason("AWS S3 error - Type: ExpiredToken, Raw: service error"), "Credentials expired");
+        assert_eq!(S3ErrorHelper::failure_reason("AWS S3 error - Type: AccessControlListNotSupported, Raw: service error"), ACL_NOT_SUPPORTED_MESSAGE);
+        assert_eq!(S3ErrorHelper::failure_reason("something else\nwith detail"), "something else");
+    }
+    
+    #[test]
+    fn test_is_retryable() {
+        let timeout: SdkError<GetObjectError> = SdkError::timeout_error("timed out");
+        let error = S3ErrorHelper::error("Failed to get a.txt", &timeout);
+        assert!(error.to_string().starts_with("Failed to get a.txt: AWS S3 error - Type: Timeout,"));
+        assert!(S3ErrorHelper::is_retryable(&error));
+        assert!(S3ErrorHelper::is_retryable(&error.context("Sync failed")));
+        
+        assert!(S3ErrorHelper::is_retryable(&anyhow::Error::new(S3Error::transient("stalled: no data received for 30 seconds".to_string()))));
+        assert!(S3ErrorHelper::is_retryable(&io::Error::from(io::ErrorKind::ConnectionReset).into()));
+        
+        // Only the error, not its message, decides
+        let construction: SdkError<GetObjectError> = SdkError::construction_failure("Type: SlowDown, connection reset");
+        assert!(!S3ErrorHelper::is_retryable(&S3ErrorHelper::error("Failed to get a.txt", &construction)));
+        assert!(!S3ErrorHelper::is_retryable(&anyhow!("AWS S3 error - Type: SlowDown, Raw: service error")));
+        assert!(!S3ErrorHelper::is_retryable(&io::Error::from(io::ErrorKind::PermissionDenied).into()));
+    }
+    
+    #[test]
+    fn test_is_network_error() {
+        assert!(S3ErrorHelper::is_network_error("Failed to list buckets: AWS S3 error - Type: NetworkError, Raw: dispatch failure"));
+        assert!(S3ErrorHelper::is_network_error("AWS S3 error - Type: Timeout, Raw: timeout"));
+        assert!(S3ErrorHelper::is_network_error("Network Error: could not resolve host"));
+        assert!(S3ErrorHelper::is_network_error("Connection refused. Please check your network connection."));
+        
+        assert!(!S3ErrorHelper::is_network_error("AWS S3 error - Type: AccessDenied, Raw: service error"));
+        assert!(!S3ErrorHelper::is_network_error("AWS S3 error - Type: SlowDown, Raw: service error"));
+    }
+    
+    #[test]
+    fn test_is_redirect_error() {
+        assert!(S3ErrorHelper::is_redirect_error("AWS S3 error - Type: PermanentRedirect, Raw: service error"));
+        assert!(S3ErrorHelper::is_redirect_error("Failed to get lifecycle configuration: AuthorizationHeaderMalformed - the region 'us-east-1' is wrong"));
+        assert!(!S3ErrorHelper::is_redirect_error("AWS S3 error - Type: NoSuchBucket, Raw: service error"));
+    }
+    
+    #[test]
+    fn test_is_kms_error() {
+        assert!(S3ErrorHelper::is_kms_error(
+            "AccessDenied: User: arn:aws:iam::123456789012:user/alice is not authorized to perform: kms:Decrypt on resource: arn:aws:kms:us-east-1:123456789012:key/abcd"
+        ));
+        assert!(S3ErrorHelper::is_kms_error("KMS.DisabledException: the key is disabled"));
+        assert!(S3ErrorHelper::is_kms_error("AWS S3 error - Type: KMSAccessDenied, Raw: service error"));
+        assert!(!S3ErrorHelper::is_kms_error("AWS S3 error - Type: AccessDenied, Raw: service error"));
+        
+        // KMS failures must not be explained as plain S3 permission problems
+        assert!(!S3ErrorHelper::is_access_denied("AWS S3 error - Type: KMSAccessDenied, Raw: service error"));
+    }
 }
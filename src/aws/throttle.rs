@@ -0,0 +1,147 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Token bucket limiting the combined rate of the transfers that share it. Each
+/// chunk takes its size in tokens; once the bucket runs dry a transfer sleeps until
+/// enough tokens have been added back at the configured rate.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// Tokens available; negative when chunks have been granted ahead of the rate
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Create a limiter for a rate in bytes per second. Up to a second's worth of
+    /// bytes can pass without waiting after an idle period.
+    pub fn new(bytes_per_second: u64) -> Self {
+        let bytes_per_second = bytes_per_second.max(1) as f64;
+        
+        Self {
+            bytes_per_second,
+            state: Mutex::new(BucketState {
+                tokens: bytes_per_second,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+    
+    /// Create a limiter for a limit in KB/s, or None if there is no limit
+    pub fn from_kb_per_second(limit: Option<u32>) -> Option<Self> {
+        limit.filter(|limit| *limit > 0).map(|limit| Self::new(limit as u64 * 1024))
+    }
+    
+    /// Wait until `bytes` may be transferred
+    pub async fn acquire(&self, bytes: u64) {
+        let delay = self.reserve(bytes, Instant::now());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+    
+    /// Take `bytes` tokens at `now`, returning how long the caller has to wait for them.
+    /// Tokens are taken even if the bucket goes negative, so concurrent callers queue
+    /// up behind each other instead of all waking at the same moment.
+    fn reserve(&self, bytes: u64, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        
+        let elapsed = now.saturating_duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.bytes_per_second).min(self.bytes_per_second);
+        state.last_refill = state.last_refill.max(now);
+        state.tokens -= bytes as f64;
+        
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.bytes_per_second)
+        }
+    }
+}
+
+/// The limiter shared by every transfer in the app, so concurrent batches split the limit
+/// between them. It is only replaced when the limit changes; transfers already running
+/// keep the limiter they started with.
+#[derive(Debug, Default)]
+pub struct SharedRateLimiter {
+    limit: Option<u32>,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl SharedRateLimiter {
+    /// Set the limit in KB/s, or None for no limit
+    pub fn set_limit(&mut self, limit: Option<u32>) {
+        let limit = limit.filter(|limit| *limit > 0);
+        if limit != self.limit {
+            self.limit = limit;
+            self.limiter = RateLimiter::from_kb_per_second(limit).map(Arc::new);
+        }
+    }
+    
+    /// Get the limiter for a transfer manager, or None if there is no limit
+    pub fn limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.limiter.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_from_kb_per_second() {
+        assert!(RateLimiter::from_kb_per_second(None).is_none());
+        assert!(RateLimiter::from_kb_per_second(Some(0)).is_none());
+        assert_eq!(RateLimiter::from_kb_per_second(Some(64)).unwrap().bytes_per_second, 65536.0);
+    }
+    
+    #[test]
+    fn test_reserve_holds_rate() {
+        let limiter = RateLimiter::new(1000);
+        let start = limiter.state.lock().unwrap().last_refill;
+        
+        // A second's worth passes straight away
+        assert_eq!(limiter.reserve(1000, start), Duration::ZERO);
+        
+        // Then each chunk waits for its share of the rate
+        assert_eq!(limiter.reserve(500, start), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(500, start), Duration::from_secs(1));
+        
+        // Time passing pays back the debt
+        assert_eq!(limiter.reserve(0, start + Duration::from_secs(1)), Duration::ZERO);
+        assert_eq!(limiter.reserve(250, start + Duration::from_secs(1)), Duration::from_millis(250));
+    }
+    
+    #[test]
+    fn test_shared_rate_limiter() {
+        let mut shared = SharedRateLimiter::default();
+        assert!(shared.limiter().is_none());
+        
+        // Every transfer gets the same limiter until the limit changes
+        shared.set_limit(Some(64));
+        let limiter = shared.limiter().unwrap();
+        shared.set_limit(Some(64));
+        assert!(Arc::ptr_eq(&limiter, &shared.limiter().unwrap()));
+        
+        shared.set_limit(Some(128));
+        assert!(!Arc::ptr_eq(&limiter, &shared.limiter().unwrap()));
+        
+        shared.set_limit(Some(0));
+        assert!(shared.limiter().is_none());
+    }
+    
+    #[test]
+    fn test_reserve_caps_burst() {
+        let limiter = RateLimiter::new(1000);
+        let start = limiter.state.lock().unwrap().last_refill;
+        
+        // Idle time doesn't build up more than a second's worth of tokens
+        assert_eq!(limiter.reserve(1000, start + Duration::from_secs(60)), Duration::ZERO);
+        assert_eq!(limiter.reserve(1000, start + Duration::from_secs(60)), Duration::from_secs(1));
+    }
+}
@@ -1,15 +1,30 @@
 use anyhow::{anyhow, Result};
-use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::operation::get_object::GetObjectOutput;
+use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Output;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::{ByteStream, DateTime, SdkBody};
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectCannedAcl, ObjectIdentifier, ServerSideEncryption, StorageClass};
 use aws_sdk_s3::Client;
 use log::debug;
 use log::error;
-use std::path::Path;
+use md5::{Digest, Md5};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::fs;
-use tokio::io::AsyncWriteExt;
+use std::time::Duration;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use chrono::{/* DateTime, */ Utc, TimeZone};
+use futures::{Stream, StreamExt};
+use tokio::io::AsyncReadExt;
 
-use crate::aws::s3::S3ErrorHelper;
+use crate::aws::encoding::{decoded_path, ContentEncoding, Decoder};
+use crate::aws::partial::{self, PartialDownload, PartialUpload};
+use crate::aws::s3::{S3Error, S3ErrorHelper};
+use crate::aws::throttle::RateLimiter;
 
 /// Progress information for a file transfer
 #[derive(Debug, Clone)]
@@ -21,16 +36,376 @@ pub struct TransferProgress {
     pub percentage: f32,
 }
 
+impl TransferProgress {
+    /// Progress after `bytes_transferred` of `total_bytes` have been transferred
+    fn new(file_name: &str, bytes_transferred: u64, total_bytes: u64) -> Self {
+        let percentage = if total_bytes > 0 {
+            (bytes_transferred as f32 / total_bytes as f32) * 100.0
+        } else {
+            100.0
+        };
+        
+        Self {
+            file_name: file_name.to_string(),
+            bytes_transferred,
+            total_bytes,
+            percentage,
+        }
+    }
+}
+
+/// Shared progress callback, so a retried request body can report progress again
+type SharedProgressCallback = Arc<dyn Fn(TransferProgress) + Send + Sync>;
+
+/// A single point in an object's version history
+#[derive(Debug, Clone)]
+pub struct ObjectVersionInfo {
+    pub version_id: String,
+    pub is_latest: bool,
+    pub is_delete_marker: bool,
+    pub size: u64,
+    /// Last modified time in seconds since the Unix epoch
+    pub last_modified: i64,
+}
+
+/// Key, size, ETag and modified time of an object, as returned by a recursive listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectSummary {
+    pub key: String,
+    pub size: u64,
+    pub e_tag: Option<String>,
+    /// Last modified time in seconds since the Unix epoch
+    pub last_modified: i64,
+}
+
+/// Properties of an object, as returned by a HEAD request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectMetadata {
+    pub size: u64,
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    pub content_language: Option<String>,
+    /// ETag without its surrounding quotes
+    pub e_tag: Option<String>,
+    /// Storage class; S3 leaves it out for STANDARD objects
+    pub storage_class: String,
+    /// Last modified time in seconds since the Unix epoch
+    pub last_modified: i64,
+    /// Server-side encryption algorithm, e.g. "AES256" or "aws:kms"
+    pub encryption: Option<String>,
+    /// KMS key the object is encrypted with
+    pub kms_key_id: Option<String>,
+    pub version_id: Option<String>,
+    /// User-defined `x-amz-meta-*` metadata, sorted by name
+    pub user_metadata: BTreeMap<String, String>,
+}
+
+/// When a lifecycle rule will expire an object, from the `x-amz-expiration` header
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectExpiration {
+    /// Expiry date as sent by S3, e.g. "Fri, 23 Dec 2012 00:00:00 GMT"
+    pub expiry_date: String,
+    /// ID of the lifecycle rule that expires the object
+    pub rule_id: String,
+}
+
+impl ObjectExpiration {
+    /// Parse an `x-amz-expiration` header value such as
+    /// `expiry-date="Fri, 23 Dec 2012 00:00:00 GMT", rule-id="delete-old-logs"`
+    pub fn parse(header: &str) -> Option<Self> {
+        let expiry_date = header_field(header, "expiry-date")?;
+        let rule_id = header_field(header, "rule-id").unwrap_or_default();
+        
+        Some(Self { expiry_date, rule_id })
+    }
+}
+
+/// An object to copy, with what a multipart copy has to set on the new object itself.
+/// A single CopyObject request carries the headers, metadata and tags over on its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopySource {
+    pub bucket: String,
+    pub key: String,
+    pub metadata: ObjectMetadata,
+    /// Tags of the object; only fetched for objects copied in parts
+    pub tags: Vec<(String, String)>,
+}
+
+impl CopySource {
+    /// Storage class to give the copy
+    fn storage_class(&self) -> StorageClass {
+        StorageClass::from(self.metadata.storage_class.as_str())
+    }
+    
+    /// Server-side encryption to give the copy, or None if the object isn't encrypted
+    fn server_side_encryption(&self) -> Option<ServerSideEncryption> {
+        self.metadata.encryption.as_deref().map(ServerSideEncryption::from)
+    }
+    
+    /// Tags as a URL-encoded `key=value&...` query string, or None if there are none
+    fn tagging(&self) -> Option<String> {
+        if self.tags.is_empty() {
+            return None;
+        }
+        
+        let tags: Vec<String> = self.tags.iter()
+            .map(|(key, value)| {
+                let mut tag = String::new();
+                percent_encode(&mut tag, key, false);
+                tag.push('=');
+                percent_encode(&mut tag, value, false);
+                tag
+            })
+            .collect();
+        Some(tags.join("&"))
+    }
+    
+    /// User-defined metadata for the copy, or None if there is none
+    fn user_metadata(&self) -> Option<HashMap<String, String>> {
+        if self.metadata.user_metadata.is_empty() {
+            None
+        } else {
+            Some(self.metadata.user_metadata.clone().into_iter().collect())
+        }
+    }
+}
+
+/// Get a quoted `name="value"` field from a header value
+fn header_field(header: &str, name: &str) -> Option<String> {
+    let start = header.find(&format!("{}=\"", name))? + name.len() + 2;
+    let length = header[start..].find('"')?;
+    Some(header[start..start + length].to_string())
+}
+
+/// Get the content type to upload a file with, from its extension. Overrides are keyed by
+/// lowercase extension; files the guesser doesn't know are uploaded as application/octet-stream.
+pub fn content_type_for(path: &Path, overrides: &HashMap<String, String>) -> String {
+    let extension = path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    
+    match overrides.get(&extension) {
+        Some(content_type) => content_type.clone(),
+        None => mime_guess::from_path(path).first_or_octet_stream().to_string(),
+    }
+}
+
+/// Maximum number of keys S3 accepts in a single DeleteObjects request
+pub const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Check whether an IO error means the disk is full
+fn is_disk_full(error: &std::io::Error) -> bool {
+    match error.raw_os_error() {
+        // ENOSPC on Unix
+        #[cfg(unix)]
+        Some(28) => true,
+        // ERROR_HANDLE_DISK_FULL and ERROR_DISK_FULL on Windows
+        #[cfg(windows)]
+        Some(39) | Some(112) => true,
+        _ => false,
+    }
+}
+
+/// Get the MD5 digest an ETag holds, or None if it isn't one. Objects uploaded in parts
+/// have ETags ending in `-<parts>`, which aren't a digest of the content.
+fn etag_md5(e_tag: &str) -> Option<&str> {
+    let e_tag = e_tag.trim_matches('"');
+    if e_tag.len() == 32 && e_tag.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(e_tag)
+    } else {
+        None
+    }
+}
+
+/// Hash the first `len` bytes of a file, to carry on hashing a resumed download
+fn md5_of_prefix(path: &Path, len: u64) -> std::io::Result<Md5> {
+    use std::io::Read;
+    
+    let mut reader = fs::File::open(path)?.take(len);
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    
+    Ok(hasher)
+}
+
+/// Describe a failure to write a downloaded file, calling out a full disk
+fn describe_write_error(error: &std::io::Error, local_path: &Path) -> String {
+    if is_disk_full(error) {
+        format!("Disk full while writing {}", local_path.display())
+    } else if error.kind() == std::io::ErrorKind::PermissionDenied {
+        format!("Permission denied writing {}", local_path.display())
+    } else {
+        format!("Failed to write {}: {}", local_path.display(), error)
+    }
+}
+
+/// Largest object that can be copied with a single CopyObject request (5 GB)
+pub const MAX_SINGLE_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Default part size for multipart copies (512 MB)
+const COPY_PART_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Maximum number of parts in a multipart upload
+const MAX_PARTS: u64 = 10_000;
+
+/// Files larger than this are uploaded with a multipart upload (100 MB)
+pub const MULTIPART_UPLOAD_THRESHOLD: u64 = 100 * 1024 * 1024;
+
+/// Default part size for multipart uploads (8 MB)
+pub const DEFAULT_UPLOAD_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Smallest part S3 accepts, except for the last part of an upload (5 MB)
+const MIN_UPLOAD_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Size of the chunks an upload body is read in (64 KB)
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How many downloaded bytes between updates of the `.s3part` sidecar
+const SIDECAR_SAVE_INTERVAL: u64 = 4 * 1024 * 1024;
+
+/// How long a transfer may go without moving any data before it is failed as stalled
+pub const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error message of a transfer stopped by its cancel token
+pub const CANCELLED_MESSAGE: &str = "Transfer cancelled";
+
 /// Manager for S3 file transfers
 #[derive(Clone)]
 pub struct TransferManager {
     client: Arc<Client>,
+    stall_timeout: Duration,
+    /// Decompress gzip and deflate Content-Encoding while downloading
+    decompress: bool,
+    /// Part size for multipart uploads
+    upload_part_size: u64,
+    /// Checked between chunks and parts; once set, transfers in progress stop
+    cancel: Option<Arc<AtomicBool>>,
+    /// Server-side encryption requested for uploads, or None for the bucket default
+    server_side_encryption: Option<ServerSideEncryption>,
+    /// KMS key for aws:kms encryption, or None for the account's AWS managed key
+    sse_kms_key_id: Option<String>,
+    /// Storage class for uploaded objects, or None for STANDARD
+    storage_class: Option<StorageClass>,
+    /// Canned ACL for uploaded objects, or None to leave it to the bucket
+    acl: Option<ObjectCannedAcl>,
+    /// Content types for file extensions (lowercase, without the dot) the guesser gets wrong
+    content_type_overrides: HashMap<String, String>,
+    /// Bandwidth limit shared by every transfer of this manager and its clones, or None for unlimited
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Check downloads against the object's size and MD5 ETag
+    verify_downloads: bool,
 }
 
 impl TransferManager {
     /// Create a new transfer manager with the given client
     pub fn new(client: Arc<Client>) -> Self {
-        Self { client }
+        Self {
+            client,
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
+            decompress: false,
+            upload_part_size: DEFAULT_UPLOAD_PART_SIZE,
+            cancel: None,
+            server_side_encryption: None,
+            sse_kms_key_id: None,
+            storage_class: None,
+            acl: None,
+            content_type_overrides: HashMap::new(),
+            rate_limiter: None,
+            verify_downloads: true,
+        }
+    }
+    
+    /// Copy this manager, with its settings, to send requests with another client,
+    /// such as one for the region a bucket turned out to be in
+    pub fn with_client(&self, client: Arc<Client>) -> Self {
+        Self {
+            client,
+            ..self.clone()
+        }
+    }
+    
+    /// Set how long a transfer may go without moving any bytes before it is failed
+    pub fn set_stall_timeout(&mut self, stall_timeout: Duration) {
+        self.stall_timeout = stall_timeout;
+    }
+    
+    /// Set whether objects stored with a gzip or deflate Content-Encoding are decompressed on download
+    pub fn set_decompress(&mut self, decompress: bool) {
+        self.decompress = decompress;
+    }
+    
+    /// Set whether downloads are checked against the object's size and, where the ETag is
+    /// an MD5, its checksum. On by default.
+    pub fn set_verify_downloads(&mut self, verify_downloads: bool) {
+        self.verify_downloads = verify_downloads;
+    }
+    
+    /// Set the part size for multipart uploads, raised to the 5 MB minimum S3 accepts
+    pub fn set_upload_part_size(&mut self, part_size: u64) {
+        self.upload_part_size = part_size.max(MIN_UPLOAD_PART_SIZE);
+    }
+    
+    /// Get the part size a file of the given size is uploaded with if it is uploaded in parts
+    pub fn upload_part_size_for(&self, total_size: u64) -> u64 {
+        self.upload_part_size.max((total_size + MAX_PARTS - 1) / MAX_PARTS)
+    }
+    
+    /// Set the server-side encryption for uploaded objects. The KMS key ID is only used with aws:kms.
+    pub fn set_encryption(&mut self, encryption: Option<ServerSideEncryption>, kms_key_id: Option<String>) {
+        self.sse_kms_key_id = match encryption {
+            Some(ServerSideEncryption::AwsKms) => kms_key_id,
+            _ => None,
+        };
+        self.server_side_encryption = encryption;
+    }
+    
+    /// Set the storage class for uploaded objects
+    pub fn set_storage_class(&mut self, storage_class: Option<StorageClass>) {
+        self.storage_class = storage_class;
+    }
+    
+    /// Set the canned ACL for uploaded objects
+    pub fn set_acl(&mut self, acl: Option<ObjectCannedAcl>) {
+        self.acl = acl;
+    }
+    
+    /// Set the content types uploads use for file extensions, instead of the guessed ones
+    pub fn set_content_type_overrides(&mut self, overrides: HashMap<String, String>) {
+        self.content_type_overrides = overrides;
+    }
+    
+    /// Limit this manager's transfers with a limiter, or lift the limit with None. Managers
+    /// and concurrent transfers given the same limiter share its rate rather than each
+    /// getting the full rate.
+    pub fn set_rate_limiter(&mut self, rate_limiter: Option<Arc<RateLimiter>>) {
+        self.rate_limiter = rate_limiter;
+    }
+    
+    /// Wait until the bandwidth limit allows `bytes` more to be transferred
+    async fn throttle(&self, bytes: u64) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(bytes).await;
+        }
+    }
+    
+    /// Set a token that stops transfers in progress once it is set
+    pub fn set_cancel_token(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+    
+    /// Check whether the cancel token has been set
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().map_or(false, |cancel| cancel.load(Ordering::SeqCst))
     }
     
     /// List S3 buckets
@@ -48,93 +423,76 @@ impl TransferManager {
         Ok(buckets)
     }
     
-    /// List objects in a bucket
-    pub async fn list_objects(&self, bucket: &str) -> Result<Vec<crate::ui::bucket_view::S3Object>> {
-        debug!("Listing objects in bucket {}", bucket);
+    /// List the objects and sub-prefixes ("directories") directly under a prefix, or with
+    /// `recursive` every object under it by its full key, with no sub-prefixes.
+    /// The prefix is empty for the top of the bucket, or ends with '/'.
+    pub async fn list_objects(&self, bucket: &str, prefix: &str, recursive: bool) -> Result<Vec<crate::ui::bucket_view::S3Object>> {
+        debug!("Listing objects in bucket {} under '{}' (recursive: {})", bucket, prefix, recursive);
         
         let mut objects = Vec::new();
         let mut continuation_token = None;
         
         loop {
-            let mut req = self.client.list_objects_v2()
-                .bucket(bucket)
-                .delimiter("/");
-                
-            if let Some(token) = &continuation_token {
-                req = req.continuation_token(token);
-            }
-            
-            // Improved error handling with detailed AWS error information
-            let resp = match req.send().await {
-                Ok(response) => response,
-                Err(e) => {
-                    // Use our helper to extract detailed error information
-                    let detailed_error = S3ErrorHelper::extract_error_details(&e);
-                    
-                    // Log the detailed error
-                    error!("Failed to list objects in bucket {}: {}", bucket, detailed_error);
-                    
-                    // Return with detailed error information
-                    return Err(anyhow!("S3 service error: {}", detailed_error));
-                }
-            };
-            
-            // Process common prefixes (directories)
-            if let Some(prefixes) = resp.common_prefixes() {
-                for prefix in prefixes {
-                    if let Some(prefix_str) = prefix.prefix() {
-                        // Remove the trailing slash
-                        let key = prefix_str.trim_end_matches('/').to_string();
-                        
-                        objects.push(crate::ui::bucket_view::S3Object {
-                            key,
-                            size: 0,
-                            last_modified: String::new(),
-                            is_directory: true,
-                        });
-                    }
-                }
-            }
-            
-            // Process objects (files)
-            if let Some(contents) = resp.contents() {
-                for object in contents {
-                    let key = object.key().unwrap_or_default().to_string();
-                    let size = object.size() as u64;
-                    let last_modified = object.last_modified()
-                        .map(|dt| {
-                            // Format the date in a human-readable format
-                            // Extract the timestamp from the debug representation
-                            let _dt_str = format!("{:?}", dt);
-                            let dt_human = Utc.timestamp_opt(dt.secs(), 0)
-                            .single()
-                            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                            .unwrap_or_default();
-                            dt_human
-                        })
-                        .unwrap_or_default();
-
-                        
-                    objects.push(crate::ui::bucket_view::S3Object {
-                        key,
-                        size,
-                        last_modified,
-                        is_directory: false,
-                    });
-                }
-            }
+            let (page, next_token) = self.list_page(bucket, prefix, continuation_token, recursive).await?;
+            objects.extend(page);
             
-            // Check if there are more objects
-            if resp.is_truncated() && resp.next_continuation_token().is_some() {
-                continuation_token = resp.next_continuation_token().map(|s| s.to_string());
-            } else {
-                break;
+            match next_token {
+                Some(token) => continuation_token = Some(token),
+                None => break,
             }
         }
         
         Ok(objects)
     }
     
+    /// List one page (up to 1000 entries) of the objects and sub-prefixes directly under a prefix,
+    /// starting from a continuation token. Returns the token for the next page, or None on the last page.
+    pub async fn list_objects_page(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<(Vec<crate::ui::bucket_view::S3Object>, Option<String>)> {
+        self.list_page(bucket, prefix, continuation_token, false).await
+    }
+    
+    /// List one page of objects under a prefix. Without `recursive` the listing stops at the
+    /// next '/', grouping deeper keys into sub-prefixes.
+    pub async fn list_page(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        continuation_token: Option<String>,
+        recursive: bool,
+    ) -> Result<(Vec<crate::ui::bucket_view::S3Object>, Option<String>)> {
+        let mut req = self.client.list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_delimiter((!recursive).then(|| "/".to_string()))
+            .fetch_owner(true);
+            
+        if let Some(token) = &continuation_token {
+            req = req.continuation_token(token);
+        }
+        
+        // Improved error handling with detailed AWS error information
+        let resp = match req.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                // Use our helper to extract detailed error information
+                let detailed_error = S3ErrorHelper::extract_error_details(&e);
+                
+                // Log the detailed error
+                error!("Failed to list objects in bucket {}: {}", bucket, detailed_error);
+                
+                // Return with detailed error information
+                return Err(S3ErrorHelper::error("S3 service error", &e));
+            }
+        };
+        
+        Ok(listing_page(&resp))
+    }
+    
     /// Upload a file to S3
     pub async fn upload_file(
         &self,
@@ -142,7 +500,7 @@ impl TransferManager {
         bucket: &str,
         s3_key: &str,
         progress_callback: Option<Box<dyn Fn(TransferProgress) + Send + Sync>>,
-    ) -> Result<()> {
+    ) -> Result<Option<ObjectExpiration>> {
         debug!("Uploading {} to s3://{}/{}", local_path.display(), bucket, s3_key);
         
         // Get file metadata
@@ -153,80 +511,459 @@ impl TransferManager {
         let file_name = local_path.file_name()
             .map(|s| s.to_string_lossy().to_string())
             .unwrap_or_else(|| s3_key.to_string());
+        
+        // Large files are uploaded in parts, which also lifts the 5 GB single upload limit
+        if total_size > MULTIPART_UPLOAD_THRESHOLD {
+            return self.upload_file_multipart(local_path, bucket, s3_key, &file_name, total_size, progress_callback).await;
+        }
             
-        // Create a ByteStream from the file
-        let body = ByteStream::from_path(local_path).await?;
+        // Stream the file as the request body, reporting progress as each chunk is sent.
+        // The body is rebuilt from the start of the file if the request is retried.
+        let progress_callback: Option<SharedProgressCallback> = progress_callback.map(Arc::from);
+        let path = local_path.to_path_buf();
+        let cancel = self.cancel.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let watch = Arc::new(UploadWatch::default());
+        let body_watch = watch.clone();
+        let body = SdkBody::retryable(move || {
+            let progress = progress_callback.clone().map(|callback| (file_name.clone(), total_size, callback));
+            let stream = upload_body_stream(path.clone(), progress, cancel.clone(), rate_limiter.clone(), body_watch.clone());
+            SdkBody::from(hyper::Body::wrap_stream(stream))
+        });
         
         // Upload the file
-        let resp = self.client.put_object()
+        let request = self.client.put_object()
             .bucket(bucket)
             .key(s3_key)
-            .body(body)
-            .send()
-            .await?;
+            .set_server_side_encryption(self.server_side_encryption.clone())
+            .set_ssekms_key_id(self.sse_kms_key_id.clone())
+            .set_storage_class(self.storage_class.clone())
+            .set_acl(self.acl.clone())
+            .content_type(content_type_for(local_path, &self.content_type_overrides))
+            .content_length(total_size as i64)
+            .body(ByteStream::new(body))
+            .send();
+        let resp = watch_upload(request, &watch, self.stall_timeout).await?
+            .map_err(|e| S3ErrorHelper::error(format!("Failed to upload {}", s3_key), &e))?;
             
         debug!("Upload complete: {:?}", resp);
         
-        // Call the progress callback with 100% completion
-        if let Some(callback) = progress_callback {
-            callback(TransferProgress {
-                file_name,
-                bytes_transferred: total_size,
-                total_bytes: total_size,
-                percentage: 100.0,
+        // Lifecycle rules that apply to the new object are reported straight away
+        Ok(resp.expiration().and_then(ObjectExpiration::parse))
+    }
+    
+    /// Create a zero-byte "folder" marker object, the way the S3 console shows an empty folder.
+    /// A '/' is added to the prefix if it doesn't end with one.
+    pub async fn create_folder_marker(&self, bucket: &str, prefix: &str) -> Result<()> {
+        let key = if prefix.ends_with('/') { prefix.to_string() } else { format!("{}/", prefix) };
+        debug!("Creating folder marker s3://{}/{}", bucket, key);
+        
+        self.client.put_object()
+            .bucket(bucket)
+            .key(&key)
+            .set_server_side_encryption(self.server_side_encryption.clone())
+            .set_ssekms_key_id(self.sse_kms_key_id.clone())
+            .set_storage_class(self.storage_class.clone())
+            .set_acl(self.acl.clone())
+            .content_length(0)
+            .body(ByteStream::from_static(b""))
+            .send()
+            .await
+            .map_err(|e| S3ErrorHelper::error(format!("Failed to create folder {}", key), &e))?;
+        
+        Ok(())
+    }
+    
+    /// Upload a large file with a multipart upload, reporting progress as each part completes.
+    /// The upload is recorded in a `.s3part` sidecar after each part, so if a part fails the
+    /// next upload of the file to the same key carries on from the parts S3 already has. It is
+    /// aborted if it is cancelled or can't be completed, so its parts don't incur storage charges.
+    ///
+    /// Keeping the parts of a failed upload to resume it means they are billed until the upload
+    /// is resumed or discarded, rather than aborted at once. So that they aren't kept forever,
+    /// an upload that can't be resumed is aborted when the file is next uploaded to the key,
+    /// as are any other unfinished uploads to the key whose sidecar is lost, and
+    /// `discard_interrupted_upload` aborts one outright.
+    async fn upload_file_multipart(
+        &self,
+        local_path: &Path,
+        bucket: &str,
+        s3_key: &str,
+        file_name: &str,
+        total_size: u64,
+        progress_callback: Option<Box<dyn Fn(TransferProgress) + Send + Sync>>,
+    ) -> Result<Option<ObjectExpiration>> {
+        debug!("Using multipart upload for {} ({} bytes)", local_path.display(), total_size);
+        
+        let modified = modified_secs(local_path);
+        let mut state = match self.resumable_upload(local_path, bucket, s3_key, total_size, modified).await {
+            Some(state) => {
+                debug!("Resuming upload of {} with {} parts already uploaded", s3_key, state.completed_parts.len());
+                state
+            },
+            None => {
+                self.abort_stale_uploads(bucket, s3_key).await;
+                
+                let create_resp = self.client.create_multipart_upload()
+                    .bucket(bucket)
+                    .key(s3_key)
+                    .set_server_side_encryption(self.server_side_encryption.clone())
+                    .set_ssekms_key_id(self.sse_kms_key_id.clone())
+                    .set_storage_class(self.storage_class.clone())
+                    .set_acl(self.acl.clone())
+                    .content_type(content_type_for(local_path, &self.content_type_overrides))
+                    .send()
+                    .await
+                    .map_err(|e| S3ErrorHelper::error("Failed to start multipart upload", &e))?;
+                    
+                let upload_id = create_resp.upload_id()
+                    .ok_or_else(|| anyhow!("No upload ID returned for multipart upload"))?
+                    .to_string();
+                
+                PartialUpload {
+                    bucket: bucket.to_string(),
+                    key: s3_key.to_string(),
+                    upload_id,
+                    part_size: self.upload_part_size,
+                    total_size,
+                    modified,
+                    completed_parts: Vec::new(),
+                }
+            }
+        };
+        if let Err(e) = partial::save(local_path, &state) {
+            debug!("Could not write sidecar for {}: {}", local_path.display(), e);
+        }
+        let upload_id = state.upload_id.clone();
+        
+        let mut file = match tokio::fs::File::open(local_path).await {
+            Ok(file) => file,
+            Err(e) => {
+                self.discard_partial_upload(local_path, bucket, s3_key, &upload_id).await;
+                return Err(anyhow!("Failed to read {}: {}", local_path.display(), e));
+            }
+        };
+        
+        for (index, (start, end)) in copy_part_ranges(total_size, self.upload_part_size).into_iter().enumerate() {
+            let part_number = index as i32 + 1;
+            
+            if state.completed_parts.iter().any(|(number, _)| *number == part_number) {
+                continue;
+            }
+            
+            if self.is_cancelled() {
+                self.discard_partial_upload(local_path, bucket, s3_key, &upload_id).await;
+                return Err(anyhow!(CANCELLED_MESSAGE));
+            }
+            
+            let mut part = vec![0u8; (end - start + 1) as usize];
+            let read = match file.seek(std::io::SeekFrom::Start(start)).await {
+                Ok(_) => file.read_exact(&mut part).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = read {
+                self.discard_partial_upload(local_path, bucket, s3_key, &upload_id).await;
+                return Err(anyhow!("Failed to read part {} of {}: {}", part_number, local_path.display(), e));
+            }
+            
+            // The part is sent in chunks, each held back as needed by the rate limiter
+            let part_size = part.len();
+            let part = Arc::new(part);
+            let rate_limiter = self.rate_limiter.clone();
+            let watch = Arc::new(UploadWatch::default());
+            let body_watch = watch.clone();
+            let body = SdkBody::retryable(move || {
+                SdkBody::from(hyper::Body::wrap_stream(part_body_stream(part.clone(), rate_limiter.clone(), body_watch.clone())))
             });
+            
+            let request = self.client.upload_part()
+                .bucket(bucket)
+                .key(s3_key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .content_length(part_size as i64)
+                .body(ByteStream::new(body))
+                .send();
+                
+            // The parts uploaded so far are kept for the next attempt
+            let e_tag = match watch_upload(request, &watch, self.stall_timeout).await {
+                Ok(Ok(resp)) => resp.e_tag().unwrap_or_default().to_string(),
+                Ok(Err(e)) => {
+                    let detailed_error = S3ErrorHelper::extract_error_details(&e);
+                    error!("Failed to upload part {} of {}: {}", part_number, s3_key, detailed_error);
+                    return Err(S3ErrorHelper::error(format!("Failed to upload part {} of {}", part_number, s3_key), &e));
+                }
+                Err(e) => {
+                    error!("Failed to upload part {} of {}: {}", part_number, s3_key, e);
+                    return Err(e);
+                }
+            };
+            
+            state.completed_parts.push((part_number, e_tag));
+            if let Err(e) = partial::save(local_path, &state) {
+                debug!("Could not write sidecar for {}: {}", local_path.display(), e);
+            }
+            
+            if let Some(callback) = &progress_callback {
+                callback(TransferProgress::new(file_name, end + 1, total_size));
+            }
         }
         
-        Ok(())
+        let mut completed_parts = state.completed_parts.clone();
+        completed_parts.sort_by_key(|(number, _)| *number);
+        let completed_upload = CompletedMultipartUpload::builder()
+            .set_parts(Some(completed_parts.into_iter()
+                .map(|(part_number, e_tag)| CompletedPart::builder()
+                    .e_tag(e_tag)
+                    .part_number(part_number)
+                    .build())
+                .collect()))
+            .build();
+            
+        let resp = match self.client.complete_multipart_upload()
+            .bucket(bucket)
+            .key(s3_key)
+            .upload_id(&upload_id)
+            .multipart_upload(completed_upload)
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                self.discard_partial_upload(local_path, bucket, s3_key, &upload_id).await;
+                return Err(S3ErrorHelper::error(format!("Failed to complete multipart upload of {}", s3_key), &e));
+            }
+        };
+        
+        // The upload is complete, so the sidecar is no longer needed
+        partial::remove(local_path);
+        
+        debug!("Multipart upload complete");
+        Ok(resp.expiration().and_then(ObjectExpiration::parse))
+    }
+    
+    /// List every object under a prefix, descending into all sub-prefixes
+    pub async fn list_prefix(&self, bucket: &str, prefix: &str) -> Result<Vec<ObjectSummary>> {
+        let objects = self.list_objects(bucket, prefix, true).await?;
+        
+        Ok(objects.into_iter()
+            .map(|object| ObjectSummary {
+                e_tag: Some(object.e_tag).filter(|e_tag| !e_tag.is_empty()),
+                key: object.key,
+                size: object.size,
+                last_modified: object.last_modified_secs,
+            })
+            .collect())
+    }
+    
+    /// Download an object into memory instead of a file. Compressed content is decoded,
+    /// so callers get the object as it was before it was compressed.
+    pub async fn download_to_bytes(&self, bucket: &str, s3_key: &str) -> Result<Vec<u8>> {
+        debug!("Downloading s3://{}/{} into memory", bucket, s3_key);
+        
+        let resp = self.client.get_object()
+            .bucket(bucket)
+            .key(s3_key)
+            .send()
+            .await
+            .map_err(|e| S3ErrorHelper::error(format!("Failed to get {}", s3_key), &e))?;
+        
+        let encoding = resp.content_encoding().and_then(ContentEncoding::parse);
+        let bytes = resp.body.collect().await
+            .map_err(|e| anyhow!("Failed to read {}: {}", s3_key, e))?
+            .into_bytes();
+        
+        match encoding {
+            Some(encoding) => {
+                let mut decoder = Decoder::new(encoding);
+                let mut decoded = decoder.decode(&bytes)?;
+                decoded.extend(decoder.finish()?);
+                Ok(decoded)
+            },
+            None => Ok(bytes.to_vec()),
+        }
     }
     
-    /// Download a file from S3
+    /// Download a file from S3, returning the path written
     pub async fn download_file(
         &self,
         bucket: &str,
         s3_key: &str,
         local_path: &Path,
         progress_callback: Option<Box<dyn Fn(TransferProgress) + Send + Sync>>,
-    ) -> Result<()> {
-        debug!("Downloading s3://{}/{} to {}", bucket, s3_key, local_path.display());
+    ) -> Result<PathBuf> {
+        self.download_file_version(bucket, s3_key, None, local_path, progress_callback).await
+    }
+    
+    /// Download a specific version of a file from S3, or the current version if none is given.
+    /// Returns the path written, which drops a `.gz` style extension if the content was decompressed.
+    pub async fn download_file_version(
+        &self,
+        bucket: &str,
+        s3_key: &str,
+        version_id: Option<&str>,
+        local_path: &Path,
+        progress_callback: Option<Box<dyn Fn(TransferProgress) + Send + Sync>>,
+    ) -> Result<PathBuf> {
+        debug!("Downloading s3://{}/{} ({}) to {}", bucket, s3_key, version_id.unwrap_or("current"), local_path.display());
         
         // Create parent directories if they don't exist
         if let Some(parent) = local_path.parent() {
             fs::create_dir_all(parent)?;
         }
         
-        // Get the object
-        let resp = self.client.get_object()
-            .bucket(bucket)
-            .key(s3_key)
-            .send()
-            .await?;
-            
-        // Get the total size
-        let total_size = resp.content_length() as u64;
+        // An earlier download of the object that was interrupted carries on where it stopped,
+        // as long as the object hasn't changed since. Decompressed content can't be resumed.
+        let mut offset = 0;
+        let mut resumed = None;
+        if let Some(state) = Self::resumable_download(bucket, s3_key, local_path) {
+            match self.get_object_from(bucket, s3_key, version_id, state.bytes_written).await {
+                Ok(resp) => {
+                    let decoded = self.decompress && resp.content_encoding().and_then(ContentEncoding::parse).is_some();
+                    if resp.e_tag() == state.e_tag.as_deref() && !decoded {
+                        debug!("Resuming download of {} at byte {}", s3_key, state.bytes_written);
+                        offset = state.bytes_written;
+                        resumed = Some(resp);
+                    } else {
+                        debug!("{} changed since its download was interrupted, starting over", s3_key);
+                    }
+                },
+                Err(e) => debug!("Could not resume download of {}, starting over: {}", s3_key, e),
+            }
+        }
+        
+        // Get the object, unless the rest of it is already being fetched
+        let resp = match resumed {
+            Some(resp) => resp,
+            None => self.get_object_from(bucket, s3_key, version_id, 0).await?,
+        };
+        
+        // Get the total size. A resumed response only holds the bytes after the offset.
+        let total_size = offset + size_from_sdk(resp.content_length());
+        
+        // Compressed content is decoded while it is written, if enabled
+        let encoding = resp.content_encoding()
+            .and_then(ContentEncoding::parse)
+            .filter(|_| self.decompress);
+        let mut decoder = encoding.map(Decoder::new);
+        let local_path = match encoding {
+            Some(encoding) => {
+                let path = decoded_path(local_path, encoding);
+                debug!("Decompressing {:?} content of {} into {}", encoding, s3_key, path.display());
+                path
+            },
+            None => local_path.to_path_buf(),
+        };
+        let local_path = local_path.as_path();
+        
+        // The content is hashed as it arrives if its ETag is an MD5. ETags of objects
+        // encrypted with SSE-KMS or SSE-C aren't, whatever they look like.
+        let plain_encryption = matches!(resp.server_side_encryption(), None | Some(ServerSideEncryption::Aes256))
+            && resp.sse_customer_algorithm().is_none();
+        let expected_md5 = resp.e_tag()
+            .and_then(etag_md5)
+            .filter(|_| self.verify_downloads && plain_encryption)
+            .map(|md5| md5.to_ascii_lowercase());
+        let mut hasher = match &expected_md5 {
+            // A resumed download picks up hashing from the bytes already in the file
+            Some(_) if offset > 0 => md5_of_prefix(local_path, offset)
+                .map_err(|e| debug!("Could not hash the start of {}, skipping the MD5 check: {}", local_path.display(), e))
+                .ok(),
+            Some(_) => Some(Md5::new()),
+            None => None,
+        };
+        
+        // Record the download in a sidecar so an interrupted transfer can be resumed.
+        // bytes_written is only a lower bound on what reached the file.
+        let mut partial_state = PartialDownload {
+            bucket: bucket.to_string(),
+            key: s3_key.to_string(),
+            total_size,
+            e_tag: resp.e_tag().map(|s| s.to_string()),
+            bytes_written: offset,
+        };
+        if let Err(e) = partial::save(local_path, &partial_state) {
+            debug!("Could not write sidecar for {}: {}", local_path.display(), e);
+        }
         
         // Get the file name for progress reporting
         let file_name = local_path.file_name()
             .map(|s| s.to_string_lossy().to_string())
             .unwrap_or_else(|| s3_key.to_string());
             
-        // Create a file to write to
-        let mut file = tokio::fs::File::create(local_path).await?;
+        // Create a file to write to, or reopen the partial file to append the rest of the object
+        let mut file = if offset > 0 {
+            let mut file = tokio::fs::OpenOptions::new().write(true).open(local_path).await
+                .map_err(|e| anyhow!(describe_write_error(&e, local_path)))?;
+            // Bytes past the recorded offset may not all have reached the disk, so they are fetched again
+            file.set_len(offset).await?;
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+            file
+        } else {
+            tokio::fs::File::create(local_path).await
+                .map_err(|e| anyhow!(describe_write_error(&e, local_path)))?
+        };
         
         // Stream the body to the file
         let mut stream = resp.body.into_async_read();
-        let mut bytes_read = 0;
+        let mut bytes_read = offset;
         let mut buffer = vec![0u8; 8192]; // 8KB buffer
         
         loop {
-            let n = tokio::io::AsyncReadExt::read(&mut stream, &mut buffer).await?;
+            // A connection can stall mid-stream without any error, so fail the
+            // download if no data arrives within the stall timeout
+            let read = tokio::io::AsyncReadExt::read(&mut stream, &mut buffer);
+            let n = match tokio::time::timeout(self.stall_timeout, read).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    if decoder.is_some() {
+                        drop(file);
+                        Self::discard_partial_download(local_path);
+                    } else {
+                        Self::keep_partial_download(file, local_path, &mut partial_state, bytes_read).await;
+                    }
+                    return Err(S3Error::transient(format!("stalled: no data received for {} seconds", self.stall_timeout.as_secs())).into());
+                }
+            };
             if n == 0 {
                 break;
             }
             
-            file.write_all(&buffer[..n]).await?;
+            self.throttle(n as u64).await;
+            
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&buffer[..n]);
+            }
+            
+            let decoded;
+            let chunk = match decoder.as_mut() {
+                Some(decoder) => match decoder.decode(&buffer[..n]) {
+                    Ok(bytes) => {
+                        decoded = bytes;
+                        &decoded[..]
+                    },
+                    Err(e) => {
+                        drop(file);
+                        Self::discard_partial_download(local_path);
+                        return Err(anyhow!("Failed to decompress {}: {}", s3_key, e));
+                    }
+                },
+                None => &buffer[..n],
+            };
+            
+            if let Err(e) = file.write_all(chunk).await {
+                return Err(Self::write_failure(file, local_path, e));
+            }
             bytes_read += n as u64;
             
+            if bytes_read - partial_state.bytes_written >= SIDECAR_SAVE_INTERVAL {
+                if let Err(e) = file.flush().await {
+                    return Err(Self::write_failure(file, local_path, e));
+                }
+                partial_state.bytes_written = bytes_read;
+                let _ = partial::save(local_path, &partial_state);
+            }
+            
             // Call the progress callback
             if let Some(callback) = &progress_callback {
                 let percentage = if total_size > 0 {
@@ -242,17 +979,250 @@ impl TransferManager {
                     percentage,
                 });
             }
+            
+            // A cancelled download is kept to be resumed, like a stalled one
+            if self.is_cancelled() {
+                if decoder.is_some() {
+                    drop(file);
+                    Self::discard_partial_download(local_path);
+                } else {
+                    Self::keep_partial_download(file, local_path, &mut partial_state, bytes_read).await;
+                }
+                return Err(anyhow!(CANCELLED_MESSAGE));
+            }
         }
         
-        // Flush and close the file
-        file.flush().await?;
-        
-        debug!("Download complete");
-        Ok(())
-    }
+        // A stream that ends early looks like a complete download, so the bytes received
+        // are checked against the object. The MD5 is of the content as stored, before decompression.
+        if self.verify_downloads {
+            let mismatch = if bytes_read != total_size {
+                Some(format!("received {} of {} bytes", bytes_read, total_size))
+            } else {
+                match (hasher, &expected_md5) {
+                    (Some(hasher), Some(expected)) => {
+                        let actual = format!("{:x}", hasher.finalize());
+                        (actual != *expected).then(|| format!("MD5 {} does not match ETag {}", actual, expected))
+                    },
+                    _ => None,
+                }
+            };
+            
+            if let Some(mismatch) = mismatch {
+                drop(file);
+                Self::discard_partial_download(local_path);
+                error!("Verification of {} failed: {}", s3_key, mismatch);
+                return Err(anyhow!("Download of {} failed verification: {}", s3_key, mismatch));
+            }
+        }
+        
+        // Write out whatever the decoder still holds; a truncated stream fails here
+        if let Some(decoder) = decoder {
+            match decoder.finish() {
+                Ok(remaining) => {
+                    if let Err(e) = file.write_all(&remaining).await {
+                        return Err(Self::write_failure(file, local_path, e));
+                    }
+                },
+                Err(e) => {
+                    drop(file);
+                    Self::discard_partial_download(local_path);
+                    return Err(anyhow!("Failed to decompress {}: {}", s3_key, e));
+                }
+            }
+        }
+        
+        // Flush and close the file
+        if let Err(e) = file.flush().await {
+            return Err(Self::write_failure(file, local_path, e));
+        }
+        
+        // The download is complete, so the sidecar is no longer needed
+        partial::remove(local_path);
+        
+        debug!("Download complete");
+        Ok(local_path.to_path_buf())
+    }
+    
+    /// Discard a download whose local file could not be written, describing why.
+    /// A truncated file would look like a complete one, so it is removed.
+    fn write_failure(file: tokio::fs::File, local_path: &Path, error: std::io::Error) -> anyhow::Error {
+        drop(file);
+        Self::discard_partial_download(local_path);
+        
+        let message = describe_write_error(&error, local_path);
+        error!("{}", message);
+        anyhow!(message)
+    }
+    
+    /// Get an object, or only the bytes from `offset` on to resume a download
+    async fn get_object_from(&self, bucket: &str, s3_key: &str, version_id: Option<&str>, offset: u64) -> Result<GetObjectOutput> {
+        self.client.get_object()
+            .bucket(bucket)
+            .key(s3_key)
+            .set_version_id(version_id.map(|id| id.to_string()))
+            .set_range((offset > 0).then(|| format!("bytes={}-", offset)))
+            .send()
+            .await
+            .map_err(|e| S3ErrorHelper::error(format!("Failed to get {}", s3_key), &e))
+    }
+    
+    /// Get the state of an interrupted download of an object to a local path, if there is
+    /// a partial file to resume. The offset is capped at the length of the partial file.
+    fn resumable_download(bucket: &str, s3_key: &str, local_path: &Path) -> Option<PartialDownload> {
+        let state: PartialDownload = partial::load(local_path)?;
+        if state.bucket != bucket || state.key != s3_key || state.e_tag.is_none() {
+            return None;
+        }
+        
+        let file_len = fs::metadata(local_path).ok()?.len();
+        let offset = state.bytes_written.min(file_len);
+        if offset == 0 || offset >= state.total_size {
+            return None;
+        }
+        
+        Some(PartialDownload {
+            bytes_written: offset,
+            ..state
+        })
+    }
+    
+    /// Keep the partial file of a download that stopped before the end, recording how
+    /// much of it reached the disk so the next download of the object can resume from there
+    async fn keep_partial_download(mut file: tokio::fs::File, local_path: &Path, state: &mut PartialDownload, bytes_written: u64) {
+        if let Err(e) = file.flush().await {
+            debug!("Could not flush partial download {}: {}", local_path.display(), e);
+            return;
+        }
+        
+        state.bytes_written = bytes_written;
+        if let Err(e) = partial::save(local_path, state) {
+            debug!("Could not write sidecar for {}: {}", local_path.display(), e);
+        }
+    }
+    
+    /// Get the state of an interrupted multipart upload of a local file to an object, keeping
+    /// only the parts S3 still has. An upload of an earlier version of the file, or one with
+    /// another part size, is aborted instead, since its parts can't be reused.
+    async fn resumable_upload(&self, local_path: &Path, bucket: &str, s3_key: &str, total_size: u64, modified: Option<u64>) -> Option<PartialUpload> {
+        let state: PartialUpload = partial::load(local_path)?;
+        if state.bucket != bucket || state.key != s3_key {
+            debug!("{} is now uploaded to another key, discarding its interrupted upload", local_path.display());
+            self.discard_partial_upload(local_path, &state.bucket, &state.key, &state.upload_id).await;
+            return None;
+        }
+        
+        if state.part_size != self.upload_part_size || state.total_size != total_size || state.modified != modified {
+            debug!("{} changed since its upload was interrupted, starting again", local_path.display());
+            self.discard_partial_upload(local_path, bucket, s3_key, &state.upload_id).await;
+            return None;
+        }
+        
+        // The upload may have been aborted or completed elsewhere since
+        let uploaded = match self.list_uploaded_parts(bucket, s3_key, &state.upload_id).await {
+            Ok(uploaded) => uploaded,
+            Err(e) => {
+                debug!("Can't resume upload of {}: {}", s3_key, e);
+                partial::remove(local_path);
+                return None;
+            }
+        };
+        
+        Some(PartialUpload {
+            completed_parts: resumable_parts(uploaded, total_size, state.part_size),
+            ..state
+        })
+    }
+    
+    /// List the number, size and ETag of each part uploaded so far in a multipart upload
+    async fn list_uploaded_parts(&self, bucket: &str, s3_key: &str, upload_id: &str) -> Result<Vec<(i32, u64, String)>> {
+        let mut parts = Vec::new();
+        let mut part_number_marker = None;
+        
+        loop {
+            let resp = self.client.list_parts()
+                .bucket(bucket)
+                .key(s3_key)
+                .upload_id(upload_id)
+                .set_part_number_marker(part_number_marker)
+                .send()
+                .await
+                .map_err(|e| S3ErrorHelper::error(format!("Failed to list parts of {}", s3_key), &e))?;
+                
+            for part in resp.parts().unwrap_or_default() {
+                parts.push((part.part_number(), size_from_sdk(part.size()), part.e_tag().unwrap_or_default().to_string()));
+            }
+            
+            if !resp.is_truncated() {
+                break;
+            }
+            part_number_marker = resp.next_part_number_marker().map(|s| s.to_string());
+        }
+        
+        Ok(parts)
+    }
+    
+    /// Abort every unfinished multipart upload to a key. Called before starting a new upload
+    /// to the key, this cleans up uploads whose sidecar was lost or unreadable, which could
+    /// otherwise never be resumed or aborted.
+    async fn abort_stale_uploads(&self, bucket: &str, s3_key: &str) {
+        let mut key_marker = None;
+        let mut upload_id_marker = None;
+        
+        loop {
+            let resp = match self.client.list_multipart_uploads()
+                .bucket(bucket)
+                .prefix(s3_key)
+                .set_key_marker(key_marker)
+                .set_upload_id_marker(upload_id_marker)
+                .send()
+                .await
+            {
+                Ok(resp) => resp,
+                Err(e) => {
+                    debug!("Can't list unfinished uploads to {}: {}", s3_key, S3ErrorHelper::extract_error_details(&e));
+                    return;
+                }
+            };
+            
+            for upload in resp.uploads().unwrap_or_default() {
+                if let (Some(s3_key), Some(upload_id)) = (upload.key().filter(|key| *key == s3_key), upload.upload_id()) {
+                    debug!("Aborting stale upload {} to {}", upload_id, s3_key);
+                    self.abort_multipart_upload(bucket, s3_key, upload_id).await;
+                }
+            }
+            
+            if !resp.is_truncated() {
+                break;
+            }
+            key_marker = resp.next_key_marker().map(|s| s.to_string());
+            upload_id_marker = resp.next_upload_id_marker().map(|s| s.to_string());
+        }
+    }
+    
+    /// Abort the interrupted multipart upload of a local file recorded in its sidecar, if
+    /// there is one, so its parts stop incurring storage charges
+    pub async fn discard_interrupted_upload(&self, local_path: &Path) {
+        if let Some(state) = partial::load::<PartialUpload>(local_path) {
+            debug!("Discarding interrupted upload of {}", local_path.display());
+            self.discard_partial_upload(local_path, &state.bucket, &state.key, &state.upload_id).await;
+        }
+    }
+    
+    /// Abort a multipart upload that can't be resumed and remove its sidecar
+    async fn discard_partial_upload(&self, local_path: &Path, bucket: &str, s3_key: &str, upload_id: &str) {
+        self.abort_multipart_upload(bucket, s3_key, upload_id).await;
+        partial::remove(local_path);
+    }
+    
+    /// Remove the partial file and sidecar of a download that can't be completed
+    fn discard_partial_download(local_path: &Path) {
+        if let Err(e) = fs::remove_file(local_path) {
+            error!("Failed to remove partial download {}: {}", local_path.display(), e);
+        }
+        partial::remove(local_path);
+    }
     
     /// Delete an object from S3
-    #[allow(dead_code)] // Will be used in future implementations
     pub async fn delete_object(&self, bucket: &str, s3_key: &str) -> Result<()> {
         debug!("Deleting object: s3://{}/{}", bucket, s3_key);
         
@@ -260,14 +1230,165 @@ impl TransferManager {
             .bucket(bucket)
             .key(s3_key)
             .send()
-            .await?;
+            .await
+            .map_err(|e| S3ErrorHelper::error("S3 service error", &e))?;
             
         debug!("Object deleted");
         Ok(())
     }
     
+    /// Delete a set of objects, batching the keys into DeleteObjects requests.
+    /// Returns the keys that could not be deleted along with the error for each.
+    pub async fn delete_objects(&self, bucket: &str, keys: &[String]) -> Vec<(String, String)> {
+        debug!("Deleting {} objects from bucket {}", keys.len(), bucket);
+        
+        let mut failed = Vec::new();
+        
+        for batch in keys.chunks(DELETE_BATCH_SIZE) {
+            let objects = batch.iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect();
+            let delete = Delete::builder()
+                .set_objects(Some(objects))
+                .quiet(true)
+                .build();
+            
+            match self.client.delete_objects()
+                .bucket(bucket)
+                .delete(delete)
+                .send()
+                .await
+            {
+                // In quiet mode only the keys that failed are listed
+                Ok(resp) => {
+                    for error in resp.errors().unwrap_or_default() {
+                        failed.push((
+                            error.key().unwrap_or_default().to_string(),
+                            format!(
+                                "{}: {}",
+                                error.code().unwrap_or("Unknown"),
+                                error.message().unwrap_or("No error message")
+                            ),
+                        ));
+                    }
+                },
+                Err(e) => {
+                    let detailed_error = S3ErrorHelper::extract_error_details(&e);
+                    error!("Failed to delete objects in bucket {}: {}", bucket, detailed_error);
+                    failed.extend(batch.iter().map(|key| (key.clone(), detailed_error.clone())));
+                }
+            }
+        }
+        
+        failed
+    }
+    
+    /// Check whether versioning is enabled or suspended on a bucket. Either way, deleting
+    /// an object only adds a delete marker and keeps its earlier versions.
+    pub async fn is_bucket_versioned(&self, bucket: &str) -> Result<bool> {
+        let resp = self.client.get_bucket_versioning()
+            .bucket(bucket)
+            .send()
+            .await?;
+        
+        Ok(resp.status().is_some())
+    }
+    
+    /// List every version and delete marker of a single object, newest first
+    pub async fn list_object_versions(&self, bucket: &str, s3_key: &str) -> Result<Vec<ObjectVersionInfo>> {
+        debug!("Listing versions of s3://{}/{}", bucket, s3_key);
+        
+        let mut versions = Vec::new();
+        let mut key_marker: Option<String> = None;
+        let mut version_id_marker: Option<String> = None;
+        
+        loop {
+            let resp = match self.client.list_object_versions()
+                .bucket(bucket)
+                .prefix(s3_key)
+                .set_key_marker(key_marker.clone())
+                .set_version_id_marker(version_id_marker.clone())
+                .send()
+                .await
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    let detailed_error = S3ErrorHelper::extract_error_details(&e);
+                    error!("Failed to list versions of {}: {}", s3_key, detailed_error);
+                    return Err(S3ErrorHelper::error(format!("Failed to list versions of {}", s3_key), &e));
+                }
+            };
+            
+            // The prefix also matches longer keys, so keep only exact matches
+            for version in resp.versions().unwrap_or_default() {
+                if version.key() == Some(s3_key) {
+                    versions.push(ObjectVersionInfo {
+                        version_id: version.version_id().unwrap_or("null").to_string(),
+                        is_latest: version.is_latest(),
+                        is_delete_marker: false,
+                        size: size_from_sdk(version.size()),
+                        last_modified: version.last_modified().map(|dt| dt.secs()).unwrap_or_default(),
+                    });
+                }
+            }
+            
+            for marker in resp.delete_markers().unwrap_or_default() {
+                if marker.key() == Some(s3_key) {
+                    versions.push(ObjectVersionInfo {
+                        version_id: marker.version_id().unwrap_or("null").to_string(),
+                        is_latest: marker.is_latest(),
+                        is_delete_marker: true,
+                        size: 0,
+                        last_modified: marker.last_modified().map(|dt| dt.secs()).unwrap_or_default(),
+                    });
+                }
+            }
+            
+            if resp.is_truncated() {
+                key_marker = resp.next_key_marker().map(|s| s.to_string());
+                version_id_marker = resp.next_version_id_marker().map(|s| s.to_string());
+            } else {
+                break;
+            }
+        }
+        
+        versions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified).then(b.is_latest.cmp(&a.is_latest)));
+        Ok(versions)
+    }
+    
+    /// Permanently delete a single version or delete marker of an object
+    pub async fn delete_object_version(&self, bucket: &str, s3_key: &str, version_id: &str) -> Result<()> {
+        debug!("Deleting version {} of s3://{}/{}", version_id, bucket, s3_key);
+        
+        self.client.delete_object()
+            .bucket(bucket)
+            .key(s3_key)
+            .version_id(version_id)
+            .send()
+            .await
+            .map_err(|e| S3ErrorHelper::error(format!("Failed to delete version {} of {}", version_id, s3_key), &e))?;
+            
+        debug!("Version deleted");
+        Ok(())
+    }
+    
+    /// Make a previous version the current version by copying it over the object
+    pub async fn restore_object_version(&self, bucket: &str, s3_key: &str, version_id: &str) -> Result<()> {
+        debug!("Restoring version {} of s3://{}/{}", version_id, bucket, s3_key);
+        
+        self.client.copy_object()
+            .copy_source(encode_copy_source(bucket, s3_key, Some(version_id)))
+            .bucket(bucket)
+            .key(s3_key)
+            .send()
+            .await
+            .map_err(|e| S3ErrorHelper::error(format!("Failed to restore version {} of {}", version_id, s3_key), &e))?;
+            
+        debug!("Version restored");
+        Ok(())
+    }
+    
     /// Check if an object exists in S3
-    #[allow(dead_code)] // Will be used in future implementations
     pub async fn object_exists(&self, bucket: &str, s3_key: &str) -> Result<bool> {
         debug!("Checking if object exists: s3://{}/{}", bucket, s3_key);
         
@@ -278,43 +1399,855 @@ impl TransferManager {
             .await
         {
             Ok(_) => Ok(true),
-            Err(e) => {
-                if e.to_string().contains("404") {
-                    Ok(false)
-                } else {
-                    Err(anyhow!("Failed to check if object exists: {}", e))
-                }
-            }
+            Err(SdkError::ServiceError(e)) if e.err().is_not_found() => Ok(false),
+            Err(e) => Err(S3ErrorHelper::error("Failed to check if object exists", &e)),
         }
     }
     
-    /// Get the size of an object in S3
-    #[allow(dead_code)] // Will be used in future implementations
-    pub async fn get_object_size(&self, bucket: &str, s3_key: &str) -> Result<u64> {
-        debug!("Getting size of object: s3://{}/{}", bucket, s3_key);
+    /// Get when a lifecycle rule will expire an object, if any rule applies to it
+    pub async fn get_object_expiration(&self, bucket: &str, s3_key: &str) -> Result<Option<ObjectExpiration>> {
+        debug!("Getting expiration of object: s3://{}/{}", bucket, s3_key);
         
         let resp = self.client.head_object()
             .bucket(bucket)
             .key(s3_key)
             .send()
-            .await?;
+            .await
+            .map_err(|e| S3ErrorHelper::error("Failed to get object expiration", &e))?;
             
-        Ok(resp.content_length() as u64)
+        Ok(resp.expiration().and_then(ObjectExpiration::parse))
+    }
+    
+    /// Create a presigned URL that lets anyone holding it download an object until `ttl` has passed
+    pub async fn presign_get(&self, bucket: &str, s3_key: &str, ttl: Duration) -> Result<String> {
+        debug!("Presigning download of s3://{}/{} for {:?}", bucket, s3_key, ttl);
+        
+        let config = PresigningConfig::expires_in(ttl)
+            .map_err(|e| anyhow!("Invalid share link expiry: {}", e))?;
+        
+        let request = self.client.get_object()
+            .bucket(bucket)
+            .key(s3_key)
+            .presigned(config)
+            .await
+            .map_err(|e| S3ErrorHelper::error("Failed to create share link", &e))?;
+        
+        Ok(request.uri().to_string())
+    }
+    
+    /// Create a presigned URL that lets anyone holding it upload to a key until `ttl` has passed
+    pub async fn presign_put(&self, bucket: &str, s3_key: &str, ttl: Duration) -> Result<String> {
+        debug!("Presigning upload to s3://{}/{} for {:?}", bucket, s3_key, ttl);
+        
+        let config = PresigningConfig::expires_in(ttl)
+            .map_err(|e| anyhow!("Invalid upload link expiry: {}", e))?;
+        
+        let request = self.client.put_object()
+            .bucket(bucket)
+            .key(s3_key)
+            .presigned(config)
+            .await
+            .map_err(|e| S3ErrorHelper::error("Failed to create upload link", &e))?;
+        
+        Ok(request.uri().to_string())
+    }
+    
+    /// Get an object ready to be copied. The tags of objects too large for a single CopyObject
+    /// are fetched too, as a multipart copy has to set them itself. This client must be for the
+    /// source bucket's region.
+    pub async fn copy_source(&self, bucket: &str, s3_key: &str) -> Result<CopySource> {
+        let metadata = self.head_object(bucket, s3_key).await?;
+        
+        let tags = if metadata.size > MAX_SINGLE_COPY_SIZE {
+            let resp = self.client.get_object_tagging()
+                .bucket(bucket)
+                .key(s3_key)
+                .send()
+                .await
+                .map_err(|e| S3ErrorHelper::error(format!("Failed to get the tags of {}", s3_key), &e))?;
+            
+            resp.tag_set().unwrap_or_default().iter()
+                .map(|tag| (tag.key().unwrap_or_default().to_string(), tag.value().unwrap_or_default().to_string()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        Ok(CopySource {
+            bucket: bucket.to_string(),
+            key: s3_key.to_string(),
+            metadata,
+            tags,
+        })
     }
     
     /// Get the ETag of an object in S3
     #[allow(dead_code)] // Will be used in future implementations
     pub async fn get_object_etag(&self, bucket: &str, s3_key: &str) -> Result<String> {
-        debug!("Getting ETag of object: s3://{}/{}", bucket, s3_key);
+        self.head_object(bucket, s3_key).await?
+            .e_tag
+            .ok_or_else(|| anyhow!("ETag not found for object"))
+    }
+    
+    /// Get an object's properties and user metadata with a single HEAD request
+    pub async fn head_object(&self, bucket: &str, s3_key: &str) -> Result<ObjectMetadata> {
+        debug!("Getting metadata of object: s3://{}/{}", bucket, s3_key);
         
         let resp = self.client.head_object()
             .bucket(bucket)
             .key(s3_key)
             .send()
-            .await?;
+            .await
+            .map_err(|e| S3ErrorHelper::error("Failed to get object metadata", &e))?;
+        
+        Ok(ObjectMetadata {
+            size: size_from_sdk(resp.content_length()),
+            content_type: resp.content_type().map(|content_type| content_type.to_string()),
+            content_encoding: resp.content_encoding().map(|content_encoding| content_encoding.to_string()),
+            cache_control: resp.cache_control().map(|cache_control| cache_control.to_string()),
+            content_disposition: resp.content_disposition().map(|content_disposition| content_disposition.to_string()),
+            content_language: resp.content_language().map(|content_language| content_language.to_string()),
+            e_tag: resp.e_tag().map(|e_tag| e_tag.trim_matches('"').to_string()),
+            storage_class: resp.storage_class()
+                .map_or_else(|| "STANDARD".to_string(), |class| class.as_str().to_string()),
+            last_modified: resp.last_modified().map(|dt| dt.secs()).unwrap_or(0),
+            encryption: resp.server_side_encryption().map(|encryption| encryption.as_str().to_string()),
+            kms_key_id: resp.ssekms_key_id().map(|key_id| key_id.to_string()),
+            version_id: resp.version_id().map(|version_id| version_id.to_string()),
+            user_metadata: resp.metadata()
+                .map(|metadata| metadata.iter().map(|(name, value)| (name.clone(), value.clone())).collect())
+                .unwrap_or_default(),
+        })
+    }
+    
+    /// Copy an object, using a multipart copy for objects larger than 5 GB. This client must
+    /// be for the destination bucket's region, while the source comes from the source's.
+    pub async fn copy_object(&self, source: &CopySource, dst_bucket: &str, dst_key: &str) -> Result<()> {
+        let (src_bucket, src_key) = (source.bucket.as_str(), source.key.as_str());
+        debug!("Copying s3://{}/{} to s3://{}/{}", src_bucket, src_key, dst_bucket, dst_key);
+        
+        if source.metadata.size > MAX_SINGLE_COPY_SIZE {
+            return self.copy_object_multipart(source, dst_bucket, dst_key).await;
+        }
+        
+        match self.client.copy_object()
+            .copy_source(encode_copy_source(src_bucket, src_key, None))
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .send()
+            .await
+        {
+            Ok(_) => {
+                debug!("Copy complete");
+                Ok(())
+            },
+            Err(e) => {
+                let detailed_error = S3ErrorHelper::extract_error_details(&e);
+                error!("Failed to copy {}: {}", src_key, detailed_error);
+                Err(S3ErrorHelper::error(format!("Failed to copy {}", src_key), &e))
+            }
+        }
+    }
+    
+    /// Copy a large object with a multipart upload, copying each byte range with UploadPartCopy.
+    /// The upload is given the source's headers, metadata, storage class, encryption and tags,
+    /// which a single CopyObject would have kept.
+    async fn copy_object_multipart(&self, source: &CopySource, dst_bucket: &str, dst_key: &str) -> Result<()> {
+        let (src_bucket, src_key) = (source.bucket.as_str(), source.key.as_str());
+        let size = source.metadata.size;
+        debug!("Using multipart copy for {} ({} bytes)", src_key, size);
+        
+        let create_resp = self.client.create_multipart_upload()
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .set_content_type(source.metadata.content_type.clone())
+            .set_content_encoding(source.metadata.content_encoding.clone())
+            .set_cache_control(source.metadata.cache_control.clone())
+            .set_content_disposition(source.metadata.content_disposition.clone())
+            .set_content_language(source.metadata.content_language.clone())
+            .set_metadata(source.user_metadata())
+            .storage_class(source.storage_class())
+            .set_server_side_encryption(source.server_side_encryption())
+            .set_ssekms_key_id(source.metadata.kms_key_id.clone())
+            .set_tagging(source.tagging())
+            .send()
+            .await
+            .map_err(|e| S3ErrorHelper::error("Failed to start multipart copy", &e))?;
             
-        resp.e_tag()
-            .map(|s| s.trim_matches('"').to_string())
-            .ok_or_else(|| anyhow!("ETag not found for object"))
+        let upload_id = create_resp.upload_id()
+            .ok_or_else(|| anyhow!("No upload ID returned for multipart copy"))?
+            .to_string();
+        
+        let copy_source = encode_copy_source(src_bucket, src_key, None);
+        let mut completed_parts = Vec::new();
+        
+        for (index, (start, end)) in copy_part_ranges(size, COPY_PART_SIZE).into_iter().enumerate() {
+            let part_number = index as i32 + 1;
+            
+            let part_resp = self.client.upload_part_copy()
+                .bucket(dst_bucket)
+                .key(dst_key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .copy_source(&copy_source)
+                .copy_source_range(format!("bytes={}-{}", start, end))
+                .send()
+                .await;
+                
+            let e_tag = match part_resp {
+                Ok(resp) => resp.copy_part_result().and_then(|r| r.e_tag()).map(|s| s.to_string()),
+                Err(e) => {
+                    let detailed_error = S3ErrorHelper::extract_error_details(&e);
+                    error!("Failed to copy part {} of {}: {}", part_number, src_key, detailed_error);
+                    self.abort_multipart_upload(dst_bucket, dst_key, &upload_id).await;
+                    return Err(S3ErrorHelper::error(format!("Failed to copy part {} of {}", part_number, src_key), &e));
+                }
+            };
+            
+            completed_parts.push(
+                CompletedPart::builder()
+                    .set_e_tag(e_tag)
+                    .part_number(part_number)
+                    .build()
+            );
+        }
+        
+        let completed_upload = CompletedMultipartUpload::builder()
+            .set_parts(Some(completed_parts))
+            .build();
+            
+        if let Err(e) = self.client.complete_multipart_upload()
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .upload_id(&upload_id)
+            .multipart_upload(completed_upload)
+            .send()
+            .await
+        {
+            self.abort_multipart_upload(dst_bucket, dst_key, &upload_id).await;
+            return Err(S3ErrorHelper::error(format!("Failed to complete multipart copy of {}", src_key), &e));
+        }
+        
+        debug!("Multipart copy complete");
+        Ok(())
+    }
+    
+    /// Abort a multipart upload so its parts don't incur storage charges
+    async fn abort_multipart_upload(&self, bucket: &str, s3_key: &str, upload_id: &str) {
+        if let Err(e) = self.client.abort_multipart_upload()
+            .bucket(bucket)
+            .key(s3_key)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            error!("Failed to abort multipart upload {} for {}: {}", upload_id, s3_key, e);
+        }
+    }
+}
+
+/// How far an upload request body has got, for the stall watchdog
+#[derive(Debug, Default)]
+struct UploadWatch {
+    /// Bytes handed to the request
+    sent: AtomicU64,
+    /// Chunks waiting on the rate limiter, which doesn't count as stalling
+    throttled: AtomicUsize,
+}
+
+impl UploadWatch {
+    /// Wait until the rate limiter, if any, lets a chunk through, then count it as sent
+    async fn send_chunk(&self, rate_limiter: Option<&RateLimiter>, bytes: u64) {
+        if let Some(rate_limiter) = rate_limiter {
+            let _throttled = ThrottledChunk::new(&self.throttled);
+            rate_limiter.acquire(bytes).await;
+        }
+        
+        self.sent.fetch_add(bytes, Ordering::SeqCst);
+    }
+    
+    fn is_throttled(&self) -> bool {
+        self.throttled.load(Ordering::SeqCst) > 0
+    }
+}
+
+/// Counts a chunk as waiting on the rate limiter until it is dropped, so a body dropped
+/// mid-wait by a failed request doesn't leave the count behind
+struct ThrottledChunk<'a>(&'a AtomicUsize);
+
+impl<'a> ThrottledChunk<'a> {
+    fn new(throttled: &'a AtomicUsize) -> Self {
+        throttled.fetch_add(1, Ordering::SeqCst);
+        Self(throttled)
+    }
+}
+
+impl Drop for ThrottledChunk<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Read a file in chunks for an upload request body. If a file name, total size and callback
+/// are given, the running byte count is reported as each chunk is handed to the request.
+/// Chunks are held back as needed to stay within the rate limiter's limit and are counted
+/// in `watch` as they are sent.
+fn upload_body_stream(
+    local_path: PathBuf,
+    progress: Option<(String, u64, SharedProgressCallback)>,
+    cancel: Option<Arc<AtomicBool>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    watch: Arc<UploadWatch>,
+) -> impl Stream<Item = std::io::Result<Vec<u8>>> {
+    futures::stream::try_unfold((None, 0u64), move |(file, bytes_sent): (Option<tokio::fs::File>, u64)| {
+        let local_path = local_path.clone();
+        let progress = progress.clone();
+        let cancel = cancel.clone();
+        let rate_limiter = rate_limiter.clone();
+        let watch = watch.clone();
+        
+        async move {
+            // Failing the body stream stops the request
+            if cancel.map_or(false, |cancel| cancel.load(Ordering::SeqCst)) {
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, CANCELLED_MESSAGE));
+            }
+            
+            // The file is opened on the first read, so building the body doesn't touch the disk
+            let mut file = match file {
+                Some(file) => file,
+                None => tokio::fs::File::open(&local_path).await?,
+            };
+            
+            let mut buffer = vec![0u8; UPLOAD_CHUNK_SIZE];
+            let n = file.read(&mut buffer).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buffer.truncate(n);
+            
+            watch.send_chunk(rate_limiter.as_deref(), n as u64).await;
+            
+            let bytes_sent = bytes_sent + n as u64;
+            if let Some((file_name, total_size, callback)) = &progress {
+                callback(TransferProgress::new(file_name, bytes_sent, *total_size));
+            }
+            
+            Ok(Some((buffer, (Some(file), bytes_sent))))
+        }
+    })
+}
+
+/// Split a part held in memory into chunks for an upload request body, held back and
+/// counted like those of `upload_body_stream`
+fn part_body_stream(
+    part: Arc<Vec<u8>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    watch: Arc<UploadWatch>,
+) -> impl Stream<Item = std::io::Result<Vec<u8>>> {
+    let starts = (0..part.len()).step_by(UPLOAD_CHUNK_SIZE);
+    futures::stream::iter(starts).then(move |start| {
+        let part = part.clone();
+        let rate_limiter = rate_limiter.clone();
+        let watch = watch.clone();
+        
+        async move {
+            let end = (start + UPLOAD_CHUNK_SIZE).min(part.len());
+            watch.send_chunk(rate_limiter.as_deref(), (end - start) as u64).await;
+            Ok(part[start..end].to_vec())
+        }
+    })
+}
+
+/// Wait for an upload request, failing it as stalled if its body goes the stall timeout
+/// without handing over any data. Time the body spends waiting on the rate limiter doesn't count.
+async fn watch_upload<T>(request: impl Future<Output = T>, watch: &UploadWatch, stall_timeout: Duration) -> Result<T> {
+    tokio::pin!(request);
+    let mut last_sent = watch.sent.load(Ordering::SeqCst);
+    
+    loop {
+        if let Ok(result) = tokio::time::timeout(stall_timeout, &mut request).await {
+            return Ok(result);
+        }
+        
+        let now_sent = watch.sent.load(Ordering::SeqCst);
+        if now_sent == last_sent && !watch.is_throttled() {
+            return Err(S3Error::transient(format!("stalled: no data sent for {} seconds", stall_timeout.as_secs())).into());
+        }
+        last_sent = now_sent;
+    }
+}
+
+/// Format an object's last modified time as `YYYY-MM-DD HH:MM:SS` in UTC
+pub fn format_last_modified(last_modified: &DateTime) -> String {
+    Utc.timestamp_opt(last_modified.secs(), 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Convert a size or content length reported by the SDK to a byte count. The SDK
+/// reports a missing value as a negative number, which counts as 0.
+pub fn size_from_sdk(size: i64) -> u64 {
+    size.max(0) as u64
+}
+
+/// Read the objects and sub-prefixes in a page of a ListObjectsV2 response, with the
+/// continuation token for the next page, or None on the last page
+fn listing_page(resp: &ListObjectsV2Output) -> (Vec<crate::ui::bucket_view::S3Object>, Option<String>) {
+    let mut objects = Vec::new();
+    
+    // Process common prefixes (directories), which only a delimited listing returns
+    if let Some(prefixes) = resp.common_prefixes() {
+        for prefix in prefixes {
+            if let Some(prefix_str) = prefix.prefix() {
+                // Remove the trailing slash
+                let key = prefix_str.trim_end_matches('/').to_string();
+                
+                objects.push(crate::ui::bucket_view::S3Object {
+                    key,
+                    size: 0,
+                    last_modified: String::new(),
+                    is_directory: true,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    
+    // Process objects (files)
+    if let Some(contents) = resp.contents() {
+        for object in contents {
+            let key = object.key().unwrap_or_default().to_string();
+            let size = size_from_sdk(object.size());
+            let last_modified = object.last_modified()
+                .map(format_last_modified)
+                .unwrap_or_default();
+            let last_modified_secs = object.last_modified().map(|dt| dt.secs()).unwrap_or(0);
+            
+            let owner = object.owner()
+                .and_then(|owner| owner.display_name().or_else(|| owner.id()))
+                .unwrap_or_default()
+                .to_string();
+            
+            objects.push(crate::ui::bucket_view::S3Object {
+                key,
+                size,
+                last_modified,
+                last_modified_secs,
+                is_directory: false,
+                storage_class: object.storage_class().map(|class| class.as_str().to_string()).unwrap_or_default(),
+                e_tag: object.e_tag().unwrap_or_default().trim_matches('"').to_string(),
+                owner,
+            });
+        }
+    }
+    
+    // Check if there are more objects
+    let next_token = if resp.is_truncated() {
+        resp.next_continuation_token().map(|s| s.to_string())
+    } else {
+        None
+    };
+    
+    (objects, next_token)
+}
+
+/// Split an object of the given size into inclusive byte ranges for a multipart copy or upload,
+/// growing the part size if needed to stay within the part count limit
+fn copy_part_ranges(total_size: u64, part_size: u64) -> Vec<(u64, u64)> {
+    let part_size = part_size.max((total_size + MAX_PARTS - 1) / MAX_PARTS);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    
+    while start < total_size {
+        let end = (start + part_size).min(total_size) - 1;
+        ranges.push((start, end));
+        start = end + 1;
+    }
+    
+    ranges
+}
+
+/// Pick the uploaded parts of an interrupted multipart upload that can be kept: those whose
+/// size matches the part's byte range, so a part cut short is uploaded again
+fn resumable_parts(uploaded: Vec<(i32, u64, String)>, total_size: u64, part_size: u64) -> Vec<(i32, String)> {
+    let ranges = copy_part_ranges(total_size, part_size);
+    
+    uploaded.into_iter()
+        .filter(|(part_number, size, e_tag)| {
+            let range = usize::try_from(*part_number - 1).ok().and_then(|index| ranges.get(index));
+            !e_tag.is_empty() && range.map_or(false, |(start, end)| end - start + 1 == *size)
+        })
+        .map(|(part_number, _, e_tag)| (part_number, e_tag))
+        .collect()
+}
+
+/// Last modified time of a file in seconds since the Unix epoch, if it can be read
+fn modified_secs(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok()?
+        .modified().ok()?
+        .duration_since(std::time::UNIX_EPOCH).ok()
+        .map(|modified| modified.as_secs())
+}
+
+/// Build a URL-encoded `bucket/key` copy source, of a given version if there is one
+fn encode_copy_source(bucket: &str, key: &str, version_id: Option<&str>) -> String {
+    let mut encoded = format!("{}/", bucket);
+    percent_encode(&mut encoded, key, true);
+    
+    if let Some(version_id) = version_id {
+        encoded.push_str("?versionId=");
+        percent_encode(&mut encoded, version_id, false);
+    }
+    
+    encoded
+}
+
+/// Append a value to a URL, percent-encoding everything but unreserved characters and,
+/// if `keep_slashes` is set, slashes
+fn percent_encode(encoded: &mut String, value: &str, keep_slashes: bool) {
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slashes => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_expiration() {
+        let expiration = ObjectExpiration::parse(r#"expiry-date="Fri, 23 Dec 2012 00:00:00 GMT", rule-id="delete-old-logs""#);
+        assert_eq!(expiration, Some(ObjectExpiration {
+            expiry_date: "Fri, 23 Dec 2012 00:00:00 GMT".to_string(),
+            rule_id: "delete-old-logs".to_string(),
+        }));
+        
+        let expiration = ObjectExpiration::parse(r#"expiry-date="Fri, 23 Dec 2012 00:00:00 GMT""#).unwrap();
+        assert_eq!(expiration.rule_id, "");
+        
+        assert_eq!(ObjectExpiration::parse(""), None);
+        assert_eq!(ObjectExpiration::parse("NotImplemented"), None);
+    }
+    
+    #[test]
+    fn test_describe_write_error() {
+        let path = Path::new("downloads/a.bin");
+        
+        #[cfg(unix)]
+        assert_eq!(
+            describe_write_error(&std::io::Error::from_raw_os_error(28), path),
+            format!("Disk full while writing {}", path.display())
+        );
+        
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(describe_write_error(&denied, path), format!("Permission denied writing {}", path.display()));
+        
+        let other = std::io::Error::new(std::io::ErrorKind::Other, "broken");
+        assert_eq!(describe_write_error(&other, path), format!("Failed to write {}: broken", path.display()));
+    }
+    
+    #[test]
+    fn test_resumable_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().join("video.mp4");
+        let state = |bytes_written: u64| PartialDownload {
+            bucket: "bucket".to_string(),
+            key: "videos/video.mp4".to_string(),
+            total_size: 1000,
+            e_tag: Some("\"abc\"".to_string()),
+            bytes_written,
+        };
+        
+        // No sidecar, nothing to resume
+        fs::write(&local_path, vec![0u8; 600]).unwrap();
+        assert!(TransferManager::resumable_download("bucket", "videos/video.mp4", &local_path).is_none());
+        
+        partial::save(&local_path, &state(400)).unwrap();
+        let resumed = TransferManager::resumable_download("bucket", "videos/video.mp4", &local_path).unwrap();
+        assert_eq!(resumed.bytes_written, 400);
+        
+        // Another object, or a sidecar ahead of the file, isn't trusted past the file's length
+        assert!(TransferManager::resumable_download("bucket", "videos/other.mp4", &local_path).is_none());
+        partial::save(&local_path, &state(800)).unwrap();
+        assert_eq!(TransferManager::resumable_download("bucket", "videos/video.mp4", &local_path).unwrap().bytes_written, 600);
+        
+        // A finished download has nothing left to fetch
+        fs::write(&local_path, vec![0u8; 1000]).unwrap();
+        partial::save(&local_path, &state(1000)).unwrap();
+        assert!(TransferManager::resumable_download("bucket", "videos/video.mp4", &local_path).is_none());
+    }
+    
+    #[test]
+    fn test_content_type_for() {
+        let no_overrides = HashMap::new();
+        assert_eq!(content_type_for(Path::new("data/config.json"), &no_overrides), "application/json");
+        assert_eq!(content_type_for(Path::new("photo.JPG"), &no_overrides), "image/jpeg");
+        assert_eq!(content_type_for(Path::new("archive.unknownext"), &no_overrides), "application/octet-stream");
+        assert_eq!(content_type_for(Path::new("Makefile"), &no_overrides), "application/octet-stream");
+        
+        let mut overrides = HashMap::new();
+        overrides.insert("log".to_string(), "text/plain".to_string());
+        assert_eq!(content_type_for(Path::new("logs/app.LOG"), &overrides), "text/plain");
+        assert_eq!(content_type_for(Path::new("config.json"), &overrides), "application/json");
+    }
+    
+    #[test]
+    fn test_etag_md5() {
+        assert_eq!(etag_md5("\"9e107d9d372bb6826bd81d3542a419d6\""), Some("9e107d9d372bb6826bd81d3542a419d6"));
+        assert_eq!(etag_md5("9E107D9D372BB6826BD81D3542A419D6"), Some("9E107D9D372BB6826BD81D3542A419D6"));
+        assert_eq!(etag_md5("\"9e107d9d372bb6826bd81d3542a419d6-3\""), None);
+        assert_eq!(etag_md5("not-an-md5"), None);
+        assert_eq!(etag_md5(""), None);
+    }
+    
+    #[test]
+    fn test_copy_part_ranges() {
+        let ranges = copy_part_ranges(1000, 300);
+        assert_eq!(ranges, vec![(0, 299), (300, 599), (600, 899), (900, 999)]);
+        
+        // Exact multiple of the part size
+        assert_eq!(copy_part_ranges(600, 300), vec![(0, 299), (300, 599)]);
+        
+        // Part size grows to keep the part count within the limit
+        let ranges = copy_part_ranges(MAX_PARTS * 10, 1);
+        assert_eq!(ranges.len() as u64, MAX_PARTS);
+        assert_eq!(ranges.last(), Some(&(MAX_PARTS * 10 - 10, MAX_PARTS * 10 - 1)));
+        
+        assert!(copy_part_ranges(0, 300).is_empty());
+    }
+    
+    #[test]
+    fn test_listing_page() {
+        use aws_sdk_s3::types::{CommonPrefix, Object};
+        
+        // A delimited listing groups deeper keys into sub-prefixes
+        let resp = ListObjectsV2Output::builder()
+            .common_prefixes(CommonPrefix::builder().prefix("photos/2023/").build())
+            .contents(Object::builder().key("photos/a.jpg").size(100).e_tag("\"abc\"").build())
+            .is_truncated(true)
+            .next_continuation_token("next")
+            .build();
+        let (objects, next_token) = listing_page(&resp);
+        assert_eq!(next_token.as_deref(), Some("next"));
+        assert_eq!(objects.len(), 2);
+        assert!(objects[0].is_directory);
+        assert_eq!(objects[0].key, "photos/2023");
+        assert_eq!((objects[1].key.as_str(), objects[1].size, objects[1].e_tag.as_str()), ("photos/a.jpg", 100, "abc"));
+        
+        // A recursive listing has every key under the prefix and no sub-prefixes
+        let resp = ListObjectsV2Output::builder()
+            .contents(Object::builder().key("photos/2023/b.jpg").size(200).build())
+            .contents(Object::builder().key("photos/2023/empty/").size(0).build())
+            .is_truncated(false)
+            .build();
+        let (objects, next_token) = listing_page(&resp);
+        assert!(next_token.is_none());
+        let keys: Vec<_> = objects.iter().map(|object| object.key.as_str()).collect();
+        assert_eq!(keys, vec!["photos/2023/b.jpg", "photos/2023/empty/"]);
+        assert!(objects.iter().all(|object| !object.is_directory));
+    }
+    
+    #[test]
+    fn test_resumable_parts() {
+        let uploaded = vec![
+            (1, 300, "\"etag1\"".to_string()),
+            (2, 300, "\"etag2\"".to_string()),
+            // Cut short, so it has to be sent again
+            (3, 120, "\"etag3\"".to_string()),
+            (4, 100, "\"etag4\"".to_string()),
+            (5, 100, "\"etag5\"".to_string()),
+        ];
+        
+        assert_eq!(
+            resumable_parts(uploaded, 1000, 300),
+            vec![(1, "\"etag1\"".to_string()), (2, "\"etag2\"".to_string()), (4, "\"etag4\"".to_string())]
+        );
+        assert!(resumable_parts(vec![(0, 300, "\"etag\"".to_string())], 1000, 300).is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_upload_body_stream_reports_progress() {
+        use futures::TryStreamExt;
+        use std::sync::Mutex;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload.bin");
+        let contents = vec![7u8; UPLOAD_CHUNK_SIZE * 2 + 100];
+        fs::write(&path, &contents).unwrap();
+        
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let reported_clone = reported.clone();
+        let callback: SharedProgressCallback = Arc::new(move |progress: TransferProgress| {
+            reported_clone.lock().unwrap().push(progress.bytes_transferred);
+        });
+        
+        let chunks: Vec<Vec<u8>> = upload_body_stream(path, Some(("upload.bin".to_string(), contents.len() as u64, callback)), None, None, Arc::default())
+            .try_collect()
+            .await
+            .unwrap();
+        
+        assert_eq!(chunks.concat(), contents);
+        
+        // One running total per chunk, ending at the file size
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), chunks.len());
+        assert!(reported.len() >= 3);
+        assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(reported.last(), Some(&(contents.len() as u64)));
+    }
+    
+    #[test]
+    fn test_format_last_modified() {
+        assert_eq!(format_last_modified(&DateTime::from_secs(1747860201)), "2025-05-21 20:43:21");
+        assert_eq!(format_last_modified(&DateTime::from_secs(0)), "1970-01-01 00:00:00");
+        
+        // Sub-second precision is dropped
+        assert_eq!(format_last_modified(&DateTime::from_fractional_secs(1747860201, 0.75)), "2025-05-21 20:43:21");
+    }
+    
+    #[test]
+    fn test_size_from_sdk() {
+        assert_eq!(size_from_sdk(1024), 1024);
+        assert_eq!(size_from_sdk(0), 0);
+        
+        // An unset size doesn't wrap around to a huge number
+        assert_eq!(size_from_sdk(-1), 0);
+        assert_eq!(size_from_sdk(i64::MIN), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_upload_body_stream_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload.bin");
+        fs::write(&path, vec![7u8; UPLOAD_CHUNK_SIZE * 4]).unwrap();
+        
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut stream = Box::pin(upload_body_stream(path, None, Some(cancel.clone()), None, Arc::default()));
+        assert!(stream.next().await.unwrap().is_ok());
+        
+        // The next chunk fails once the token is set
+        cancel.store(true, Ordering::SeqCst);
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), CANCELLED_MESSAGE);
+    }
+    
+    #[tokio::test]
+    async fn test_part_body_stream() {
+        use futures::TryStreamExt;
+        
+        let part: Vec<u8> = (0..UPLOAD_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        let watch = Arc::new(UploadWatch::default());
+        
+        let chunks: Vec<Vec<u8>> = part_body_stream(Arc::new(part.clone()), None, watch.clone())
+            .try_collect()
+            .await
+            .unwrap();
+        
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), part);
+        assert_eq!(watch.sent.load(Ordering::SeqCst), part.len() as u64);
+    }
+    
+    #[tokio::test]
+    async fn test_watch_upload() {
+        let timeout = Duration::from_millis(50);
+        
+        // A request that keeps sending data may take longer than the timeout
+        let watch = Arc::new(UploadWatch::default());
+        let sending = watch.clone();
+        let request = async move {
+            for _ in 0..4 {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                sending.send_chunk(None, 1).await;
+            }
+            "done"
+        };
+        assert_eq!(watch_upload(request, &watch, timeout).await.unwrap(), "done");
+        
+        // One that sends nothing is failed as a transient error
+        let idle = UploadWatch::default();
+        let error = watch_upload(std::future::pending::<()>(), &idle, timeout).await.unwrap_err();
+        assert!(error.to_string().contains("stalled"));
+        assert!(S3ErrorHelper::is_retryable(&error));
+    }
+    
+    #[tokio::test]
+    async fn test_watch_upload_throttled() {
+        use futures::TryStreamExt;
+        
+        // Two parts sharing a limiter that lets 10 chunks through a second: once the first
+        // 10 are through, each waits longer than the stall timeout for its next chunk
+        let rate_limiter = Arc::new(RateLimiter::new(UPLOAD_CHUNK_SIZE as u64 * 10));
+        let timeout = Duration::from_millis(50);
+        
+        let upload = |rate_limiter: Arc<RateLimiter>| async move {
+            let part = Arc::new(vec![7u8; UPLOAD_CHUNK_SIZE * 6]);
+            let watch = Arc::new(UploadWatch::default());
+            let request = part_body_stream(part, Some(rate_limiter), watch.clone())
+                .try_collect::<Vec<Vec<u8>>>();
+            watch_upload(request, &watch, timeout).await
+        };
+        
+        let (first, second) = tokio::join!(upload(rate_limiter.clone()), upload(rate_limiter));
+        assert_eq!(first.unwrap().unwrap().len(), 6);
+        assert_eq!(second.unwrap().unwrap().len(), 6);
+    }
+    
+    #[test]
+    fn test_copy_source_headers() {
+        let mut source = CopySource {
+            bucket: "bucket".to_string(),
+            key: "large.bin".to_string(),
+            metadata: ObjectMetadata {
+                size: MAX_SINGLE_COPY_SIZE + 1,
+                content_type: Some("video/mp4".to_string()),
+                content_encoding: None,
+                cache_control: Some("max-age=3600".to_string()),
+                content_disposition: None,
+                content_language: None,
+                e_tag: None,
+                storage_class: "GLACIER_IR".to_string(),
+                last_modified: 0,
+                encryption: Some("aws:kms".to_string()),
+                kms_key_id: Some("arn:aws:kms:us-east-1:123456789012:key/abc".to_string()),
+                version_id: None,
+                user_metadata: BTreeMap::from([("camera".to_string(), "a7".to_string())]),
+            },
+            tags: vec![
+                ("project".to_string(), "film night".to_string()),
+                ("owner".to_string(), "a&b=c".to_string()),
+            ],
+        };
+        
+        assert_eq!(source.storage_class(), StorageClass::GlacierIr);
+        assert_eq!(source.server_side_encryption(), Some(ServerSideEncryption::AwsKms));
+        assert_eq!(source.user_metadata(), Some(HashMap::from([("camera".to_string(), "a7".to_string())])));
+        assert_eq!(source.tagging().as_deref(), Some("project=film%20night&owner=a%26b%3Dc"));
+        
+        // Nothing is set for an unencrypted object without metadata or tags
+        source.metadata.storage_class = "STANDARD".to_string();
+        source.metadata.encryption = None;
+        source.metadata.user_metadata.clear();
+        source.tags.clear();
+        assert_eq!(source.storage_class(), StorageClass::Standard);
+        assert_eq!(source.server_side_encryption(), None);
+        assert_eq!(source.user_metadata(), None);
+        assert_eq!(source.tagging(), None);
+    }
+    
+    #[test]
+    fn test_encode_copy_source() {
+        assert_eq!(encode_copy_source("bucket", "a/b/file.txt", None), "bucket/a/b/file.txt");
+        assert_eq!(encode_copy_source("bucket", "my file+1.txt", None), "bucket/my%20file%2B1.txt");
+        assert_eq!(encode_copy_source("bucket", "é", None), "bucket/%C3%A9");
+        assert_eq!(
+            encode_copy_source("bucket", "a b", Some("3/L4kqtJl+0=")),
+            "bucket/a%20b?versionId=3%2FL4kqtJl%2B0%3D"
+        );
     }
 }
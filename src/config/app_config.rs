@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::settings::AppSettings;
+
+/// Name of the unified configuration file
+const CONFIG_FILE: &str = "config.json";
+
+/// Settings file written by earlier versions, migrated into the unified file on first load
+const LEGACY_SETTINGS_FILE: &str = "settings.json";
+
+/// Everything the application persists, loaded once at startup and saved as a whole:
+/// settings (including folder to bucket/prefix mappings), the file filter and the access
+/// key ID. Only the secret key is kept outside it, in the system keyring.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub settings: AppSettings,
+    /// AWS access key ID, stored when credentials are saved. The secret key is in the keyring.
+    pub access_key_id: String,
+    pub filter: FilterConfig,
+}
+
+/// File filter as entered in the filter view
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterConfig {
+    /// Include patterns, one per line
+    pub include_patterns: String,
+    /// Exclude patterns, one per line
+    pub exclude_patterns: String,
+    /// Included extensions, comma-separated
+    pub include_extensions: String,
+    /// Excluded extensions, comma-separated
+    pub exclude_extensions: String,
+    /// Minimum file size, e.g. "10KB"
+    pub min_size: String,
+    /// Maximum file size, e.g. "1GB"
+    pub max_size: String,
+}
+
+impl FilterConfig {
+    /// Check whether no filter is configured
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl AppConfig {
+    /// Load the configuration from the config directory, falling back to the defaults
+    /// if it can't be read so the application can still start
+    pub fn load() -> Self {
+        match Self::config_dir().and_then(|dir| Self::load_from(&dir)) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Failed to load configuration, using defaults: {}", e);
+                Self::default()
+            }
+        }
+    }
+    
+    /// Load the configuration from a directory. Settings saved by earlier versions are
+    /// picked up if there is no unified file yet.
+    pub fn load_from(dir: &Path) -> Result<Self> {
+        let config_path = dir.join(CONFIG_FILE);
+        if config_path.exists() {
+            let config_str = fs::read_to_string(&config_path)?;
+            let config = serde_json::from_str(&config_str)
+                .map_err(|e| anyhow!("Invalid configuration file {}: {}", config_path.display(), e))?;
+            
+            info!("Loaded configuration from {}", config_path.display());
+            return Ok(config);
+        }
+        
+        let legacy_path = dir.join(LEGACY_SETTINGS_FILE);
+        if legacy_path.exists() {
+            let settings_str = fs::read_to_string(&legacy_path)?;
+            let settings = serde_json::from_str(&settings_str)
+                .map_err(|e| anyhow!("Invalid settings file {}: {}", legacy_path.display(), e))?;
+            
+            info!("Migrating settings from {}", legacy_path.display());
+            return Ok(Self {
+                settings,
+                ..Self::default()
+            });
+        }
+        
+        info!("No configuration found in {}, using defaults", dir.display());
+        Ok(Self::default())
+    }
+    
+    /// Save the configuration to the config directory
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::config_dir()?)
+    }
+    
+    /// Save the configuration to a directory. It is written to a temporary file first
+    /// so an interruption never leaves a truncated configuration behind.
+    pub fn save_to(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        
+        let config_path = dir.join(CONFIG_FILE);
+        let temp_path = dir.join(format!("{}.tmp", CONFIG_FILE));
+        
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, &config_path)?;
+        
+        info!("Saved configuration to {}", config_path.display());
+        Ok(())
+    }
+    
+    /// Get the directory holding the configuration
+    pub fn config_dir() -> Result<PathBuf> {
+        Ok(dirs::config_dir()
+            .ok_or_else(|| anyhow!("Could not determine config directory"))?
+            .join("s3sync"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::settings::SyncFolderConfig;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_config_round_trip() {
+        let dir = tempdir().unwrap();
+        
+        // Nothing saved yet
+        let config = AppConfig::load_from(dir.path()).unwrap();
+        assert!(config.access_key_id.is_empty());
+        assert!(config.filter.is_empty());
+        
+        let config = AppConfig {
+            settings: AppSettings {
+                aws_region: "eu-west-1".to_string(),
+                folders: vec![SyncFolderConfig {
+                    path: "/home/user/docs".to_string(),
+                    enabled: true,
+                    bucket: "docs".to_string(),
+                    prefix: Some("backup/".to_string()),
+                    storage_class: Some("GLACIER_IR".to_string()),
+                    acl: Some("public-read".to_string()),
+                }],
+                ..AppSettings::default()
+            },
+            access_key_id: "AKIAEXAMPLE".to_string(),
+            filter: FilterConfig {
+                exclude_patterns: "*.tmp".to_string(),
+                max_size: "1GB".to_string(),
+                ..FilterConfig::default()
+            },
+        };
+        config.save_to(dir.path()).unwrap();
+        
+        let loaded = AppConfig::load_from(dir.path()).unwrap();
+        assert_eq!(loaded.access_key_id, "AKIAEXAMPLE");
+        assert_eq!(loaded.settings.aws_region, "eu-west-1");
+        assert_eq!(loaded.settings.folders[0].bucket, "docs");
+        assert_eq!(loaded.settings.folders[0].prefix.as_deref(), Some("backup/"));
+        assert_eq!(loaded.settings.folders[0].storage_class.as_deref(), Some("GLACIER_IR"));
+        assert_eq!(loaded.settings.folders[0].acl.as_deref(), Some("public-read"));
+        assert_eq!(loaded.filter, config.filter);
+        assert!(!dir.path().join("config.json.tmp").exists());
+    }
+    
+    #[test]
+    fn test_legacy_settings_migration() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("settings.json"),
+            r#"{ "aws_region": "ap-southeast-2", "delete_enabled": true }"#,
+        ).unwrap();
+        
+        let config = AppConfig::load_from(dir.path()).unwrap();
+        assert_eq!(config.settings.aws_region, "ap-southeast-2");
+        assert!(config.settings.delete_enabled);
+        assert!(config.filter.is_empty());
+        
+        // Once saved, the unified file takes precedence
+        let mut config = config;
+        config.settings.aws_region = "us-west-2".to_string();
+        config.save_to(dir.path()).unwrap();
+        assert_eq!(AppConfig::load_from(dir.path()).unwrap().settings.aws_region, "us-west-2");
+    }
+    
+    #[test]
+    fn test_invalid_config() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("config.json"), "not json").unwrap();
+        
+        assert!(AppConfig::load_from(dir.path()).is_err());
+    }
+}
@@ -5,39 +5,28 @@ use log::{debug, info};
 /// Service name for keyring
 const SERVICE_NAME: &str = "s3sync";
 
-/// Credential manager for securely storing AWS credentials
+/// Credential manager for securely storing AWS credentials.
+///
+/// Only the secret key is written to the keyring; the access key ID and region are part of
+/// the application configuration. Earlier versions also stored those two in the keyring,
+/// so they can still be read from there to migrate them.
 #[derive(Default)]
 pub struct CredentialManager;
 
 impl CredentialManager {
-    /// Save AWS credentials to the system keyring
-    pub fn save_credentials(access_key: &str, secret_key: &str, region: &str) -> Result<()> {
-        // Save access key
-        let access_key_entry = Entry::new(SERVICE_NAME, "aws_access_key");
-        
-        if let Err(e) = access_key_entry.set_password(access_key) {
-            return Err(anyhow!("Failed to save access key: {}", e));
-        }
-        
-        // Save secret key
+    /// Save the AWS secret key to the system keyring
+    pub fn save_secret_key(secret_key: &str) -> Result<()> {
         let secret_key_entry = Entry::new(SERVICE_NAME, "aws_secret_key");
         
         if let Err(e) = secret_key_entry.set_password(secret_key) {
             return Err(anyhow!("Failed to save secret key: {}", e));
         }
         
-        // Save region
-        let region_entry = Entry::new(SERVICE_NAME, "aws_region");
-        
-        if let Err(e) = region_entry.set_password(region) {
-            return Err(anyhow!("Failed to save region: {}", e));
-        }
-        
-        info!("AWS credentials saved to keyring");
+        info!("AWS secret key saved to keyring");
         Ok(())
     }
     
-    /// Load AWS access key from the system keyring
+    /// Load AWS access key from the system keyring, where earlier versions stored it
     pub fn load_access_key() -> Result<String> {
         let entry = Entry::new(SERVICE_NAME, "aws_access_key");
         
@@ -63,7 +52,7 @@ impl CredentialManager {
         }
     }
     
-    /// Load AWS region from the system keyring
+    /// Load AWS region from the system keyring, where earlier versions stored it
     pub fn load_region() -> Result<String> {
         let entry = Entry::new(SERVICE_NAME, "aws_region");
         
@@ -77,33 +66,37 @@ impl CredentialManager {
     }
     
     /// Clear AWS credentials from the system keyring
-    #[allow(dead_code)] // Will be used in future implementations
     pub fn clear_credentials() -> Result<()> {
-        // Clear access key
-        let access_key_entry = Entry::new(SERVICE_NAME, "aws_access_key");
-        
-        let _ = access_key_entry.delete_password();
-        
         // Clear secret key
         let secret_key_entry = Entry::new(SERVICE_NAME, "aws_secret_key");
         
         let _ = secret_key_entry.delete_password();
         
+        Self::clear_legacy_entries();
+        
+        info!("AWS credentials cleared from keyring");
+        Ok(())
+    }
+    
+    /// Remove the access key and region stored by earlier versions, once they have been
+    /// migrated into the configuration
+    pub fn clear_legacy_entries() {
+        // Clear access key
+        let access_key_entry = Entry::new(SERVICE_NAME, "aws_access_key");
+        
+        let _ = access_key_entry.delete_password();
+        
         // Clear region
         let region_entry = Entry::new(SERVICE_NAME, "aws_region");
         
         let _ = region_entry.delete_password();
         
-        info!("AWS credentials cleared from keyring");
-        Ok(())
+        debug!("Legacy AWS credential entries removed from keyring");
     }
     
-    /// Test if credentials are available
+    /// Test if a secret key is available
     pub fn has_credentials() -> bool {
-        match (Self::load_access_key(), Self::load_secret_key()) {
-            (Ok(access_key), Ok(secret_key)) => !access_key.is_empty() && !secret_key.is_empty(),
-            _ => false,
-        }
+        matches!(Self::load_secret_key(), Ok(secret_key) if !secret_key.is_empty())
     }
 }
 
@@ -116,21 +109,16 @@ mod tests {
         // This test is commented out because it would modify the system keyring
         // Uncomment to test manually
         /*
-        let test_access_key = "test_access_key";
         let test_secret_key = "test_secret_key";
-        let test_region = "us-west-2";
         
         // Save credentials
-        CredentialManager::save_credentials(test_access_key, test_secret_key, test_region).unwrap();
+        CredentialManager::save_secret_key(test_secret_key).unwrap();
         
         // Load and verify
-        let loaded_access_key = CredentialManager::load_access_key().unwrap();
         let loaded_secret_key = CredentialManager::load_secret_key().unwrap();
-        let loaded_region = CredentialManager::load_region().unwrap();
         
-        assert_eq!(loaded_access_key, test_access_key);
         assert_eq!(loaded_secret_key, test_secret_key);
-        assert_eq!(loaded_region, test_region);
+        assert!(CredentialManager::has_credentials());
         
         // Clean up
         CredentialManager::clear_credentials().unwrap();
@@ -1,2 +1,5 @@
+pub mod app_config;
 pub mod settings;
 pub mod credentials;
+pub mod region_cache;
+pub mod transfer_history;
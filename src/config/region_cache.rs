@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use log::{debug, error};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::app_config::AppConfig;
+
+/// Name of the bucket region cache in the config directory
+const REGION_CACHE_FILE: &str = "bucket_regions.json";
+
+/// Regions of the buckets seen so far, kept in the config directory so a bucket's
+/// location only has to be looked up once rather than in every session
+#[derive(Debug)]
+pub struct RegionCache {
+    path: PathBuf,
+}
+
+impl RegionCache {
+    /// Open the cache in the config directory
+    pub fn open() -> Result<Self> {
+        Ok(Self::open_in(&AppConfig::config_dir()?))
+    }
+    
+    /// Open the cache in a directory
+    pub fn open_in(dir: &Path) -> Self {
+        Self {
+            path: dir.join(REGION_CACHE_FILE),
+        }
+    }
+    
+    /// Load the cached regions by bucket name. A missing or unreadable cache is empty,
+    /// since every region can be looked up again.
+    pub fn load(&self) -> HashMap<String, String> {
+        if !self.path.exists() {
+            return HashMap::new();
+        }
+        
+        match self.try_load() {
+            Ok(regions) => {
+                debug!("Loaded {} cached bucket regions", regions.len());
+                regions
+            },
+            Err(e) => {
+                error!("{}", e);
+                HashMap::new()
+            }
+        }
+    }
+    
+    fn try_load(&self) -> Result<HashMap<String, String>> {
+        let contents = fs::read_to_string(&self.path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid bucket region cache {}: {}", self.path.display(), e))
+    }
+    
+    /// Save the regions, replacing the cache. Failures are logged rather than returned:
+    /// the regions are still known for this session.
+    pub fn save(&self, regions: &HashMap<String, String>) {
+        if let Err(e) = self.try_save(regions) {
+            error!("Failed to save bucket regions to {}: {}", self.path.display(), e);
+        }
+    }
+    
+    fn try_save(&self, regions: &HashMap<String, String>) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        
+        // Written to a temporary file first so an interruption never leaves a truncated cache
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(regions)?)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let cache = RegionCache::open_in(dir.path());
+        assert!(cache.load().is_empty());
+        
+        let mut regions = HashMap::new();
+        regions.insert("photos".to_string(), "eu-west-1".to_string());
+        regions.insert("backups".to_string(), "us-east-2".to_string());
+        cache.save(&regions);
+        
+        let reopened = RegionCache::open_in(dir.path());
+        assert_eq!(reopened.load(), regions);
+        
+        // A corrupt cache is treated as empty
+        fs::write(dir.path().join(REGION_CACHE_FILE), "{not json").unwrap();
+        assert!(reopened.load().is_empty());
+    }
+}
@@ -2,15 +2,20 @@ use anyhow::{anyhow, Result};
 use log::info;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::ui::folder_list::SyncFolder;
+use crate::ui::folder_list::{SyncFolder, SyncStatus};
 
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
     /// AWS region
     pub aws_region: String,
+    /// Endpoint of an S3-compatible service such as MinIO, Wasabi or R2 (empty = AWS)
+    pub endpoint_url: String,
+    /// Named profile from the shared AWS files to take credentials from (empty = access keys)
+    pub aws_profile: String,
     /// Sync interval in minutes (0 = manual only)
     pub sync_interval: u32,
     /// Whether to delete files from S3 that were deleted locally
@@ -21,6 +26,84 @@ pub struct AppSettings {
     pub exclude_patterns: Vec<String>,
     /// Folders to sync
     pub folders: Vec<SyncFolderConfig>,
+    /// Look up each bucket's region instead of assuming the configured region (multi-region accounts)
+    pub lookup_bucket_regions: bool,
+    /// Seconds a file must go unmodified before sync uploads it
+    pub quiet_period_secs: u64,
+    /// Minutes before expiry at which temporary credentials are refreshed
+    pub credential_refresh_minutes: u32,
+    /// Show an OS notification summarizing each scheduled sync
+    pub sync_notifications: bool,
+    /// Seconds a transfer may go without moving any bytes before it is failed as stalled
+    pub stall_timeout_secs: u64,
+    /// Stop a batch transfer at the first failed file instead of continuing and reporting at the end
+    pub stop_on_error: bool,
+    /// Decompress objects stored with a gzip or deflate Content-Encoding when downloading them
+    pub decompress_downloads: bool,
+    /// Size in MB of the parts files larger than the multipart threshold are uploaded in
+    pub upload_part_size_mb: u64,
+    /// Number of files an upload or download batch transfers at the same time
+    pub max_concurrent_transfers: usize,
+    /// Compare same-sized files by content (MD5/ETag) when syncing, instead of by size alone
+    pub deep_compare: bool,
+    /// Server-side encryption for uploads: "AES256", "aws:kms", or empty for the bucket default
+    pub server_side_encryption: String,
+    /// KMS key ID or ARN for aws:kms encryption; empty for the AWS managed key
+    pub sse_kms_key_id: String,
+    /// Storage class for uploads, one of `UPLOAD_STORAGE_CLASSES`; empty for STANDARD
+    pub storage_class: String,
+    /// Times a transfer or listing that failed with a transient error (throttling, timeout, 5xx) is retried
+    pub max_retries: u32,
+    /// Wait in milliseconds before the first retry; it doubles with each further retry
+    pub retry_base_delay_ms: u64,
+    /// Check the size of each downloaded file, and its MD5 where the ETag is one, removing files that don't match
+    pub verify_downloads: bool,
+    /// Content types for extensions the guesser gets wrong, as `ext=type` pairs separated by commas
+    pub content_type_overrides: String,
+    /// Canned ACL for uploads, one of `UPLOAD_ACLS`; empty to send none and keep the bucket default
+    pub upload_acl: String,
+    /// Upload empty directories as zero-byte "folder/" marker objects
+    pub upload_folder_markers: bool,
+    /// Leave files out of uploads if their object has the same size and is at least as new
+    pub skip_unchanged_uploads: bool,
+    /// Light or dark visuals, or follow the operating system
+    pub theme: Theme,
+}
+
+/// Color theme of the user interface
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the operating system's dark mode setting
+    System,
+    Light,
+    Dark,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::System
+    }
+}
+
+impl Theme {
+    /// Name shown in the settings
+    pub fn label(&self) -> &'static str {
+        match self {
+            Theme::System => "System",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+    
+    /// Check whether the theme is dark, asking the operating system for `System`
+    pub fn is_dark(&self) -> bool {
+        match self {
+            Theme::System => dark_light::detect() == dark_light::Mode::Dark,
+            Theme::Light => false,
+            Theme::Dark => true,
+        }
+    }
 }
 
 /// Configuration for a folder to sync
@@ -34,93 +117,430 @@ pub struct SyncFolderConfig {
     pub bucket: String,
     /// Prefix within the bucket (optional)
     pub prefix: Option<String>,
+    /// Storage class for files uploaded from this folder, overriding the one in the settings
+    pub storage_class: Option<String>,
+    /// Canned ACL for files uploaded from this folder, overriding the one in the settings
+    pub acl: Option<String>,
 }
 
+/// Storage classes objects can be uploaded to, besides the default STANDARD
+pub const UPLOAD_STORAGE_CLASSES: &[&str] = &[
+    "STANDARD_IA",
+    "ONEZONE_IA",
+    "INTELLIGENT_TIERING",
+    "GLACIER_IR",
+    "GLACIER",
+    "DEEP_ARCHIVE",
+];
+
+/// Canned ACLs objects can be uploaded with. Buckets with ACLs disabled only accept
+/// bucket-owner-full-control, or no ACL at all.
+pub const UPLOAD_ACLS: &[&str] = &[
+    "private",
+    "public-read",
+    "bucket-owner-full-control",
+];
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             aws_region: "us-east-1".to_string(),
+            endpoint_url: String::new(),
+            aws_profile: String::new(),
             sync_interval: 0, // Manual sync by default
             delete_enabled: false,
             bandwidth_limit: None,
             exclude_patterns: vec![],
             folders: vec![],
+            lookup_bucket_regions: false,
+            quiet_period_secs: 5,
+            credential_refresh_minutes: 5,
+            sync_notifications: false,
+            stall_timeout_secs: 30,
+            stop_on_error: false,
+            decompress_downloads: false,
+            upload_part_size_mb: 8,
+            max_concurrent_transfers: 4,
+            deep_compare: false,
+            server_side_encryption: String::new(),
+            sse_kms_key_id: String::new(),
+            storage_class: String::new(),
+            max_retries: 3,
+            retry_base_delay_ms: 500,
+            verify_downloads: true,
+            content_type_overrides: String::new(),
+            upload_acl: String::new(),
+            upload_folder_markers: false,
+            skip_unchanged_uploads: false,
+            theme: Theme::System,
         }
     }
 }
 
 impl AppSettings {
-    /// Load settings from the config file
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn load() -> Result<Self> {
-        let config_path = Self::get_config_path()?;
-        
-        if !config_path.exists() {
-            info!("Config file not found, creating default settings");
-            let default_settings = Self::default();
-            default_settings.save()?;
-            return Ok(default_settings);
-        }
+    /// Export settings to a JSON file for use on another machine.
+    /// Credentials are stored separately and are never part of the export.
+    pub fn export_to(&self, path: &Path) -> Result<()> {
+        let config_str = serde_json::to_string_pretty(self)?;
+        fs::write(path, config_str)?;
         
-        let config_str = fs::read_to_string(&config_path)?;
-        let settings: AppSettings = serde_json::from_str(&config_str)?;
+        info!("Exported settings to {}", path.display());
+        Ok(())
+    }
+    
+    /// Import settings from a JSON file, merging them into the current settings
+    pub fn import_from(&mut self, path: &Path) -> Result<()> {
+        let config_str = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        self.merge_json(&config_str)?;
         
-        info!("Loaded settings from {}", config_path.display());
-        Ok(settings)
+        info!("Imported settings from {}", path.display());
+        Ok(())
     }
     
-    /// Save settings to the config file
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn save(&self) -> Result<()> {
-        let config_path = Self::get_config_path()?;
+    /// Merge settings from a JSON document. Only fields present in the document are
+    /// applied; folders are matched by path and exclude patterns are added to the
+    /// existing list. Nothing is changed if the document fails validation.
+    pub fn merge_json(&mut self, json: &str) -> Result<()> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| anyhow!("Invalid settings file: {}", e))?;
+        let fields = value.as_object()
+            .ok_or_else(|| anyhow!("Invalid settings file: expected a JSON object"))?;
+        let imported: AppSettings = serde_json::from_value(value.clone())
+            .map_err(|e| anyhow!("Invalid settings file: {}", e))?;
+        
+        imported.validate()?;
         
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)?;
+        if fields.contains_key("aws_region") {
+            self.aws_region = imported.aws_region;
+        }
+        if fields.contains_key("endpoint_url") {
+            self.endpoint_url = imported.endpoint_url;
+        }
+        if fields.contains_key("aws_profile") {
+            self.aws_profile = imported.aws_profile;
+        }
+        if fields.contains_key("sync_interval") {
+            self.sync_interval = imported.sync_interval;
+        }
+        if fields.contains_key("delete_enabled") {
+            self.delete_enabled = imported.delete_enabled;
+        }
+        if fields.contains_key("bandwidth_limit") {
+            self.bandwidth_limit = imported.bandwidth_limit;
+        }
+        if fields.contains_key("lookup_bucket_regions") {
+            self.lookup_bucket_regions = imported.lookup_bucket_regions;
+        }
+        if fields.contains_key("quiet_period_secs") {
+            self.quiet_period_secs = imported.quiet_period_secs;
+        }
+        if fields.contains_key("credential_refresh_minutes") {
+            self.credential_refresh_minutes = imported.credential_refresh_minutes;
+        }
+        if fields.contains_key("sync_notifications") {
+            self.sync_notifications = imported.sync_notifications;
+        }
+        if fields.contains_key("stall_timeout_secs") {
+            self.stall_timeout_secs = imported.stall_timeout_secs;
+        }
+        if fields.contains_key("stop_on_error") {
+            self.stop_on_error = imported.stop_on_error;
+        }
+        if fields.contains_key("decompress_downloads") {
+            self.decompress_downloads = imported.decompress_downloads;
+        }
+        if fields.contains_key("upload_part_size_mb") {
+            self.upload_part_size_mb = imported.upload_part_size_mb;
+        }
+        if fields.contains_key("max_concurrent_transfers") {
+            self.max_concurrent_transfers = imported.max_concurrent_transfers;
+        }
+        if fields.contains_key("deep_compare") {
+            self.deep_compare = imported.deep_compare;
+        }
+        if fields.contains_key("server_side_encryption") {
+            self.server_side_encryption = imported.server_side_encryption;
+        }
+        if fields.contains_key("sse_kms_key_id") {
+            self.sse_kms_key_id = imported.sse_kms_key_id;
+        }
+        if fields.contains_key("storage_class") {
+            self.storage_class = imported.storage_class;
+        }
+        if fields.contains_key("max_retries") {
+            self.max_retries = imported.max_retries;
+        }
+        if fields.contains_key("retry_base_delay_ms") {
+            self.retry_base_delay_ms = imported.retry_base_delay_ms;
+        }
+        if fields.contains_key("verify_downloads") {
+            self.verify_downloads = imported.verify_downloads;
+        }
+        if fields.contains_key("content_type_overrides") {
+            self.content_type_overrides = imported.content_type_overrides;
+        }
+        if fields.contains_key("upload_acl") {
+            self.upload_acl = imported.upload_acl;
+        }
+        if fields.contains_key("upload_folder_markers") {
+            self.upload_folder_markers = imported.upload_folder_markers;
+        }
+        if fields.contains_key("skip_unchanged_uploads") {
+            self.skip_unchanged_uploads = imported.skip_unchanged_uploads;
+        }
+        if fields.contains_key("theme") {
+            self.theme = imported.theme;
         }
         
-        let config_str = serde_json::to_string_pretty(self)?;
-        fs::write(&config_path, config_str)?;
+        for pattern in imported.exclude_patterns {
+            if !self.exclude_patterns.contains(&pattern) {
+                self.exclude_patterns.push(pattern);
+            }
+        }
+        
+        for folder in imported.folders {
+            match self.folders.iter_mut().find(|existing| existing.path == folder.path) {
+                Some(existing) => *existing = folder,
+                None => self.folders.push(folder),
+            }
+        }
         
-        info!("Saved settings to {}", config_path.display());
         Ok(())
     }
     
-    /// Get the path to the config file
-    #[allow(dead_code)] // Will be used in future implementations
-    fn get_config_path() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow!("Could not determine config directory"))?
-            .join("s3sync");
-            
-        Ok(config_dir.join("settings.json"))
+    /// Check that the settings contain usable values
+    fn validate(&self) -> Result<()> {
+        let region_valid = !self.aws_region.is_empty()
+            && self.aws_region
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !region_valid {
+            return Err(anyhow!("Invalid AWS region: '{}'", self.aws_region));
+        }
+        
+        let endpoint_url = self.endpoint_url.trim();
+        if !endpoint_url.is_empty() && !endpoint_url.starts_with("http://") && !endpoint_url.starts_with("https://") {
+            return Err(anyhow!("Endpoint URL must start with http:// or https://, got '{}'", endpoint_url));
+        }
+        
+        if self.sync_interval > 1440 {
+            return Err(anyhow!("Sync interval must be at most 1440 minutes, got {}", self.sync_interval));
+        }
+        
+        if self.bandwidth_limit == Some(0) {
+            return Err(anyhow!("Bandwidth limit must be greater than 0 KB/s"));
+        }
+        
+        if self.quiet_period_secs > 300 {
+            return Err(anyhow!("Quiet period must be at most 300 seconds, got {}", self.quiet_period_secs));
+        }
+        
+        if !(1..=60).contains(&self.credential_refresh_minutes) {
+            return Err(anyhow!("Credential refresh must be 1 to 60 minutes before expiry, got {}", self.credential_refresh_minutes));
+        }
+        
+        if !(5..=600).contains(&self.stall_timeout_secs) {
+            return Err(anyhow!("Stall timeout must be 5 to 600 seconds, got {}", self.stall_timeout_secs));
+        }
+        
+        if !(5..=512).contains(&self.upload_part_size_mb) {
+            return Err(anyhow!("Upload part size must be 5 to 512 MB, got {}", self.upload_part_size_mb));
+        }
+        
+        if self.max_retries > 10 {
+            return Err(anyhow!("Retries must be at most 10, got {}", self.max_retries));
+        }
+        
+        if !(100..=10_000).contains(&self.retry_base_delay_ms) {
+            return Err(anyhow!("Retry delay must be 100 to 10000 ms, got {}", self.retry_base_delay_ms));
+        }
+        
+        if !(1..=32).contains(&self.max_concurrent_transfers) {
+            return Err(anyhow!("Concurrent transfers must be 1 to 32, got {}", self.max_concurrent_transfers));
+        }
+        
+        if !["", "AES256", "aws:kms"].contains(&self.server_side_encryption.as_str()) {
+            return Err(anyhow!("Server-side encryption must be AES256 or aws:kms, got '{}'", self.server_side_encryption));
+        }
+        
+        let storage_classes = std::iter::once(self.storage_class.as_str())
+            .chain(self.folders.iter().filter_map(|folder| folder.storage_class.as_deref()));
+        for storage_class in storage_classes {
+            if !storage_class.is_empty() && storage_class != "STANDARD" && !UPLOAD_STORAGE_CLASSES.contains(&storage_class) {
+                return Err(anyhow!("Unsupported storage class: '{}'", storage_class));
+            }
+        }
+        
+        let acls = std::iter::once(self.upload_acl.as_str())
+            .chain(self.folders.iter().filter_map(|folder| folder.acl.as_deref()));
+        for acl in acls {
+            if !acl.is_empty() && !UPLOAD_ACLS.contains(&acl) {
+                return Err(anyhow!("Unsupported ACL: '{}'", acl));
+            }
+        }
+        
+        if let Some(pattern) = self.exclude_patterns.iter().find(|p| glob::Pattern::new(p).is_err()) {
+            return Err(anyhow!("Invalid exclude pattern: '{}'", pattern));
+        }
+        
+        if self.folders.iter().any(|folder| folder.path.trim().is_empty()) {
+            return Err(anyhow!("Folder entries must have a path"));
+        }
+        
+        Ok(())
     }
     
-    /// Convert folder configs to SyncFolder objects
-    #[allow(dead_code)] // Will be used in future implementations
+    /// Convert folder configs to SyncFolder objects. Folders that no longer exist are
+    /// kept with an error status, so their bucket mapping isn't lost if the folder is
+    /// only temporarily unavailable (e.g. on an unmounted drive).
     pub fn to_sync_folders(&self) -> Vec<SyncFolder> {
         self.folders
             .iter()
-            .map(|folder_config| SyncFolder {
-                path: PathBuf::from(&folder_config.path),
-                enabled: folder_config.enabled,
-                status: crate::ui::folder_list::SyncStatus::Pending,
-                last_synced: None,
+            .map(|folder_config| {
+                let path = PathBuf::from(&folder_config.path);
+                let status = if path.is_dir() {
+                    SyncStatus::Pending
+                } else {
+                    SyncStatus::Error("Folder not found".to_string())
+                };
+                
+                SyncFolder {
+                    path,
+                    enabled: folder_config.enabled,
+                    bucket: Some(folder_config.bucket.clone()).filter(|bucket| !bucket.is_empty()),
+                    prefix: folder_config.prefix.clone().filter(|prefix| !prefix.is_empty()),
+                    status,
+                    last_synced: None,
+                }
             })
             .collect()
     }
     
-    /// Update folder configs from SyncFolder objects
-    #[allow(dead_code)] // Will be used in future implementations
+    /// Update folder configs from SyncFolder objects, keeping the storage class and ACL set
+    /// for each folder
     pub fn update_from_sync_folders(&mut self, folders: &[SyncFolder]) {
         self.folders = folders
             .iter()
-            .map(|folder| SyncFolderConfig {
-                path: folder.path.to_string_lossy().to_string(),
-                enabled: folder.enabled,
-                bucket: "".to_string(), // This would need to be stored elsewhere
-                prefix: None,
+            .map(|folder| {
+                let path = folder.path.to_string_lossy().to_string();
+                let existing = self.folders.iter().find(|config| config.path == path);
+                
+                SyncFolderConfig {
+                    bucket: folder.bucket.clone().unwrap_or_default(),
+                    prefix: folder.prefix.clone(),
+                    storage_class: existing.and_then(|config| config.storage_class.clone()),
+                    acl: existing.and_then(|config| config.acl.clone()),
+                    path,
+                    enabled: folder.enabled,
+                }
             })
             .collect();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_merge_json() {
+        let mut settings = AppSettings {
+            exclude_patterns: vec!["*.tmp".to_string()],
+            folders: vec![SyncFolderConfig {
+                path: "/home/user/docs".to_string(),
+                enabled: true,
+                bucket: "docs".to_string(),
+                prefix: None,
+                storage_class: None,
+                acl: None,
+            }],
+            ..AppSettings::default()
+        };
+        
+        let json = r#"{
+            "aws_region": "eu-west-1",
+            "aws_secret_key": "ignored",
+            "exclude_patterns": ["*.tmp", "*.log"],
+            "folders": [
+                { "path": "/home/user/docs", "enabled": false, "bucket": "docs", "prefix": "backup" },
+                { "path": "/home/user/photos", "enabled": true, "bucket": "photos", "prefix": null }
+            ]
+        }"#;
+        settings.merge_json(json).unwrap();
+        
+        assert_eq!(settings.aws_region, "eu-west-1");
+        // Fields missing from the import keep their current values
+        assert_eq!(settings.quiet_period_secs, 5);
+        assert_eq!(settings.exclude_patterns, vec!["*.tmp", "*.log"]);
+        assert_eq!(settings.folders.len(), 2);
+        assert!(!settings.folders[0].enabled);
+        assert_eq!(settings.folders[0].prefix.as_deref(), Some("backup"));
+        
+        // Invalid imports leave the settings untouched
+        assert!(settings.merge_json(r#"{ "aws_region": "us-west-2", "sync_interval": 5000 }"#).is_err());
+        assert!(settings.merge_json(r#"{ "aws_region": "" }"#).is_err());
+        assert!(settings.merge_json("[]").is_err());
+        assert!(settings.merge_json(r#"{ "endpoint_url": "localhost:9000" }"#).is_err());
+        assert_eq!(settings.aws_region, "eu-west-1");
+        
+        settings.merge_json(r#"{ "endpoint_url": "http://localhost:9000" }"#).unwrap();
+        assert_eq!(settings.endpoint_url, "http://localhost:9000");
+        
+        // Storage classes are checked for the settings and for each folder
+        assert!(settings.merge_json(r#"{ "storage_class": "COLD" }"#).is_err());
+        assert!(settings.merge_json(r#"{ "folders": [{ "path": "/tmp", "enabled": true, "bucket": "b", "prefix": null, "storage_class": "COLD" }] }"#).is_err());
+        settings.merge_json(r#"{ "storage_class": "STANDARD_IA" }"#).unwrap();
+        assert_eq!(settings.storage_class, "STANDARD_IA");
+        
+        // So are ACLs
+        assert!(settings.merge_json(r#"{ "upload_acl": "public-read-write" }"#).is_err());
+        assert!(settings.merge_json(r#"{ "folders": [{ "path": "/tmp", "enabled": true, "bucket": "b", "prefix": null, "acl": "world" }] }"#).is_err());
+        settings.merge_json(r#"{ "upload_acl": "public-read" }"#).unwrap();
+        assert_eq!(settings.upload_acl, "public-read");
+        
+        assert!(settings.merge_json(r#"{ "theme": "purple" }"#).is_err());
+        settings.merge_json(r#"{ "theme": "dark" }"#).unwrap();
+        assert_eq!(settings.theme, Theme::Dark);
+    }
+    
+    #[test]
+    fn test_to_sync_folders_marks_missing_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = |path: &Path| SyncFolderConfig {
+            path: path.to_string_lossy().to_string(),
+            enabled: true,
+            bucket: "docs".to_string(),
+            prefix: None,
+            storage_class: None,
+            acl: None,
+        };
+        
+        let settings = AppSettings {
+            folders: vec![folder(dir.path()), folder(&dir.path().join("missing"))],
+            ..AppSettings::default()
+        };
+        
+        let folders = settings.to_sync_folders();
+        assert_eq!(folders.len(), 2);
+        assert_eq!(folders[0].status, SyncStatus::Pending);
+        assert!(matches!(folders[1].status, SyncStatus::Error(_)));
+        assert_eq!(folders[0].bucket.as_deref(), Some("docs"));
+        
+        // Mappings edited in the folder list are written back
+        let mut folders = folders;
+        folders[0].bucket = None;
+        folders[1].prefix = Some("archive".to_string());
+        let mut settings = settings;
+        settings.update_from_sync_folders(&folders);
+        assert_eq!(settings.folders[0].bucket, "");
+        assert_eq!(settings.folders[1].bucket, "docs");
+        assert_eq!(settings.folders[1].prefix.as_deref(), Some("archive"));
+    }
+    
+    #[test]
+    fn test_export_excludes_secrets() {
+        let json = serde_json::to_string(&AppSettings::default()).unwrap();
+        assert!(!json.contains("secret"));
+    }
+}
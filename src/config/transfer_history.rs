@@ -0,0 +1,190 @@
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::app_config::AppConfig;
+
+/// Name of the transfer history file in the config directory
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Size at which the history file is rotated. The previous file is kept alongside it,
+/// so at most twice this much history is stored.
+const MAX_HISTORY_BYTES: u64 = 1024 * 1024;
+
+/// A finished transfer, written as one line of JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferRecord {
+    /// When the transfer finished, as an RFC 3339 timestamp in local time
+    pub timestamp: String,
+    /// "Upload", "Download" or "Delete"
+    pub operation: String,
+    pub bucket: String,
+    pub key: String,
+    pub bytes: u64,
+    /// "Completed", or "Failed: " followed by the reason
+    pub status: String,
+}
+
+impl TransferRecord {
+    /// Create a record timestamped now
+    pub fn new(operation: &str, bucket: &str, key: &str, bytes: u64, status: String) -> Self {
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            operation: operation.to_string(),
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            bytes,
+            status,
+        }
+    }
+    
+    /// Check whether the transfer failed
+    pub fn is_failed(&self) -> bool {
+        self.status != "Completed"
+    }
+}
+
+/// Append-only log of finished transfers, kept as JSON lines in the config directory
+#[derive(Debug)]
+pub struct TransferHistory {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Serialises appends from concurrent transfers so lines are never interleaved
+    lock: Mutex<()>,
+}
+
+impl TransferHistory {
+    /// Open the history in the config directory
+    pub fn open() -> Result<Self> {
+        Ok(Self::open_in(&AppConfig::config_dir()?))
+    }
+    
+    /// Open the history in a directory
+    pub fn open_in(dir: &Path) -> Self {
+        Self {
+            path: dir.join(HISTORY_FILE),
+            max_bytes: MAX_HISTORY_BYTES,
+            lock: Mutex::new(()),
+        }
+    }
+    
+    /// Path of the rotated file holding the previous history
+    fn rotated_path(&self) -> PathBuf {
+        self.path.with_extension("jsonl.1")
+    }
+    
+    /// Append a record. Failures are logged rather than returned, since history is
+    /// never worth failing a transfer over.
+    pub fn append(&self, record: &TransferRecord) {
+        if let Err(e) = self.try_append(record) {
+            error!("Failed to write transfer history to {}: {}", self.path.display(), e);
+        }
+    }
+    
+    fn try_append(&self, record: &TransferRecord) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        
+        // Start a new file once the current one is full, keeping the previous one
+        if fs::metadata(&self.path).map_or(false, |metadata| metadata.len() >= self.max_bytes) {
+            fs::rename(&self.path, self.rotated_path())?;
+            info!("Rotated transfer history {}", self.path.display());
+        }
+        
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+    
+    /// Load every record, oldest first. Lines that can't be parsed are skipped.
+    pub fn load(&self) -> Result<Vec<TransferRecord>> {
+        let _guard = self.lock.lock().unwrap();
+        let mut records = Vec::new();
+        
+        for path in [self.rotated_path(), self.path.clone()] {
+            if !path.exists() {
+                continue;
+            }
+            
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Could not read transfer history {}: {}", path.display(), e))?;
+            records.extend(contents.lines().filter_map(|line| serde_json::from_str(line).ok()));
+        }
+        
+        Ok(records)
+    }
+    
+    /// Delete all recorded history
+    pub fn clear(&self) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        
+        for path in [self.rotated_path(), self.path.clone()] {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+        }
+        
+        info!("Cleared transfer history");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_append_and_load() {
+        let dir = tempdir().unwrap();
+        let history = TransferHistory::open_in(dir.path());
+        assert!(history.load().unwrap().is_empty());
+        
+        history.append(&TransferRecord::new("Upload", "bucket", "a.txt", 10, "Completed".to_string()));
+        history.append(&TransferRecord::new("Download", "bucket", "b.txt", 0, "Failed: Access denied".to_string()));
+        
+        let records = history.load().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].key, "a.txt");
+        assert!(!records[0].is_failed());
+        assert_eq!(records[1].operation, "Download");
+        assert!(records[1].is_failed());
+        
+        // Corrupt lines are skipped
+        fs::write(dir.path().join(HISTORY_FILE), "not json\n").unwrap();
+        assert!(history.load().unwrap().is_empty());
+        
+        history.clear().unwrap();
+        assert!(history.load().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_rotation() {
+        let dir = tempdir().unwrap();
+        let mut history = TransferHistory::open_in(dir.path());
+        history.max_bytes = 200;
+        
+        for i in 0..10 {
+            history.append(&TransferRecord::new("Upload", "bucket", &format!("file{}.txt", i), i, "Completed".to_string()));
+        }
+        
+        // Only the current and previous files are kept, each around the limit
+        assert!(dir.path().join("history.jsonl.1").exists());
+        assert!(fs::metadata(dir.path().join(HISTORY_FILE)).unwrap().len() < 400);
+        
+        let records = history.load().unwrap();
+        assert!(records.len() < 10);
+        assert_eq!(records.last().unwrap().key, "file9.txt");
+        assert!(records.windows(2).all(|pair| pair[0].bytes < pair[1].bytes));
+        
+        history.clear().unwrap();
+        assert!(!dir.path().join("history.jsonl.1").exists());
+    }
+}
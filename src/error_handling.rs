@@ -1,8 +1,13 @@
 use std::error::Error;
 use std::fmt;
+use std::future::Future;
 use std::io;
 use std::path::PathBuf;
-use log::error;
+use std::time::Duration;
+use log::{error, warn};
+
+/// Longest wait between two retries, however many attempts have failed
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 /// Custom error types for the application
 #[derive(Debug)]
@@ -204,30 +209,101 @@ impl ErrorHandler {
         }
     }
     
-    /// Try an operation with retry logic
-    pub async fn retry<F, T, E>(operation: F, retries: usize, delay_ms: u64) -> Result<T, E>
+    /// Try an operation, retrying failures that `is_retryable` accepts with exponential
+    /// backoff. Other failures, and the last failure once the retries run out, are returned.
+    pub async fn retry<F, Fut, T, E, R>(mut operation: F, policy: RetryPolicy, is_retryable: R) -> Result<T, E>
     where
-        F: Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, E>> + Send>>,
-        E: Error + Send + Sync + 'static,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: fmt::Display,
+        R: Fn(&E) -> bool,
     {
-        let mut attempts = 0;
-        let mut last_error = None;
+        let mut retry = 0;
         
-        while attempts < retries {
+        loop {
             match operation().await {
                 Ok(result) => return Ok(result),
-                Err(err) => {
-                    attempts += 1;
-                    last_error = Some(err);
-                    
-                    if attempts < retries {
-                        // Wait before retrying
-                        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
-                    }
+                Err(err) if retry < policy.max_retries && is_retryable(&err) => {
+                    retry += 1;
+                    let delay = policy.delay(retry);
+                    warn!("Attempt {} failed, retrying in {:?}: {}", retry, delay, err);
+                    tokio::time::sleep(delay).await;
                 }
+                Err(err) => return Err(err),
             }
         }
+    }
+}
+
+/// How often and how patiently a failed operation is retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = never retry)
+    pub max_retries: u32,
+    /// Wait before the first retry; each further retry waits twice as long
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the given retry (counting from 1), capped at `MAX_RETRY_DELAY`
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy { max_retries: 5, base_delay: Duration::from_millis(500) };
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(2));
+        assert_eq!(policy.delay(10), MAX_RETRY_DELAY);
+        assert_eq!(policy.delay(40), MAX_RETRY_DELAY);
+    }
+    
+    #[tokio::test]
+    async fn test_retry() {
+        let policy = RetryPolicy { max_retries: 3, base_delay: Duration::from_millis(1) };
         
-        Err(last_error.unwrap())
+        // Transient failures are retried until the operation succeeds
+        let mut attempts = 0;
+        let result: Result<u32, String> = ErrorHandler::retry(|| {
+            attempts += 1;
+            let attempt = attempts;
+            async move { if attempt < 3 { Err("SlowDown".to_string()) } else { Ok(attempt) } }
+        }, policy, |_| true).await;
+        assert_eq!(result, Ok(3));
+        
+        // Retries run out
+        let mut attempts = 0;
+        let result: Result<(), String> = ErrorHandler::retry(|| {
+            attempts += 1;
+            async { Err("SlowDown".to_string()) }
+        }, policy, |_| true).await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 4);
+        
+        // Permanent failures are returned straight away
+        let mut attempts = 0;
+        let result: Result<(), String> = ErrorHandler::retry(|| {
+            attempts += 1;
+            async { Err("AccessDenied".to_string()) }
+        }, policy, |e: &String| e != "AccessDenied").await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
     }
 }
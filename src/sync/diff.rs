@@ -1,4 +1,10 @@
-use std::path::PathBuf;
+use md5::{Digest, Md5};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::aws::transfer::ObjectSummary;
 
 /// Action to take for a file
 #[derive(Debug, Clone, PartialEq)]
@@ -49,10 +55,141 @@ pub fn calculate_file_hash(path: &PathBuf) -> Result<String, std::io::Error> {
     Ok(format!("{:x}", result))
 }
 
+/// Calculate the ETag S3 gives a file's content: the hex MD5 of the file, or for a multipart
+/// upload with the given part size, the MD5 of the concatenated part MD5s followed by `-<parts>`
+pub fn calculate_etag(path: &Path, part_size: Option<u64>) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut hasher = Md5::new();
+    let mut part_digests = Vec::new();
+    let mut part_bytes = 0u64;
+    
+    loop {
+        // Reads stop at part boundaries so each part is hashed on its own
+        let limit = match part_size {
+            Some(part_size) => (part_size - part_bytes).min(buffer.len() as u64) as usize,
+            None => buffer.len(),
+        };
+        let bytes_read = file.read(&mut buffer[..limit])?;
+        if bytes_read == 0 {
+            break;
+        }
+        
+        hasher.update(&buffer[..bytes_read]);
+        part_bytes += bytes_read as u64;
+        
+        if Some(part_bytes) == part_size {
+            part_digests.extend_from_slice(&hasher.finalize_reset());
+            part_bytes = 0;
+        }
+    }
+    
+    if part_size.is_none() {
+        return Ok(format!("{:x}", hasher.finalize()));
+    }
+    
+    if part_bytes > 0 {
+        part_digests.extend_from_slice(&hasher.finalize());
+    }
+    Ok(format!("{:x}-{}", Md5::digest(&part_digests), part_digests.len() / 16))
+}
+
+/// Get the number of parts from a multipart upload's ETag, e.g. 3 for "...-3"
+pub fn multipart_part_count(etag: &str) -> Option<u64> {
+    etag.trim_matches('"').rsplit_once('-')?.1.parse().ok()
+}
+
+/// Check whether a local file has the content an S3 ETag describes. A multipart ETag
+/// depends on the part size, so it is recomputed with `part_size` and with the whole
+/// number of MB that splits the file into the same number of parts.
+pub fn local_matches_etag(path: &Path, size: u64, etag: &str, part_size: u64) -> io::Result<bool> {
+    const MB: u64 = 1024 * 1024;
+    
+    let etag = etag.trim_matches('"');
+    let part_count = match multipart_part_count(etag) {
+        Some(part_count) if part_count > 0 => part_count,
+        _ => return Ok(calculate_etag(path, None)? == etag),
+    };
+    
+    let derived = ((size + part_count - 1) / part_count + MB - 1) / MB * MB;
+    let mut candidates = vec![part_size, derived];
+    candidates.dedup();
+    
+    for candidate in candidates {
+        if candidate == 0 || (size + candidate - 1) / candidate != part_count {
+            continue;
+        }
+        if calculate_etag(path, Some(candidate))? == etag {
+            return Ok(true);
+        }
+    }
+    
+    Ok(false)
+}
+
+/// Differences between two S3 locations, keyed by path relative to each prefix
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefixDiff {
+    /// Keys that only exist under the first prefix
+    pub only_in_a: Vec<String>,
+    /// Keys that only exist under the second prefix
+    pub only_in_b: Vec<String>,
+    /// Keys under both prefixes whose size or ETag differ
+    pub differing: Vec<String>,
+    /// Number of keys that are identical under both prefixes
+    pub identical: usize,
+}
+
+/// Compare two object listings by relative key, size and ETag
+pub fn compare_prefixes(a: &[ObjectSummary], prefix_a: &str, b: &[ObjectSummary], prefix_b: &str) -> PrefixDiff {
+    let relative = |object: &ObjectSummary, prefix: &str| -> String {
+        object.key.strip_prefix(prefix).unwrap_or(&object.key).to_string()
+    };
+    
+    let b_objects: HashMap<String, &ObjectSummary> = b.iter()
+        .map(|object| (relative(object, prefix_b), object))
+        .collect();
+        
+    let mut diff = PrefixDiff::default();
+    let mut seen = HashSet::new();
+    
+    for object in a {
+        let key = relative(object, prefix_a);
+        
+        match b_objects.get(&key) {
+            Some(other) => {
+                // ETags are only comparable when both are present
+                let etag_differs = match (&object.e_tag, &other.e_tag) {
+                    (Some(a_tag), Some(b_tag)) => a_tag != b_tag,
+                    _ => false,
+                };
+                
+                if object.size != other.size || etag_differs {
+                    diff.differing.push(key.clone());
+                } else {
+                    diff.identical += 1;
+                }
+            },
+            None => diff.only_in_a.push(key.clone()),
+        }
+        
+        seen.insert(key);
+    }
+    
+    diff.only_in_b = b_objects.into_keys()
+        .filter(|key| !seen.contains(key))
+        .collect();
+        
+    diff.only_in_a.sort();
+    diff.only_in_b.sort();
+    diff.differing.sort();
+    diff
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::File;
+    use std::fs;
     use std::io::Write;
     use tempfile::tempdir;
     
@@ -74,4 +211,67 @@ mod tests {
         
         assert_eq!(hash, expected);
     }
+    
+    #[test]
+    fn test_calculate_etag() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, b"Hello, world!").unwrap();
+        
+        // Single upload: the plain MD5
+        assert_eq!(calculate_etag(&file_path, None).unwrap(), "6cd3556deb0da54bca060b4c39479839");
+        
+        // Multipart upload in 4 byte parts: "0123", "4567", "89"
+        fs::write(&file_path, b"0123456789").unwrap();
+        assert_eq!(calculate_etag(&file_path, Some(4)).unwrap(), "61e3716e3a7767581863b67c4e785584-3");
+    }
+    
+    #[test]
+    fn test_local_matches_etag() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, b"Hello, world!").unwrap();
+        
+        assert!(local_matches_etag(&file_path, 13, "\"6cd3556deb0da54bca060b4c39479839\"", 4).unwrap());
+        assert!(!local_matches_etag(&file_path, 13, "00000000000000000000000000000000", 4).unwrap());
+        
+        fs::write(&file_path, b"0123456789").unwrap();
+        assert!(local_matches_etag(&file_path, 10, "61e3716e3a7767581863b67c4e785584-3", 4).unwrap());
+        
+        // Same number of parts, different content
+        assert!(!local_matches_etag(&file_path, 10, "11111111111111111111111111111111-3", 4).unwrap());
+        
+        assert_eq!(multipart_part_count("\"abc-12\""), Some(12));
+        assert_eq!(multipart_part_count("abc"), None);
+    }
+    
+    #[test]
+    fn test_compare_prefixes() {
+        let object = |key: &str, size: u64, e_tag: &str| ObjectSummary {
+            key: key.to_string(),
+            size,
+            e_tag: Some(e_tag.to_string()),
+            last_modified: 0,
+        };
+        
+        let prod = vec![
+            object("prod/a.txt", 10, "aaa"),
+            object("prod/b.txt", 20, "bbb"),
+            object("prod/c.txt", 30, "ccc"),
+            object("prod/d/e.txt", 40, "eee"),
+        ];
+        let staging = vec![
+            object("staging/a.txt", 10, "aaa"),
+            object("staging/b.txt", 20, "changed"),
+            object("staging/d/e.txt", 41, "eee"),
+            object("staging/f.txt", 50, "fff"),
+        ];
+        
+        let diff = compare_prefixes(&prod, "prod/", &staging, "staging/");
+        
+        assert_eq!(diff.only_in_a, vec!["c.txt"]);
+        assert_eq!(diff.only_in_b, vec!["f.txt"]);
+        assert_eq!(diff.differing, vec!["b.txt", "d/e.txt"]);
+        assert_eq!(diff.identical, 1);
+    }
 }
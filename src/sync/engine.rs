@@ -1,8 +1,18 @@
 use anyhow::{anyhow, Result};
+use log::debug;
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-use crate::aws::transfer::{TransferManager/* , TransferProgress */};
+use crate::aws::partial;
+use crate::aws::s3::S3ErrorHelper;
+use crate::aws::transfer::{TransferManager, TransferProgress};
+use crate::error_handling::{ErrorHandler, RetryPolicy};
+use crate::sync::diff::local_matches_etag;
+use crate::sync::filter::FileFilter;
+use crate::ui::bucket_view::S3Object;
 
 /// Result of a sync operation
 #[derive(Default)]
@@ -11,9 +21,74 @@ pub struct SyncResult {
     pub files_uploaded: usize,
     pub files_downloaded: usize,
     pub files_deleted: usize,
+    /// Files skipped because they were still being written
+    pub files_deferred: Vec<String>,
+    /// Local and remote files left alone because the file filter excludes them
+    pub files_filtered: usize,
     pub errors: Vec<String>,
 }
 
+impl SyncResult {
+    /// Summarize what the sync did, e.g. "3 uploaded, 1 downloaded, 0 deleted, 2 errors"
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} uploaded, {} downloaded, {} deleted",
+            self.files_uploaded, self.files_downloaded, self.files_deleted
+        );
+        
+        if !self.files_deferred.is_empty() {
+            summary.push_str(&format!(", {} deferred", self.files_deferred.len()));
+        }
+        
+        if self.files_filtered > 0 {
+            summary.push_str(&format!(", {} filtered", self.files_filtered));
+        }
+        
+        match self.errors.len() {
+            0 => {},
+            1 => summary.push_str(", 1 error"),
+            count => summary.push_str(&format!(", {} errors", count)),
+        }
+        
+        summary
+    }
+}
+
+/// Kind of change a sync makes to a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncOperation {
+    Upload,
+    Download,
+    Delete,
+}
+
+/// Progress of a sync, reported as it runs. Files are identified by their S3 key.
+#[derive(Debug, Clone)]
+pub enum SyncProgress {
+    /// Files found so far while scanning the local folder
+    ScanningLocal(usize),
+    /// Objects found so far while listing the bucket
+    ListingRemote(usize),
+    /// The folder and bucket have been compared; these changes will be made, with their sizes
+    Planned(Vec<(String, SyncOperation, u64)>),
+    /// A change has started
+    Started(String),
+    /// Bytes transferred so far
+    Transfer(TransferProgress),
+    /// A change has been made
+    Completed(String, u64),
+    /// A change failed, with the error
+    Failed(String, String),
+    /// A file was still being written, so it is left for the next sync
+    Deferred(String),
+}
+
+/// How many files the scan phase finds between reports of the count so far
+const SCAN_REPORT_INTERVAL: usize = 100;
+
+/// Callback receiving the progress of a sync
+pub type SyncProgressCallback = Arc<dyn Fn(SyncProgress) + Send + Sync>;
+
 /// Action to take for a file
 #[derive(Debug, PartialEq)]
 #[allow(dead_code)] // Will be used in future implementations
@@ -22,6 +97,8 @@ enum FileAction {
     Download,
     Delete,
     Skip,
+    /// Excluded by the file filter, so neither copied nor deleted
+    Filtered,
 }
 
 /// Difference between local and remote files
@@ -31,44 +108,168 @@ struct FileDiff {
     action: FileAction,
     local_path: Option<PathBuf>,
     s3_key: Option<String>,
-    #[allow(dead_code)] // Will be used in future implementations
     size: u64,
 }
 
 /// Engine for syncing files between local and S3
-#[allow(dead_code)] // Will be used in future implementations
 pub struct SyncEngine {
     transfer_manager: TransferManager,
+    quiet_period: Duration,
+    filter: FileFilter,
+    /// Compare the content of same-sized files against their ETag instead of trusting the size
+    deep_compare: bool,
+    /// Prefix the folder is synced under: empty, or ending with '/'
+    prefix: String,
+    /// Keys confirmed for deletion in a sync preview. When set, no other key is deleted,
+    /// even if it has gone missing locally since the preview.
+    confirmed_deletes: Option<HashSet<String>>,
+    /// How transfers and deletions that fail with a transient error are retried
+    retry_policy: RetryPolicy,
 }
 
 impl SyncEngine {
     /// Create a new sync engine
-    #[allow(dead_code)] // Will be used in future implementations
     pub fn new(transfer_manager: TransferManager) -> Self {
         Self {
             transfer_manager,
+            quiet_period: Duration::from_secs(0),
+            filter: FileFilter::default(),
+            deep_compare: false,
+            prefix: String::new(),
+            confirmed_deletes: None,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+    
+    /// Set the prefix the folder is synced under, e.g. "backup/". Only keys under it are
+    /// compared, and the files are keyed relative to it.
+    pub fn set_prefix(&mut self, prefix: String) {
+        self.prefix = prefix;
+    }
+    
+    /// Set the filter for the files to sync. Keys are matched relative to the synced folder.
+    pub fn set_filter(&mut self, filter: FileFilter) {
+        self.filter = filter;
+    }
+    
+    /// Set how long a file must go unmodified before it is uploaded
+    pub fn set_quiet_period(&mut self, quiet_period: Duration) {
+        self.quiet_period = quiet_period;
+    }
+    
+    /// Set whether files of the same size are compared by content. Hashing reads every
+    /// such file in full, so it is off by default.
+    pub fn set_deep_compare(&mut self, deep_compare: bool) {
+        self.deep_compare = deep_compare;
+    }
+    
+    /// Only delete these keys (including the prefix) from the bucket, as confirmed in a
+    /// preview of the sync. Other remote files missing locally are left alone.
+    pub fn set_confirmed_deletes(&mut self, keys: Option<HashSet<String>>) {
+        self.confirmed_deletes = keys;
+    }
+    
+    /// Set how transfers and deletions that fail with a transient error are retried
+    pub fn set_retry(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+    
+    /// Work out the changes a sync would make, with their S3 keys and sizes, without making any
+    pub async fn plan_sync(&self, folder_path: &Path, bucket: &str, delete_removed: bool) -> Result<Vec<(String, SyncOperation, u64)>> {
+        let diffs = self.plan_changes(folder_path, bucket, delete_removed, None).await?;
+        Ok(Self::planned_changes(&diffs))
+    }
+    
+    /// Compare the folder with the bucket and determine the action for each file, keyed under the prefix
+    async fn plan_changes(
+        &self,
+        folder_path: &Path,
+        bucket: &str,
+        delete_removed: bool,
+        progress: Option<&SyncProgressCallback>,
+    ) -> Result<Vec<FileDiff>> {
+        // Get the local files
+        let local_files = Self::scan_local_folder_reporting(folder_path, |found| {
+            Self::report(progress, SyncProgress::ScanningLocal(found));
+        })?;
+        
+        // Get the remote files
+        let remote_files = self.list_remote_files(bucket, progress).await?;
+        
+        // Compare files and determine actions, then key them under the prefix
+        let mut diffs = self.compare_files(&local_files, &remote_files, delete_removed);
+        for diff in &mut diffs {
+            // A file whose download was interrupted isn't missing locally: its download is resumed
+            if diff.action == FileAction::Delete {
+                if let Some(key) = diff.s3_key.as_deref().filter(|key| partial::is_partial_download(&folder_path.join(key))) {
+                    diff.local_path = Some(PathBuf::from(key));
+                    diff.action = FileAction::Download;
+                }
+            }
+        }
+        for diff in &mut diffs {
+            diff.s3_key = diff.s3_key.take().map(|key| format!("{}{}", self.prefix, key));
+        }
+        
+        self.skip_unconfirmed_deletes(&mut diffs);
+        Ok(diffs)
+    }
+    
+    /// Skip deleting any key that wasn't confirmed, if deletes had to be confirmed
+    fn skip_unconfirmed_deletes(&self, diffs: &mut [FileDiff]) {
+        let confirmed = match &self.confirmed_deletes {
+            Some(confirmed) => confirmed,
+            None => return,
+        };
+        
+        for diff in diffs.iter_mut().filter(|diff| diff.action == FileAction::Delete) {
+            if !diff.s3_key.as_ref().map_or(false, |key| confirmed.contains(key)) {
+                debug!("Keeping {:?}: its deletion wasn't confirmed", diff.s3_key);
+                diff.action = FileAction::Skip;
+            }
         }
     }
     
+    /// Get the changes to make from the diffs, leaving out skipped and filtered files
+    fn planned_changes(diffs: &[FileDiff]) -> Vec<(String, SyncOperation, u64)> {
+        diffs.iter()
+            .filter_map(|diff| {
+                let operation = match diff.action {
+                    FileAction::Upload => SyncOperation::Upload,
+                    FileAction::Download => SyncOperation::Download,
+                    FileAction::Delete => SyncOperation::Delete,
+                    FileAction::Skip | FileAction::Filtered => return None,
+                };
+                Some((diff.s3_key.clone()?, operation, diff.size))
+            })
+            .collect()
+    }
+    
     /// Sync a folder with an S3 bucket
-    #[allow(dead_code)] // Will be used in future implementations
     pub async fn sync_folder(
         &mut self,
         folder_path: &Path,
         bucket: &str,
         delete_removed: bool,
-        _progress_callback: Option<()>,
+        progress: Option<SyncProgressCallback>,
     ) -> Result<SyncResult> {
+        let progress = progress.as_ref();
         let mut result = SyncResult::default();
         
-        // Get the local files
-        let local_files = self.scan_local_folder(folder_path)?;
+        let diffs = self.plan_changes(folder_path, bucket, delete_removed, progress).await?;
         
-        // Get the remote files
-        let remote_files = self.list_remote_files(bucket).await?;
+        // Snapshot the files to upload so changes made during the sync can be detected
+        let snapshots: HashMap<PathBuf, (u64, SystemTime)> = diffs.iter()
+            .filter(|diff| diff.action == FileAction::Upload)
+            .filter_map(|diff| diff.local_path.as_ref())
+            .filter_map(|path| Self::file_snapshot(path).map(|snapshot| (path.clone(), snapshot)))
+            .collect();
+        
+        // Report the changes to make before making them
+        Self::report(progress, SyncProgress::Planned(Self::planned_changes(&diffs)));
         
-        // Compare files and determine actions
-        let diffs = self.compare_files(&local_files, &remote_files, delete_removed);
+        // Files that were still being written, re-queued until the end of this pass
+        let mut deferred = Vec::new();
         
         // Process each diff
         for diff in diffs {
@@ -77,59 +278,167 @@ impl SyncEngine {
                     let local_path = diff.local_path.ok_or_else(|| anyhow!("Missing local path"))?;
                     let s3_key = diff.s3_key.ok_or_else(|| anyhow!("Missing S3 key"))?;
                     
-                    // Create a simple callback that doesn't need to be Send + Sync
-                    let boxed_callback = None;
-                    
-                    match self.transfer_manager.upload_file(&local_path, bucket, &s3_key, boxed_callback).await {
-                        Ok(_) => {
-                            result.files_uploaded += 1;
-                        },
-                        Err(e) => {
-                            result.errors.push(format!("Failed to upload {}: {}", local_path.display(), e));
-                        }
+                    if !self.is_file_stable(&local_path, snapshots.get(&local_path).copied()) {
+                        debug!("Deferring {} as it is still being written", local_path.display());
+                        let snapshot = Self::file_snapshot(&local_path);
+                        deferred.push((local_path, s3_key, diff.size, snapshot));
+                        continue;
                     }
+                    
+                    self.upload_for_sync(&local_path, bucket, &s3_key, diff.size, progress, &mut result).await;
                 },
                 FileAction::Download => {
-                    let local_path = diff.local_path.ok_or_else(|| anyhow!("Missing local path"))?;
+                    // Remote-only files are keyed relative to the synced folder
+                    let local_path = folder_path.join(diff.local_path.ok_or_else(|| anyhow!("Missing local path"))?);
                     let s3_key = diff.s3_key.ok_or_else(|| anyhow!("Missing S3 key"))?;
                     
-                    // Create a simple callback that doesn't need to be Send + Sync
-                    let boxed_callback = None;
+                    Self::report(progress, SyncProgress::Started(s3_key.clone()));
                     
-                    match self.transfer_manager.download_file(bucket, &s3_key, &local_path, boxed_callback).await {
+                    let download = self.retry(|| {
+                        let callback = Self::transfer_callback(progress, &s3_key);
+                        self.transfer_manager.download_file(bucket, &s3_key, &local_path, callback)
+                    }).await;
+                    match download {
                         Ok(_) => {
                             result.files_downloaded += 1;
+                            Self::report(progress, SyncProgress::Completed(s3_key, diff.size));
                         },
                         Err(e) => {
                             result.errors.push(format!("Failed to download {}: {}", s3_key, e));
+                            Self::report(progress, SyncProgress::Failed(s3_key, e.to_string()));
                         }
                     }
                 },
                 FileAction::Delete => {
                     let s3_key = diff.s3_key.ok_or_else(|| anyhow!("Missing S3 key"))?;
                     
-                    match self.transfer_manager.delete_object(bucket, &s3_key).await {
+                    Self::report(progress, SyncProgress::Started(s3_key.clone()));
+                    
+                    match self.retry(|| self.transfer_manager.delete_object(bucket, &s3_key)).await {
                         Ok(_) => {
                             result.files_deleted += 1;
+                            Self::report(progress, SyncProgress::Completed(s3_key, 0));
                         },
                         Err(e) => {
                             result.errors.push(format!("Failed to delete {}: {}", s3_key, e));
+                            Self::report(progress, SyncProgress::Failed(s3_key, e.to_string()));
                         }
                     }
                 },
                 FileAction::Skip => {
                     // Nothing to do
+                },
+                FileAction::Filtered => {
+                    result.files_filtered += 1;
                 }
             }
         }
         
+        // Retry deferred files once; anything still changing is left for the next sync
+        for (local_path, s3_key, size, snapshot) in deferred {
+            if self.is_file_stable(&local_path, snapshot) {
+                self.upload_for_sync(&local_path, bucket, &s3_key, size, progress, &mut result).await;
+            } else {
+                Self::report(progress, SyncProgress::Deferred(s3_key.clone()));
+                result.files_deferred.push(s3_key);
+            }
+        }
+        
         Ok(result)
     }
     
-    /// Scan a local folder for files
-    #[allow(dead_code)] // Will be used in future implementations
-    fn scan_local_folder(&self, folder: &Path) -> Result<HashMap<String, (PathBuf, u64)>> {
+    /// Upload a single file as part of a sync, recording the outcome in the result
+    async fn upload_for_sync(
+        &self,
+        local_path: &Path,
+        bucket: &str,
+        s3_key: &str,
+        size: u64,
+        progress: Option<&SyncProgressCallback>,
+        result: &mut SyncResult,
+    ) {
+        Self::report(progress, SyncProgress::Started(s3_key.to_string()));
+        
+        let upload = self.retry(|| {
+            let callback = Self::transfer_callback(progress, s3_key);
+            self.transfer_manager.upload_file(local_path, bucket, s3_key, callback)
+        }).await;
+        match upload {
+            Ok(_) => {
+                result.files_uploaded += 1;
+                Self::report(progress, SyncProgress::Completed(s3_key.to_string(), size));
+            },
+            Err(e) => {
+                result.errors.push(format!("Failed to upload {}: {}", local_path.display(), e));
+                Self::report(progress, SyncProgress::Failed(s3_key.to_string(), e.to_string()));
+            }
+        }
+    }
+    
+    /// Run a transfer or deletion, retrying transient failures with the retry policy
+    async fn retry<F, Fut, T>(&self, operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        ErrorHandler::retry(operation, self.retry_policy, S3ErrorHelper::is_retryable).await
+    }
+    
+    /// Pass a progress update to the callback, if there is one
+    fn report(progress: Option<&SyncProgressCallback>, update: SyncProgress) {
+        if let Some(callback) = progress {
+            callback(update);
+        }
+    }
+    
+    /// Create a transfer callback forwarding byte counts to the sync callback. Transfers
+    /// report the local file name, so the updates are relabelled with the S3 key.
+    fn transfer_callback(progress: Option<&SyncProgressCallback>, s3_key: &str) -> Option<Box<dyn Fn(TransferProgress) + Send + Sync>> {
+        let callback = progress?.clone();
+        let s3_key = s3_key.to_string();
+        
+        Some(Box::new(move |transfer| {
+            callback(SyncProgress::Transfer(TransferProgress {
+                file_name: s3_key.clone(),
+                ..transfer
+            }));
+        }))
+    }
+    
+    /// Get the size and modification time of a local file
+    fn file_snapshot(path: &Path) -> Option<(u64, SystemTime)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.len(), metadata.modified().ok()?))
+    }
+    
+    /// Check that a file hasn't changed since it was scanned and has been quiet for the quiet period
+    fn is_file_stable(&self, path: &Path, snapshot: Option<(u64, SystemTime)>) -> bool {
+        let current = match Self::file_snapshot(path) {
+            Some(current) => current,
+            None => return false,
+        };
+        
+        if snapshot != Some(current) {
+            return false;
+        }
+        
+        match SystemTime::now().duration_since(current.1) {
+            Ok(age) => age >= self.quiet_period,
+            // A modification time in the future is most likely clock skew, don't defer forever
+            Err(_) => true,
+        }
+    }
+    
+    /// Scan a local folder for files, keyed by their path relative to the folder
+    pub fn scan_local_folder(folder: &Path) -> Result<HashMap<String, (PathBuf, u64)>> {
+        Self::scan_local_folder_reporting(folder, |_| {})
+    }
+    
+    /// Scan a local folder for files, passing the number found so far to `on_found` every
+    /// so often and once the scan is done
+    fn scan_local_folder_reporting(folder: &Path, mut on_found: impl FnMut(usize)) -> Result<HashMap<String, (PathBuf, u64)>> {
         let mut files = HashMap::new();
+        on_found(0);
         
         // Use walkdir to recursively scan the folder
         for entry in walkdir::WalkDir::new(folder)
@@ -137,7 +446,8 @@ impl SyncEngine {
             .into_iter()
             .filter_map(|e| e.ok())
         {
-            if entry.file_type().is_file() {
+            // Sidecars of interrupted transfers, and the files of unfinished downloads, are not synced
+            if entry.file_type().is_file() && !partial::is_sidecar(entry.path()) && !partial::is_partial_download(entry.path()) {
                 let path = entry.path().to_path_buf();
                 let size = entry.metadata()?.len();
                 
@@ -146,40 +456,108 @@ impl SyncEngine {
                 let key = rel_path.to_string_lossy().replace("\\", "/");
                 
                 files.insert(key.to_string(), (path, size));
+                if files.len() % SCAN_REPORT_INTERVAL == 0 {
+                    on_found(files.len());
+                }
             }
         }
         
+        on_found(files.len());
         Ok(files)
     }
     
-    /// List files in an S3 bucket
-    #[allow(dead_code)] // Will be used in future implementations
-    async fn list_remote_files(&self, _bucket: &str) -> Result<HashMap<String, u64>> {
-        let files = HashMap::new();
+    /// Find the empty directories in a folder, including the folder itself if it is empty.
+    /// Scanning skips them, as they have no files to sync.
+    pub fn empty_folders(folder: &Path) -> Vec<PathBuf> {
+        walkdir::WalkDir::new(folder)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_dir())
+            .filter(|entry| fs::read_dir(entry.path()).map_or(false, |mut entries| entries.next().is_none()))
+            .map(|entry| entry.path().to_path_buf())
+            .collect()
+    }
+    
+    /// List files under the prefix in an S3 bucket with their sizes and ETags, keyed
+    /// relative to the prefix like `scan_local_folder` so the two can be compared
+    async fn list_remote_files(&self, bucket: &str, progress: Option<&SyncProgressCallback>) -> Result<HashMap<String, (u64, Option<String>)>> {
+        Self::report(progress, SyncProgress::ListingRemote(0));
         
-        // TODO: Implement this using the AWS SDK
-        // For now, return an empty map
+        let objects = self.transfer_manager.list_objects(bucket, &self.prefix, true).await?;
+        let files = Self::remote_files(objects, &self.prefix);
         
+        Self::report(progress, SyncProgress::ListingRemote(files.len()));
+        debug!("Found {} objects in bucket {}", files.len(), bucket);
         Ok(files)
     }
     
+    /// Key the files of a recursive listing relative to the prefix, with their sizes and
+    /// ETags. Folder markers are left out.
+    fn remote_files(objects: Vec<S3Object>, prefix: &str) -> HashMap<String, (u64, Option<String>)> {
+        objects.into_iter()
+            .filter_map(|object| {
+                let key = object.key.strip_prefix(prefix).unwrap_or(&object.key);
+                if key.is_empty() || Self::is_folder_marker(key) {
+                    return None;
+                }
+                
+                let e_tag = Some(object.e_tag.clone()).filter(|e_tag| !e_tag.is_empty());
+                Some((key.to_string(), (object.size, e_tag)))
+            })
+            .collect()
+    }
+    
+    /// Check whether a key is an empty "folder" placeholder, such as the ones created by the
+    /// S3 console, which has no local file to compare against
+    fn is_folder_marker(key: &str) -> bool {
+        key.ends_with('/')
+    }
+    
+    /// Check whether a local file has the same content as its remote copy. Without deep
+    /// compare, or without an ETag to compare against, files of the same size match.
+    fn content_matches(&self, path: &Path, size: u64, remote_e_tag: Option<&str>) -> bool {
+        let e_tag = match remote_e_tag {
+            Some(e_tag) if self.deep_compare => e_tag,
+            _ => return true,
+        };
+        
+        match local_matches_etag(path, size, e_tag, self.transfer_manager.upload_part_size_for(size)) {
+            Ok(matches) => matches,
+            Err(e) => {
+                // Uploading again is safer than assuming the content is unchanged
+                debug!("Could not hash {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+    
     /// Compare local and remote files to determine actions
-    #[allow(dead_code)] // Will be used in future implementations
     fn compare_files(
         &self,
         local_files: &HashMap<String, (PathBuf, u64)>,
-        remote_files: &HashMap<String, u64>,
+        remote_files: &HashMap<String, (u64, Option<String>)>,
         delete_removed: bool,
     ) -> Vec<FileDiff> {
         let mut diffs = Vec::new();
         
         // Check local files against remote
         for (key, (path, size)) in local_files {
+            if self.filter.skip_reason(Path::new(key), *size).is_some() {
+                diffs.push(FileDiff {
+                    action: FileAction::Filtered,
+                    local_path: Some(path.clone()),
+                    s3_key: Some(key.clone()),
+                    size: *size,
+                });
+                continue;
+            }
+            
             match remote_files.get(key) {
-                Some(remote_size) => {
+                Some((remote_size, remote_e_tag)) => {
                     // File exists in both places
-                    if size != remote_size {
-                        // Sizes differ, upload the local file
+                    if size != remote_size || !self.content_matches(path, *size, remote_e_tag.as_deref()) {
+                        // Sizes or content differ, upload the local file
                         diffs.push(FileDiff {
                             action: FileAction::Upload,
                             local_path: Some(path.clone()),
@@ -209,8 +587,19 @@ impl SyncEngine {
         }
         
         // Check remote files against local
-        for (key, size) in remote_files {
+        for (key, (size, _)) in remote_files {
             if !local_files.contains_key(key) {
+                // A filtered file is never downloaded, nor deleted because it's missing locally
+                if self.filter.skip_reason(Path::new(key), *size).is_some() {
+                    diffs.push(FileDiff {
+                        action: FileAction::Filtered,
+                        local_path: None,
+                        s3_key: Some(key.clone()),
+                        size: *size,
+                    });
+                    continue;
+                }
+                
                 // File exists remotely but not locally
                 if delete_removed {
                     // Delete the remote file
@@ -247,7 +636,6 @@ mod tests {
     use std::sync::Arc;
     
     #[test]
-    #[ignore] // Ignore this test as it's environment-dependent
     fn test_scan_local_folder() {
         // Create a temporary directory
         let dir = tempdir().unwrap();
@@ -265,13 +653,8 @@ mod tests {
         let mut file2 = File::create(&file2_path).unwrap();
         file2.write_all(b"Hello, again!").unwrap();
         
-        // Create a sync engine with mock client for testing
-        // Note: In a real test, we would use a proper SDK config
-        #[allow(unused_variables)]
-        let engine = SyncEngine::new(TransferManager::new(Arc::new(aws_sdk_s3::Client::new(&aws_types::sdk_config::SdkConfig::builder().build()))));
-        
         // Scan the folder
-        let files = engine.scan_local_folder(path).unwrap();
+        let files = SyncEngine::scan_local_folder(path).unwrap();
         
         // Check the results
         assert_eq!(files.len(), 2);
@@ -281,6 +664,35 @@ mod tests {
         // We don't check file sizes as they may vary by environment
     }
     
+    #[test]
+    fn test_empty_folders() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("photos/empty")).unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/a.txt"), "a").unwrap();
+        
+        // Directories holding only other directories aren't empty
+        assert_eq!(SyncEngine::empty_folders(dir.path()), vec![dir.path().join("photos/empty")]);
+        
+        let empty = dir.path().join("photos/empty");
+        assert_eq!(SyncEngine::empty_folders(&empty), vec![empty.clone()]);
+    }
+    
+    #[test]
+    fn test_scan_reports_files_found() {
+        let dir = tempdir().unwrap();
+        for i in 0..(SCAN_REPORT_INTERVAL + 5) {
+            fs::write(dir.path().join(format!("file{}.txt", i)), "x").unwrap();
+        }
+        
+        let mut reports = Vec::new();
+        let files = SyncEngine::scan_local_folder_reporting(dir.path(), |found| reports.push(found)).unwrap();
+        
+        // At the start, every interval and at the end
+        assert_eq!(files.len(), SCAN_REPORT_INTERVAL + 5);
+        assert_eq!(reports, vec![0, SCAN_REPORT_INTERVAL, SCAN_REPORT_INTERVAL + 5]);
+    }
+    
     #[test]
     fn test_compare_files() {
         // Create local and remote file maps
@@ -290,9 +702,9 @@ mod tests {
         local_files.insert("file3.txt".to_string(), (PathBuf::from("file3.txt"), 300));
         
         let mut remote_files = HashMap::new();
-        remote_files.insert("file1.txt".to_string(), 100);
-        remote_files.insert("file2.txt".to_string(), 250); // Different size
-        remote_files.insert("file4.txt".to_string(), 400); // Only remote
+        remote_files.insert("file1.txt".to_string(), (100, None));
+        remote_files.insert("file2.txt".to_string(), (250, None)); // Different size
+        remote_files.insert("file4.txt".to_string(), (400, None)); // Only remote
         
         // Create a sync engine with mock client for testing
         // Note: In a real test, we would use a proper SDK config
@@ -326,4 +738,158 @@ mod tests {
         // file4.txt should be deleted (only remote)
         assert!(diffs.iter().any(|d| d.action == FileAction::Delete && d.s3_key == Some("file4.txt".to_string())));
     }
+    
+    #[test]
+    fn test_compare_files_with_filter() {
+        let mut local_files = HashMap::new();
+        local_files.insert("notes.txt".to_string(), (PathBuf::from("notes.txt"), 100));
+        local_files.insert("build.tmp".to_string(), (PathBuf::from("build.tmp"), 100));
+        
+        let mut remote_files = HashMap::new();
+        remote_files.insert("cache/old.tmp".to_string(), (50, None));
+        remote_files.insert("photo.jpg".to_string(), (400, None));
+        
+        let mut filter = FileFilter::new();
+        filter.parse_patterns("!*.tmp").unwrap();
+        
+        let mut engine = SyncEngine::new(TransferManager::new(Arc::new(aws_sdk_s3::Client::new(&aws_types::sdk_config::SdkConfig::builder().build()))));
+        engine.set_filter(filter);
+        
+        let diffs = engine.compare_files(&local_files, &remote_files, true);
+        let action = |key: &str| &diffs.iter().find(|d| d.s3_key.as_deref() == Some(key)).unwrap().action;
+        
+        assert_eq!(action("notes.txt"), &FileAction::Upload);
+        assert_eq!(action("build.tmp"), &FileAction::Filtered);
+        
+        // Excluded remote files aren't deleted even though they're missing locally
+        assert_eq!(action("cache/old.tmp"), &FileAction::Filtered);
+        assert_eq!(action("photo.jpg"), &FileAction::Delete);
+    }
+    
+    #[test]
+    fn test_compare_files_deep_compare() {
+        let dir = tempdir().unwrap();
+        let unchanged = dir.path().join("unchanged.txt");
+        let edited = dir.path().join("edited.txt");
+        fs::write(&unchanged, b"Hello, world!").unwrap();
+        fs::write(&edited, b"Hello, World!").unwrap();
+        
+        let mut local_files = HashMap::new();
+        local_files.insert("unchanged.txt".to_string(), (unchanged, 13));
+        local_files.insert("edited.txt".to_string(), (edited, 13));
+        
+        // Both remote copies hold "Hello, world!"
+        let e_tag = Some("\"6cd3556deb0da54bca060b4c39479839\"".to_string());
+        let mut remote_files = HashMap::new();
+        remote_files.insert("unchanged.txt".to_string(), (13, e_tag.clone()));
+        remote_files.insert("edited.txt".to_string(), (13, e_tag));
+        
+        let mut engine = SyncEngine::new(TransferManager::new(Arc::new(aws_sdk_s3::Client::new(&aws_types::sdk_config::SdkConfig::builder().build()))));
+        
+        // Sizes match, so nothing is uploaded without deep compare
+        let diffs = engine.compare_files(&local_files, &remote_files, false);
+        assert!(diffs.iter().all(|d| d.action == FileAction::Skip));
+        
+        engine.set_deep_compare(true);
+        let diffs = engine.compare_files(&local_files, &remote_files, false);
+        let action = |key: &str| &diffs.iter().find(|d| d.s3_key.as_deref() == Some(key)).unwrap().action;
+        assert_eq!(action("unchanged.txt"), &FileAction::Skip);
+        assert_eq!(action("edited.txt"), &FileAction::Upload);
+    }
+    
+    #[test]
+    fn test_confirmed_deletes() {
+        let mut local_files = HashMap::new();
+        local_files.insert("kept.txt".to_string(), (PathBuf::from("kept.txt"), 100));
+        
+        let mut remote_files = HashMap::new();
+        remote_files.insert("old.txt".to_string(), (50, None));
+        remote_files.insert("removed-since.txt".to_string(), (50, None));
+        
+        let mut engine = SyncEngine::new(TransferManager::new(Arc::new(aws_sdk_s3::Client::new(&aws_types::sdk_config::SdkConfig::builder().build()))));
+        engine.set_confirmed_deletes(Some(["old.txt".to_string()].into_iter().collect()));
+        
+        // Only the deletion shown in the preview goes ahead
+        let mut diffs = engine.compare_files(&local_files, &remote_files, true);
+        engine.skip_unconfirmed_deletes(&mut diffs);
+        let planned = SyncEngine::planned_changes(&diffs);
+        
+        assert!(planned.contains(&("old.txt".to_string(), SyncOperation::Delete, 50)));
+        assert!(planned.contains(&("kept.txt".to_string(), SyncOperation::Upload, 100)));
+        assert!(!planned.iter().any(|(key, _, _)| key == "removed-since.txt"));
+    }
+    
+    #[test]
+    fn test_remote_files() {
+        let object = |key: &str, size: u64, e_tag: &str| S3Object {
+            key: key.to_string(),
+            size,
+            e_tag: e_tag.to_string(),
+            ..Default::default()
+        };
+        let objects = vec![
+            object("photos/", 0, "d41d8cd98f00b204e9800998ecf8427e"),
+            object("photos/2023/a.jpg", 100, "abc"),
+            object("photos/2023/empty/", 0, "d41d8cd98f00b204e9800998ecf8427e"),
+            object("photos/b.jpg", 200, ""),
+        ];
+        
+        let files = SyncEngine::remote_files(objects, "photos/");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files.get("2023/a.jpg"), Some(&(100, Some("abc".to_string()))));
+        assert_eq!(files.get("b.jpg"), Some(&(200, None)));
+    }
+    
+    #[test]
+    fn test_is_folder_marker() {
+        assert!(SyncEngine::is_folder_marker("photos/"));
+        assert!(!SyncEngine::is_folder_marker("photos/cat.jpg"));
+        assert!(!SyncEngine::is_folder_marker("notes.txt"));
+    }
+    
+    #[test]
+    fn test_sync_result_summary() {
+        let mut result = SyncResult {
+            files_uploaded: 3,
+            files_downloaded: 1,
+            ..Default::default()
+        };
+        assert_eq!(result.summary(), "3 uploaded, 1 downloaded, 0 deleted");
+        
+        result.files_deferred.push("notes.txt".to_string());
+        result.errors.push("Failed to upload a.txt".to_string());
+        assert_eq!(result.summary(), "3 uploaded, 1 downloaded, 0 deleted, 1 deferred, 1 error");
+        
+        result.errors.push("Failed to upload b.txt".to_string());
+        assert_eq!(result.summary(), "3 uploaded, 1 downloaded, 0 deleted, 1 deferred, 2 errors");
+        
+        result.files_filtered = 4;
+        assert_eq!(result.summary(), "3 uploaded, 1 downloaded, 0 deleted, 1 deferred, 4 filtered, 2 errors");
+    }
+    
+    #[test]
+    fn test_is_file_stable() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("growing.log");
+        fs::write(&file_path, b"first line").unwrap();
+        
+        let mut engine = SyncEngine::new(TransferManager::new(Arc::new(aws_sdk_s3::Client::new(&aws_types::sdk_config::SdkConfig::builder().build()))));
+        let snapshot = SyncEngine::file_snapshot(&file_path);
+        
+        // Unchanged since the snapshot and no quiet period
+        assert!(engine.is_file_stable(&file_path, snapshot));
+        
+        // Modified within the quiet period
+        engine.set_quiet_period(Duration::from_secs(3600));
+        assert!(!engine.is_file_stable(&file_path, snapshot));
+        
+        // Grown since the snapshot
+        engine.set_quiet_period(Duration::from_secs(0));
+        let mut file = fs::OpenOptions::new().append(true).open(&file_path).unwrap();
+        file.write_all(b", second line").unwrap();
+        assert!(!engine.is_file_stable(&file_path, snapshot));
+        
+        // Missing files are never stable
+        assert!(!engine.is_file_stable(&dir.path().join("missing.log"), None));
+    }
 }
@@ -4,6 +4,36 @@ use log::debug; // Only keep debug, remove error
 use std::fmt;
 use std::path::Path;
 
+/// Reason a file was skipped during a transfer
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    /// Matched an exclude pattern
+    ExcludedByPattern(String),
+    /// Did not match any include pattern
+    NotIncluded,
+    /// Smaller than the configured minimum size
+    TooSmall(u64),
+    /// Larger than the configured maximum size
+    TooLarge(u64),
+    /// The remote copy is already up to date
+    UpToDate,
+    /// Another file in the same batch uploads to this key
+    DuplicateKey,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::ExcludedByPattern(pattern) => write!(f, "Excluded by pattern '{}'", pattern),
+            SkipReason::NotIncluded => write!(f, "Not matched by any include pattern"),
+            SkipReason::TooSmall(min_size) => write!(f, "Smaller than minimum size ({} bytes)", min_size),
+            SkipReason::TooLarge(max_size) => write!(f, "Larger than maximum size ({} bytes)", max_size),
+            SkipReason::UpToDate => write!(f, "Already up to date"),
+            SkipReason::DuplicateKey => write!(f, "Another selected file uploads to the same key"),
+        }
+    }
+}
+
 /// Filter for files during sync operations
 #[derive(Clone, Default)]
 pub struct FileFilter {
@@ -106,18 +136,22 @@ impl FileFilter {
     }
     
     /// Check if a file should be included
-    #[allow(dead_code)] // Will be used in future implementations
     pub fn should_include(&self, path: &Path, size: u64) -> bool {
+        self.skip_reason(path, size).is_none()
+    }
+    
+    /// Get the reason a file would be skipped, or None if it should be included
+    pub fn skip_reason(&self, path: &Path, size: u64) -> Option<SkipReason> {
         // Check size constraints
         if let Some(min_size) = self.min_size {
             if size < min_size {
-                return false;
+                return Some(SkipReason::TooSmall(min_size));
             }
         }
         
         if let Some(max_size) = self.max_size {
             if size > max_size {
-                return false;
+                return Some(SkipReason::TooLarge(max_size));
             }
         }
         
@@ -128,25 +162,25 @@ impl FileFilter {
         for pattern in &self.exclude_patterns {
             if pattern.matches(&path_str) {
                 debug!("Path {} excluded by pattern {}", path_str, pattern);
-                return false;
+                return Some(SkipReason::ExcludedByPattern(pattern.to_string()));
             }
         }
         
         // If there are no include patterns, include everything not excluded
         if self.include_patterns.is_empty() {
-            return true;
+            return None;
         }
         
         // Check include patterns
         for pattern in &self.include_patterns {
             if pattern.matches(&path_str) {
                 debug!("Path {} included by pattern {}", path_str, pattern);
-                return true;
+                return None;
             }
         }
         
         // If there are include patterns but none matched, exclude the file
-        false
+        Some(SkipReason::NotIncluded)
     }
     
     /// Get the include patterns
@@ -265,4 +299,21 @@ mod tests {
         assert!(filter.should_include(&PathBuf::from("test.txt"), 50));
         assert!(filter.should_include(&PathBuf::from("test.tmp"), 50));
     }
+    
+    #[test]
+    fn test_skip_reasons() {
+        let mut filter = FileFilter::new();
+        filter.parse_patterns("*.txt\n!*.tmp").unwrap();
+        filter.set_min_size(100);
+        filter.set_max_size(1000);
+        
+        assert_eq!(filter.skip_reason(&PathBuf::from("test.txt"), 500), None);
+        assert_eq!(filter.skip_reason(&PathBuf::from("test.txt"), 50), Some(SkipReason::TooSmall(100)));
+        assert_eq!(filter.skip_reason(&PathBuf::from("test.txt"), 2000), Some(SkipReason::TooLarge(1000)));
+        assert_eq!(
+            filter.skip_reason(&PathBuf::from("test.tmp"), 500),
+            Some(SkipReason::ExcludedByPattern("*.tmp".to_string()))
+        );
+        assert_eq!(filter.skip_reason(&PathBuf::from("test.jpg"), 500), Some(SkipReason::NotIncluded));
+    }
 }
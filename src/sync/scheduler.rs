@@ -1,116 +1,89 @@
-use anyhow::Result;
-use log::{error, info};
-use std::sync::{Arc, Mutex};
+use log::info;
+use std::sync::mpsc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
 use tokio::time;
 
-use crate::ui::folder_list::SyncFolder;
+use crate::ui::app_state::StatusMessage;
 
 /// Scheduler for periodic sync operations
-#[allow(dead_code)] // Will be used in future implementations
+#[derive(Default)]
 pub struct SyncScheduler {
     interval_minutes: u32,
-    running: Arc<Mutex<bool>>,
-    folders: Arc<Mutex<Vec<SyncFolder>>>,
-}
-
-/// Sync task message
-#[allow(dead_code)] // Will be used in future implementations
-pub enum SyncTask {
-    /// Sync a specific folder
-    SyncFolder(usize),
-    /// Sync all folders
-    SyncAll,
-    /// Stop all sync operations
-    Stop,
+    /// Timer task sending the scheduled syncs, while one is running
+    timer: Option<JoinHandle<()>>,
 }
 
 impl SyncScheduler {
-    /// Create a new sync scheduler
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn new(interval_minutes: u32) -> Self {
-        Self {
-            interval_minutes,
-            running: Arc::new(Mutex::new(false)),
-            folders: Arc::new(Mutex::new(Vec::new())),
+    /// Set the sync interval in minutes (0 = manual only), restarting the timer if it changed.
+    /// `StatusMessage::ScheduledSyncDue` is sent each time the interval passes.
+    pub fn set_interval(&mut self, rt: &Handle, minutes: u32, tx: mpsc::Sender<StatusMessage>) {
+        if minutes == self.interval_minutes && (minutes == 0 || self.timer.is_some()) {
+            return;
         }
-    }
-    
-    /// Set the sync interval in minutes (0 = manual only)
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn set_interval(&mut self, minutes: u32) {
+        
+        self.stop();
         self.interval_minutes = minutes;
-    }
-    
-    /// Update the folders to sync
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn update_folders(&mut self, folders: Vec<SyncFolder>) {
-        let mut folders_lock = self.folders.lock().unwrap();
-        *folders_lock = folders;
-    }
-    
-    /// Start the scheduler
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn start(&self, tx: mpsc::Sender<SyncTask>) -> Result<()> {
-        if self.interval_minutes == 0 {
-            info!("Scheduler not started (manual sync only)");
-            return Ok(());
-        }
         
-        {
-            let mut running = self.running.lock().unwrap();
-            if *running {
-                return Ok(());
-            }
-            *running = true;
+        if minutes == 0 {
+            info!("Scheduler not started (manual sync only)");
+            return;
         }
         
-        let interval_minutes = self.interval_minutes;
-        let running = self.running.clone();
-        let folders = self.folders.clone();
-        
-        tokio::spawn(async move {
-            info!("Starting sync scheduler with interval of {} minutes", interval_minutes);
+        info!("Starting sync scheduler with interval of {} minutes", minutes);
+        self.start(rt, Duration::from_secs(minutes as u64 * 60), tx);
+    }
+    
+    /// Start the timer, sending a scheduled sync every period
+    fn start(&mut self, rt: &Handle, period: Duration, tx: mpsc::Sender<StatusMessage>) {
+        self.timer = Some(rt.spawn(async move {
+            // The first tick would fire at once, so start a full interval from now
+            let mut interval = time::interval_at(time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
             
             loop {
-                // Wait for the specified interval
-                time::sleep(Duration::from_secs(interval_minutes as u64 * 60)).await;
-                
-                // Check if we should still be running
-                {
-                    let running_lock = running.lock().unwrap();
-                    if !*running_lock {
-                        break;
-                    }
-                }
-                
-                // Get the number of folders
-                let folder_count = {
-                    let folders_lock = folders.lock().unwrap();
-                    folders_lock.len()
-                };
+                interval.tick().await;
                 
-                // Send sync task for each folder
-                for i in 0..folder_count {
-                    if tx.send(SyncTask::SyncFolder(i)).await.is_err() {
-                        error!("Failed to send sync task for folder {}", i);
-                        break;
-                    }
+                if tx.send(StatusMessage::ScheduledSyncDue).is_err() {
+                    // The app has shut down
+                    break;
                 }
             }
-            
-            info!("Sync scheduler stopped");
-        });
-        
-        Ok(())
+        }));
     }
     
     /// Stop the scheduler
-    #[allow(dead_code)] // Will be used in future implementations
-    pub fn stop(&self) {
-        let mut running = self.running.lock().unwrap();
-        *running = false;
-        info!("Stopping sync scheduler");
+    pub fn stop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+            info!("Stopping sync scheduler");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_scheduled_sync_due() {
+        let (tx, rx) = mpsc::channel();
+        let mut scheduler = SyncScheduler::default();
+        
+        scheduler.set_interval(&Handle::current(), 0, tx.clone());
+        assert!(scheduler.timer.is_none());
+        
+        // Nothing is sent until a full interval has passed
+        scheduler.start(&Handle::current(), Duration::from_millis(50), tx);
+        time::sleep(Duration::from_millis(20)).await;
+        assert!(rx.try_recv().is_err());
+        
+        time::sleep(Duration::from_millis(60)).await;
+        assert!(matches!(rx.try_recv(), Ok(StatusMessage::ScheduledSyncDue)));
+        
+        scheduler.stop();
+        time::sleep(Duration::from_millis(120)).await;
+        assert!(rx.try_recv().is_err());
     }
 }
@@ -0,0 +1,98 @@
+use eframe::egui;
+
+use crate::ui::shortcuts::SHORTCUTS;
+use crate::ui::utils::copy_button;
+
+/// About dialog: version, configured region and keyboard shortcuts
+pub struct AboutView;
+
+impl AboutView {
+    /// Show the dialog as a window, clearing `open` when it is closed
+    pub fn show(ctx: &egui::Context, open: &mut bool, region: &str) {
+        let mut close = false;
+        
+        egui::Window::new("About S3Sync")
+            .open(open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.heading("S3Sync");
+                ui.label("Sync local folders with Amazon S3 buckets.");
+                
+                ui.separator();
+                
+                egui::Grid::new("about_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Version:");
+                        ui.label(env!("CARGO_PKG_VERSION"));
+                        ui.end_row();
+                        
+                        ui.label("AWS SDK for S3:");
+                        ui.label(aws_sdk_s3::meta::PKG_VERSION);
+                        ui.end_row();
+                        
+                        ui.label("Region:");
+                        ui.label(region);
+                        ui.end_row();
+                    });
+                
+                ui.separator();
+                ui.strong("Keyboard shortcuts");
+                ui.label("Available in the main view when no text field is being edited.");
+                
+                egui::Grid::new("shortcuts_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for (_, keys, description) in SHORTCUTS {
+                            ui.label(egui::RichText::new(keys).monospace());
+                            ui.label(description);
+                            ui.end_row();
+                        }
+                    });
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    // Versions, region and OS for a bug report
+                    copy_button(ui, "Copy diagnostics", &Self::diagnostics(region));
+                    
+                    if ui.button("Close").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        
+        if close {
+            *open = false;
+        }
+    }
+    
+    /// Version, region and OS details to paste into a bug report
+    pub fn diagnostics(region: &str) -> String {
+        format!(
+            "S3Sync {}\naws-sdk-s3 {}\nRegion: {}\nOS: {} ({})",
+            env!("CARGO_PKG_VERSION"),
+            aws_sdk_s3::meta::PKG_VERSION,
+            if region.is_empty() { "not set" } else { region },
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_diagnostics() {
+        let diagnostics = AboutView::diagnostics("eu-west-1");
+        assert!(diagnostics.starts_with(&format!("S3Sync {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(diagnostics.contains("Region: eu-west-1\n"));
+        assert!(diagnostics.contains(std::env::consts::OS));
+        
+        assert!(AboutView::diagnostics("").contains("Region: not set"));
+    }
+}
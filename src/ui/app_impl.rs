@@ -1,25 +1,48 @@
 use eframe::egui;
 use eframe::epi;
 // use log::debug;
-use std::sync::Arc;
+use log::warn;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc;
 use tokio::runtime::Handle;
-use tokio::sync::Mutex as TokioMutex;
 
-use crate::aws::auth::AwsAuth;
+use crate::aws::auth::{AwsAuth, SharedAuth};
+use crate::aws::throttle::SharedRateLimiter;
+use crate::aws::s3::S3ErrorHelper;
+use crate::config::app_config::AppConfig;
 use crate::config::credentials::CredentialManager;
-use crate::ui::app_state::{AppState, CurrentView, StatusMessage};
-// use crate::ui::aws_operations::AwsOperations;
+use crate::config::region_cache::RegionCache;
+use crate::config::transfer_history::TransferHistory;
+use crate::ui::about_view::AboutView;
+use crate::ui::app_state::{AppState, ConnectionState, CurrentView, StatusMessage};
+use crate::ui::aws_operations::AwsOperations;
 use crate::ui::bucket_view::BucketView;
+use crate::ui::compare_view::CompareView;
+use crate::ui::compare_view_renderer::CompareViewRenderer;
+use crate::ui::copy_object_view::ObjectCopy;
+use crate::ui::filter_view::FilterView;
 use crate::ui::filter_view_renderer::FilterViewRenderer;
 use crate::ui::folder_content::FolderContent;
-use crate::ui::folder_list::FolderList;
+use crate::ui::folder_list::{FolderList, SyncStatus};
 use crate::ui::main_view_renderer::MainViewRenderer;
+use crate::sync::engine::SyncResult;
+use crate::sync::filter::FileFilter;
+use crate::sync::scheduler::SyncScheduler;
 use crate::ui::menu_bar_renderer::MenuBarRenderer;
-use crate::ui::progress::ProgressView;
+use crate::ui::notification;
+use crate::ui::object_history::HistoryAction;
+use crate::ui::preview::PreviewPane;
+use crate::ui::progress::{ProgressAction, ProgressView};
 use crate::ui::settings::SettingsView;
+use crate::ui::session_token_view::SessionTokenView;
 use crate::ui::settings_view_renderer::SettingsViewRenderer;
+use crate::ui::shortcuts::Shortcut;
 use crate::ui::status_bar_renderer::StatusBarRenderer;
+use crate::ui::status_log::StatusLog;
+use crate::ui::transfer_history_view::TransferHistoryView;
+use crate::ui::transfer_history_view_renderer::TransferHistoryViewRenderer;
+use crate::ui::utils::warning_color;
 
 /// Main application implementation
 pub struct S3SyncApp {
@@ -39,54 +62,150 @@ impl Default for S3SyncApp {
                 settings_view: SettingsView::default(),
                 progress_view: ProgressView::default(),
                 filter_view: None,
+                object_history: None,
+                object_properties: None,
+                preview: PreviewPane::default(),
+                bucket_config: None,
+                delete_preview: None,
+                sync_preview: None,
+                sync_dry_run: false,
+                session_prompt: None,
+                create_bucket: None,
+                copy_object: None,
+                compare_view: CompareView::default(),
+                sync_scheduler: SyncScheduler::default(),
+                history_view: TransferHistoryView::default(),
+                transfer_history: match TransferHistory::open() {
+                    Ok(history) => Some(Arc::new(history)),
+                    Err(e) => {
+                        warn!("Transfer history is disabled: {}", e);
+                        None
+                    }
+                },
                 current_view: CurrentView::Main,
                 show_progress: false,
-                aws_auth: Arc::new(TokioMutex::new(AwsAuth::default())),
-                status_message: String::new(),
-                status_is_error: false,
+                flatten_downloads: false,
+                upload_to_prefix: false,
+                upload_key_template: String::new(),
+                aws_auth: Arc::new(SharedAuth::new(AwsAuth::default())),
+                rate_limiter: SharedRateLimiter::default(),
+                credentials_expiry: None,
+                offline: false,
+                connection: ConnectionState::Unknown,
+                status: StatusLog::default(),
+                show_status_log: false,
+                show_about: false,
+                failed_transfers: Vec::new(),
+                access_denied: None,
                 status_tx: tx,
                 status_rx: rx,
                 rt: Handle::current(),
                 credential_manager: CredentialManager::default(),
+                config: AppConfig::load(),
+                applied_theme: None,
             }
         };
         
-        // Try to load credentials from the system keyring
+        // Record finished transfers in the history
+        app.state.progress_view.set_history(app.state.transfer_history.clone());
+        
+        // Start with the bucket regions found in earlier sessions
+        match RegionCache::open() {
+            Ok(cache) => app.state.bucket_view.set_region_cache(cache),
+            Err(e) => warn!("Bucket regions won't be kept between sessions: {}", e),
+        }
+        
+        // Apply the saved configuration: settings, folder mappings and the file filter
+        let config = app.state.config.clone();
+        app.state.settings_view.load_from(&config.settings);
+        app.state.rate_limiter.set_limit(config.settings.bandwidth_limit);
+        let app_retries = app.state.settings_view.retry_policy().max_retries > 0;
+        AwsOperations::update_auth(&app.state, "applying retry settings", move |auth| auth.set_app_retries(app_retries));
+        app.state.folder_list.folders = config.settings.to_sync_folders();
+        if !config.filter.is_empty() {
+            let filter_view = FilterView::from_config(Arc::new(Mutex::new(FileFilter::new())), &config.filter);
+            let filter_string = filter_view.get_filter().lock().unwrap().to_string();
+            app.state.folder_content.set_filter(filter_string.clone());
+            app.state.bucket_view.set_filter(filter_string);
+            app.state.filter_view = Some(filter_view);
+        }
+        
+        // Try to load credentials. The secret key is in the system keyring; credentials saved
+        // by earlier versions also keep the access key and region there.
         if CredentialManager::has_credentials() {
-            match (
-                CredentialManager::load_access_key(),
-                CredentialManager::load_secret_key(),
-                CredentialManager::load_region()
-            ) {
-                (Ok(access_key), Ok(secret_key), Ok(region)) if !access_key.is_empty() && !secret_key.is_empty() => {
+            let legacy = config.access_key_id.is_empty();
+            let (access_key, region) = if legacy {
+                (
+                    CredentialManager::load_access_key().unwrap_or_default(),
+                    CredentialManager::load_region().unwrap_or(config.settings.aws_region),
+                )
+            } else {
+                (config.access_key_id, config.settings.aws_region)
+            };
+            
+            match CredentialManager::load_secret_key() {
+                Ok(secret_key) if !access_key.is_empty() && !secret_key.is_empty() => {
+                    // Keep migrated credentials in the configuration from now on, removing
+                    // them from the keyring once the configuration holds them
+                    app.state.config.access_key_id = access_key.clone();
+                    app.state.config.settings.aws_region = region.clone();
+                    if legacy {
+                        match app.state.config.save() {
+                            Ok(()) => CredentialManager::clear_legacy_entries(),
+                            Err(e) => warn!("Failed to save migrated credentials: {}", e),
+                        }
+                    }
+                    
                     // Update the settings view with the loaded credentials
                     app.state.settings_view.set_aws_access_key(access_key.clone());
                     app.state.settings_view.set_aws_secret_key(secret_key.clone());
                     app.state.settings_view.set_aws_region(region.clone());
+                    app.state.settings_view.set_save_credentials(true);
                     
                     // Update AWS auth with the loaded credentials
-                    let auth_clone = app.state.aws_auth.clone();
                     let access_key_clone = access_key.clone();
-                    let secret_key_clone = secret_key.clone();
                     let region_clone = region.clone();
+                    let endpoint_url = app.state.settings_view.endpoint_url();
                     
-                    // Use a blocking task to set the credentials
-                    tokio::task::block_in_place(|| {
-                        app.state.rt.block_on(async {
-                            let mut auth = auth_clone.lock().await;
-                            auth.set_credentials(access_key_clone, secret_key_clone, region_clone);
-                        });
+                    AwsOperations::update_auth(&app.state, "loading saved credentials", move |auth| {
+                        auth.set_credentials(access_key_clone, secret_key, None, region_clone);
+                        auth.set_endpoint_url(endpoint_url);
                     });
                     
-                    app.state.status_message = format!("Loaded credentials from keyring for region {}", region);
+                    app.state.set_status_info(&format!("Loaded credentials from keyring for region {}", region));
                 },
                 _ => {
                     // No credentials found or error loading them
-                    app.state.status_message = "No saved credentials found. Please enter your AWS credentials in Settings.".to_string();
+                    app.state.set_status_info("No saved credentials found. Please enter your AWS credentials in Settings.");
                 }
             }
         }
         
+        // A named profile from the shared AWS files takes the place of the access keys
+        if let Some(profile) = app.state.settings_view.aws_profile() {
+            let region = app.state.settings_view.aws_region();
+            let endpoint_url = app.state.settings_view.endpoint_url();
+            let profile_clone = profile.clone();
+            
+            AwsOperations::update_auth(&app.state, "selecting a profile", move |auth| {
+                auth.set_region(region);
+                auth.set_endpoint_url(endpoint_url);
+                auth.set_profile(Some(profile_clone));
+            });
+            
+            app.state.set_status_info(&format!("Using AWS profile {}", profile));
+        }
+        
+        // Keep temporary credentials fresh for the whole session
+        AwsOperations::start_credential_refresh(&app.state);
+        
+        // Switch to offline mode while AWS can't be reached
+        AwsOperations::start_connectivity_check(&app.state);
+        
+        // Sync the folders at the configured interval, if there is one
+        let tx = app.state.status_tx.clone();
+        app.state.sync_scheduler.set_interval(&app.state.rt, config.settings.sync_interval, tx);
+        
         app
     }
 }
@@ -96,13 +215,143 @@ impl epi::App for S3SyncApp {
         "S3 Sync"
     }
     
+    fn on_exit(&mut self) {
+        // Folders added or removed during the session are saved with the rest of the configuration
+        self.state.save_config();
+    }
+    
     fn update(&mut self, ctx: &egui::Context, _frame: &epi::Frame) {
         // Process any status messages
-        self.process_status_messages();
+        self.process_status_messages(ctx);
+        
+        self.apply_theme(ctx);
         
         // Show progress view if needed
         if self.state.show_progress {
-            self.state.progress_view.show(ctx);
+            let (open, action) = self.state.progress_view.show(ctx);
+            self.state.show_progress = open;
+            
+            if let Some(ProgressAction::RetryFailed) = action {
+                // Retried here, so they leave the failed list under the status bar
+                let failures = self.state.progress_view.failed_transfers();
+                self.state.failed_transfers.retain(|listed| {
+                    !failures.iter().any(|failure| failure.bucket == listed.bucket && failure.key == listed.key)
+                });
+                AwsOperations::retry_transfers(&mut self.state, failures);
+            }
+        }
+        
+        // Show the object history window if open
+        if let Some(history) = &self.state.object_history {
+            let (open, action) = history.show(ctx);
+            let key = history.key.clone();
+            
+            match action {
+                Some(HistoryAction::Download(version_id)) => {
+                    AwsOperations::download_object_version(&mut self.state, &key, &version_id);
+                },
+                Some(HistoryAction::Restore(version)) => {
+                    AwsOperations::restore_object_version(&mut self.state, &key, version);
+                },
+                None => {}
+            }
+            
+            if !open {
+                self.state.object_history = None;
+            }
+        }
+        
+        // Show the object properties window if open
+        if let Some(properties) = &self.state.object_properties {
+            if !properties.show(ctx) {
+                self.state.object_properties = None;
+            }
+        }
+        
+        // Show the bucket configuration window if open
+        if let Some(config) = &self.state.bucket_config {
+            if !config.show(ctx) {
+                self.state.bucket_config = None;
+            }
+        }
+        
+        // Show the delete preview if open
+        if let Some(preview) = &self.state.delete_preview {
+            let (open, confirmed) = preview.show(ctx);
+            
+            if confirmed {
+                let bucket = preview.bucket.clone();
+                let keys = preview.keys();
+                AwsOperations::delete_objects(&mut self.state, &bucket, keys);
+            }
+            
+            if !open {
+                self.state.delete_preview = None;
+            }
+        }
+        
+        // Show the sync preview if open
+        if let Some(preview) = &self.state.sync_preview {
+            let (open, confirmed) = preview.show(ctx);
+            
+            if confirmed {
+                let (folder, bucket, key_prefix) = (preview.folder.clone(), preview.bucket.clone(), preview.key_prefix.clone());
+                let deletes = preview.delete_keys();
+                AwsOperations::run_sync(&mut self.state, folder, bucket, key_prefix, Some(deletes));
+            }
+            
+            if !open {
+                self.state.sync_preview = None;
+            }
+        }
+        
+        // Ask for new credentials if the session token has expired
+        if let Some(dialog) = &mut self.state.session_prompt {
+            let (open, confirmed) = dialog.show(ctx);
+            
+            if let Some(credentials) = confirmed {
+                if let Some(dialog) = self.state.session_prompt.take() {
+                    AwsOperations::renew_session(&mut self.state, credentials, dialog);
+                }
+            } else if !open {
+                // Nothing is retried, but the failed transfers stay listed
+                if let Some(dialog) = self.state.session_prompt.take() {
+                    let (failures, _) = dialog.into_pending();
+                    self.state.failed_transfers.extend(failures);
+                }
+            }
+        }
+        
+        // Show the new bucket dialog if open
+        if let Some(dialog) = &mut self.state.create_bucket {
+            let (open, confirmed) = dialog.show(ctx);
+            
+            if let Some((name, region)) = confirmed {
+                AwsOperations::create_bucket(&mut self.state, &name, &region);
+            }
+            
+            if !open {
+                self.state.create_bucket = None;
+            }
+        }
+        
+        // Show the rename or copy dialog if open
+        if let Some(dialog) = &mut self.state.copy_object {
+            let (open, confirmed) = dialog.show(ctx);
+            
+            if let Some(copy) = confirmed {
+                AwsOperations::copy_object(&mut self.state, copy);
+            }
+            
+            if !open {
+                self.state.copy_object = None;
+            }
+        }
+        
+        // Show the About dialog if open
+        if self.state.show_about {
+            let region = self.state.settings_view.aws_region();
+            AboutView::show(ctx, &mut self.state.show_about, &region);
         }
         
         // Render the menu bar
@@ -111,6 +360,16 @@ impl epi::App for S3SyncApp {
         // Render the status bar
         StatusBarRenderer::render(&mut self.state, ctx);
         
+        // Explain offline mode while it lasts
+        if self.state.offline {
+            egui::TopBottomPanel::top("offline_banner").show(ctx, |ui| {
+                ui.colored_label(
+                    warning_color(ui),
+                    "⚠ Offline: AWS actions are disabled. Local folders and settings are still available.",
+                );
+            });
+        }
+        
         // Main content
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.state.current_view {
@@ -119,38 +378,373 @@ impl epi::App for S3SyncApp {
                 },
                 CurrentView::Filter => FilterViewRenderer::render(&mut self.state, ui),
                 CurrentView::Settings => SettingsViewRenderer::render(&mut self.state, ui),
+                CurrentView::Compare => CompareViewRenderer::render(&mut self.state, ui),
+                CurrentView::History => TransferHistoryViewRenderer::render(&mut self.state, ui),
             }
         });
+        
+        // Files and folders dropped from the file manager are uploaded to the bucket shown
+        if matches!(self.state.current_view, CurrentView::Main) {
+            self.upload_dropped_files(ctx);
+            self.handle_shortcuts(ctx);
+        }
     }
 }
 
 impl S3SyncApp {
+    /// Switch between light and dark visuals when the theme setting changes.
+    /// The operating system is only asked for its mode when System is chosen.
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        let theme = self.state.settings_view.theme();
+        if self.state.applied_theme == Some(theme) {
+            return;
+        }
+        
+        if theme.is_dark() {
+            ctx.set_visuals(egui::Visuals::dark());
+        } else {
+            ctx.set_visuals(egui::Visuals::light());
+        }
+        self.state.applied_theme = Some(theme);
+    }
+    
+    /// Upload the files and folders dropped onto the window this frame
+    fn upload_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped: Vec<PathBuf> = ctx.input().raw.dropped_files.iter()
+            .filter_map(|file| file.path.clone())
+            .collect();
+        
+        if !dropped.is_empty() {
+            AwsOperations::upload_dropped(&mut self.state, dropped);
+        }
+    }
+    
+    /// Run the actions for the keyboard shortcuts pressed this frame
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        for shortcut in Shortcut::pressed(ctx) {
+            let has_selection = !self.state.bucket_view.selected_objects().is_empty();
+            
+            match shortcut {
+                Shortcut::Refresh => match self.state.bucket_view.selected_bucket() {
+                    Some(bucket) => AwsOperations::load_bucket_objects(&mut self.state, &bucket),
+                    None => AwsOperations::refresh_buckets(&mut self.state),
+                },
+                Shortcut::SelectAll => self.state.bucket_view.select_all_visible(),
+                // The delete preview asks for confirmation before anything is deleted
+                Shortcut::Delete if has_selection => AwsOperations::preview_delete_selected(&mut self.state),
+                Shortcut::Upload => AwsOperations::upload_selected(&mut self.state),
+                Shortcut::Download if has_selection => AwsOperations::download_selected(&mut self.state),
+                Shortcut::Delete | Shortcut::Download => {}
+            }
+        }
+    }
+    
+    /// Close the copy dialog once an object has been copied and refresh the listing,
+    /// or keep the dialog open with the error so the destination can be changed
+    fn report_object_copy(&mut self, copy: ObjectCopy, result: Result<(), String>) {
+        let dialog_matches = self.state.copy_object.as_ref().map_or(false, |dialog| dialog.key == copy.src_key);
+        
+        match result {
+            Ok(()) => {
+                if dialog_matches {
+                    self.state.copy_object = None;
+                }
+                
+                let verb = if copy.remove_source { "Moved" } else { "Copied" };
+                self.state.set_status_info(&format!(
+                    "{} s3://{}/{} to s3://{}/{}",
+                    verb, copy.src_bucket, copy.src_key, copy.dst_bucket, copy.dst_key
+                ));
+                
+                // Show the new key, and drop the old one after a move
+                if let Some(bucket) = self.state.bucket_view.selected_bucket() {
+                    if bucket == copy.dst_bucket || (copy.remove_source && bucket == copy.src_bucket) {
+                        AwsOperations::load_bucket_objects(&mut self.state, &bucket);
+                    }
+                }
+            },
+            Err(e) => {
+                if let Some(dialog) = self.state.copy_object.as_mut().filter(|_| dialog_matches) {
+                    dialog.set_failed(e.clone());
+                }
+                self.state.set_status_error(&e);
+            }
+        }
+    }
+    
+    /// Report how many keys matched a finished search, unless it has been replaced since
+    fn report_search(&mut self, bucket: &str, query: &str, result: Result<bool, String>) {
+        let search = match self.state.bucket_view.finish_search(bucket, query) {
+            Some(search) => search,
+            None => return,
+        };
+        let found = search.matches.len();
+        let scanned = search.scanned;
+        
+        match result {
+            Ok(true) => {
+                self.state.set_status_info(&format!("Found {} objects matching '{}' in {} keys", found, query, scanned));
+            },
+            Ok(false) => {
+                self.state.set_status_info(&format!("Search cancelled: {} objects matching '{}' in the first {} keys", found, query, scanned));
+            },
+            Err(e) => {
+                self.state.set_status_error(&e);
+            }
+        }
+    }
+    
+    /// Get the prompt for new credentials, opening it if it isn't already
+    fn session_prompt(&mut self) -> &mut SessionTokenView {
+        let access_key = self.state.settings_view.get_settings().aws_access_key;
+        let custom_endpoint = self.state.settings_view.endpoint_url().is_some();
+        self.state.session_prompt.get_or_insert_with(|| SessionTokenView::new(access_key, custom_endpoint))
+    }
+    
+    /// Report how many objects are listed, and whether there are more to load
+    fn report_loaded_objects(&mut self) {
+        let count = self.state.bucket_view.objects().len();
+        let message = if self.state.bucket_view.has_more_objects() {
+            format!("Loaded {} objects, more available", count)
+        } else {
+            format!("Loaded {} objects", count)
+        };
+        self.state.set_status_info(&message);
+    }
+    
     /// Process any status messages in the queue
-    fn process_status_messages(&mut self) {
+    fn process_status_messages(&mut self, ctx: &egui::Context) {
         while let Ok(msg) = self.state.status_rx.try_recv() {
             match msg {
                 StatusMessage::Info(text) => {
-                    self.state.status_message = text;
-                    self.state.status_is_error = false;
+                    self.state.set_status_info(&text);
                 },
                 StatusMessage::Error(text) => {
-                    self.state.status_message = text;
-                    self.state.status_is_error = true;
+                    if S3ErrorHelper::is_network_error(&text) {
+                        self.state.connection = ConnectionState::Disconnected;
+                    }
+                    
+                    if S3ErrorHelper::is_expired_token(&text) {
+                        self.state.set_status_error(&format!("{}. Your session token has expired: enter a new one in Settings.", text));
+                    } else {
+                        self.state.set_status_error(&text);
+                    }
+                },
+                StatusMessage::ObjectList(objects, continuation_token) => {
+                    self.state.connection = ConnectionState::Connected;
+                    self.state.bucket_view.set_objects(objects, continuation_token);
+                    self.report_loaded_objects();
+                },
+                StatusMessage::MoreObjects(bucket, prefix, objects, continuation_token) => {
+                    // Pages for a bucket or prefix the user has since left are dropped
+                    let current = self.state.bucket_view.selected_bucket().as_deref() == Some(bucket.as_str())
+                        && self.state.bucket_view.prefix() == prefix;
+                    if current {
+                        self.state.bucket_view.append_objects(objects, continuation_token);
+                        self.report_loaded_objects();
+                    }
+                },
+                StatusMessage::SearchMatches(bucket, query, matches, scanned) => {
+                    self.state.bucket_view.add_search_matches(&bucket, &query, matches, scanned);
                 },
-                StatusMessage::ObjectList(objects) => {
-                    self.state.bucket_view.set_objects(objects);
-                    self.state.status_message = format!("Loaded {} objects", self.state.bucket_view.objects().len());
+                StatusMessage::SearchFinished(bucket, query, result) => {
+                    self.report_search(&bucket, &query, result);
                 },
                 StatusMessage::BucketList(buckets) => {
+                    self.state.connection = ConnectionState::Connected;
                     self.state.bucket_view.set_buckets(buckets);
                 },
-                StatusMessage::Progress(progress) => {
-                    self.state.progress_view.update_progress(progress);
+                StatusMessage::BucketRegion(bucket, region) => {
+                    self.state.bucket_view.set_bucket_region(&bucket, region);
+                },
+                StatusMessage::BucketRegionMoved(bucket) => {
+                    self.state.bucket_view.forget_bucket_region(&bucket);
+                },
+                StatusMessage::BucketCreated(bucket, result) => {
+                    match result {
+                        Ok(()) => {
+                            if self.state.create_bucket.as_ref().map_or(false, |dialog| dialog.name() == bucket) {
+                                self.state.create_bucket = None;
+                            }
+                            self.state.set_status_info(&format!("Created bucket {}", bucket));
+                        },
+                        Err(e) => {
+                            if let Some(dialog) = self.state.create_bucket.as_mut().filter(|dialog| dialog.name() == bucket) {
+                                dialog.set_failed(e.clone());
+                            }
+                            self.state.set_status_error(&e);
+                        },
+                    }
+                },
+                StatusMessage::ObjectVersions(key, versions) => {
+                    if let Some(history) = self.state.object_history.as_mut().filter(|h| h.key == key) {
+                        history.set_versions(versions);
+                    }
+                },
+                StatusMessage::ObjectExpiration(key, expiration) => {
+                    if let Some(history) = self.state.object_history.as_mut().filter(|h| h.key == key) {
+                        history.set_expiration(expiration);
+                    }
                 },
-                StatusMessage::SyncComplete => {
-                    self.state.show_progress = false;
+                StatusMessage::ObjectCopied(copy, result) => {
+                    self.report_object_copy(copy, result);
+                },
+                StatusMessage::CopyDestinationExists(copy) => {
+                    let message = format!("s3://{}/{} already exists", copy.dst_bucket, copy.dst_key);
+                    if let Some(dialog) = self.state.copy_object.as_mut().filter(|dialog| dialog.key == copy.src_key) {
+                        dialog.set_destination_exists(copy);
+                        self.state.set_status_info(&message);
+                    } else {
+                        self.state.set_status_error(&format!("Not copied: {}", message));
+                    }
+                },
+                StatusMessage::Preview(key, content) => {
+                    self.state.preview.set_content(&key, content);
+                },
+                StatusMessage::ObjectMetadata(key, metadata) => {
+                    if let Some(properties) = self.state.object_properties.as_mut().filter(|p| p.key == key) {
+                        properties.set_metadata(metadata);
+                    }
+                },
+                StatusMessage::ShareLink(key, url, upload) => {
+                    ctx.output().copied_text = url;
+                    let kind = if upload { "upload link" } else { "share link" };
+                    self.state.set_status_info(&format!("Copied {} for {} to the clipboard", kind, key));
+                },
+                StatusMessage::BucketConfiguration(bucket, lifecycle, cors) => {
+                    if let Some(config) = self.state.bucket_config.as_mut().filter(|c| c.bucket == bucket) {
+                        config.set_configuration(lifecycle, cors);
+                    }
+                },
+                StatusMessage::DeletePreview(bucket, objects, versioned) => {
+                    if let Some(preview) = self.state.delete_preview.as_mut().filter(|p| p.bucket == bucket) {
+                        preview.set_objects(objects, versioned);
+                    }
+                },
+                StatusMessage::CredentialsExpiry(expiry) => {
+                    self.state.credentials_expiry = expiry;
+                },
+                StatusMessage::TransferFailures(failures) => {
+                    if failures.iter().any(|failure| S3ErrorHelper::is_network_error(&failure.error)) {
+                        self.state.connection = ConnectionState::Disconnected;
+                    }
+                    for failure in failures.iter().filter(|failure| S3ErrorHelper::is_redirect_error(&failure.error)) {
+                        self.state.bucket_view.forget_bucket_region(&failure.bucket);
+                    }
+                    
+                    // Transfers that failed on an expired session token are retried once new credentials are entered
+                    let (expired, failures): (Vec<_>, Vec<_>) = failures.into_iter()
+                        .partition(|failure| S3ErrorHelper::is_expired_token(&failure.error));
+                    if !expired.is_empty() {
+                        self.session_prompt().add_failures(expired);
+                    }
+                    self.state.failed_transfers.extend(failures);
+                },
+                StatusMessage::SessionExpired => {
+                    self.session_prompt().set_reload_objects();
+                },
+                StatusMessage::SessionRenewed(failures, reload_objects) => {
+                    self.state.set_status_info("Using the new credentials");
+                    if !failures.is_empty() {
+                        AwsOperations::retry_transfers(&mut self.state, failures);
+                    }
+                    if reload_objects {
+                        if let Some(bucket) = self.state.bucket_view.selected_bucket() {
+                            AwsOperations::load_bucket_objects(&mut self.state, &bucket);
+                        }
+                    }
+                },
+                StatusMessage::Connectivity(online) => {
+                    if online == self.state.offline {
+                        self.state.offline = !online;
+                        if online {
+                            self.state.set_status_info("Back online");
+                        } else {
+                            self.state.set_status_error("No network connection. AWS actions are disabled until it returns.");
+                        }
+                    }
+                },
+                StatusMessage::AccessDenied(context) => {
+                    self.state.access_denied = Some(context);
+                },
+                StatusMessage::ScheduledSyncDue => {
+                    AwsOperations::run_scheduled_sync(&mut self.state);
+                },
+                StatusMessage::ScheduledSyncComplete(results) => {
+                    self.report_scheduled_sync(results);
+                },
+                StatusMessage::CompareResult(result) => {
+                    self.state.compare_view.set_result(result);
+                },
+                StatusMessage::SyncPlan(folder, bucket, plan) => {
+                    if let Some(preview) = self.state.sync_preview.as_mut().filter(|p| p.folder == folder && p.bucket == bucket) {
+                        preview.set_plan(plan);
+                    }
+                },
+                StatusMessage::SyncFinished(folder, bucket, result) => {
+                    self.report_sync(folder, &bucket, result);
+                }
+            }
+        }
+    }    
+    /// Post the outcome of syncing a folder to the status bar and the folder list
+    fn report_sync(&mut self, folder: PathBuf, bucket: &str, result: Result<SyncResult, String>) {
+        match result {
+            Ok(result) => {
+                for error in &result.errors {
+                    warn!("Sync of {}: {}", folder.display(), error);
+                }
+                
+                let message = format!("Synced {} with bucket {}: {}", folder.display(), bucket, result.summary());
+                if result.errors.is_empty() {
+                    self.state.folder_list.update_status(&folder, SyncStatus::Synced);
+                    self.state.set_status_info(&message);
+                } else {
+                    let status = SyncStatus::Error(format!("{} errors", result.errors.len()));
+                    self.state.folder_list.update_status(&folder, status);
+                    self.state.set_status_error(&message);
                 }
+            },
+            Err(e) => {
+                self.state.folder_list.update_status(&folder, SyncStatus::Error(e.clone()));
+                self.state.set_status_error(&format!("Failed to sync {} with bucket {}: {}", folder.display(), bucket, e));
             }
         }
     }
+    
+    /// Post a summary of a scheduled sync to the status bar, and as a desktop notification if enabled
+    fn report_scheduled_sync(&mut self, results: Vec<(PathBuf, SyncResult)>) {
+        let mut lines = Vec::new();
+        let mut has_errors = false;
+        
+        for (folder, result) in &results {
+            let name = folder.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| folder.display().to_string());
+            lines.push(format!("{}: {}", name, result.summary()));
+            
+            for error in &result.errors {
+                warn!("Scheduled sync of {}: {}", folder.display(), error);
+            }
+            has_errors |= !result.errors.is_empty();
+            
+            let status = if result.errors.is_empty() {
+                SyncStatus::Synced
+            } else {
+                SyncStatus::Error(format!("{} errors", result.errors.len()))
+            };
+            self.state.folder_list.update_status(folder, status);
+        }
+        
+        let message = format!("Scheduled sync finished. {}", lines.join("; "));
+        if has_errors {
+            self.state.set_status_error(&message);
+        } else {
+            self.state.set_status_info(&message);
+        }
+        
+        if self.state.settings_view.sync_notifications() {
+            notification::show("Scheduled sync finished", &lines.join("\n"));
+        }
+    }
 }
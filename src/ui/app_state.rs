@@ -1,35 +1,116 @@
+use log::error;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::mpsc;
+use std::time::SystemTime;
 use tokio::runtime::Handle;
-use tokio::sync::Mutex as TokioMutex;
 
-use crate::aws::auth::AwsAuth;
-use crate::aws::transfer::TransferProgress;
+use crate::aws::auth::SharedAuth;
+use crate::aws::throttle::SharedRateLimiter;
+use crate::aws::bucket::{CorsRuleInfo, LifecycleRuleInfo};
+use crate::aws::s3::PermissionContext;
+use crate::aws::transfer::{ObjectExpiration, ObjectMetadata, ObjectSummary, ObjectVersionInfo};
+use crate::config::app_config::AppConfig;
+use crate::config::settings::Theme;
 use crate::config::credentials::CredentialManager;
+use crate::config::transfer_history::TransferHistory;
+use crate::ui::bucket_config_view::BucketConfigView;
 use crate::ui::bucket_view::{BucketView, S3Object};
+use crate::ui::compare_view::CompareView;
+use crate::ui::copy_object_view::{CopyObjectView, ObjectCopy};
+use crate::ui::create_bucket_view::CreateBucketView;
+use crate::ui::delete_preview::DeletePreviewView;
+use crate::ui::failed_transfers::FailedTransfer;
 use crate::ui::folder_list::FolderList;
 use crate::ui::folder_content::FolderContent;
+use crate::ui::object_history::ObjectHistoryView;
+use crate::ui::object_properties::ObjectPropertiesView;
+use crate::ui::preview::{PreviewContent, PreviewPane};
 use crate::ui::settings::SettingsView;
+use crate::ui::status_log::StatusLog;
+use crate::ui::session_token_view::SessionTokenView;
+use crate::ui::sync_preview::SyncPreviewView;
+use crate::ui::transfer_history_view::TransferHistoryView;
 use crate::ui::progress::ProgressView;
 use crate::ui::filter_view::FilterView;
+use crate::sync::diff::PrefixDiff;
+use crate::sync::engine::{SyncOperation, SyncResult};
+use crate::sync::filter::FileFilter;
+use crate::sync::scheduler::SyncScheduler;
 
 /// Current view in the application
 pub enum CurrentView {
     Main,
     Settings,
     Filter,
+    Compare,
+    History,
+}
+
+/// Whether AWS answered the most recent requests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    /// Nothing has been requested yet
+    Unknown,
+    Connected,
+    /// The last request failed because AWS couldn't be reached
+    Disconnected,
 }
 
 /// Status messages for communication between threads
 pub enum StatusMessage {
     Info(String),
     Error(String),
-    ObjectList(Vec<S3Object>),
+    /// First page of objects under the prefix being browsed, and the token for the next page
+    ObjectList(Vec<S3Object>, Option<String>),
+    /// A further page of objects for a bucket and prefix, and the token for the page after it
+    MoreObjects(String, String, Vec<S3Object>, Option<String>),
+    /// Matches from one page of a bucket search, with the number of keys searched so far
+    SearchMatches(String, String, Vec<S3Object>, usize),
+    /// End of a bucket search: whether it listed every key, or why it failed
+    SearchFinished(String, String, Result<bool, String>),
     BucketList(Vec<String>),
-    #[allow(dead_code)] // Will be used in future implementations
-    Progress(TransferProgress),
-    #[allow(dead_code)] // Will be used in future implementations
-    SyncComplete,
+    BucketRegion(String, String),
+    /// A request was redirected away from the bucket's cached region, so it has moved
+    BucketRegionMoved(String),
+    /// Result of creating a bucket
+    BucketCreated(String, Result<(), String>),
+    ObjectVersions(String, Vec<ObjectVersionInfo>),
+    ObjectExpiration(String, Option<ObjectExpiration>),
+    /// Result of copying, moving or renaming an object
+    ObjectCopied(ObjectCopy, Result<(), String>),
+    /// A copy wasn't made because an object is already at its destination
+    CopyDestinationExists(ObjectCopy),
+    /// Content of an object for the preview pane
+    Preview(String, PreviewContent),
+    /// Metadata of an object, or why it couldn't be loaded
+    ObjectMetadata(String, Result<ObjectMetadata, String>),
+    /// Presigned link created for a key, to be copied to the clipboard, and whether it is
+    /// an upload link rather than a download link
+    ShareLink(String, String, bool),
+    /// Lifecycle and CORS rules of a bucket
+    BucketConfiguration(String, Result<Vec<LifecycleRuleInfo>, String>, Result<Vec<CorsRuleInfo>, String>),
+    /// Objects a pending delete will remove, and whether the bucket is versioned
+    DeletePreview(String, Result<Vec<ObjectSummary>, String>, Option<bool>),
+    CredentialsExpiry(Option<SystemTime>),
+    CompareResult(Option<PrefixDiff>),
+    AccessDenied(PermissionContext),
+    /// Files that failed in a batch transfer
+    TransferFailures(Vec<FailedTransfer>),
+    /// Listing the bucket being browsed failed because the session token expired
+    SessionExpired,
+    /// New credentials are in use: the transfers to retry, and whether to reload the bucket listing
+    SessionRenewed(Vec<FailedTransfer>, bool),
+    /// Result of the periodic connectivity check
+    Connectivity(bool),
+    /// Changes a sync of a folder with a bucket would make, for the sync preview
+    SyncPlan(PathBuf, String, Result<Vec<(String, SyncOperation, u64)>, String>),
+    /// Result of syncing a folder with a bucket
+    SyncFinished(PathBuf, String, Result<SyncResult, String>),
+    /// The sync interval has passed, so the folders are due a sync
+    ScheduledSyncDue,
+    /// Results of a sync run by the scheduler, per folder
+    ScheduledSyncComplete(Vec<(PathBuf, SyncResult)>),
 }
 
 /// Main application state
@@ -40,28 +121,117 @@ pub struct AppState {
     pub settings_view: SettingsView,
     pub progress_view: ProgressView,
     pub filter_view: Option<FilterView>,
+    pub object_history: Option<ObjectHistoryView>,
+    pub object_properties: Option<ObjectPropertiesView>,
+    /// Preview of the selected object's content
+    pub preview: PreviewPane,
+    pub bucket_config: Option<BucketConfigView>,
+    pub delete_preview: Option<DeletePreviewView>,
+    /// Changes a sync will make, shown for confirmation before anything is deleted
+    pub sync_preview: Option<SyncPreviewView>,
+    /// Only preview what Sync would change, without changing anything
+    pub sync_dry_run: bool,
+    /// Prompt for new temporary credentials, open once the session token has expired
+    pub session_prompt: Option<SessionTokenView>,
+    /// New bucket dialog, open while a bucket is being named or created
+    pub create_bucket: Option<CreateBucketView>,
+    /// Rename or copy dialog for an object
+    pub copy_object: Option<CopyObjectView>,
+    pub compare_view: CompareView,
+    /// Sends a sync of the folders at the interval set in the settings
+    pub sync_scheduler: SyncScheduler,
+    /// Past transfers, loaded from the history file when the view is opened
+    pub history_view: TransferHistoryView,
+    /// Where finished transfers are recorded; None if the config directory can't be found
+    pub transfer_history: Option<Arc<TransferHistory>>,
     pub current_view: CurrentView,
     pub show_progress: bool,
-    pub aws_auth: Arc<TokioMutex<AwsAuth>>,
-    pub status_message: String,
-    pub status_is_error: bool,
+    /// Download objects into a single folder instead of recreating their prefixes
+    pub flatten_downloads: bool,
+    /// Upload into the prefix being browsed in the bucket panel, instead of the folder's own bucket and prefix
+    pub upload_to_prefix: bool,
+    /// Template for upload keys, e.g. `backups/{date}/{relpath}` (empty = relative path)
+    pub upload_key_template: String,
+    /// Shared by the background tasks. Lock it with `lock_auth` only long enough to take a
+    /// client or the credentials out, and update it from the UI thread with `AwsOperations::update_auth`.
+    pub aws_auth: Arc<SharedAuth>,
+    /// Bandwidth limit shared by every transfer, kept in line with the settings
+    pub rate_limiter: SharedRateLimiter,
+    /// Expiry of the current temporary credentials, if any
+    pub credentials_expiry: Option<SystemTime>,
+    /// AWS can't be reached; AWS actions are disabled until it can
+    pub offline: bool,
+    /// Outcome of the last AWS call, shown in the status bar
+    pub connection: ConnectionState,
+    /// Status bar messages, where errors stick until acknowledged, and their history
+    pub status: StatusLog,
+    pub show_status_log: bool,
+    /// Whether the About dialog with the keyboard shortcuts is open
+    pub show_about: bool,
+    /// Files that failed in the last batch transfer, for inspection and retry
+    pub failed_transfers: Vec<FailedTransfer>,
+    /// The last request denied for lack of permissions, for the "Why?" explanation
+    pub access_denied: Option<PermissionContext>,
     pub status_tx: mpsc::Sender<StatusMessage>,
     pub status_rx: mpsc::Receiver<StatusMessage>,
     pub rt: Handle,
     #[allow(dead_code)] // Will be used in future implementations
     pub credential_manager: CredentialManager,
+    /// Persisted configuration, loaded at startup and saved whenever it changes
+    pub config: AppConfig,
+    /// Theme the visuals were last set for, so they're only replaced when it changes
+    pub applied_theme: Option<Theme>,
 }
 
 impl AppState {
     /// Set a status info message
     pub fn set_status_info(&mut self, message: &str) {
-        self.status_message = message.to_string();
-        self.status_is_error = false;
+        self.status.push_info(message);
+        self.access_denied = None;
+    }
+    
+    /// Get a copy of the active file filter (an empty filter if none is configured)
+    pub fn active_filter(&self) -> FileFilter {
+        self.filter_view
+            .as_ref()
+            .and_then(|view| view.get_filter().lock().ok().map(|filter| filter.clone()))
+            .unwrap_or_default()
     }
     
     /// Set a status error message
     pub fn set_status_error(&mut self, message: &str) {
-        self.status_message = message.to_string();
-        self.status_is_error = true;
+        self.status.push_error(message);
+    }
+    
+    /// Save the configuration with the current settings, folders and file filter
+    pub fn save_config(&mut self) {
+        self.settings_view.apply_to(&mut self.config.settings);
+        self.config.settings.update_from_sync_folders(&self.folder_list.folders);
+        if let Some(filter_view) = &self.filter_view {
+            self.config.filter = filter_view.to_config();
+        }
+        
+        if let Err(e) = self.config.save() {
+            error!("Failed to save configuration: {}", e);
+            self.set_status_error(&format!("Failed to save configuration: {}", e));
+        }
+    }
+    
+    /// Save credentials for the next start: the secret key goes to the keyring and the
+    /// access key ID is saved with the rest of the configuration
+    pub fn save_credentials(&mut self, access_key: &str, secret_key: &str) {
+        match CredentialManager::save_secret_key(secret_key) {
+            Ok(()) => self.config.access_key_id = access_key.to_string(),
+            Err(e) => {
+                error!("Failed to save credentials: {}", e);
+                self.set_status_error(&format!("Failed to save credentials: {}", e));
+            }
+        }
+    }
+    
+    /// Dismiss the current status error, along with its permissions explanation
+    pub fn acknowledge_status_error(&mut self) {
+        self.status.acknowledge();
+        self.access_denied = None;
     }
 }
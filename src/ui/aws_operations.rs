@@ -1,17 +1,252 @@
-use log::{error, debug};
-// use std::sync::Arc; // Unused
+use aws_sdk_s3::types::{ObjectCannedAcl, ServerSideEncryption, StorageClass};
+use aws_sdk_s3::Client;
+use futures::stream::{self, StreamExt};
+use log::{error, debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::aws::transfer::TransferManager;
+use crate::aws::auth::{lock_auth, AwsAuth, SharedAuth};
+use crate::aws::bucket::{get_bucket_region, BucketManager};
+use crate::aws::partial;
+use crate::aws::throttle::RateLimiter;
+use crate::aws::s3::{PermissionContext, S3ErrorHelper, S3Operation, KMS_DECRYPT_MESSAGE};
+use crate::aws::transfer::{CopySource, ObjectSummary, ObjectVersionInfo, TransferManager, TransferProgress, DELETE_BATCH_SIZE};
+use crate::sync::diff::compare_prefixes;
+use crate::error_handling::{ErrorHandler, RetryPolicy};
+use crate::sync::engine::{SyncEngine, SyncOperation, SyncProgress, SyncProgressCallback, SyncResult};
+use crate::sync::filter::{FileFilter, SkipReason};
 use crate::ui::app_state::{AppState, StatusMessage};
-use crate::ui::bucket_view::S3Object;
+use crate::ui::bucket_config_view::BucketConfigView;
+use crate::ui::bucket_view::{BucketView, S3Object};
+use crate::ui::compare_view::CopyDirection;
+use crate::ui::copy_object_view::ObjectCopy;
+use crate::ui::delete_preview::DeletePreviewView;
+use crate::ui::failed_transfers::FailedTransfer;
+use crate::ui::folder_list::{SyncFolder, SyncStatus};
+use crate::ui::object_history::ObjectHistoryView;
+use crate::ui::object_properties::ObjectPropertiesView;
+use crate::ui::preview::{PreviewContent, PreviewKind, PREVIEW_SIZE_LIMIT};
+use crate::ui::progress::{OperationType, ProgressInfo, ProgressStatus, ProgressView};
+use crate::ui::session_token_view::{RenewedCredentials, SessionTokenView};
+use crate::ui::sync_preview::SyncPreviewView;
+use crate::ui::utils::{common_key_prefix, flat_download_path, format_size, resolve_key_template, split_directory_markers, unique_download_path, validate_key_prefix};
+
+/// How often the credential refresh timer checks for upcoming expiry
+const CREDENTIAL_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often network connectivity is checked
+const CONNECTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long the connectivity check waits for a connection before treating the network as down
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A local file queued for upload
+struct UploadFile {
+    path: PathBuf,
+    /// Key relative to the uploaded folder, which the file filter is matched against
+    relative_key: String,
+    s3_key: String,
+    size: u64,
+    /// Modification time in seconds since the epoch
+    modified: i64,
+}
+
+/// A sync of a folder with a bucket, with the settings it runs with taken on the UI thread
+struct SyncJob {
+    folder_path: PathBuf,
+    bucket: String,
+    key_prefix: String,
+    /// Keys the sync preview confirmed for deletion, or None to delete whatever was removed locally
+    confirmed_deletes: Option<HashSet<String>>,
+    bucket_region: Option<String>,
+    lookup_bucket_regions: bool,
+    filter: FileFilter,
+    delete_removed: bool,
+    quiet_period: Duration,
+    deep_compare: bool,
+    stall_timeout: Duration,
+    verify_downloads: bool,
+    upload_part_size: u64,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    encryption: Option<ServerSideEncryption>,
+    kms_key_id: Option<String>,
+    content_types: HashMap<String, String>,
+    storage_class: Option<StorageClass>,
+    acl: Option<ObjectCannedAcl>,
+    retry_policy: RetryPolicy,
+}
+
+impl SyncJob {
+    fn new(app_state: &AppState, folder_path: PathBuf, bucket: String, key_prefix: String, confirmed_deletes: Option<HashSet<String>>) -> Self {
+        Self {
+            bucket_region: app_state.bucket_view.get_bucket_region(&bucket).cloned(),
+            lookup_bucket_regions: app_state.settings_view.lookup_bucket_regions(),
+            filter: app_state.active_filter(),
+            delete_removed: app_state.settings_view.delete_enabled(),
+            quiet_period: app_state.settings_view.quiet_period(),
+            deep_compare: app_state.settings_view.deep_compare(),
+            stall_timeout: app_state.settings_view.stall_timeout(),
+            verify_downloads: app_state.settings_view.verify_downloads(),
+            upload_part_size: app_state.settings_view.upload_part_size(),
+            rate_limiter: app_state.rate_limiter.limiter(),
+            encryption: app_state.settings_view.server_side_encryption(),
+            kms_key_id: app_state.settings_view.sse_kms_key_id(),
+            content_types: app_state.settings_view.content_type_overrides(),
+            storage_class: AwsOperations::upload_storage_class(app_state, &folder_path),
+            acl: AwsOperations::upload_acl(app_state, &folder_path),
+            retry_policy: app_state.settings_view.retry_policy(),
+            folder_path,
+            bucket,
+            key_prefix,
+            confirmed_deletes,
+        }
+    }
+    
+    /// Run the sync in the background, showing its progress in the progress view
+    async fn run(self, auth: &SharedAuth, tx: &mpsc::Sender<StatusMessage>, progress_view: &ProgressView) -> Result<SyncResult, String> {
+        // Get a client for the bucket's region
+        let bucket_client = AwsOperations::client_for_bucket(auth, &self.bucket, self.bucket_region.clone(), self.lookup_bucket_regions, tx).await
+            .ok_or_else(|| "Could not get a client for the bucket".to_string())?;
+        
+        let (client, _) = bucket_client.current().await;
+        let mut transfer_manager = TransferManager::new(client);
+        transfer_manager.set_stall_timeout(self.stall_timeout);
+        transfer_manager.set_upload_part_size(self.upload_part_size);
+        transfer_manager.set_encryption(self.encryption.clone(), self.kms_key_id.clone());
+        transfer_manager.set_storage_class(self.storage_class.clone());
+        transfer_manager.set_acl(self.acl.clone());
+        transfer_manager.set_content_type_overrides(self.content_types.clone());
+        transfer_manager.set_rate_limiter(self.rate_limiter.clone());
+        transfer_manager.set_verify_downloads(self.verify_downloads);
+        transfer_manager.set_cancel_token(progress_view.cancel_token());
+        
+        // A sync sent to the wrong region fails while listing the bucket, before anything is
+        // transferred, so it is started over in the bucket's region
+        let (job, transfer_manager) = (&self, &transfer_manager);
+        let result = bucket_client.run(|client| async move {
+            let mut engine = SyncEngine::new(transfer_manager.with_client(client));
+            engine.set_filter(job.filter.clone());
+            engine.set_quiet_period(job.quiet_period);
+            engine.set_deep_compare(job.deep_compare);
+            engine.set_prefix(job.key_prefix.clone());
+            engine.set_confirmed_deletes(job.confirmed_deletes.clone());
+            engine.set_retry(job.retry_policy);
+            
+            let callback = AwsOperations::sync_progress(progress_view.clone(), job.bucket.clone(), job.folder_path.clone(), job.key_prefix.clone());
+            engine.sync_folder(&job.folder_path, &job.bucket, job.delete_removed, Some(callback)).await
+                .map_err(|e| e.to_string())
+        }).await;
+        progress_view.finish_scan();
+        
+        result
+    }
+}
+
+/// A client for the region a bucket is in. It starts out in the region the bucket is known
+/// or assumed to be in, and moves to the bucket's actual region the first time a request
+/// fails because it was sent to the wrong one.
+struct BucketClient {
+    auth: SharedAuth,
+    bucket: String,
+    tx: mpsc::Sender<StatusMessage>,
+    /// The client requests are sent with and its region
+    current: tokio::sync::Mutex<(Arc<Client>, String)>,
+    /// Set once the bucket's location has been looked up, so a failed lookup isn't repeated
+    looked_up: AtomicBool,
+}
+
+impl BucketClient {
+    fn new(auth: SharedAuth, bucket: String, tx: mpsc::Sender<StatusMessage>, client: Arc<Client>, region: String) -> Self {
+        Self {
+            auth,
+            bucket,
+            tx,
+            current: tokio::sync::Mutex::new((client, region)),
+            looked_up: AtomicBool::new(false),
+        }
+    }
+    
+    /// Get the client requests are sent with and its region
+    async fn current(&self) -> (Arc<Client>, String) {
+        self.current.lock().await.clone()
+    }
+    
+    /// Run an operation with the client. If it fails with a region error, the bucket's
+    /// location is looked up and the operation is run once more in that region.
+    async fn run<T, E, F, Fut>(&self, operation: F) -> Result<T, E>
+    where
+        F: Fn(Arc<Client>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: fmt::Display,
+    {
+        let (client, _) = self.current().await;
+        match operation(client.clone()).await {
+            Err(e) if S3ErrorHelper::is_redirect_error(&e.to_string()) => match self.relocate(&client).await {
+                Some((client, _)) => operation(client).await,
+                None => Err(e),
+            },
+            result => result,
+        }
+    }
+    
+    /// Move to the bucket's actual region after a request sent with `failed` went to the
+    /// wrong one. Requests that fail at the same time wait for a single lookup. The region
+    /// is only remembered for the bucket once its location has been confirmed.
+    async fn relocate(&self, failed: &Arc<Client>) -> Option<(Arc<Client>, String)> {
+        let mut current = self.current.lock().await;
+        if !Arc::ptr_eq(&current.0, failed) {
+            // Another request has already moved to the bucket's region
+            return Some(current.clone());
+        }
+        if self.looked_up.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        
+        debug!("Bucket {} is not in region {}, looking up its location", self.bucket, current.1);
+        let region = AwsOperations::lookup_bucket_region(&self.auth, &self.bucket).await?;
+        let client = lock_auth(&self.auth, "getting a client for a bucket's region").await.get_client_for_region(&region).await;
+        match client {
+            Ok(client) => {
+                let _ = self.tx.send(StatusMessage::BucketRegion(self.bucket.clone(), region.clone()));
+                *current = (client, region);
+                Some(current.clone())
+            },
+            Err(e) => {
+                error!("Failed to get AWS client for region {}: {}", region, e);
+                None
+            }
+        }
+    }
+}
 
 /// AWS-related operations for the application
 pub struct AwsOperations;
 
 impl AwsOperations {
+    /// Update the auth from the UI thread without waiting for the lock. The update is made
+    /// at once if the auth is free, as it is at startup, or else queued ahead of every
+    /// operation started after it, with a background task to apply it if none comes.
+    pub fn update_auth(app_state: &AppState, purpose: &'static str, update: impl FnOnce(&mut AwsAuth) + Send + 'static) {
+        if !app_state.aws_auth.update(update) {
+            let auth_clone = app_state.aws_auth.clone();
+            app_state.rt.spawn(async move {
+                drop(lock_auth(&auth_clone, purpose).await);
+            });
+        }
+    }
+    
     /// Connect to AWS
     pub fn connect_to_aws(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
         let auth_clone = app_state.aws_auth.clone();
         let tx = app_state.status_tx.clone();
         let bucket_view_tx = app_state.status_tx.clone();
@@ -19,30 +254,17 @@ impl AwsOperations {
         app_state.set_status_info("Connecting to AWS...");
         
         app_state.rt.spawn(async move {
-            // Get a mutable reference to the auth
-            let mut auth = auth_clone.lock().await;
+            // Initialize the AWS SDK, releasing the auth before anything is sent to AWS
+            let client = lock_auth(&auth_clone, "connecting to AWS").await.prepare_client().await;
             
-            // Initialize the AWS SDK
-            match auth.initialize().await {
-                Ok(_) => {
-                    // Successfully connected
-                    let _ = tx.send(StatusMessage::Info("Connected to AWS".to_string()));
-                    
-                    // Create a transfer manager
-                    let client = match auth.get_client().await {
-                        Ok(client) => client,
-                        Err(e) => {
-                            error!("Failed to get AWS client: {}", e);
-                            let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
-                            return;
-                        }
-                    };
-                    
+            match client {
+                Ok(client) => {
                     let transfer_manager = TransferManager::new(client);
                     
-                    // List buckets
+                    // List buckets, which also tests the credentials
                     match transfer_manager.list_buckets().await {
                         Ok(buckets) => {
+                            let _ = tx.send(StatusMessage::Info("Connected to AWS".to_string()));
                             let _ = bucket_view_tx.send(StatusMessage::BucketList(buckets));
                         },
                         Err(e) => {
@@ -59,96 +281,170 @@ impl AwsOperations {
         });
     }
     
-    /// Load objects from a bucket
-    pub fn load_bucket_objects(app_state: &mut AppState, bucket: &str) {
+    /// Search every key in the selected bucket for the query in the search box.
+    /// Matches are sent a page at a time so they appear while large buckets are still being listed.
+    pub fn search_objects(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        let (query, cancel) = match app_state.bucket_view.start_search(&bucket) {
+            Some(search) => search,
+            None => return,
+        };
+        
         let auth_clone = app_state.aws_auth.clone();
         let tx = app_state.status_tx.clone();
-        let bucket_name = bucket.to_string();
-        
-        app_state.set_status_info(&format!("Loading objects from bucket {}...", bucket));
-        app_state.bucket_view.set_loading(true);
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
         
-        // Get the bucket region from the bucket view
-        let bucket_region = app_state.bucket_view.get_bucket_region(bucket).cloned();
+        app_state.set_status_info(&format!("Searching {} for '{}'...", bucket, query));
         
         app_state.rt.spawn(async move {
-            // Get the AWS client for the specific region if available
-            let mut auth = auth_clone.lock().await;
-            
-            // First, try to get the region if we don't have it yet
-            let region = if let Some(region) = bucket_region {
-                debug!("Using cached region {} for bucket {}", region, bucket_name);
-                region
-            } else {
-                // Try to get the default client to query the bucket location
-                let default_client = match auth.get_client().await {
-                    Ok(client) => client,
-                    Err(e) => {
-                        error!("Failed to get default AWS client: {}", e);
-                        let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
+            let bucket_client = {
+                match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                    Some(bucket_client) => bucket_client,
+                    None => {
+                        let _ = tx.send(StatusMessage::SearchFinished(bucket, query, Err("Could not get a client for the bucket".to_string())));
                         return;
                     }
-                };
-                
-                // Try to get the bucket location
-                match Self::get_bucket_location(&default_client, &bucket_name).await {
-                    Ok(region) => {
-                        debug!("Detected region {} for bucket {}", region, bucket_name);
-                        region
-                    },
-                    Err(e) => {
-                        error!("Failed to get region for bucket {}: {}", bucket_name, e);
-                        // Default to us-east-1 if we can't determine the region
-                        "us-east-1".to_string()
-                    }
                 }
             };
             
-            // Now get a client for the specific region
-            let client = match auth.get_client_for_region(&region).await {
-                Ok(client) => {
-                    debug!("Using region-specific client for bucket {} in region {}", bucket_name, region);
-                    client
+            let result = bucket_client.run(|client| {
+                let (bucket, query, cancel, tx) = (&bucket, &query, &cancel, &tx);
+                async move {
+                    BucketView::search_objects(&TransferManager::new(client), bucket, query, cancel, |matches, scanned| {
+                        let _ = tx.send(StatusMessage::SearchMatches(bucket.clone(), query.clone(), matches, scanned));
+                    }).await
+                }
+            }).await;
+            
+            if let Err(e) = &result {
+                Self::report_access_denied(&tx, S3Operation::ListObjects, &bucket, None, e);
+            }
+            let _ = tx.send(StatusMessage::SearchFinished(bucket, query, result));
+        });
+    }
+    
+    /// Load the next page of objects under the prefix being browsed
+    pub fn load_more_objects(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let (bucket, continuation_token) = match (app_state.bucket_view.selected_bucket(), app_state.bucket_view.continuation_token()) {
+            (Some(bucket), Some(token)) => (bucket, token.clone()),
+            _ => return,
+        };
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let prefix = app_state.bucket_view.prefix().to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        let retry_policy = app_state.settings_view.retry_policy();
+        
+        app_state.bucket_view.set_loading_more(true);
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let listing = bucket_client.run(|client| {
+                let (bucket, prefix, continuation_token) = (&bucket, &prefix, &continuation_token);
+                async move {
+                    let transfer_manager = TransferManager::new(client);
+                    ErrorHandler::retry(
+                        || transfer_manager.list_objects_page(bucket, prefix, Some(continuation_token.clone())),
+                        retry_policy,
+                        S3ErrorHelper::is_retryable,
+                    ).await
+                }
+            }).await;
+            match listing {
+                Ok((objects, next_token)) => {
+                    let _ = tx.send(StatusMessage::MoreObjects(bucket, prefix, objects, next_token));
                 },
                 Err(e) => {
-                    error!("Failed to get AWS client for region {}: {}", region, e);
-                    
-                    // Try with us-east-2 as a fallback
-                    match auth.get_client_for_region("us-east-2").await {
-                        Ok(client) => {
-                            debug!("Using fallback us-east-2 client for bucket {}", bucket_name);
-                            client
-                        },
-                        Err(fallback_err) => {
-                            error!("Failed to get fallback AWS client: {}", fallback_err);
-                            let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
-                            return;
-                        }
+                    error!("Failed to load more objects: {}", e);
+                    // Keep the token so loading can be retried
+                    let _ = tx.send(StatusMessage::MoreObjects(bucket.clone(), prefix, Vec::new(), Some(continuation_token)));
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to load more objects: {}", e)));
+                    if S3ErrorHelper::is_expired_token(&e.to_string()) {
+                        let _ = tx.send(StatusMessage::SessionExpired);
                     }
+                    Self::report_access_denied(&tx, S3Operation::ListObjects, &bucket, None, &e.to_string());
                 }
+            }
+        });
+    }
+    
+    /// Load objects from a bucket
+    pub fn load_bucket_objects(app_state: &mut AppState, bucket: &str) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let bucket_name = bucket.to_string();
+        let prefix = app_state.bucket_view.prefix().to_string();
+        
+        app_state.set_status_info(&format!("Loading objects from bucket {}...", bucket));
+        app_state.bucket_view.set_loading(true);
+        
+        // Get the bucket region from the bucket view
+        let bucket_region = app_state.bucket_view.get_bucket_region(bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let retry_policy = app_state.settings_view.retry_policy();
+        
+        app_state.rt.spawn(async move {
+            // Get a client for the bucket's region
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket_name, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
             };
             
-            // Create a transfer manager
-            let transfer_manager = TransferManager::new(client);
-            
-            // List objects with improved error handling
-            match transfer_manager.list_objects(&bucket_name).await {
-                Ok(objects) => {
-                    let _ = tx.send(StatusMessage::ObjectList(objects));
+            // List objects with improved error handling, retrying transient failures
+            let listing = bucket_client.run(|client| {
+                let (bucket_name, prefix) = (&bucket_name, &prefix);
+                async move {
+                    let transfer_manager = TransferManager::new(client);
+                    ErrorHandler::retry(
+                        || transfer_manager.list_objects_page(bucket_name, prefix, None),
+                        retry_policy,
+                        S3ErrorHelper::is_retryable,
+                    ).await
+                }
+            }).await;
+            match listing {
+                Ok((objects, continuation_token)) => {
+                    let _ = tx.send(StatusMessage::ObjectList(objects, continuation_token));
                 },
                 Err(e) => {
+                    let (_, region) = bucket_client.current().await;
+                    
                     // If we get an error and we're not using us-east-2, try that region as a fallback
                     if region != "us-east-2" {
                         error!("Failed with region {}, trying us-east-2 as fallback", region);
                         
                         // Try with us-east-2 client
-                        match auth.get_client_for_region("us-east-2").await {
+                        let fallback_client = lock_auth(&auth_clone, "getting a fallback client").await.get_client_for_region("us-east-2").await;
+                        match fallback_client {
                             Ok(client) => {
                                 let transfer_manager = TransferManager::new(client);
-                                match transfer_manager.list_objects(&bucket_name).await {
-                                    Ok(objects) => {
+                                match transfer_manager.list_objects_page(&bucket_name, &prefix, None).await {
+                                    Ok((objects, continuation_token)) => {
                                         debug!("Successfully listed objects using us-east-2 region");
-                                        let _ = tx.send(StatusMessage::ObjectList(objects));
+                                        let _ = tx.send(StatusMessage::ObjectList(objects, continuation_token));
                                         return;
                                     },
                                     Err(fallback_err) => {
@@ -165,6 +461,11 @@ impl AwsOperations {
                     // Log the detailed error
                     error!("Failed to list objects: {}", e);
                     
+                    // Look the region up again next time if the bucket has moved
+                    if S3ErrorHelper::is_redirect_error(&e.to_string()) {
+                        let _ = tx.send(StatusMessage::BucketRegionMoved(bucket_name.clone()));
+                    }
+                    
                     // Send a more user-friendly error message to the UI
                     let error_message = if e.to_string().contains("S3 service error") {
                         // This is our enhanced error message from the transfer.rs improvement
@@ -175,242 +476,625 @@ impl AwsOperations {
                     };
                     
                     let _ = tx.send(StatusMessage::Error(error_message));
+                    if S3ErrorHelper::is_expired_token(&e.to_string()) {
+                        let _ = tx.send(StatusMessage::SessionExpired);
+                    }
+                    Self::report_access_denied(&tx, S3Operation::ListObjects, &bucket_name, None, &e.to_string());
                 }
             }
         });
     }
     
-    /// Helper function to get the location (region) of a bucket
-    async fn get_bucket_location(client: &aws_sdk_s3::Client, bucket: &str) -> Result<String, String> {
-        match client.get_bucket_location().bucket(bucket).send().await {
-            Ok(resp) => {
-                // Extract the location constraint as a string
-                let location_str = match resp.location_constraint() {
-                    Some(constraint) => {
-                        // Convert the enum to a debug string and extract the value
-                        let debug_str = format!("{:?}", constraint);
-                        if debug_str.contains("\"\"") || debug_str == "Empty" {
-                            // Empty constraint means us-east-1
-                            "us-east-1".to_string()
-                        } else if debug_str.starts_with("Unknown(") {
-                            // Extract the value from Unknown("value")
-                            let start = debug_str.find('(').map(|i| i + 2).unwrap_or(0);
-                            let end = debug_str.rfind('"').unwrap_or(debug_str.len());
-                            if start < end {
-                                debug_str[start..end].to_string()
-                            } else {
-                                "us-east-1".to_string() // Default if parsing fails
-                            }
-                        } else {
-                            // For known enum variants, extract the region name
-                            let region_name = match debug_str.as_str() {
-                                "EuWest1" => "eu-west-1",
-                                "UsWest1" => "us-west-1",
-                                "UsWest2" => "us-west-2",
-                                "EuWest2" => "eu-west-2",
-                                "EuWest3" => "eu-west-3",
-                                "UsEast2" => "us-east-2",
-                                "ApSouth1" => "ap-south-1",
-                                "ApSoutheast1" => "ap-southeast-1",
-                                "ApSoutheast2" => "ap-southeast-2",
-                                "ApNortheast1" => "ap-northeast-1",
-                                "ApNortheast2" => "ap-northeast-2",
-                                "ApNortheast3" => "ap-northeast-3",
-                                "SaEast1" => "sa-east-1",
-                                "CnNorth1" => "cn-north-1",
-                                "CnNorthwest1" => "cn-northwest-1",
-                                "UsGovWest1" => "us-gov-west-1",
-                                "UsGovEast1" => "us-gov-east-1",
-                                "EuCentral1" => "eu-central-1",
-                                "EuNorth1" => "eu-north-1",
-                                "MeSouth1" => "me-south-1",
-                                "AfSouth1" => "af-south-1",
-                                "EuSouth1" => "eu-south-1",
-                                "ApEast1" => "ap-east-1",
-                                _ => "us-east-1", // Default for unknown regions
-                            };
-                            region_name.to_string()
-                        }
-                    },
-                    None => "us-east-1".to_string(), // Default if no constraint is specified
-                };
+    /// Check that the network is available before starting an AWS operation
+    fn ensure_online(app_state: &mut AppState) -> bool {
+        if app_state.offline {
+            app_state.set_status_error("Offline: AWS actions are unavailable until the network connection returns");
+            return false;
+        }
+        
+        true
+    }
+    
+    /// Offer a permissions explanation if an operation failed with AccessDenied
+    fn report_access_denied(tx: &mpsc::Sender<StatusMessage>, operation: S3Operation, bucket: &str, key: Option<&str>, error: &str) {
+        if S3ErrorHelper::is_access_denied(error) {
+            let _ = tx.send(StatusMessage::AccessDenied(PermissionContext {
+                operation,
+                bucket: bucket.to_string(),
+                key: key.map(|k| k.to_string()),
+            }));
+        }
+    }
+    
+    /// Get a client for the region a bucket lives in.
+    ///
+    /// Unless per-bucket region lookup is enabled, a bucket whose region isn't known yet is
+    /// assumed to be in the configured region. Operations run with the client look the bucket's
+    /// location up only if they fail with a region error. The auth is only locked while clients
+    /// are built, not during the lookups.
+    async fn client_for_bucket(
+        auth: &SharedAuth,
+        bucket: &str,
+        cached_region: Option<String>,
+        lookup_bucket_regions: bool,
+        tx: &mpsc::Sender<StatusMessage>,
+    ) -> Option<BucketClient> {
+        let known_region = match cached_region {
+            Some(region) => {
+                debug!("Using cached region {} for bucket {}", region, bucket);
+                Some(region)
+            },
+            None if lookup_bucket_regions => {
+                let region = Self::lookup_bucket_region(auth, bucket).await;
+                if let Some(region) = &region {
+                    let _ = tx.send(StatusMessage::BucketRegion(bucket.to_string(), region.clone()));
+                }
+                region
+            },
+            None => None,
+        };
+        let region = match known_region {
+            Some(region) => region,
+            None => lock_auth(auth, "reading the configured region").await.region().to_string(),
+        };
+        
+        // Now get a client for the specific region
+        let client = lock_auth(auth, "getting a client for a bucket's region").await.get_client_for_region(&region).await;
+        let (client, region) = match client {
+            Ok(client) => {
+                debug!("Using region-specific client for bucket {} in region {}", bucket, region);
+                (client, region)
+            },
+            Err(e) => {
+                error!("Failed to get AWS client for region {}: {}", region, e);
                 
-                Ok(location_str)
+                // Try with us-east-2 as a fallback
+                let fallback_client = lock_auth(auth, "getting a fallback client").await.get_client_for_region("us-east-2").await;
+                match fallback_client {
+                    Ok(client) => {
+                        debug!("Using fallback us-east-2 client for bucket {}", bucket);
+                        (client, "us-east-2".to_string())
+                    },
+                    Err(fallback_err) => {
+                        error!("Failed to get fallback AWS client: {}", fallback_err);
+                        let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
+                        return None;
+                    }
+                }
+            }
+        };
+        
+        Some(BucketClient::new(auth.clone(), bucket.to_string(), tx.clone(), client, region))
+    }
+    
+    /// Look up the region of a bucket, or None if it can't be determined
+    async fn lookup_bucket_region(auth: &SharedAuth, bucket: &str) -> Option<String> {
+        // Use the default client to query the bucket location
+        let default_client = lock_auth(auth, "getting the default client").await.get_client().await;
+        let default_client = match default_client {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Failed to get default AWS client: {}", e);
+                return None;
+            }
+        };
+        
+        match get_bucket_region(&default_client, bucket).await {
+            Ok(region) => {
+                debug!("Detected region {} for bucket {}", region, bucket);
+                Some(region)
             },
-            Err(err) => {
-                // Convert to a string error without using code() and message()
-                Err(format!("Failed to get bucket location: {}", err))
+            Err(e) => {
+                error!("Failed to get region for bucket {}: {}", bucket, e);
+                None
             }
         }
     }
     
     /// Upload selected files to S3
     pub fn upload_selected(app_state: &mut AppState) {
-        // Check if we have a selected bucket and folder
-        let bucket = match app_state.bucket_view.selected_bucket() {
-            Some(bucket) => bucket.clone(),
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        // Get the selected folder, and the bucket and prefix it is synced with
+        let folder = match app_state.folder_list.selected() {
+            Some(folder) => folder.clone(),
             None => {
-                app_state.set_status_error("No S3 bucket selected for upload");
+                app_state.set_status_error("No local folder selected for upload");
                 return;
             }
         };
+        let folder_path = folder.path.clone();
         
-        // Get the selected folder path
-        let folder_path = match app_state.folder_list.selected_folder() {
-            Some(path) => path.clone(),
+        // Files go under the prefix being browsed if asked, otherwise where the folder syncs to
+        let target = if app_state.upload_to_prefix {
+            app_state.bucket_view.selected_bucket().map(|bucket| (bucket, app_state.bucket_view.prefix().to_string()))
+        } else {
+            Self::folder_target(app_state, &folder)
+        };
+        let (bucket, key_prefix) = match target {
+            Some(target) => target,
             None => {
-                app_state.set_status_error("No local folder selected for upload");
+                app_state.set_status_error("No S3 bucket selected for upload");
                 return;
             }
         };
+        if let Err(e) = validate_key_prefix(&key_prefix) {
+            app_state.set_status_error(&e);
+            return;
+        }
         
-        // Get the selected files
-        let selected_files = app_state.folder_content.selected_files();
-        if selected_files.is_empty() {
-            app_state.set_status_error("No files selected to upload");
+        // Check the key template before starting
+        let key_template = app_state.upload_key_template.clone();
+        let upload_date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let folder_name = folder_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Err(e) = resolve_key_template(&key_template, &folder_name, "file", &upload_date) {
+            app_state.set_status_error(&e);
             return;
         }
         
-        // Clone necessary data for the async task
-        let auth_clone = app_state.aws_auth.clone();
-        let tx = app_state.status_tx.clone();
-        let bucket_name = bucket.clone();
-        let folder_path_clone = folder_path.clone();
-        let files_to_upload: Vec<PathBuf> = selected_files.iter()
-            .filter(|file| !file.is_directory) // Skip directories
+        // Only the checked files are uploaded, or the whole folder if none are checked.
+        // Directories are expanded recursively inside the task.
+        let selected_paths: Vec<PathBuf> = app_state.folder_content.selected_files().iter()
             .map(|file| file.path.clone())
             .collect();
+        let upload_all = selected_paths.is_empty();
+        let paths_to_upload = if upload_all {
+            vec![folder_path.clone()]
+        } else {
+            selected_paths
+        };
+        let storage_class = Self::upload_storage_class(app_state, &folder_path);
+        let acl = Self::upload_acl(app_state, &folder_path);
+        let folder_markers = app_state.settings_view.upload_folder_markers();
         
-        // Get the bucket region from the bucket view
-        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        if upload_all {
+            app_state.set_status_info(&format!("No files selected, uploading all of {} to s3://{}/{}...", folder_path.display(), bucket, key_prefix));
+        } else {
+            app_state.set_status_info(&format!("Uploading {} selected items to s3://{}/{}...", paths_to_upload.len(), bucket, key_prefix));
+        }
+        
+        Self::start_upload(app_state, bucket, storage_class, acl, move || {
+            Self::expand_upload_paths(&paths_to_upload, folder_markers)
+                .into_iter()
+                // An empty folder has no key of its own to mark it with
+                .filter(|(file_path, _, _)| file_path != &folder_path)
+                .map(|(file_path, size, modified)| {
+                    let relative_key = Self::upload_key(&folder_path, &file_path)
+                        .ok_or_else(|| format!("Could not determine file name for {}", file_path.display()))?;
+                    
+                    // The template was checked before starting, so this only fails on odd file names
+                    let s3_key = resolve_key_template(&key_template, &folder_name, &relative_key, &upload_date)
+                        .map(|key| format!("{}{}", key_prefix, key))
+                        .map_err(|e| format!("Could not build key for {}: {}", file_path.display(), e))?;
+                    
+                    // Empty directories are uploaded as folder markers, whose keys end in '/'
+                    if file_path.is_dir() {
+                        return Ok(UploadFile { path: file_path, relative_key: format!("{}/", relative_key), s3_key: format!("{}/", s3_key), size, modified });
+                    }
+                    
+                    Ok(UploadFile { path: file_path, relative_key, s3_key, size, modified })
+                })
+                .collect()
+        });
+    }
+    
+    /// Upload files and folders dropped onto the window to the current prefix of the
+    /// selected bucket. A dropped folder is uploaded with its contents under its own name.
+    pub fn upload_dropped(app_state: &mut AppState, paths: Vec<PathBuf>) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket.clone(),
+            None => {
+                app_state.set_status_error("Select a bucket to upload dropped files to");
+                return;
+            }
+        };
         
-        app_state.set_status_info(&format!("Uploading {} files to bucket {}...", files_to_upload.len(), bucket));
+        let prefix = app_state.bucket_view.prefix().to_string();
+        let storage_class = app_state.settings_view.storage_class();
+        let acl = app_state.settings_view.upload_acl();
+        let folder_markers = app_state.settings_view.upload_folder_markers();
         
-        // Spawn an async task to handle the upload
-        app_state.rt.spawn(async move {
-            // Get the AWS client
-            let mut auth = auth_clone.lock().await;
+        app_state.set_status_info(&format!("Uploading {} dropped items to s3://{}/{}...", paths.len(), bucket, prefix));
+        
+        Self::start_upload(app_state, bucket, storage_class, acl, move || {
+            let mut files = Vec::new();
             
-            // First, try to get the region if we don't have it yet
-            let region = if let Some(region) = bucket_region {
-                debug!("Using cached region {} for bucket {}", region, bucket_name);
-                region
-            } else {
-                // Try to get the default client to query the bucket location
-                let default_client = match auth.get_client().await {
-                    Ok(client) => client,
-                    Err(e) => {
-                        error!("Failed to get default AWS client: {}", e);
-                        let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
-                        return;
+            for path in &paths {
+                let name = match path.file_name() {
+                    Some(name) => name.to_string_lossy().to_string(),
+                    None => {
+                        files.push(Err(format!("Could not determine file name for {}", path.display())));
+                        continue;
                     }
                 };
                 
-                // Try to get the bucket location
-                match Self::get_bucket_location(&default_client, &bucket_name).await {
-                    Ok(region) => {
-                        debug!("Detected region {} for bucket {}", region, bucket_name);
-                        region
-                    },
-                    Err(e) => {
-                        error!("Failed to get region for bucket {}: {}", bucket_name, e);
-                        // Try us-east-1 as default
-                        "us-east-1".to_string()
+                if path.is_dir() {
+                    let scanned = match SyncEngine::scan_local_folder(path) {
+                        Ok(scanned) => scanned,
+                        Err(e) => {
+                            files.push(Err(format!("Failed to scan {}: {}", path.display(), e)));
+                            continue;
+                        }
+                    };
+                    
+                    for (key, (file_path, size)) in scanned {
+                        let modified = std::fs::metadata(&file_path)
+                            .map(|metadata| Self::modified_seconds(&metadata))
+                            .unwrap_or(0);
+                        let relative_key = format!("{}/{}", name, key);
+                        let s3_key = format!("{}{}", prefix, relative_key);
+                        files.push(Ok(UploadFile { path: file_path, relative_key, s3_key, size, modified }));
                     }
-                }
-            };
-            
-            // Now get a client for the specific region
-            let client = match auth.get_client_for_region(&region).await {
-                Ok(client) => {
-                    debug!("Using region-specific client for bucket {} in region {}", bucket_name, region);
-                    client
-                },
-                Err(e) => {
-                    error!("Failed to get AWS client for region {}: {}", region, e);
                     
-                    // Try with us-east-2 as a fallback
-                    match auth.get_client_for_region("us-east-2").await {
-                        Ok(client) => {
-                            debug!("Using fallback us-east-2 client for bucket {}", bucket_name);
-                            client
-                        },
-                        Err(fallback_err) => {
-                            error!("Failed to get fallback AWS client: {}", fallback_err);
-                            let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
-                            return;
+                    if folder_markers {
+                        for dir in SyncEngine::empty_folders(path) {
+                            let modified = std::fs::metadata(&dir)
+                                .map(|metadata| Self::modified_seconds(&metadata))
+                                .unwrap_or(0);
+                            let relative_key = match Self::upload_key(path, &dir) {
+                                Some(key) if !key.is_empty() => format!("{}/{}/", name, key),
+                                _ => format!("{}/", name),
+                            };
+                            let s3_key = format!("{}{}", prefix, relative_key);
+                            files.push(Ok(UploadFile { path: dir, relative_key, s3_key, size: 0, modified }));
                         }
                     }
+                } else {
+                    match std::fs::metadata(path) {
+                        Ok(metadata) => files.push(Ok(UploadFile {
+                            path: path.clone(),
+                            s3_key: format!("{}{}", prefix, name),
+                            relative_key: name,
+                            size: metadata.len(),
+                            modified: Self::modified_seconds(&metadata),
+                        })),
+                        Err(e) => files.push(Err(format!("Failed to read metadata for {}: {}", path.display(), e))),
+                    }
                 }
+            }
+            
+            files
+        });
+    }
+    
+    /// Upload files to a bucket in the background and show the progress window. `collect`
+    /// lists the files with their keys; it runs inside the task so walking large folders
+    /// doesn't hold up the UI.
+    fn start_upload<F>(app_state: &mut AppState, bucket: String, storage_class: Option<StorageClass>, acl: Option<ObjectCannedAcl>, collect: F)
+    where
+        F: FnOnce() -> Vec<Result<UploadFile, String>> + Send + 'static,
+    {
+        // Clone necessary data for the async task
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let bucket_name = bucket.clone();
+        let prefix = app_state.bucket_view.prefix().to_string();
+        // The listing is only refreshed if the files went to the bucket being browsed
+        let refresh = app_state.bucket_view.selected_bucket().as_deref() == Some(bucket.as_str());
+        let filter = app_state.active_filter();
+        let progress_view = app_state.progress_view.clone();
+        
+        // Get the bucket region from the bucket view
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let stop_on_error = app_state.settings_view.stop_on_error();
+        let upload_part_size = app_state.settings_view.upload_part_size();
+        let stall_timeout = app_state.settings_view.stall_timeout();
+        let rate_limiter = app_state.rate_limiter.limiter();
+        let retry_policy = app_state.settings_view.retry_policy();
+        let encryption = app_state.settings_view.server_side_encryption();
+        let kms_key_id = app_state.settings_view.sse_kms_key_id();
+        let content_types = app_state.settings_view.content_type_overrides();
+        let max_concurrent = app_state.settings_view.max_concurrent_transfers();
+        let skip_unchanged = app_state.settings_view.skip_unchanged_uploads();
+        
+        app_state.show_progress = true;
+        app_state.failed_transfers.clear();
+        
+        // Spawn an async task to handle the upload
+        app_state.rt.spawn(async move {
+            // Scan phase: enumerate and size everything so the totals are accurate from the start
+            progress_view.start_scan();
+            
+            // Get a client for the bucket's region
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket_name, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
             };
             
             // Create a transfer manager
-            let transfer_manager = TransferManager::new(client);
+            let (client, _) = bucket_client.current().await;
+            let mut transfer_manager = TransferManager::new(client);
+            transfer_manager.set_stall_timeout(stall_timeout);
+            transfer_manager.set_upload_part_size(upload_part_size);
+            transfer_manager.set_encryption(encryption, kms_key_id);
+            transfer_manager.set_storage_class(storage_class);
+            transfer_manager.set_acl(acl);
+            transfer_manager.set_content_type_overrides(content_types);
+            transfer_manager.set_rate_limiter(rate_limiter);
+            transfer_manager.set_cancel_token(progress_view.cancel_token());
             
-            // Track upload statistics
-            let mut success_count = 0;
+            // Expand directories and apply the active filter, recording why files are skipped
+            let mut skipped: Vec<(String, SkipReason)> = Vec::new();
+            let mut files_to_upload: Vec<(PathBuf, String, u64)> = Vec::new();
             let mut error_count = 0;
             
-            // Process each file
-            for file_path in files_to_upload {
-                // Calculate the S3 key by removing the folder path prefix
-                let rel_path = match file_path.strip_prefix(&folder_path_clone) {
-                    Ok(rel) => rel,
-                    Err(_) => {
-                        // If we can't determine the relative path, use the file name
-                        match file_path.file_name() {
-                            Some(name) => Path::new(name),
-                            None => {
-                                error!("Could not determine file name for {}", file_path.display());
-                                error_count += 1;
-                                continue;
-                            }
-                        }
-                    }
-                };
-                
-                let s3_key = rel_path.to_string_lossy().replace('\\', "/");
+            // Keys queued so far. Progress entries are keyed by S3 key, so each key is uploaded once.
+            let mut queued_keys: HashSet<String> = HashSet::new();
+            
+            // Keys seen so far, lowercased, to warn about keys that differ only by case
+            let mut seen_keys: HashMap<String, String> = HashMap::new();
+            let mut case_conflicts = 0;
+            
+            let files = collect();
+            
+            // Size and modified time of the objects already under the destination, listed once
+            // if unchanged files are left out
+            let mut remote: HashMap<String, (u64, i64)> = HashMap::new();
+            if skip_unchanged {
+                let prefix = common_key_prefix(files.iter().filter_map(|file| file.as_ref().ok()).map(|file| file.s3_key.as_str()));
+                let listing = bucket_client.run(|client| {
+                    let (transfer_manager, bucket_name, prefix) = (&transfer_manager, &bucket_name, &prefix);
+                    async move { transfer_manager.with_client(client).list_prefix(bucket_name, prefix).await }
+                }).await;
+                match listing {
+                    Ok(objects) => {
+                        remote = objects.into_iter()
+                            .map(|object| (object.key, (object.size, object.last_modified)))
+                            .collect();
+                    },
+                    Err(e) => warn!("Could not list s3://{}/{} to find unchanged files, uploading them all: {}", bucket_name, prefix, e),
+                }
+            }
+            
+            for file in files {
+                if progress_view.is_cancelled() {
+                    break;
+                }
+                
+                let UploadFile { path: file_path, relative_key, s3_key, size, modified: local_modified } = match file {
+                    Ok(file) => file,
+                    Err(e) => {
+                        error!("{}", e);
+                        error_count += 1;
+                        continue;
+                    }
+                };
+                
+                if let Some(reason) = filter.skip_reason(Path::new(&relative_key), size) {
+                    skipped.push((s3_key, reason));
+                    continue;
+                }
+                
+                // Skip files whose remote copy has the same size and is at least as new
+                if let Some(&(remote_size, remote_modified)) = remote.get(&s3_key) {
+                    if remote_size == size && remote_modified >= local_modified {
+                        skipped.push((s3_key, SkipReason::UpToDate));
+                        continue;
+                    }
+                }
+                
+                if !queued_keys.insert(s3_key.clone()) {
+                    skipped.push((s3_key, SkipReason::DuplicateKey));
+                    continue;
+                }
+                
+                if let Some(existing) = seen_keys.insert(s3_key.to_lowercase(), s3_key.clone()) {
+                    warn!("{} and {} differ only by case and will collide when downloaded to a case-insensitive filesystem", existing, s3_key);
+                    case_conflicts += 1;
+                }
+                
+                progress_view.add_scanned(size);
+                files_to_upload.push((file_path, s3_key, size));
+            }
+            
+            // Transfer phase
+            let total_bytes = files_to_upload.iter().map(|(_, _, size)| *size).sum();
+            progress_view.start_sync(files_to_upload.len(), total_bytes);
+            
+            for (s3_key, reason) in &skipped {
+                progress_view.add_skipped(s3_key, &reason.to_string());
+            }
+            
+            for (file_path, s3_key, size) in &files_to_upload {
+                progress_view.add_entry(ProgressInfo {
+                    file_name: s3_key.clone(),
+                    bucket: bucket_name.clone(),
+                    s3_key: s3_key.clone(),
+                    operation_type: OperationType::Upload,
+                    bytes_transferred: 0,
+                    total_bytes: *size,
+                    percentage: 0.0,
+                    status: ProgressStatus::Pending,
+                    message: String::new(),
+                    timestamp: Instant::now(),
+                    local_path: file_path.clone(),
+                });
+            }
+            
+            // Track upload statistics
+            let mut success_count = 0;
+            let mut cancelled_count = 0;
+            let mut last_denied: Option<(String, String)> = None;
+            
+            // Uploaded objects that a lifecycle rule will expire
+            let mut expiring = Vec::new();
+            
+            let mut failures = Vec::new();
+            
+            // The file that stopped the batch, if it stops at the first failure
+            let mut stopped: Option<(String, String)> = None;
+            
+            // Set once the batch stops, so files that haven't started are left pending and cancelled
+            let stopping = AtomicBool::new(false);
+            
+            // Upload several files at a time, handling each result as it finishes
+            let mut uploads = stream::iter(files_to_upload)
+                .map(|(file_path, s3_key, size)| {
+                    let transfer_manager = &transfer_manager;
+                    let bucket_client = &bucket_client;
+                    let bucket_name = &bucket_name;
+                    let stopping = &stopping;
+                    let callback_view = progress_view.clone();
+                    
+                    async move {
+                        if stopping.load(Ordering::SeqCst) || callback_view.is_cancelled() {
+                            return (file_path, s3_key, size, None);
+                        }
+                        
+                        callback_view.start_operation(bucket_name, &s3_key);
+                        
+                        // Throttling and other transient failures are retried with backoff
+                        let result = bucket_client.run(|client| {
+                            let transfer_manager = transfer_manager.with_client(client);
+                            let (file_path, s3_key, callback_view) = (&file_path, &s3_key, &callback_view);
+                            async move {
+                                let transfer_manager = &transfer_manager;
+                                ErrorHandler::retry(
+                                    || {
+                                        let callback_view = callback_view.clone();
+                                        let callback_bucket = bucket_name.clone();
+                                        let callback_key = s3_key.clone();
+                                        let callback: Box<dyn Fn(TransferProgress) + Send + Sync> = Box::new(move |progress| {
+                                            callback_view.update_entry(&callback_bucket, &callback_key, progress.bytes_transferred, progress.percentage);
+                                        });
+                                        async move {
+                                            // Empty directories are queued with keys ending in '/'
+                                            if s3_key.ends_with('/') {
+                                                transfer_manager.create_folder_marker(bucket_name, s3_key).await.map(|()| None)
+                                            } else {
+                                                transfer_manager.upload_file(file_path, bucket_name, s3_key, Some(callback)).await
+                                            }
+                                        }
+                                    },
+                                    retry_policy,
+                                    |e| !callback_view.is_cancelled() && S3ErrorHelper::is_retryable(e),
+                                ).await
+                            }
+                        }).await;
+                        (file_path, s3_key, size, Some(result))
+                    }
+                })
+                .buffer_unordered(max_concurrent);
+            
+            while let Some((file_path, s3_key, size, result)) = uploads.next().await {
+                // Not started because the batch stopped
+                let result = match result {
+                    Some(result) => result,
+                    None => continue,
+                };
                 
-                // Upload the file
-                match transfer_manager.upload_file(&file_path, &bucket_name, &s3_key, None).await {
-                    Ok(_) => {
+                match result {
+                    Ok(expiration) => {
+                        if let Some(expiration) = expiration {
+                            debug!("{} expires {} (rule {})", s3_key, expiration.expiry_date, expiration.rule_id);
+                            expiring.push((s3_key.clone(), expiration));
+                        }
                         success_count += 1;
+                        progress_view.complete_operation(&bucket_name, &s3_key, size);
                         debug!("Successfully uploaded {} to s3://{}/{}", file_path.display(), bucket_name, s3_key);
                     },
+                    Err(_) if progress_view.is_cancelled() => {
+                        cancelled_count += 1;
+                        progress_view.cancel_operation(&bucket_name, &s3_key);
+                    },
                     Err(e) => {
                         error_count += 1;
+                        progress_view.fail_operation(&bucket_name, &s3_key, &e.to_string());
                         error!("Failed to upload {}: {}", file_path.display(), e);
                         error!("Error details: {:#?}", e.to_string());
+                        failures.push(FailedTransfer {
+                            operation: OperationType::Upload,
+                            bucket: bucket_name.clone(),
+                            key: s3_key.clone(),
+                            local_path: file_path.clone(),
+                            reason: S3ErrorHelper::failure_reason(&e.to_string()),
+                            error: e.to_string(),
+                        });
+                        last_denied = Some((s3_key.clone(), e.to_string()));
+                        
+                        // Files already in flight finish, the rest are cancelled. An expired session
+                        // token fails every file, so the batch goes on and they are all retried once
+                        // new credentials are entered.
+                        if stop_on_error && stopped.is_none() && !S3ErrorHelper::is_expired_token(&e.to_string()) {
+                            stopped = Some((s3_key.clone(), e.to_string()));
+                            stopping.store(true, Ordering::SeqCst);
+                        }
                     }
                 }
             }
             
+            if !failures.is_empty() {
+                let _ = tx.send(StatusMessage::TransferFailures(failures));
+            }
+            
             // Send status message
-            if error_count == 0 {
+            if progress_view.is_cancelled() {
+                let cancelled = cancelled_count + progress_view.cancel_pending();
+                let _ = tx.send(StatusMessage::Info(
+                    format!("Upload cancelled: {} uploaded, {} failed, {} cancelled", success_count, error_count, cancelled)
+                ));
+            } else if let Some((s3_key, error)) = &stopped {
+                let cancelled = progress_view.cancel_pending();
+                let _ = tx.send(StatusMessage::Error(
+                    format!("Upload stopped after {} failed: {} ({} uploaded, {} cancelled)", s3_key, error, success_count, cancelled)
+                ));
+            } else if case_conflicts > 0 {
+                let _ = tx.send(StatusMessage::Error(
+                    format!("Uploaded {} files to bucket {} ({} failed, {} skipped), but {} keys differ only by case from another uploaded key", success_count, bucket_name, error_count, skipped.len(), case_conflicts)
+                ));
+            } else if error_count == 0 {
                 let _ = tx.send(StatusMessage::Info(
-                    format!("Successfully uploaded {} files to bucket {}", success_count, bucket_name)
+                    format!("Successfully uploaded {} files to bucket {} ({} skipped)", success_count, bucket_name, skipped.len())
                 ));
             } else {
                 let _ = tx.send(StatusMessage::Error(
-                    format!("Upload completed with errors: {} succeeded, {} failed", success_count, error_count)
+                    format!("Upload completed with errors: {} succeeded, {} failed, {} skipped", success_count, error_count, skipped.len())
                 ));
             }
             
             // Refresh the bucket objects
-            let _ = tx.send(StatusMessage::Info(format!("Refreshing bucket contents...")));
-            match transfer_manager.list_objects(&bucket_name).await {
-                Ok(objects) => {
-                    let _ = tx.send(StatusMessage::ObjectList(objects));
-                },
-                Err(e) => {
-                    error!("Failed to refresh bucket objects: {}", e);
+            if refresh {
+                let _ = tx.send(StatusMessage::Info(format!("Refreshing bucket contents...")));
+                let (client, _) = bucket_client.current().await;
+                match transfer_manager.with_client(client).list_objects_page(&bucket_name, &prefix, None).await {
+                    Ok((objects, continuation_token)) => {
+                        let _ = tx.send(StatusMessage::ObjectList(objects, continuation_token));
+                    },
+                    Err(e) => {
+                        error!("Failed to refresh bucket objects: {}", e);
+                    }
                 }
             }
+            
+            // Report lifecycle rules that will expire the new objects
+            if let Some((s3_key, expiration)) = expiring.first() {
+                let _ = tx.send(StatusMessage::Info(format!(
+                    "{} uploaded objects are scheduled to expire, e.g. {} on {} (lifecycle rule \"{}\")",
+                    expiring.len(), s3_key, expiration.expiry_date, expiration.rule_id
+                )));
+            }
+            
+            if let Some((s3_key, error)) = &last_denied {
+                Self::report_access_denied(&tx, S3Operation::PutObject, &bucket_name, Some(s3_key), error);
+            }
         });
     }
     
     /// Download selected objects from S3
     pub fn download_selected(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
         // Check if we have a selected bucket
         let bucket = match app_state.bucket_view.selected_bucket() {
             Some(bucket) => bucket.clone(),
@@ -432,7 +1116,7 @@ impl AwsOperations {
         // Get the selected objects
         let selected_objects = app_state.bucket_view.selected_objects();
         if selected_objects.is_empty() {
-            app_state.set_status_error("No objects selected to download");
+            app_state.set_status_error("No objects selected to download: check the objects or prefixes to download first");
             return;
         }
         
@@ -441,87 +1125,156 @@ impl AwsOperations {
         let tx = app_state.status_tx.clone();
         let bucket_name = bucket.clone();
         let folder_path_clone = folder_path.clone();
+        let flatten = app_state.flatten_downloads;
         
-        // Create a vector of objects to download
+        // Selected directories are expanded to the objects under them inside the task
         let objects_to_download: Vec<S3Object> = selected_objects.iter()
-            .filter(|obj| !obj.is_directory) // Skip directories
             .map(|&obj| obj.clone())
             .collect();
+        let progress_view = app_state.progress_view.clone();
         
         // Get the bucket region from the bucket view
         let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let stall_timeout = app_state.settings_view.stall_timeout();
+        let rate_limiter = app_state.rate_limiter.limiter();
+        let retry_policy = app_state.settings_view.retry_policy();
+        let decompress = app_state.settings_view.decompress_downloads();
+        let verify_downloads = app_state.settings_view.verify_downloads();
+        let stop_on_error = app_state.settings_view.stop_on_error();
+        let max_concurrent = app_state.settings_view.max_concurrent_transfers();
         
-        app_state.set_status_info(&format!("Downloading {} files from bucket {}...", objects_to_download.len(), bucket));
+        app_state.set_status_info(&format!("Downloading {} selected items from bucket {}...", objects_to_download.len(), bucket));
+        app_state.show_progress = true;
+        app_state.failed_transfers.clear();
         
         // Spawn an async task to handle the download
         app_state.rt.spawn(async move {
-            // Get the AWS client
-            let mut auth = auth_clone.lock().await;
+            // Scan phase: enumerate and size everything so the totals are accurate from the start
+            progress_view.start_scan();
             
-            // First, try to get the region if we don't have it yet
-            let region = if let Some(region) = bucket_region {
-                debug!("Using cached region {} for bucket {}", region, bucket_name);
-                region
-            } else {
-                // Try to get the default client to query the bucket location
-                let default_client = match auth.get_client().await {
-                    Ok(client) => client,
-                    Err(e) => {
-                        error!("Failed to get default AWS client: {}", e);
-                        let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
-                        return;
+            // Get a client for the bucket's region
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket_name, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            // Create a transfer manager
+            let (client, _) = bucket_client.current().await;
+            let mut transfer_manager = TransferManager::new(client);
+            transfer_manager.set_stall_timeout(stall_timeout);
+            transfer_manager.set_decompress(decompress);
+            transfer_manager.set_verify_downloads(verify_downloads);
+            transfer_manager.set_rate_limiter(rate_limiter);
+            transfer_manager.set_cancel_token(progress_view.cancel_token());
+            
+            // Track download statistics
+            let mut success_count = 0;
+            let mut error_count = 0;
+            let mut cancelled_count = 0;
+            
+            let mut last_denied: Option<(String, String)> = None;
+            
+            // Objects whose KMS key can't be used, reported apart from S3 permission errors
+            let mut kms_denied_count = 0;
+            
+            let mut failures = Vec::new();
+            
+            // Progress entries are keyed by object key, so an object selected both directly
+            // and through its prefix is only downloaded once
+            let mut queued_keys: HashSet<String> = HashSet::new();
+            
+            let mut files_to_download: Vec<(String, u64)> = Vec::new();
+            for object in objects_to_download {
+                if progress_view.is_cancelled() {
+                    break;
+                }
+                
+                if !object.is_directory {
+                    if queued_keys.insert(object.key.clone()) {
+                        progress_view.add_scanned(object.size);
+                        files_to_download.push((object.key, object.size));
                     }
-                };
+                    continue;
+                }
                 
-                // Try to get the bucket location
-                match Self::get_bucket_location(&default_client, &bucket_name).await {
-                    Ok(region) => {
-                        debug!("Detected region {} for bucket {}", region, bucket_name);
-                        region
+                let listing = bucket_client.run(|client| {
+                    let (transfer_manager, bucket_name, prefix) = (&transfer_manager, &bucket_name, format!("{}/", object.key));
+                    async move { transfer_manager.with_client(client).list_prefix(bucket_name, &prefix).await }
+                }).await;
+                match listing {
+                    Ok(summaries) => {
+                        for summary in summaries {
+                            if queued_keys.insert(summary.key.clone()) {
+                                progress_view.add_scanned(summary.size);
+                                files_to_download.push((summary.key, summary.size));
+                            }
+                        }
                     },
                     Err(e) => {
-                        error!("Failed to get region for bucket {}: {}", bucket_name, e);
-                        // Try us-east-1 as default
-                        "us-east-1".to_string()
+                        error_count += 1;
+                        error!("Failed to list {}: {}", object.key, e);
                     }
                 }
-            };
+            }
             
-            // Now get a client for the specific region
-            let client = match auth.get_client_for_region(&region).await {
-                Ok(client) => {
-                    debug!("Using region-specific client for bucket {} in region {}", bucket_name, region);
-                    client
-                },
-                Err(e) => {
-                    error!("Failed to get AWS client for region {}: {}", region, e);
-                    
-                    // Try with us-east-2 as a fallback
-                    match auth.get_client_for_region("us-east-2").await {
-                        Ok(client) => {
-                            debug!("Using fallback us-east-2 client for bucket {}", bucket_name);
-                            client
-                        },
-                        Err(fallback_err) => {
-                            error!("Failed to get fallback AWS client: {}", fallback_err);
-                            let _ = tx.send(StatusMessage::Error(format!("Failed to get AWS client: {}", e)));
-                            return;
-                        }
+            // Folder marker objects aren't files; just make sure the directory exists
+            let (files_to_download, directory_markers) = split_directory_markers(files_to_download);
+            if !flatten {
+                for marker in &directory_markers {
+                    let directory = folder_path_clone.join(marker.trim_end_matches('/').replace('/', std::path::MAIN_SEPARATOR_STR));
+                    if let Err(e) = std::fs::create_dir_all(&directory) {
+                        error!("Failed to create directory {}: {}", directory.display(), e);
                     }
                 }
-            };
+            }
             
-            // Create a transfer manager
-            let transfer_manager = TransferManager::new(client);
+            // Transfer phase
+            let total_bytes = files_to_download.iter().map(|(_, size)| *size).sum();
+            progress_view.start_sync(files_to_download.len(), total_bytes);
             
-            // Track download statistics
-            let mut success_count = 0;
-            let mut error_count = 0;
+            for (key, size) in &files_to_download {
+                progress_view.add_entry(ProgressInfo {
+                    file_name: key.clone(),
+                    bucket: bucket_name.clone(),
+                    s3_key: key.clone(),
+                    operation_type: OperationType::Download,
+                    bytes_transferred: 0,
+                    total_bytes: *size,
+                    percentage: 0.0,
+                    status: ProgressStatus::Pending,
+                    message: String::new(),
+                    timestamp: Instant::now(),
+                    // Set once the path is worked out below
+                    local_path: PathBuf::new(),
+                });
+            }
             
-            // Process each object
-            for object in objects_to_download {
-                // Calculate the local file path
-                let local_path = folder_path_clone.join(object.key.replace('/', std::path::MAIN_SEPARATOR_STR));
+            // Local paths already used by this download, for de-duplicating flattened names
+            // and keys that differ only by case
+            let mut used_paths = HashSet::new();
+            
+            // The file that stopped the batch, if it stops at the first failure
+            let mut stopped: Option<(String, String)> = None;
+            
+            // Work out every local path up front: de-duplicating depends on the order
+            // files are queued in, which concurrent downloads don't keep
+            let mut downloads_to_start: Vec<(String, u64, PathBuf)> = Vec::new();
+            for (key, size) in files_to_download {
+                if progress_view.is_cancelled() {
+                    break;
+                }
+                
+                // Calculate the local file path, either flattened or recreating the key's prefixes
+                let local_path = if flatten {
+                    flat_download_path(&folder_path_clone, &key, &mut used_paths)
+                } else {
+                    unique_download_path(
+                        folder_path_clone.join(key.replace('/', std::path::MAIN_SEPARATOR_STR)),
+                        &mut used_paths,
+                    )
+                };
+                progress_view.set_local_path(&bucket_name, &key, &local_path);
                 
                 // Create parent directories if they don't exist
                 if let Some(parent) = local_path.parent() {
@@ -529,30 +1282,152 @@ impl AwsOperations {
                         if let Err(e) = std::fs::create_dir_all(parent) {
                             error!("Failed to create directory {}: {}", parent.display(), e);
                             error_count += 1;
+                            progress_view.fail_operation(&bucket_name, &key, &e.to_string());
+                            failures.push(FailedTransfer {
+                                operation: OperationType::Download,
+                                bucket: bucket_name.clone(),
+                                key: key.clone(),
+                                local_path: local_path.clone(),
+                                reason: format!("Could not create {}", parent.display()),
+                                error: e.to_string(),
+                            });
+                            
+                            if stop_on_error {
+                                stopped = Some((key.clone(), e.to_string()));
+                                break;
+                            }
                             continue;
                         }
                     }
                 }
                 
-                // Download the file
-                match transfer_manager.download_file(&bucket_name, &object.key, &local_path, None).await {
-                    Ok(_) => {
+                downloads_to_start.push((key, size, local_path));
+            }
+            if stopped.is_some() {
+                downloads_to_start.clear();
+            }
+            
+            // Set once the batch stops, so files that haven't started are left pending and cancelled
+            let stopping = AtomicBool::new(false);
+            
+            // Download several files at a time, handling each result as it finishes
+            let mut downloads = stream::iter(downloads_to_start)
+                .map(|(key, size, local_path)| {
+                    let transfer_manager = &transfer_manager;
+                    let bucket_client = &bucket_client;
+                    let bucket_name = &bucket_name;
+                    let stopping = &stopping;
+                    let callback_view = progress_view.clone();
+                    
+                    async move {
+                        if stopping.load(Ordering::SeqCst) || callback_view.is_cancelled() {
+                            return (key, size, local_path, None);
+                        }
+                        
+                        callback_view.start_operation(bucket_name, &key);
+                        
+                        // Throttling and other transient failures are retried with backoff
+                        let result = bucket_client.run(|client| {
+                            let transfer_manager = transfer_manager.with_client(client);
+                            let (key, local_path, callback_view) = (&key, &local_path, &callback_view);
+                            async move {
+                                let transfer_manager = &transfer_manager;
+                                ErrorHandler::retry(
+                                    || {
+                                        let callback_view = callback_view.clone();
+                                        let callback_bucket = bucket_name.clone();
+                                        let callback_key = key.clone();
+                                        let callback: Box<dyn Fn(TransferProgress) + Send + Sync> = Box::new(move |progress| {
+                                            callback_view.update_entry(&callback_bucket, &callback_key, progress.bytes_transferred, progress.percentage);
+                                        });
+                                        transfer_manager.download_file(bucket_name, key, local_path, Some(callback))
+                                    },
+                                    retry_policy,
+                                    |e| !callback_view.is_cancelled() && S3ErrorHelper::is_retryable(e),
+                                ).await
+                            }
+                        }).await;
+                        (key, size, local_path, Some(result))
+                    }
+                })
+                .buffer_unordered(max_concurrent);
+            
+            while let Some((key, size, local_path, result)) = downloads.next().await {
+                // Not started because the batch stopped
+                let result = match result {
+                    Some(result) => result,
+                    None => continue,
+                };
+                
+                match result {
+                    Ok(written_path) => {
                         success_count += 1;
-                        debug!("Successfully downloaded s3://{}/{} to {}", bucket_name, object.key, local_path.display());
+                        progress_view.complete_operation(&bucket_name, &key, size);
+                        debug!("Successfully downloaded s3://{}/{} to {}", bucket_name, key, written_path.display());
+                    },
+                    Err(_) if progress_view.is_cancelled() => {
+                        cancelled_count += 1;
+                        progress_view.cancel_operation(&bucket_name, &key);
                     },
                     Err(e) => {
                         error_count += 1;
-                        error!("Failed to download {}: {}", object.key, e);
-                        error!("Error details: {:#?}", e.to_string());
+                        error!("Failed to download {}: {}", key, e);
+                        
+                        if S3ErrorHelper::is_kms_error(&e.to_string()) {
+                            kms_denied_count += 1;
+                            progress_view.fail_operation(&bucket_name, &key, KMS_DECRYPT_MESSAGE);
+                        } else {
+                            progress_view.fail_operation(&bucket_name, &key, &e.to_string());
+                            error!("Error details: {:#?}", e.to_string());
+                            last_denied = Some((key.clone(), e.to_string()));
+                        }
+                        
+                        failures.push(FailedTransfer {
+                            operation: OperationType::Download,
+                            bucket: bucket_name.clone(),
+                            key: key.clone(),
+                            local_path: local_path.clone(),
+                            reason: S3ErrorHelper::failure_reason(&e.to_string()),
+                            error: e.to_string(),
+                        });
+                        
+                        // Files already in flight finish, the rest are cancelled. An expired session
+                        // token fails every file, so the batch goes on and they are all retried once
+                        // new credentials are entered.
+                        if stop_on_error && stopped.is_none() && !S3ErrorHelper::is_expired_token(&e.to_string()) {
+                            stopped = Some((key.clone(), e.to_string()));
+                            stopping.store(true, Ordering::SeqCst);
+                        }
                     }
                 }
             }
             
+            if !failures.is_empty() {
+                let _ = tx.send(StatusMessage::TransferFailures(failures));
+            }
+            
             // Send status message
-            if error_count == 0 {
+            if progress_view.is_cancelled() {
+                let cancelled = cancelled_count + progress_view.cancel_pending();
+                let _ = tx.send(StatusMessage::Info(
+                    format!("Download cancelled: {} downloaded, {} failed, {} cancelled", success_count, error_count, cancelled)
+                ));
+            } else if let Some((key, error)) = &stopped {
+                let cancelled = progress_view.cancel_pending();
+                let _ = tx.send(StatusMessage::Error(
+                    format!("Download stopped after {} failed: {} ({} downloaded, {} cancelled)", key, error, success_count, cancelled)
+                ));
+            } else if error_count == 0 {
                 let _ = tx.send(StatusMessage::Info(
                     format!("Successfully downloaded {} files from bucket {}", success_count, bucket_name)
                 ));
+            } else if kms_denied_count > 0 {
+                let _ = tx.send(StatusMessage::Error(
+                    format!(
+                        "Download completed with errors: {} succeeded, {} failed ({} encrypted with a KMS key you can't decrypt)",
+                        success_count, error_count, kms_denied_count
+                    )
+                ));
             } else {
                 let _ = tx.send(StatusMessage::Error(
                     format!("Download completed with errors: {} succeeded, {} failed", success_count, error_count)
@@ -561,13 +1436,1471 @@ impl AwsOperations {
             
             // Refresh the local folder contents
             let _ = tx.send(StatusMessage::Info(format!("Refreshing local folder contents...")));
+            
+            if let Some((key, error)) = &last_denied {
+                Self::report_access_denied(&tx, S3Operation::GetObject, &bucket_name, Some(key), error);
+            }
         });
     }
     
-    /// Sync selected folders with S3
-    pub fn sync_selected(app_state: &mut AppState) {
-        // Implementation will go here
-        app_state.set_status_info("Sync functionality not yet implemented");
+    /// List everything the selected objects and prefixes cover and open a preview,
+    /// so the delete only runs once the full key set has been confirmed
+    pub fn preview_delete_selected(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => {
+                app_state.set_status_error("No S3 bucket selected");
+                return;
+            }
+        };
+        
+        let selected_objects: Vec<S3Object> = app_state.bucket_view.selected_objects()
+            .iter()
+            .map(|&obj| obj.clone())
+            .collect();
+        if selected_objects.is_empty() {
+            app_state.set_status_error("No objects selected to delete");
+            return;
+        }
+        
+        app_state.delete_preview = Some(DeletePreviewView::new(bucket.clone()));
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let versioning = bucket_client.run(|client| {
+                let bucket = &bucket;
+                async move { TransferManager::new(client).is_bucket_versioned(bucket).await }
+            }).await;
+            let versioned = match versioning {
+                Ok(versioned) => Some(versioned),
+                Err(e) => {
+                    debug!("Could not read versioning of bucket {}: {}", bucket, e);
+                    None
+                }
+            };
+            
+            // Prefixes are expanded to every object under them, including folder markers
+            let mut objects = Vec::new();
+            let mut seen = HashSet::new();
+            for object in selected_objects {
+                let summaries = if object.is_directory {
+                    let listing = bucket_client.run(|client| {
+                        let (bucket, prefix) = (&bucket, format!("{}/", object.key));
+                        async move { TransferManager::new(client).list_prefix(bucket, &prefix).await }
+                    }).await;
+                    match listing {
+                        Ok(summaries) => summaries,
+                        Err(e) => {
+                            error!("Failed to list {}: {}", object.key, e);
+                            let _ = tx.send(StatusMessage::DeletePreview(
+                                bucket.clone(),
+                                Err(format!("Failed to list {}: {}", object.key, e)),
+                                versioned,
+                            ));
+                            Self::report_access_denied(&tx, S3Operation::ListObjects, &bucket, None, &e.to_string());
+                            return;
+                        }
+                    }
+                } else {
+                    vec![ObjectSummary {
+                        key: object.key,
+                        size: object.size,
+                        e_tag: None,
+                        last_modified: object.last_modified_secs,
+                    }]
+                };
+                
+                for summary in summaries {
+                    if seen.insert(summary.key.clone()) {
+                        objects.push(summary);
+                    }
+                }
+            }
+            
+            let _ = tx.send(StatusMessage::DeletePreview(bucket, Ok(objects), versioned));
+        });
+    }
+    
+    /// Delete objects that were confirmed in the delete preview
+    pub fn delete_objects(app_state: &mut AppState, bucket: &str, keys: Vec<String>) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let bucket_name = bucket.to_string();
+        let prefix = app_state.bucket_view.prefix().to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let progress_view = app_state.progress_view.clone();
+        
+        app_state.set_status_info(&format!("Deleting {} objects from bucket {}...", keys.len(), bucket));
+        app_state.show_progress = true;
+        app_state.failed_transfers.clear();
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket_name, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            progress_view.start_sync(keys.len(), 0);
+            for key in &keys {
+                progress_view.add_entry(ProgressInfo {
+                    file_name: key.clone(),
+                    bucket: bucket_name.clone(),
+                    s3_key: key.clone(),
+                    operation_type: OperationType::Delete,
+                    bytes_transferred: 0,
+                    total_bytes: 0,
+                    percentage: 0.0,
+                    status: ProgressStatus::Pending,
+                    message: String::new(),
+                    timestamp: Instant::now(),
+                    local_path: PathBuf::new(),
+                });
+            }
+            
+            // Delete a request's worth of keys at a time, so the progress moves with each batch
+            let mut failed = HashMap::new();
+            let mut failures = Vec::new();
+            for batch in keys.chunks(DELETE_BATCH_SIZE) {
+                for key in batch {
+                    progress_view.start_operation(&bucket_name, key);
+                }
+                
+                // A batch sent to the wrong region fails every key with the same error, and is
+                // sent again in the bucket's region
+                let deleted = bucket_client.run(|client| {
+                    let bucket_name = &bucket_name;
+                    async move {
+                        let failed = TransferManager::new(client).delete_objects(bucket_name, batch).await;
+                        match failed.first() {
+                            Some((_, e)) if S3ErrorHelper::is_redirect_error(e) => Err(e.clone()),
+                            _ => Ok(failed),
+                        }
+                    }
+                }).await;
+                let batch_failed: HashMap<String, String> = match deleted {
+                    Ok(failed) => failed.into_iter().collect(),
+                    Err(e) => batch.iter().map(|key| (key.clone(), e.clone())).collect(),
+                };
+                
+                for key in batch {
+                    match batch_failed.get(key) {
+                        Some(e) => {
+                            progress_view.fail_operation(&bucket_name, key, e);
+                            failures.push(FailedTransfer {
+                                operation: OperationType::Delete,
+                                bucket: bucket_name.clone(),
+                                key: key.clone(),
+                                local_path: PathBuf::new(),
+                                reason: S3ErrorHelper::failure_reason(e),
+                                error: e.clone(),
+                            });
+                        },
+                        None => progress_view.complete_operation(&bucket_name, key, 0),
+                    }
+                }
+                
+                failed.extend(batch_failed);
+            }
+            
+            if failed.is_empty() {
+                let _ = tx.send(StatusMessage::Info(
+                    format!("Deleted {} objects from bucket {}", keys.len(), bucket_name)
+                ));
+            } else {
+                let _ = tx.send(StatusMessage::Error(
+                    format!("Delete completed with errors: {} deleted, {} failed", keys.len() - failed.len(), failed.len())
+                ));
+                if let Some((key, error)) = failed.iter().next() {
+                    Self::report_access_denied(&tx, S3Operation::DeleteObject, &bucket_name, Some(key.as_str()), error);
+                }
+                let _ = tx.send(StatusMessage::TransferFailures(failures));
+            }
+            
+            // Show the bucket without the deleted objects
+            let (client, _) = bucket_client.current().await;
+            match TransferManager::new(client).list_objects_page(&bucket_name, &prefix, None).await {
+                Ok((objects, continuation_token)) => {
+                    let _ = tx.send(StatusMessage::ObjectList(objects, continuation_token));
+                },
+                Err(e) => {
+                    error!("Failed to refresh bucket {}: {}", bucket_name, e);
+                }
+            }
+        });
+    }
+    
+    /// Switch to new temporary credentials entered after the session token expired, then
+    /// retry what failed with the old ones
+    pub fn renew_session(app_state: &mut AppState, credentials: RenewedCredentials, prompt: SessionTokenView) {
+        app_state.settings_view.set_aws_access_key(credentials.access_key.clone());
+        app_state.settings_view.set_aws_secret_key(credentials.secret_key.clone());
+        app_state.settings_view.set_aws_session_token(credentials.session_token.clone());
+        
+        // Keep the new keys the same way applying the settings does
+        if app_state.settings_view.get_settings().save_credentials {
+            app_state.save_credentials(&credentials.access_key, &credentials.secret_key);
+            app_state.save_config();
+        }
+        
+        let region = app_state.settings_view.aws_region();
+        let (failures, reload_objects) = prompt.into_pending();
+        let tx = app_state.status_tx.clone();
+        
+        // The retries are only sent once the credentials are in place, which may be after
+        // a wait for the auth lock
+        Self::update_auth(app_state, "renewing the session", move |auth| {
+            auth.set_credentials(credentials.access_key, credentials.secret_key, Some(credentials.session_token), region);
+            let _ = tx.send(StatusMessage::SessionRenewed(failures, reload_objects));
+        });
+    }
+    
+    /// Retry files that failed in a batch transfer, a few at a time. Any that fail again
+    /// are returned to the failed list with the new error.
+    pub fn retry_transfers(app_state: &mut AppState, failures: Vec<FailedTransfer>) {
+        if !Self::ensure_online(app_state) {
+            app_state.failed_transfers.extend(failures);
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let stall_timeout = app_state.settings_view.stall_timeout();
+        let decompress = app_state.settings_view.decompress_downloads();
+        let verify_downloads = app_state.settings_view.verify_downloads();
+        let upload_part_size = app_state.settings_view.upload_part_size();
+        let rate_limiter = app_state.rate_limiter.limiter();
+        let encryption = app_state.settings_view.server_side_encryption();
+        let kms_key_id = app_state.settings_view.sse_kms_key_id();
+        let content_types = app_state.settings_view.content_type_overrides();
+        let max_concurrent = app_state.settings_view.max_concurrent_transfers();
+        let progress_view = app_state.progress_view.clone();
+        
+        // The region, storage class and ACL depend on each file's bucket and folder
+        let retries: Vec<_> = failures.into_iter()
+            .map(|failure| {
+                let bucket_region = app_state.bucket_view.get_bucket_region(&failure.bucket).cloned();
+                let storage_class = Self::upload_storage_class(app_state, &failure.local_path);
+                let acl = Self::upload_acl(app_state, &failure.local_path);
+                (failure, bucket_region, storage_class, acl)
+            })
+            .collect();
+        
+        match retries.as_slice() {
+            [(failure, ..)] => app_state.set_status_info(&format!("Retrying {}...", failure.key)),
+            retries => app_state.set_status_info(&format!("Retrying {} failed transfers...", retries.len())),
+        }
+        
+        app_state.rt.spawn(async move {
+            let total = retries.len();
+            
+            // One client per bucket, so each bucket's location is looked up at most once
+            let mut bucket_clients: HashMap<String, BucketClient> = HashMap::new();
+            for (failure, bucket_region, ..) in &retries {
+                if !bucket_clients.contains_key(&failure.bucket) {
+                    if let Some(bucket_client) = Self::client_for_bucket(&auth_clone, &failure.bucket, bucket_region.clone(), lookup_bucket_regions, &tx).await {
+                        bucket_clients.insert(failure.bucket.clone(), bucket_client);
+                    }
+                }
+            }
+            
+            let results: Vec<Result<String, FailedTransfer>> = stream::iter(retries)
+                .map(|(failure, _, storage_class, acl)| {
+                    let bucket_clients = &bucket_clients;
+                    let encryption = encryption.clone();
+                    let kms_key_id = kms_key_id.clone();
+                    let content_types = content_types.clone();
+                    let rate_limiter = rate_limiter.clone();
+                    let progress_view = progress_view.clone();
+                    
+                    async move {
+                        let bucket_client = match bucket_clients.get(&failure.bucket) {
+                            Some(bucket_client) => bucket_client,
+                            None => return Err(failure),
+                        };
+                        
+                        let (client, _) = bucket_client.current().await;
+                        let mut transfer_manager = TransferManager::new(client);
+                        transfer_manager.set_stall_timeout(stall_timeout);
+                        transfer_manager.set_decompress(decompress);
+                        transfer_manager.set_verify_downloads(verify_downloads);
+                        transfer_manager.set_upload_part_size(upload_part_size);
+                        transfer_manager.set_encryption(encryption, kms_key_id);
+                        transfer_manager.set_storage_class(storage_class);
+                        transfer_manager.set_acl(acl);
+                        transfer_manager.set_content_type_overrides(content_types);
+                        transfer_manager.set_rate_limiter(rate_limiter);
+                        
+                        // Files still listed in the progress view show the retry there
+                        let size = progress_view.restart_operation(&failure.bucket, &failure.key);
+                        let callback = || -> Option<Box<dyn Fn(TransferProgress) + Send + Sync>> {
+                            let callback_view = progress_view.clone();
+                            let callback_bucket = failure.bucket.clone();
+                            let callback_key = failure.key.clone();
+                            Some(Box::new(move |progress| {
+                                callback_view.update_entry(&callback_bucket, &callback_key, progress.bytes_transferred, progress.percentage);
+                            }))
+                        };
+                        
+                        let result = bucket_client.run(|client| {
+                            let transfer_manager = transfer_manager.with_client(client);
+                            let (failure, callback) = (&failure, &callback);
+                            async move {
+                                match failure.operation {
+                                    // Empty folders are uploaded as zero-byte markers
+                                    OperationType::Upload if failure.key.ends_with('/') => transfer_manager
+                                        .create_folder_marker(&failure.bucket, &failure.key)
+                                        .await,
+                                    OperationType::Upload => transfer_manager
+                                        .upload_file(&failure.local_path, &failure.bucket, &failure.key, callback())
+                                        .await
+                                        .map(|_| ()),
+                                    OperationType::Delete => transfer_manager
+                                        .delete_object(&failure.bucket, &failure.key)
+                                        .await,
+                                    _ => transfer_manager
+                                        .download_file(&failure.bucket, &failure.key, &failure.local_path, callback())
+                                        .await
+                                        .map(|_| ()),
+                                }
+                            }
+                        }).await;
+                        
+                        match result {
+                            Ok(_) => {
+                                if let Some(size) = size {
+                                    progress_view.complete_operation(&failure.bucket, &failure.key, size);
+                                }
+                                Ok(failure.key)
+                            },
+                            Err(e) => {
+                                if size.is_some() {
+                                    progress_view.fail_operation(&failure.bucket, &failure.key, &e.to_string());
+                                }
+                                error!("Retry of {} failed: {}", failure.key, e);
+                                Err(FailedTransfer {
+                                    reason: S3ErrorHelper::failure_reason(&e.to_string()),
+                                    error: e.to_string(),
+                                    ..failure
+                                })
+                            }
+                        }
+                    }
+                })
+                .buffer_unordered(max_concurrent)
+                .collect()
+                .await;
+            
+            let (succeeded, failed): (Vec<_>, Vec<_>) = results.into_iter().partition(|result| result.is_ok());
+            let failed: Vec<FailedTransfer> = failed.into_iter().filter_map(Result::err).collect();
+            
+            match (succeeded.as_slice(), failed.as_slice()) {
+                ([Ok(key)], []) => {
+                    let _ = tx.send(StatusMessage::Info(format!("Retried {} successfully", key)));
+                },
+                (_, []) => {
+                    let _ = tx.send(StatusMessage::Info(format!("Retried {} transfers successfully", total)));
+                },
+                (_, [failure]) if total == 1 => {
+                    let _ = tx.send(StatusMessage::Error(format!("Retry of {} failed: {}", failure.key, failure.error)));
+                },
+                (_, failed) => {
+                    let _ = tx.send(StatusMessage::Error(format!("{} of {} retried transfers failed again", failed.len(), total)));
+                },
+            }
+            
+            if !failed.is_empty() {
+                let _ = tx.send(StatusMessage::TransferFailures(failed));
+            }
+        });
+    }
+    
+    /// Abort the interrupted multipart uploads of failed uploads that are dismissed rather than
+    /// retried, so their parts don't keep incurring storage charges. Offline, they are left for
+    /// the next upload of each file to clean up.
+    pub fn discard_interrupted_uploads(app_state: &mut AppState, failures: Vec<FailedTransfer>) {
+        if app_state.offline {
+            return;
+        }
+        
+        let uploads: Vec<_> = failures.into_iter()
+            .filter(|failure| matches!(failure.operation, OperationType::Upload) && partial::sidecar_path(&failure.local_path).exists())
+            .map(|failure| {
+                let bucket_region = app_state.bucket_view.get_bucket_region(&failure.bucket).cloned();
+                (failure, bucket_region)
+            })
+            .collect();
+        if uploads.is_empty() {
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.rt.spawn(async move {
+            for (failure, bucket_region) in uploads {
+                // An upload sent to the wrong region has already looked up and remembered the bucket's region
+                if let Some(bucket_client) = Self::client_for_bucket(&auth_clone, &failure.bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                    let (client, _) = bucket_client.current().await;
+                    TransferManager::new(client).discard_interrupted_upload(&failure.local_path).await;
+                }
+            }
+        });
+    }
+    
+    /// Get the bucket and key prefix a local folder is synced with: the bucket mapped to the
+    /// folder if it has one, otherwise the bucket selected in the bucket panel
+    fn folder_target(app_state: &AppState, folder: &SyncFolder) -> Option<(String, String)> {
+        let bucket = folder.bucket.clone().or_else(|| app_state.bucket_view.selected_bucket())?;
+        Some((bucket, folder.key_prefix()))
+    }
+    
+    /// Get the storage class for uploading a local path: the one set on the sync folder
+    /// holding it if there is one, otherwise the one in the settings
+    fn upload_storage_class(app_state: &AppState, local_path: &Path) -> Option<StorageClass> {
+        app_state.config.settings.folders.iter()
+            .filter(|folder| local_path.starts_with(&folder.path))
+            .max_by_key(|folder| folder.path.len())
+            .and_then(|folder| folder.storage_class.as_deref())
+            .map(StorageClass::from)
+            .or_else(|| app_state.settings_view.storage_class())
+    }
+    
+    /// Get the canned ACL for uploading a local path: the one set on the sync folder
+    /// holding it if there is one, otherwise the one in the settings
+    fn upload_acl(app_state: &AppState, local_path: &Path) -> Option<ObjectCannedAcl> {
+        app_state.config.settings.folders.iter()
+            .filter(|folder| local_path.starts_with(&folder.path))
+            .max_by_key(|folder| folder.path.len())
+            .and_then(|folder| folder.acl.as_deref())
+            .map(ObjectCannedAcl::from)
+            .or_else(|| app_state.settings_view.upload_acl())
+    }
+    
+    /// Expand selected paths into (file, size, modified seconds) entries, walking directories recursively
+    fn expand_upload_paths(paths: &[PathBuf], folder_markers: bool) -> Vec<(PathBuf, u64, i64)> {
+        let mut files = Vec::new();
+        
+        for path in paths {
+            // Empty directories are listed too, with no size, to upload as folder markers
+            if folder_markers && path.is_dir() {
+                for dir in SyncEngine::empty_folders(path) {
+                    let modified = std::fs::metadata(&dir)
+                        .map(|metadata| Self::modified_seconds(&metadata))
+                        .unwrap_or(0);
+                    files.push((dir, 0, modified));
+                }
+            }
+            
+            for entry in walkdir::WalkDir::new(path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(|e| e.ok())
+            {
+                // Skip directories, the sidecars of interrupted transfers and unfinished downloads
+                if !entry.file_type().is_file() || partial::is_sidecar(entry.path()) || partial::is_partial_download(entry.path()) {
+                    continue;
+                }
+                
+                match entry.metadata() {
+                    Ok(metadata) => {
+                        files.push((entry.path().to_path_buf(), metadata.len(), Self::modified_seconds(&metadata)));
+                    },
+                    Err(e) => {
+                        error!("Failed to read metadata for {}: {}", entry.path().display(), e);
+                    }
+                }
+            }
+        }
+        
+        files
+    }
+    
+    /// Get a file's modification time in seconds since the epoch, or 0 if it isn't available
+    fn modified_seconds(metadata: &std::fs::Metadata) -> i64 {
+        metadata.modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0)
+    }
+    
+    /// Calculate the S3 key for a file by removing the folder path prefix
+    fn upload_key(folder_path: &Path, file_path: &Path) -> Option<String> {
+        let rel_path = match file_path.strip_prefix(folder_path) {
+            Ok(rel) => rel,
+            // If we can't determine the relative path, use the file name
+            Err(_) => Path::new(file_path.file_name()?),
+        };
+        
+        Some(rel_path.to_string_lossy().replace('\\', "/"))
+    }
+    
+    /// Open the history window for an object and load its versions
+    pub fn show_object_history(app_state: &mut AppState, key: &str) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => {
+                app_state.set_status_error("No S3 bucket selected");
+                return;
+            }
+        };
+        
+        app_state.object_history = Some(ObjectHistoryView::new(bucket.clone(), key.to_string()));
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let key = key.to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let expiration = bucket_client.run(|client| {
+                let (bucket, key) = (&bucket, &key);
+                async move { TransferManager::new(client).get_object_expiration(bucket, key).await }
+            }).await;
+            match expiration {
+                Ok(expiration) => {
+                    let _ = tx.send(StatusMessage::ObjectExpiration(key.clone(), expiration));
+                },
+                Err(e) => {
+                    debug!("Could not read expiration of {}: {}", key, e);
+                }
+            }
+            
+            let versions = bucket_client.run(|client| {
+                let (bucket, key) = (&bucket, &key);
+                async move { TransferManager::new(client).list_object_versions(bucket, key).await }
+            }).await;
+            match versions {
+                Ok(versions) => {
+                    let _ = tx.send(StatusMessage::ObjectVersions(key, versions));
+                },
+                Err(e) => {
+                    error!("Failed to load history for {}: {}", key, e);
+                    let _ = tx.send(StatusMessage::ObjectVersions(key.clone(), Vec::new()));
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to load history for {}: {}", key, e)));
+                    Self::report_access_denied(&tx, S3Operation::ListObjectVersions, &bucket, Some(&key), &e.to_string());
+                }
+            }
+        });
+    }
+    
+    /// Open the properties window for an object and load its metadata
+    pub fn show_object_properties(app_state: &mut AppState, key: &str) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => {
+                app_state.set_status_error("No S3 bucket selected");
+                return;
+            }
+        };
+        
+        app_state.object_properties = Some(ObjectPropertiesView::new(bucket.clone(), key.to_string()));
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let key = key.to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let metadata = bucket_client.run(|client| {
+                let (bucket, key) = (&bucket, &key);
+                async move { TransferManager::new(client).head_object(bucket, key).await }
+            }).await;
+            match metadata {
+                Ok(metadata) => {
+                    let _ = tx.send(StatusMessage::ObjectMetadata(key, Ok(metadata)));
+                },
+                Err(e) => {
+                    error!("Failed to load properties of {}: {}", key, e);
+                    let _ = tx.send(StatusMessage::ObjectMetadata(key.clone(), Err(e.to_string())));
+                    Self::report_access_denied(&tx, S3Operation::GetObject, &bucket, Some(&key), &e.to_string());
+                }
+            }
+        });
+    }
+    
+    /// Preview an object's content in the preview pane. Only images and text under the
+    /// size limit are downloaded; the content type comes from a HEAD request.
+    pub fn load_preview(app_state: &mut AppState, key: &str, size: u64) {
+        app_state.preview.start(key);
+        
+        if size > PREVIEW_SIZE_LIMIT {
+            let reason = format!("{} is larger than the {} preview limit", format_size(size), format_size(PREVIEW_SIZE_LIMIT));
+            app_state.preview.set_content(key, PreviewContent::Unavailable(reason));
+            return;
+        }
+        
+        if app_state.offline {
+            app_state.preview.set_content(key, PreviewContent::Unavailable("Previews aren't available offline".to_string()));
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let key = key.to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let metadata = bucket_client.run(|client| {
+                let (bucket, key) = (&bucket, &key);
+                async move { TransferManager::new(client).head_object(bucket, key).await }
+            }).await;
+            let content = match metadata {
+                Ok(metadata) => {
+                    let content_type = metadata.content_type.unwrap_or_default();
+                    let (client, _) = bucket_client.current().await;
+                    match PreviewKind::from_content_type(&content_type) {
+                        Some(kind) => match TransferManager::new(client).download_to_bytes(&bucket, &key).await {
+                            Ok(bytes) => PreviewContent::decode(kind, &bytes),
+                            Err(e) => PreviewContent::Failed(e.to_string()),
+                        },
+                        None if content_type.is_empty() => PreviewContent::Unavailable("The object has no content type".to_string()),
+                        None => PreviewContent::Unavailable(format!("{} content can't be previewed", content_type)),
+                    }
+                },
+                Err(e) => PreviewContent::Failed(e.to_string()),
+            };
+            
+            let _ = tx.send(StatusMessage::Preview(key, content));
+        });
+    }
+    
+    /// Create a presigned link for an object that expires after `ttl`, and copy it to the clipboard.
+    /// The link downloads the object, or with `upload` set, uploads a replacement for it.
+    pub fn copy_share_link(app_state: &mut AppState, key: &str, ttl: Duration, upload: bool) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => {
+                app_state.set_status_error("No S3 bucket selected");
+                return;
+            }
+        };
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let key = key.to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        
+        app_state.rt.spawn(async move {
+            // Links are signed for the bucket's own region, or S3 rejects them. Presigning sends
+            // no request that could fail with a region error, so an unknown region is looked up.
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, true, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let (client, _) = bucket_client.current().await;
+            let transfer_manager = TransferManager::new(client);
+            
+            let link = if upload {
+                transfer_manager.presign_put(&bucket, &key, ttl).await
+            } else {
+                transfer_manager.presign_get(&bucket, &key, ttl).await
+            };
+            let kind = if upload { "upload link" } else { "share link" };
+            
+            match link {
+                Ok(url) => {
+                    let _ = tx.send(StatusMessage::ShareLink(key, url, upload));
+                },
+                Err(e) => {
+                    error!("Failed to create {} for {}: {}", kind, key, e);
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to create {} for {}: {}", kind, key, e)));
+                }
+            }
+        });
+    }
+    
+    /// Open the configuration window for a bucket and load its lifecycle and CORS rules
+    pub fn show_bucket_configuration(app_state: &mut AppState, bucket: &str) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        app_state.bucket_config = Some(BucketConfigView::new(bucket.to_string()));
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let bucket = bucket.to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let read_configuration = |region: String| {
+                let (auth_clone, bucket) = (&auth_clone, &bucket);
+                async move {
+                    let mut bucket_manager = BucketManager::new(lock_auth(auth_clone, "reading bucket configuration").await.clone());
+                    let lifecycle = bucket_manager.get_bucket_lifecycle_configuration(bucket, &region).await
+                        .map_err(|e| e.to_string());
+                    let cors = bucket_manager.get_bucket_cors(bucket, &region).await
+                        .map_err(|e| e.to_string());
+                    (lifecycle, cors)
+                }
+            };
+            
+            // The rules are read again in the bucket's region if they were asked for in the wrong one
+            let (client, region) = bucket_client.current().await;
+            let (mut lifecycle, mut cors) = read_configuration(region).await;
+            let wrong_region = matches!(&lifecycle, Err(e) if S3ErrorHelper::is_redirect_error(e))
+                || matches!(&cors, Err(e) if S3ErrorHelper::is_redirect_error(e));
+            if wrong_region {
+                if let Some((_, region)) = bucket_client.relocate(&client).await {
+                    (lifecycle, cors) = read_configuration(region).await;
+                }
+            }
+            
+            let _ = tx.send(StatusMessage::BucketConfiguration(bucket, lifecycle, cors));
+        });
+    }
+    
+    /// Download a specific version of an object next to the current local copy
+    pub fn download_object_version(app_state: &mut AppState, key: &str, version_id: &str) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => {
+                app_state.set_status_error("No S3 bucket selected for download");
+                return;
+            }
+        };
+        
+        let folder_path = match app_state.folder_list.selected_folder() {
+            Some(path) => path.clone(),
+            None => {
+                app_state.set_status_error("No local folder selected as download destination");
+                return;
+            }
+        };
+        
+        // Keep the version ID in the file name so the current copy isn't overwritten
+        let name = key.rsplit('/').next().unwrap_or(key);
+        let short_id: String = version_id.chars().take(8).collect();
+        let versioned_name = match name.rfind('.') {
+            Some(index) if index > 0 => format!("{} ({}){}", &name[..index], short_id, &name[index..]),
+            _ => format!("{} ({})", name, short_id),
+        };
+        let local_path = folder_path.join(versioned_name);
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let key = key.to_string();
+        let version_id = version_id.to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let stall_timeout = app_state.settings_view.stall_timeout();
+        let rate_limiter = app_state.rate_limiter.limiter();
+        let decompress = app_state.settings_view.decompress_downloads();
+        let verify_downloads = app_state.settings_view.verify_downloads();
+        
+        app_state.set_status_info(&format!("Downloading version {} of {}...", version_id, key));
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let (client, _) = bucket_client.current().await;
+            let mut transfer_manager = TransferManager::new(client);
+            transfer_manager.set_stall_timeout(stall_timeout);
+            transfer_manager.set_decompress(decompress);
+            transfer_manager.set_verify_downloads(verify_downloads);
+            transfer_manager.set_rate_limiter(rate_limiter);
+            
+            let download = bucket_client.run(|client| {
+                let (transfer_manager, bucket, key, version_id, local_path) = (&transfer_manager, &bucket, &key, &version_id, &local_path);
+                async move { transfer_manager.with_client(client).download_file_version(bucket, key, Some(version_id), local_path, None).await }
+            }).await;
+            match download {
+                Ok(written_path) => {
+                    let _ = tx.send(StatusMessage::Info(format!("Downloaded version of {} to {}", key, written_path.display())));
+                },
+                Err(e) => {
+                    error!("Failed to download version {} of {}: {}", version_id, key, e);
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to download version of {}: {}", key, e)));
+                    Self::report_access_denied(&tx, S3Operation::GetObject, &bucket, Some(&key), &e.to_string());
+                }
+            }
+        });
+    }
+    
+    /// Restore an object to a point in its history. A delete marker is removed,
+    /// any other version is copied over the object as the new current version.
+    pub fn restore_object_version(app_state: &mut AppState, key: &str, version: ObjectVersionInfo) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let bucket = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => bucket,
+            None => {
+                app_state.set_status_error("No S3 bucket selected");
+                return;
+            }
+        };
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let key = key.to_string();
+        let prefix = app_state.bucket_view.prefix().to_string();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.set_status_info(&format!("Restoring {}...", key));
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => return,
+            };
+            
+            let result = bucket_client.run(|client| {
+                let (bucket, key, version) = (&bucket, &key, &version);
+                async move {
+                    let transfer_manager = TransferManager::new(client);
+                    if version.is_delete_marker {
+                        transfer_manager.delete_object_version(bucket, key, &version.version_id).await
+                    } else {
+                        transfer_manager.restore_object_version(bucket, key, &version.version_id).await
+                    }
+                }
+            }).await;
+            
+            match result {
+                Ok(_) => {
+                    let _ = tx.send(StatusMessage::Info(format!("Restored {}", key)));
+                },
+                Err(e) => {
+                    error!("Failed to restore {}: {}", key, e);
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to restore {}: {}", key, e)));
+                    let operation = if version.is_delete_marker { S3Operation::DeleteObjectVersion } else { S3Operation::CopyObject };
+                    Self::report_access_denied(&tx, operation, &bucket, Some(&key), &e.to_string());
+                    return;
+                }
+            }
+            
+            // Reload the timeline and the bucket contents
+            let (client, _) = bucket_client.current().await;
+            let transfer_manager = TransferManager::new(client);
+            if let Ok(versions) = transfer_manager.list_object_versions(&bucket, &key).await {
+                let _ = tx.send(StatusMessage::ObjectVersions(key.clone(), versions));
+            }
+            
+            match transfer_manager.list_objects_page(&bucket, &prefix, None).await {
+                Ok((objects, continuation_token)) => {
+                    let _ = tx.send(StatusMessage::ObjectList(objects, continuation_token));
+                },
+                Err(e) => {
+                    error!("Failed to refresh bucket objects: {}", e);
+                }
+            }
+        });
+    }
+    
+    /// Start a background check that reports whether AWS can be reached, so the UI can
+    /// switch to offline mode instead of letting every action time out
+    pub fn start_connectivity_check(app_state: &AppState) {
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        
+        app_state.rt.spawn(async move {
+            let mut interval = tokio::time::interval(CONNECTIVITY_CHECK_INTERVAL);
+            
+            loop {
+                interval.tick().await;
+                
+                // Check the endpoint requests go to, which follows the settings
+                let host = lock_auth(&auth_clone, "checking connectivity").await.endpoint_host();
+                let connect = tokio::net::TcpStream::connect(host.as_str());
+                let online = matches!(tokio::time::timeout(CONNECTIVITY_TIMEOUT, connect).await, Ok(Ok(_)));
+                
+                if !online {
+                    debug!("Connectivity check to {} failed", host);
+                }
+                
+                if tx.send(StatusMessage::Connectivity(online)).is_err() {
+                    // The app has shut down
+                    break;
+                }
+            }
+        });
+    }
+    
+    /// Start a background timer that refreshes temporary credentials before they
+    /// expire and reports the remaining time. Static credentials are never refreshed.
+    pub fn start_credential_refresh(app_state: &AppState) {
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        
+        app_state.rt.spawn(async move {
+            let mut interval = tokio::time::interval(CREDENTIAL_CHECK_INTERVAL);
+            
+            loop {
+                interval.tick().await;
+                
+                let provider = lock_auth(&auth_clone, "checking credential expiry").await.refresh_provider();
+                
+                // The new credentials are fetched with the auth unlocked, so operations can
+                // keep starting with the current ones until they arrive
+                if let Some(provider) = provider {
+                    match AwsAuth::fetch_credentials(&provider).await {
+                        Ok(credentials) => {
+                            lock_auth(&auth_clone, "applying refreshed credentials").await.apply_credentials(&credentials);
+                            let _ = tx.send(StatusMessage::Info("Refreshed temporary AWS credentials".to_string()));
+                        },
+                        Err(e) => {
+                            error!("{}", e);
+                            let _ = tx.send(StatusMessage::Error(e.to_string()));
+                        }
+                    }
+                }
+                
+                let expiration = lock_auth(&auth_clone, "checking credential expiry").await.expiration();
+                if tx.send(StatusMessage::CredentialsExpiry(expiration)).is_err() {
+                    // The app has shut down
+                    break;
+                }
+            }
+        });
+    }
+    
+    /// Compare the two locations in the compare view, listing both concurrently
+    pub fn compare_locations(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let location_a = app_state.compare_view.location_a.clone();
+        let location_b = app_state.compare_view.location_b.clone();
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let region_a = app_state.bucket_view.get_bucket_region(&location_a.bucket).cloned();
+        let region_b = app_state.bucket_view.get_bucket_region(&location_b.bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        app_state.compare_view.set_running();
+        app_state.set_status_info(&format!(
+            "Comparing s3://{}/{} with s3://{}/{}...",
+            location_a.bucket, location_a.prefix, location_b.bucket, location_b.prefix
+        ));
+        
+        app_state.rt.spawn(async move {
+            let (client_a, client_b) = {
+                let client_a = Self::client_for_bucket(&auth_clone, &location_a.bucket, region_a, lookup_bucket_regions, &tx).await;
+                let client_b = Self::client_for_bucket(&auth_clone, &location_b.bucket, region_b, lookup_bucket_regions, &tx).await;
+                
+                match (client_a, client_b) {
+                    (Some(client_a), Some(client_b)) => (client_a, client_b),
+                    _ => {
+                        let _ = tx.send(StatusMessage::CompareResult(None));
+                        return;
+                    }
+                }
+            };
+            
+            let (location_a, location_b) = (&location_a, &location_b);
+            let (listing_a, listing_b) = tokio::join!(
+                client_a.run(|client| async move { TransferManager::new(client).list_prefix(&location_a.bucket, &location_a.prefix).await }),
+                client_b.run(|client| async move { TransferManager::new(client).list_prefix(&location_b.bucket, &location_b.prefix).await })
+            );
+            
+            match (listing_a, listing_b) {
+                (Ok(objects_a), Ok(objects_b)) => {
+                    let diff = compare_prefixes(&objects_a, &location_a.prefix, &objects_b, &location_b.prefix);
+                    let _ = tx.send(StatusMessage::Info(format!(
+                        "Compared {} and {} objects: {} only in A, {} only in B, {} differing",
+                        objects_a.len(), objects_b.len(), diff.only_in_a.len(), diff.only_in_b.len(), diff.differing.len()
+                    )));
+                    let _ = tx.send(StatusMessage::CompareResult(Some(diff)));
+                },
+                (Err(e), _) | (_, Err(e)) => {
+                    error!("Failed to compare locations: {}", e);
+                    let _ = tx.send(StatusMessage::CompareResult(None));
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to compare locations: {}", e)));
+                }
+            }
+        });
+    }
+    
+    /// Copy the keys missing or differing at the destination from the compared source location
+    pub fn copy_differences(app_state: &mut AppState, direction: CopyDirection) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let result = match app_state.compare_view.result() {
+            Some(result) => result,
+            None => return,
+        };
+        
+        let (source, destination, missing) = match direction {
+            CopyDirection::AToB => (&app_state.compare_view.location_a, &app_state.compare_view.location_b, &result.only_in_a),
+            CopyDirection::BToA => (&app_state.compare_view.location_b, &app_state.compare_view.location_a, &result.only_in_b),
+        };
+        
+        let keys: Vec<String> = missing.iter().chain(result.differing.iter()).cloned().collect();
+        let source = source.clone();
+        let destination = destination.clone();
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let source_region = app_state.bucket_view.get_bucket_region(&source.bucket).cloned();
+        let destination_region = app_state.bucket_view.get_bucket_region(&destination.bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let stop_on_error = app_state.settings_view.stop_on_error();
+        
+        app_state.set_status_info(&format!("Copying {} objects to s3://{}/{}...", keys.len(), destination.bucket, destination.prefix));
+        
+        app_state.rt.spawn(async move {
+            let (source_client, destination_client) = {
+                let source_client = Self::client_for_bucket(&auth_clone, &source.bucket, source_region, lookup_bucket_regions, &tx).await;
+                let destination_client = Self::client_for_bucket(&auth_clone, &destination.bucket, destination_region, lookup_bucket_regions, &tx).await;
+                
+                match (source_client, destination_client) {
+                    (Some(source_client), Some(destination_client)) => (source_client, destination_client),
+                    _ => return,
+                }
+            };
+            
+            let mut success_count = 0;
+            let mut error_count = 0;
+            let mut stopped: Option<(String, String)> = None;
+            
+            for key in &keys {
+                let source_key = format!("{}{}", source.prefix, key);
+                let destination_key = format!("{}{}", destination.prefix, key);
+                
+                // Sources are read in their own region; the copy itself is sent to the destination region
+                let result = match Self::read_copy_source(&source_client, &source.bucket, &source_key).await {
+                    Ok(object) => Self::copy_to(&destination_client, &object, &destination.bucket, &destination_key).await,
+                    Err(e) => Err(e),
+                };
+                
+                match result {
+                    Ok(_) => success_count += 1,
+                    Err(e) => {
+                        error_count += 1;
+                        error!("Failed to copy {}: {}", source_key, e);
+                        
+                        if stop_on_error {
+                            stopped = Some((source_key, e.to_string()));
+                            break;
+                        }
+                    }
+                }
+            }
+            
+            if let Some((source_key, error)) = &stopped {
+                let _ = tx.send(StatusMessage::Error(format!(
+                    "Copy stopped after {} failed: {} ({} copied, {} not attempted)",
+                    source_key, error, success_count, keys.len() - success_count - error_count
+                )));
+            } else if error_count == 0 {
+                let _ = tx.send(StatusMessage::Info(format!("Copied {} objects to s3://{}/{}", success_count, destination.bucket, destination.prefix)));
+            } else {
+                let _ = tx.send(StatusMessage::Error(format!("Copy completed with errors: {} succeeded, {} failed", success_count, error_count)));
+            }
+        });
+    }
+    
+    /// Copy an object to another key, possibly in another bucket. For a move or rename
+    /// the source is deleted once the copy is in place. An object already at the destination
+    /// is left alone unless the copy is marked to overwrite it.
+    pub fn copy_object(app_state: &mut AppState, copy: ObjectCopy) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let source_region = app_state.bucket_view.get_bucket_region(&copy.src_bucket).cloned();
+        let destination_region = app_state.bucket_view.get_bucket_region(&copy.dst_bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        
+        let verb = if copy.remove_source { "Moving" } else { "Copying" };
+        app_state.set_status_info(&format!("{} {} to s3://{}/{}...", verb, copy.src_key, copy.dst_bucket, copy.dst_key));
+        
+        app_state.rt.spawn(async move {
+            let (source_client, destination_client) = {
+                let source_client = Self::client_for_bucket(&auth_clone, &copy.src_bucket, source_region, lookup_bucket_regions, &tx).await;
+                let destination_client = Self::client_for_bucket(&auth_clone, &copy.dst_bucket, destination_region, lookup_bucket_regions, &tx).await;
+                
+                match (source_client, destination_client) {
+                    (Some(source_client), Some(destination_client)) => (source_client, destination_client),
+                    _ => {
+                        let _ = tx.send(StatusMessage::ObjectCopied(copy, Err("Could not get a client for the buckets".to_string())));
+                        return;
+                    }
+                }
+            };
+            
+            // An object already at the destination is only replaced once that is confirmed
+            if !copy.overwrite {
+                let exists = destination_client.run(|client| {
+                    let copy = &copy;
+                    async move { TransferManager::new(client).object_exists(&copy.dst_bucket, &copy.dst_key).await }
+                }).await;
+                match exists {
+                    Ok(false) => {},
+                    Ok(true) => {
+                        let _ = tx.send(StatusMessage::CopyDestinationExists(copy));
+                        return;
+                    },
+                    Err(e) => {
+                        error!("Failed to check {}: {}", copy.dst_key, e);
+                        let _ = tx.send(StatusMessage::ObjectCopied(copy, Err(e.to_string())));
+                        return;
+                    }
+                }
+            }
+            
+            // The source is read in its own region; the copy itself is sent to the destination region
+            let copied = match Self::read_copy_source(&source_client, &copy.src_bucket, &copy.src_key).await {
+                Ok(source) => Self::copy_to(&destination_client, &source, &copy.dst_bucket, &copy.dst_key).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = copied {
+                error!("Failed to copy {}: {}", copy.src_key, e);
+                Self::report_access_denied(&tx, S3Operation::CopyObject, &copy.src_bucket, Some(&copy.src_key), &e.to_string());
+                let _ = tx.send(StatusMessage::ObjectCopied(copy, Err(e.to_string())));
+                return;
+            }
+            
+            if copy.remove_source {
+                let (client, _) = source_client.current().await;
+                if let Err(e) = TransferManager::new(client).delete_object(&copy.src_bucket, &copy.src_key).await {
+                    error!("Failed to delete {} after copying it: {}", copy.src_key, e);
+                    Self::report_access_denied(&tx, S3Operation::DeleteObject, &copy.src_bucket, Some(&copy.src_key), &e.to_string());
+                    let message = format!("Copied {} to {}, but the original could not be deleted: {}", copy.src_key, copy.dst_key, e);
+                    let _ = tx.send(StatusMessage::ObjectCopied(copy, Err(message)));
+                    return;
+                }
+            }
+            
+            let _ = tx.send(StatusMessage::ObjectCopied(copy, Ok(())));
+        });
+    }
+    
+    /// Read an object to copy, with its headers and tags, in the source bucket's region
+    async fn read_copy_source(source_client: &BucketClient, bucket: &str, key: &str) -> anyhow::Result<CopySource> {
+        source_client.run(|client| async move { TransferManager::new(client).copy_source(bucket, key).await }).await
+    }
+    
+    /// Copy an object read with `read_copy_source`, sending the copy to the destination bucket's region
+    async fn copy_to(destination_client: &BucketClient, source: &CopySource, bucket: &str, key: &str) -> anyhow::Result<()> {
+        destination_client.run(|client| async move { TransferManager::new(client).copy_object(source, bucket, key).await }).await
+    }
+    
+    /// Sync selected folders with S3
+    pub fn sync_selected(app_state: &mut AppState) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let folder = match app_state.folder_list.selected() {
+            Some(folder) => folder.clone(),
+            None => {
+                app_state.set_status_error("No local folder selected to sync");
+                return;
+            }
+        };
+        let folder_path = folder.path.clone();
+        
+        let (bucket, key_prefix) = match Self::folder_target(app_state, &folder) {
+            Some(target) => target,
+            None => {
+                app_state.set_status_error("No S3 bucket selected to sync with");
+                return;
+            }
+        };
+        
+        // A sync that may delete objects is previewed and confirmed first, as is a dry run
+        if app_state.sync_dry_run || app_state.settings_view.delete_enabled() {
+            Self::preview_sync(app_state, folder_path, bucket, key_prefix);
+        } else {
+            Self::run_sync(app_state, folder_path, bucket, key_prefix, None);
+        }
+    }
+    
+    /// Work out what a sync would change without changing anything, and show it in the sync preview
+    fn preview_sync(app_state: &mut AppState, folder_path: PathBuf, bucket: String, key_prefix: String) {
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let filter = app_state.active_filter();
+        let bucket_region = app_state.bucket_view.get_bucket_region(&bucket).cloned();
+        let lookup_bucket_regions = app_state.settings_view.lookup_bucket_regions();
+        let delete_removed = app_state.settings_view.delete_enabled();
+        let quiet_period = app_state.settings_view.quiet_period();
+        let deep_compare = app_state.settings_view.deep_compare();
+        
+        app_state.sync_preview = Some(SyncPreviewView::new(folder_path.clone(), bucket.clone(), key_prefix.clone(), app_state.sync_dry_run));
+        
+        app_state.rt.spawn(async move {
+            let bucket_client = match Self::client_for_bucket(&auth_clone, &bucket, bucket_region, lookup_bucket_regions, &tx).await {
+                Some(bucket_client) => bucket_client,
+                None => {
+                    let _ = tx.send(StatusMessage::SyncPlan(folder_path, bucket, Err("Could not get a client for the bucket".to_string())));
+                    return;
+                }
+            };
+            
+            let plan = bucket_client.run(|client| {
+                let (filter, key_prefix, folder_path, bucket) = (&filter, &key_prefix, &folder_path, &bucket);
+                async move {
+                    let mut engine = SyncEngine::new(TransferManager::new(client));
+                    engine.set_filter(filter.clone());
+                    engine.set_quiet_period(quiet_period);
+                    engine.set_deep_compare(deep_compare);
+                    engine.set_prefix(key_prefix.clone());
+                    
+                    engine.plan_sync(folder_path, bucket, delete_removed).await
+                        .map_err(|e| e.to_string())
+                }
+            }).await;
+            
+            let _ = tx.send(StatusMessage::SyncPlan(folder_path, bucket, plan));
+        });
+    }
+    
+    /// Sync a folder with a bucket under a key prefix. If `confirmed_deletes` is set, only
+    /// those keys are deleted, as confirmed in the sync preview.
+    pub fn run_sync(app_state: &mut AppState, folder_path: PathBuf, bucket: String, key_prefix: String, confirmed_deletes: Option<HashSet<String>>) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let progress_view = app_state.progress_view.clone();
+        let job = SyncJob::new(app_state, folder_path.clone(), bucket.clone(), key_prefix.clone(), confirmed_deletes);
+        
+        app_state.set_status_info(&format!("Syncing {} with s3://{}/{}...", folder_path.display(), bucket, key_prefix));
+        app_state.folder_list.update_status(&folder_path, SyncStatus::Syncing);
+        app_state.show_progress = true;
+        progress_view.start_scan();
+        
+        app_state.rt.spawn(async move {
+            let result = job.run(&auth_clone, &tx, &progress_view).await;
+            let _ = tx.send(StatusMessage::SyncFinished(folder_path, bucket, result));
+        });
+    }
+    
+    /// Sync each enabled folder mapped to a bucket, one after another, for the sync scheduler.
+    /// Nothing is deleted, since deletions are only made once confirmed in the sync preview.
+    /// The run is skipped while offline or while another sync is running.
+    pub fn run_scheduled_sync(app_state: &mut AppState) {
+        if app_state.offline || app_state.folder_list.folders.iter().any(|folder| folder.status == SyncStatus::Syncing) {
+            debug!("Skipping scheduled sync");
+            return;
+        }
+        
+        let targets: Vec<_> = app_state.folder_list.folders.iter()
+            .filter(|folder| folder.enabled)
+            .filter_map(|folder| Some((folder.path.clone(), folder.bucket.clone()?, folder.key_prefix())))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        
+        let jobs: Vec<SyncJob> = targets.into_iter()
+            .map(|(folder_path, bucket, key_prefix)| {
+                let mut job = SyncJob::new(app_state, folder_path, bucket, key_prefix, None);
+                job.delete_removed = false;
+                job
+            })
+            .collect();
+        for job in &jobs {
+            app_state.folder_list.update_status(&job.folder_path, SyncStatus::Syncing);
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let progress_view = app_state.progress_view.clone();
+        app_state.show_progress = true;
+        
+        app_state.rt.spawn(async move {
+            let mut results = Vec::new();
+            
+            for job in jobs {
+                let (folder_path, bucket) = (job.folder_path.clone(), job.bucket.clone());
+                progress_view.start_scan();
+                
+                match job.run(&auth_clone, &tx, &progress_view).await {
+                    Ok(result) => results.push((folder_path, result)),
+                    Err(e) => {
+                        let _ = tx.send(StatusMessage::SyncFinished(folder_path, bucket, Err(e)));
+                    }
+                }
+            }
+            
+            if !results.is_empty() {
+                let _ = tx.send(StatusMessage::ScheduledSyncComplete(results));
+            }
+        });
+    }
+    
+    /// Create a callback showing the progress of a sync of a folder with the keys under a
+    /// prefix of a bucket in the progress view
+    fn sync_progress(progress_view: ProgressView, bucket: String, folder_path: PathBuf, key_prefix: String) -> SyncProgressCallback {
+        Arc::new(move |progress| match progress {
+            SyncProgress::ScanningLocal(found) => {
+                progress_view.set_scan_step("local", format!("Scanning local folder... {} files found", found));
+            },
+            SyncProgress::ListingRemote(found) => {
+                progress_view.set_scan_step("remote", format!("Listing s3://{}/{}... {} objects found", bucket, key_prefix, found));
+            },
+            SyncProgress::Planned(changes) => {
+                let total_bytes = changes.iter()
+                    .filter(|(_, operation, _)| *operation != SyncOperation::Delete)
+                    .map(|(_, _, size)| *size)
+                    .sum();
+                progress_view.start_sync(changes.len(), total_bytes);
+                
+                for (s3_key, operation, size) in changes {
+                    let (operation_type, total_bytes) = match operation {
+                        SyncOperation::Upload => (OperationType::Upload, size),
+                        SyncOperation::Download => (OperationType::Download, size),
+                        SyncOperation::Delete => (OperationType::Delete, 0),
+                    };
+                    let local_path = match operation {
+                        SyncOperation::Delete => PathBuf::new(),
+                        _ => {
+                            let relative_key = s3_key.strip_prefix(key_prefix.as_str()).unwrap_or(&s3_key);
+                            folder_path.join(relative_key.replace('/', std::path::MAIN_SEPARATOR_STR))
+                        },
+                    };
+                    progress_view.add_entry(ProgressInfo {
+                        file_name: s3_key.clone(),
+                        bucket: bucket.clone(),
+                        s3_key,
+                        operation_type,
+                        bytes_transferred: 0,
+                        total_bytes,
+                        percentage: 0.0,
+                        status: ProgressStatus::Pending,
+                        message: String::new(),
+                        timestamp: Instant::now(),
+                        local_path,
+                    });
+                }
+            },
+            SyncProgress::Started(s3_key) => progress_view.start_operation(&bucket, &s3_key),
+            SyncProgress::Transfer(transfer) => progress_view.update_progress(&bucket, transfer),
+            SyncProgress::Completed(s3_key, size) => progress_view.complete_operation(&bucket, &s3_key, size),
+            SyncProgress::Failed(s3_key, error) => progress_view.fail_operation(&bucket, &s3_key, &error),
+            SyncProgress::Deferred(s3_key) => {
+                progress_view.cancel_operation(&bucket, &s3_key);
+                progress_view.add_skipped(&s3_key, "Still being written");
+            },
+        })
+    }
+    
+    /// Create a bucket in a region, then refresh the bucket list to show it
+    pub fn create_bucket(app_state: &mut AppState, bucket: &str, region: &str) {
+        if !Self::ensure_online(app_state) {
+            return;
+        }
+        
+        let auth_clone = app_state.aws_auth.clone();
+        let tx = app_state.status_tx.clone();
+        let bucket = bucket.to_string();
+        let region = region.to_string();
+        
+        app_state.set_status_info(&format!("Creating bucket {} in {}...", bucket, region));
+        
+        app_state.rt.spawn(async move {
+            let mut bucket_manager = BucketManager::new(lock_auth(&auth_clone, "creating a bucket").await.clone());
+            if let Err(e) = bucket_manager.create_bucket(&bucket, &region).await {
+                let _ = tx.send(StatusMessage::BucketCreated(bucket, Err(e.to_string())));
+                return;
+            }
+            
+            let _ = tx.send(StatusMessage::BucketCreated(bucket.clone(), Ok(())));
+            let _ = tx.send(StatusMessage::BucketRegion(bucket, region));
+            
+            let client = lock_auth(&auth_clone, "refreshing the bucket list").await.get_client().await;
+            let client = match client {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Failed to get AWS client: {}", e);
+                    return;
+                }
+            };
+            
+            match TransferManager::new(client).list_buckets().await {
+                Ok(buckets) => {
+                    let _ = tx.send(StatusMessage::BucketList(buckets));
+                },
+                Err(e) => {
+                    error!("Failed to refresh buckets: {}", e);
+                    let _ = tx.send(StatusMessage::Error(format!("Failed to list buckets: {}", e)));
+                }
+            }
+        });
     }
     
     /// Refresh the list of buckets
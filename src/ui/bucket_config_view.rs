@@ -0,0 +1,155 @@
+use eframe::egui;
+
+use crate::aws::bucket::{CorsRuleInfo, LifecycleRuleInfo};
+use crate::ui::utils::{error_color, success_color};
+
+/// Read-only view of a bucket's lifecycle and CORS configuration
+pub struct BucketConfigView {
+    pub bucket: String,
+    lifecycle: Option<Result<Vec<LifecycleRuleInfo>, String>>,
+    cors: Option<Result<Vec<CorsRuleInfo>, String>>,
+}
+
+impl BucketConfigView {
+    /// Create a view for a bucket, waiting for its configuration to load
+    pub fn new(bucket: String) -> Self {
+        Self {
+            bucket,
+            lifecycle: None,
+            cors: None,
+        }
+    }
+    
+    /// Set the loaded configuration
+    pub fn set_configuration(
+        &mut self,
+        lifecycle: Result<Vec<LifecycleRuleInfo>, String>,
+        cors: Result<Vec<CorsRuleInfo>, String>,
+    ) {
+        self.lifecycle = Some(lifecycle);
+        self.cors = Some(cors);
+    }
+    
+    /// Show the configuration as a window, returning whether it is still open
+    pub fn show(&self, ctx: &egui::Context) -> bool {
+        let mut open = true;
+        
+        egui::Window::new(format!("Configuration: {}", self.bucket))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([450.0, 400.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::CollapsingHeader::new("Lifecycle Rules")
+                        .default_open(true)
+                        .show(ui, |ui| Self::lifecycle_ui(ui, &self.lifecycle));
+                    
+                    egui::CollapsingHeader::new("CORS Rules")
+                        .default_open(true)
+                        .show(ui, |ui| Self::cors_ui(ui, &self.cors));
+                });
+            });
+        
+        open
+    }
+    
+    /// Render the lifecycle rules
+    fn lifecycle_ui(ui: &mut egui::Ui, lifecycle: &Option<Result<Vec<LifecycleRuleInfo>, String>>) {
+        let rules = match lifecycle {
+            None => {
+                ui.add(egui::Spinner::new());
+                return;
+            },
+            Some(Err(e)) => {
+                ui.colored_label(error_color(ui), e);
+                return;
+            },
+            Some(Ok(rules)) if rules.is_empty() => {
+                ui.label("None configured");
+                return;
+            },
+            Some(Ok(rules)) => rules,
+        };
+        
+        for rule in rules {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.strong(if rule.id.is_empty() { "(unnamed rule)" } else { &rule.id });
+                    if rule.enabled {
+                        ui.colored_label(success_color(ui), "Enabled");
+                    } else {
+                        ui.colored_label(egui::Color32::GRAY, "Disabled");
+                    }
+                });
+                ui.label(format!("Applies to: {}", rule.filter));
+                
+                for transition in &rule.transitions {
+                    ui.label(format!("Transition to {}", transition));
+                }
+                if let Some(expiration) = &rule.expiration {
+                    ui.label(expiration);
+                }
+                for noncurrent in &rule.noncurrent_versions {
+                    ui.label(format!("Noncurrent versions: {}", noncurrent));
+                }
+            });
+        }
+    }
+    
+    /// Render the CORS rules
+    fn cors_ui(ui: &mut egui::Ui, cors: &Option<Result<Vec<CorsRuleInfo>, String>>) {
+        let rules = match cors {
+            None => {
+                ui.add(egui::Spinner::new());
+                return;
+            },
+            Some(Err(e)) => {
+                ui.colored_label(error_color(ui), e);
+                return;
+            },
+            Some(Ok(rules)) if rules.is_empty() => {
+                ui.label("None configured");
+                return;
+            },
+            Some(Ok(rules)) => rules,
+        };
+        
+        for (index, rule) in rules.iter().enumerate() {
+            ui.group(|ui| {
+                if let Some(id) = &rule.id {
+                    ui.strong(id);
+                }
+                
+                egui::Grid::new(("cors_rule", index))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Origins:");
+                        ui.label(rule.allowed_origins.join(", "));
+                        ui.end_row();
+                        
+                        ui.label("Methods:");
+                        ui.label(rule.allowed_methods.join(", "));
+                        ui.end_row();
+                        
+                        if !rule.allowed_headers.is_empty() {
+                            ui.label("Allowed headers:");
+                            ui.label(rule.allowed_headers.join(", "));
+                            ui.end_row();
+                        }
+                        
+                        if !rule.expose_headers.is_empty() {
+                            ui.label("Exposed headers:");
+                            ui.label(rule.expose_headers.join(", "));
+                            ui.end_row();
+                        }
+                        
+                        if let Some(max_age) = rule.max_age_seconds {
+                            ui.label("Max age:");
+                            ui.label(format!("{} seconds", max_age));
+                            ui.end_row();
+                        }
+                    });
+            });
+        }
+    }
+}
@@ -1,12 +1,12 @@
-use chrono::{/* DateTime, */ Utc, TimeZone};
 use eframe::egui;
 use std::sync::{Arc};
+use std::sync::atomic::{AtomicBool, Ordering};
 use log::{error, debug};
 use std::collections::{HashMap, HashSet};
-use tokio::sync::Mutex as TokioMutex;
-use aws_sdk_s3::error::ProvideErrorMetadata;
 
-use crate::aws::auth::AwsAuth;
+use crate::aws::transfer::TransferManager;
+use crate::config::region_cache::RegionCache;
+use crate::ui::utils::format_size;
 
 /// Component for viewing and interacting with S3 buckets
 #[derive(Default)]
@@ -14,21 +14,108 @@ pub struct BucketView {
     buckets: Vec<String>,
     objects: Vec<S3Object>,
     selected_bucket: Option<String>,
+    /// Prefix ("directory") being browsed: empty at the top of the bucket, otherwise ending in '/'
+    current_prefix: String,
     selected_objects: HashSet<String>,
     filter: String,
     loading: bool,
+    /// Token for the next page of objects under the current prefix, if the listing was truncated
+    continuation_token: Option<String>,
+    /// A further page of objects is being listed
+    loading_more: bool,
     #[allow(dead_code)] // Will be used in future implementations
     error_message: Option<String>,
     bucket_regions: HashMap<String, String>,
+    /// Where the regions are kept between sessions; None if the config directory can't be found
+    region_cache: Option<RegionCache>,
+    columns: ObjectColumns,
+    /// Query for the recursive search, as typed
+    search_query: String,
+    /// Running or finished recursive search, shown instead of the prefix listing
+    search: Option<ObjectSearch>,
+}
+
+/// Search for a substring in every key of a bucket, whatever prefix it is under
+pub struct ObjectSearch {
+    pub bucket: String,
+    /// Query as entered, matched case-insensitively
+    pub query: String,
+    /// Objects whose key contains the query, in key order
+    pub matches: Vec<S3Object>,
+    /// Number of keys looked at so far
+    pub scanned: usize,
+    running: bool,
+    /// Set to stop the listing after the current page
+    cancel: Arc<AtomicBool>,
+}
+
+impl ObjectSearch {
+    /// Check whether the search is still listing the bucket
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+    
+    /// Check whether results for a bucket and query belong to this search
+    fn is_for(&self, bucket: &str, query: &str) -> bool {
+        self.bucket == bucket && self.query == query
+    }
 }
 
 /// Represents an object in an S3 bucket
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct S3Object {
     pub key: String,
     pub size: u64,
     pub last_modified: String,
+    /// Last modified time in seconds since the Unix epoch, 0 for directories
+    pub last_modified_secs: i64,
     pub is_directory: bool,
+    pub storage_class: String,
+    pub e_tag: String,
+    pub owner: String,
+}
+
+/// Optional columns shown in the object table
+#[derive(Clone, Copy)]
+pub struct ObjectColumns {
+    pub size: bool,
+    pub last_modified: bool,
+    pub storage_class: bool,
+    pub e_tag: bool,
+    pub owner: bool,
+}
+
+impl Default for ObjectColumns {
+    fn default() -> Self {
+        Self {
+            size: true,
+            last_modified: true,
+            storage_class: false,
+            e_tag: false,
+            owner: false,
+        }
+    }
+}
+
+impl ObjectColumns {
+    /// Number of optional columns currently shown
+    pub fn count(&self) -> usize {
+        [self.size, self.last_modified, self.storage_class, self.e_tag, self.owner]
+            .iter()
+            .filter(|shown| **shown)
+            .count()
+    }
+    
+    /// Render a menu for choosing which columns are shown
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Columns", |ui| {
+            ui.checkbox(&mut self.size, "Size");
+            ui.checkbox(&mut self.last_modified, "Last Modified");
+            ui.checkbox(&mut self.storage_class, "Storage Class");
+            ui.checkbox(&mut self.e_tag, "ETag");
+            ui.checkbox(&mut self.owner, "Owner");
+        });
+    }
 }
 
 impl BucketView {
@@ -104,6 +191,7 @@ impl BucketView {
                         if ui.selectable_label(is_selected, bucket).clicked() {
                             if !is_selected {
                                 self.selected_bucket = Some(bucket.clone());
+                                self.current_prefix.clear();
                                 selection_changed = true;
                             }
                         }
@@ -111,6 +199,10 @@ impl BucketView {
                 });
         });
         
+        if selection_changed {
+            self.clear_search();
+        }
+        
         // Bucket list (as a fallback/alternative view)
         // egui::ScrollArea::vertical().show(ui, |ui| {
         //     for bucket in &self.buckets {
@@ -132,6 +224,51 @@ impl BucketView {
         selection_changed
     }
     
+    /// Get the prefix being browsed, empty at the top of the bucket
+    pub fn prefix(&self) -> &str {
+        &self.current_prefix
+    }
+    
+    /// Browse a prefix, e.g. "photos/2023/", or "" for the top of the bucket
+    pub fn set_prefix(&mut self, prefix: &str) {
+        self.current_prefix = prefix.to_string();
+        self.selected_objects.clear();
+    }
+    
+    /// Get the prefix a directory object leads to. Directory keys are listed without their trailing slash.
+    pub fn directory_prefix(key: &str) -> String {
+        format!("{}/", key.trim_end_matches('/'))
+    }
+    
+    /// Get the parent of the current prefix: "photos/2023/" becomes "photos/", and a
+    /// top-level prefix becomes ""
+    pub fn parent_prefix(&self) -> String {
+        let trimmed = self.current_prefix.trim_end_matches('/');
+        match trimmed.rfind('/') {
+            Some(index) => trimmed[..index + 1].to_string(),
+            None => String::new(),
+        }
+    }
+    
+    /// Breadcrumb trail for the current prefix: each segment's name and the prefix it leads to
+    pub fn breadcrumbs(&self) -> Vec<(String, String)> {
+        let mut crumbs = Vec::new();
+        let mut prefix = String::new();
+        
+        for segment in self.current_prefix.split('/').filter(|segment| !segment.is_empty()) {
+            prefix.push_str(segment);
+            prefix.push('/');
+            crumbs.push((segment.to_string(), prefix.clone()));
+        }
+        
+        crumbs
+    }
+    
+    /// Name of an object relative to the current prefix, for display
+    pub fn display_name<'a>(&self, key: &'a str) -> &'a str {
+        key.strip_prefix(self.current_prefix.as_str()).unwrap_or(key)
+    }
+    
     /// Get a mutable reference to the selected bucket
     #[allow(dead_code)] // Will be used in future implementations
     pub fn selected_bucket_mut(&mut self) -> &mut Option<String> {
@@ -167,6 +304,73 @@ impl BucketView {
         self.filter.clear();
     }
     
+    /// Get a mutable reference to the search query
+    pub fn search_query_mut(&mut self) -> &mut String {
+        &mut self.search_query
+    }
+    
+    /// Get the running or finished search, if results are being shown
+    pub fn search(&self) -> Option<&ObjectSearch> {
+        self.search.as_ref()
+    }
+    
+    /// Start searching a bucket for the current query, stopping any search already running.
+    /// Returns the query and the flag that cancels the search, or None if the query is empty.
+    pub fn start_search(&mut self, bucket: &str) -> Option<(String, Arc<AtomicBool>)> {
+        let query = self.search_query.trim().to_string();
+        if query.is_empty() {
+            return None;
+        }
+        
+        self.cancel_search();
+        
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.search = Some(ObjectSearch {
+            bucket: bucket.to_string(),
+            query: query.clone(),
+            matches: Vec::new(),
+            scanned: 0,
+            running: true,
+            cancel: cancel.clone(),
+        });
+        
+        Some((query, cancel))
+    }
+    
+    /// Add a page of matches to the search for a bucket and query. Pages from a
+    /// search that has since been replaced or cleared are dropped.
+    pub fn add_search_matches(&mut self, bucket: &str, query: &str, matches: Vec<S3Object>, scanned: usize) {
+        if let Some(search) = self.search.as_mut().filter(|search| search.is_for(bucket, query)) {
+            search.matches.extend(matches);
+            search.scanned = scanned;
+        }
+    }
+    
+    /// Mark the search for a bucket and query as finished, returning it if it is still shown
+    pub fn finish_search(&mut self, bucket: &str, query: &str) -> Option<&ObjectSearch> {
+        let search = self.search.as_mut().filter(|search| search.is_for(bucket, query))?;
+        search.running = false;
+        Some(search)
+    }
+    
+    /// Stop the running search after its current page, keeping the matches found so far
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = &self.search {
+            search.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+    
+    /// Stop any running search and go back to the prefix listing
+    pub fn clear_search(&mut self) {
+        self.cancel_search();
+        self.search = None;
+    }
+    
+    /// Check whether a key contains a lowercased query, ignoring case
+    fn key_matches(key: &str, query: &str) -> bool {
+        key.to_lowercase().contains(query)
+    }
+    
     /// Get the list of buckets
     pub fn buckets(&self) -> &[String] {
         &self.buckets
@@ -182,14 +386,108 @@ impl BucketView {
         self.bucket_regions.get(bucket)
     }
     
-    /// Set the objects for the current bucket
-    pub fn set_objects(&mut self, objects: Vec<S3Object>) {
+    /// Get the `s3://bucket/key` URI of an object
+    pub fn object_uri(bucket: &str, key: &str) -> String {
+        format!("s3://{}/{}", bucket, key)
+    }
+    
+    /// Get the virtual-hosted HTTPS URL of an object, in the bucket's region when it is
+    /// known and `default_region` otherwise. us-east-1 buckets use the global endpoint.
+    /// With a custom endpoint the URL is path-style, as the requests to it are.
+    pub fn object_url(&self, bucket: &str, key: &str, default_region: &str, endpoint_url: Option<&str>) -> String {
+        if let Some(endpoint_url) = endpoint_url {
+            return format!("{}/{}/{}", endpoint_url.trim_end_matches('/'), bucket, Self::encode_key(key));
+        }
+        
+        let region = self.get_bucket_region(bucket).map_or(default_region, String::as_str);
+        let host = match region {
+            "" | "us-east-1" => format!("{}.s3.amazonaws.com", bucket),
+            region if region.starts_with("cn-") => format!("{}.s3.{}.amazonaws.com.cn", bucket, region),
+            region => format!("{}.s3.{}.amazonaws.com", bucket, region),
+        };
+        
+        format!("https://{}/{}", host, Self::encode_key(key))
+    }
+    
+    /// Percent-encode a key for a URL path, keeping the '/' between segments
+    fn encode_key(key: &str) -> String {
+        key.bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (byte as char).to_string(),
+                _ => format!("%{:02X}", byte),
+            })
+            .collect()
+    }
+    
+    /// Remember the region for a bucket, saving it for later sessions
+    pub fn set_bucket_region(&mut self, bucket: &str, region: String) {
+        if self.bucket_regions.get(bucket) == Some(&region) {
+            return;
+        }
+        
+        self.bucket_regions.insert(bucket.to_string(), region);
+        self.save_bucket_regions();
+    }
+    
+    /// Forget the cached region for a bucket that has moved, e.g. after being deleted and
+    /// recreated elsewhere, so it is looked up again on the next request
+    pub fn forget_bucket_region(&mut self, bucket: &str) {
+        if self.bucket_regions.remove(bucket).is_some() {
+            debug!("Forgot the cached region of bucket {}", bucket);
+            self.save_bucket_regions();
+        }
+    }
+    
+    /// Use a cache of bucket regions from earlier sessions
+    pub fn set_region_cache(&mut self, cache: RegionCache) {
+        self.bucket_regions.extend(cache.load());
+        self.region_cache = Some(cache);
+    }
+    
+    fn save_bucket_regions(&self) {
+        if let Some(cache) = &self.region_cache {
+            cache.save(&self.bucket_regions);
+        }
+    }
+    
+    /// Set the first page of objects for the current bucket, with the token for the next page if there are more
+    pub fn set_objects(&mut self, objects: Vec<S3Object>, continuation_token: Option<String>) {
         self.objects = objects;
+        self.continuation_token = continuation_token;
+        self.loading_more = false;
         self.selected_objects.clear();
         // Reset loading state when objects are set
         self.loading = false;
     }
     
+    /// Add a further page of objects, keeping directories first and the rest sorted by key
+    pub fn append_objects(&mut self, objects: Vec<S3Object>, continuation_token: Option<String>) {
+        self.objects.extend(objects);
+        self.objects.sort_by(|a, b| b.is_directory.cmp(&a.is_directory).then_with(|| a.key.cmp(&b.key)));
+        self.continuation_token = continuation_token;
+        self.loading_more = false;
+    }
+    
+    /// Token for the next page of objects, or None if every object under the prefix is loaded
+    pub fn continuation_token(&self) -> Option<&String> {
+        self.continuation_token.as_ref()
+    }
+    
+    /// Check whether more objects can be loaded under the current prefix
+    pub fn has_more_objects(&self) -> bool {
+        self.continuation_token.is_some()
+    }
+    
+    /// Set whether a further page of objects is being listed
+    pub fn set_loading_more(&mut self, loading_more: bool) {
+        self.loading_more = loading_more;
+    }
+    
+    /// Check whether a further page of objects is being listed
+    pub fn is_loading_more(&self) -> bool {
+        self.loading_more
+    }
+    
     /// Toggle selection of an object
     pub fn toggle_object_selection(&mut self, key: &str) {
         if self.selected_objects.contains(key) {
@@ -211,14 +509,45 @@ impl BucketView {
             .collect()
     }
     
+    /// Get the visible objects (those that match the current filter)
+    pub fn visible_objects(&self) -> Vec<&S3Object> {
+        let filter = self.filter.to_lowercase();
+        
+        self.objects.iter()
+            .filter(|obj| filter.is_empty() || obj.key.to_lowercase().contains(&filter))
+            .collect()
+    }
+    
     /// Select all visible objects (those that match the current filter)
     pub fn select_all_visible(&mut self) {
-        let filter = self.filter.to_lowercase();
+        let keys: Vec<String> = self.visible_objects().iter().map(|obj| obj.key.clone()).collect();
+        self.selected_objects.extend(keys);
+    }
+    
+    /// Write the visible objects as CSV, with a header row
+    pub fn visible_objects_csv(&self) -> String {
+        let mut csv = String::from("key,size_bytes,size,last_modified,is_directory\n");
         
-        for obj in &self.objects {
-            if filter.is_empty() || obj.key.to_lowercase().contains(&filter) {
-                self.selected_objects.insert(obj.key.clone());
-            }
+        for obj in self.visible_objects() {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                Self::csv_field(&obj.key),
+                obj.size,
+                Self::csv_field(&format_size(obj.size)),
+                Self::csv_field(&obj.last_modified),
+                obj.is_directory
+            ));
+        }
+        
+        csv
+    }
+    
+    /// Quote a CSV field if it contains a separator, quote or line break, doubling any quotes
+    fn csv_field(value: &str) -> String {
+        if value.contains(&[',', '"', '\n', '\r'][..]) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
         }
     }
     
@@ -227,296 +556,190 @@ impl BucketView {
         self.selected_objects.clear();
     }
     
+    /// Get the columns shown in the object table
+    pub fn columns(&self) -> ObjectColumns {
+        self.columns
+    }
+    
+    /// Get the columns shown in the object table for editing
+    pub fn columns_mut(&mut self) -> &mut ObjectColumns {
+        &mut self.columns
+    }
+    
     /// Get the number of objects
     pub fn object_count(&self) -> usize {
         self.objects.len()
     }
     
-    /// Load buckets from AWS
-    pub async fn load_buckets(&mut self, aws_auth: Arc<TokioMutex<AwsAuth>>) -> Result<Vec<String>, String> {
-        debug!("Loading buckets from AWS");
-        self.loading = true;
+    /// Search every key in a bucket for a substring, ignoring case. The bucket is listed
+    /// without a delimiter so keys under all prefixes are walked, and the matches from
+    /// each page are passed to `on_page` with the number of keys looked at so far.
+    /// Returns false if the search was cancelled before the last page.
+    pub async fn search_objects<F>(
+        transfer_manager: &TransferManager,
+        bucket: &str,
+        query: &str,
+        cancel: &AtomicBool,
+        mut on_page: F,
+    ) -> Result<bool, String>
+    where
+        F: FnMut(Vec<S3Object>, usize),
+    {
+        debug!("Searching bucket {} for '{}'", bucket, query);
         
-        // Get the client
-        let client = {
-            let mut auth = aws_auth.lock().await;
-            match auth.get_client().await {
-                Ok(client) => client.clone(),
-                Err(e) => {
-                    let error = format!("Failed to get AWS client: {}", e);
-                    error!("{}", error);
-                    self.loading = false;
-                    return Err(error);
-                }
-            }
-        };
+        let query = query.to_lowercase();
+        let mut scanned = 0;
+        let mut continuation_token: Option<String> = None;
         
-        // List buckets
-        match client.list_buckets().send().await {
-            Ok(resp) => {
-                let bucket_names: Vec<String> = resp.buckets()
-                    .unwrap_or_default()
-                    .iter()
-                    .filter_map(|b| b.name().map(|s| s.to_string()))
-                    .collect();
-                    
-                debug!("Found {} buckets", bucket_names.len());
-                
-                // Get the region for each bucket
-                for bucket in &bucket_names {
-                    if !self.bucket_regions.contains_key(bucket) {
-                        match self.get_bucket_location(&client, bucket).await {
-                            Ok(region) => {
-                                debug!("Bucket {} is in region {}", bucket, region);
-                                self.bucket_regions.insert(bucket.to_string(), region);
-                            },
-                            Err(e) => {
-                                error!("Failed to get region for bucket {}: {}", bucket, e);
-                                // Default to us-east-1
-                                self.bucket_regions.insert(bucket.to_string(), "us-east-1".to_string());
-                            }
-                        }
-                    }
-                }
-                
-                self.buckets = bucket_names.clone();
-                self.loading = false;
-                Ok(bucket_names)
-            },
-            Err(err) => {
-                let sdk_error = err.into_service_error();
-                let error_code = sdk_error.code().unwrap_or("Unknown");
-                let error_message = sdk_error.message().unwrap_or("No error message");
-                
-                let error = format!("Failed to list buckets: {} - {}", error_code, error_message);
-                error!("{}", error);
-                self.loading = false;
-                Err(error)
+        loop {
+            if cancel.load(Ordering::SeqCst) {
+                debug!("Search of bucket {} cancelled after {} keys", bucket, scanned);
+                return Ok(false);
+            }
+            
+            let (objects, next_token) = transfer_manager.list_page(bucket, "", continuation_token, true).await
+                .map_err(|e| {
+                    let error = format!("Failed to search bucket {}: {}", bucket, e);
+                    error!("{}", error);
+                    error
+                })?;
+            scanned += objects.len();
+            
+            let matches = objects.into_iter()
+                .filter(|object| Self::key_matches(&object.key, &query))
+                .map(|object| S3Object {
+                    is_directory: object.key.ends_with('/'),
+                    ..object
+                })
+                .collect();
+            on_page(matches, scanned);
+            
+            match next_token {
+                Some(token) => continuation_token = Some(token),
+                None => break,
             }
         }
+        
+        debug!("Searched {} keys in bucket {}", scanned, bucket);
+        Ok(true)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     
-    // Load objects from the selected bucket
-    pub async fn load_objects(&mut self, aws_auth: Arc<TokioMutex<AwsAuth>>, bucket: &str) -> Result<Vec<S3Object>, String> {
-        debug!("Loading objects from bucket: {}", bucket);
-        self.loading = true;
-        
-        // Get the region for this bucket
-        let bucket_region = match self.get_bucket_region(bucket) {
-            Some(region) => region.clone(),
-            None => {
-                // Try to get the region
-                let client = {
-                    let mut auth = aws_auth.lock().await;
-                    match auth.get_client().await {
-                        Ok(client) => client.clone(),
-                        Err(e) => {
-                            let error = format!("Failed to get AWS client: {}", e);
-                            error!("{}", error);
-                            self.loading = false;
-                            return Err(error);
-                        }
-                    }
-                };
-                
-                match self.get_bucket_location(&client, bucket).await {
-                    Ok(region) => {
-                        debug!("Bucket {} is in region {}", bucket, region);
-                        self.bucket_regions.insert(bucket.to_string(), region.clone());
-                        region
-                    },
-                    Err(e) => {
-                        error!("Failed to get region for bucket {}: {}", bucket, e);
-                        // Default to us-east-1
-                        "us-east-1".to_string()
-                    }
-                }
-            }
-        };
+    #[test]
+    fn test_prefix_navigation() {
+        let mut view = BucketView::default();
+        assert!(view.breadcrumbs().is_empty());
         
-        // Get a client for the specific region
-        let client = {
-            let mut auth = aws_auth.lock().await;
-            match auth.get_client_for_region(&bucket_region).await {
-                Ok(client) => client,
-                Err(e) => {
-                    let error = format!("Failed to get AWS client for region {}: {}", bucket_region, e);
-                    error!("{}", error);
-                    self.loading = false;
-                    return Err(error);
-                }
-            }
+        view.set_prefix(&BucketView::directory_prefix("photos/2023"));
+        assert_eq!(view.prefix(), "photos/2023/");
+        assert_eq!(view.display_name("photos/2023/cat.jpg"), "cat.jpg");
+        assert_eq!(view.breadcrumbs(), vec![
+            ("photos".to_string(), "photos/".to_string()),
+            ("2023".to_string(), "photos/2023/".to_string()),
+        ]);
+        
+        assert_eq!(view.parent_prefix(), "photos/");
+        view.set_prefix("photos/");
+        assert_eq!(view.parent_prefix(), "");
+        view.set_prefix("");
+        assert_eq!(view.parent_prefix(), "");
+    }
+    
+    #[test]
+    fn test_append_objects() {
+        let object = |key: &str, is_directory: bool| S3Object { key: key.to_string(), is_directory, ..Default::default() };
+        
+        let mut view = BucketView::default();
+        view.set_objects(vec![object("docs", true), object("a.txt", false), object("c.txt", false)], Some("token".to_string()));
+        assert!(view.has_more_objects());
+        
+        // Directories from later pages still come first
+        view.append_objects(vec![object("logs", true), object("b.txt", false)], None);
+        let keys: Vec<&str> = view.objects().iter().map(|object| object.key.as_str()).collect();
+        assert_eq!(keys, vec!["docs", "logs", "a.txt", "b.txt", "c.txt"]);
+        assert!(!view.has_more_objects());
+    }
+    
+    #[test]
+    fn test_visible_objects_csv() {
+        let object = |key: &str, size: u64| S3Object {
+            key: key.to_string(),
+            size,
+            last_modified: "2024-01-02 03:04:05".to_string(),
+            ..Default::default()
         };
         
-        // List objects
-        match client.list_objects_v2().bucket(bucket).send().await {
-            Ok(resp) => {
-                let mut s3_objects = Vec::new();
-                let mut directories = HashSet::new();
-                
-                // Process the objects
-                for obj in resp.contents().unwrap_or_default() {
-                    let key = obj.key().unwrap_or_default();
-                    let size = obj.size() as u64;
-                    println!("{:?}",obj.last_modified());
-                    // Get the last modified timestamp
-                    let last_modified = obj.last_modified()
-                        .map(|dt| {
-                            // Format the date in a human-readable format
-                            // Extract the timestamp from the debug representation
-                            let dt_str = format!("{:?}", dt);
-                            println!("{:?}",dt_str);
-                            
-                            // Parse the seconds from the debug format: DateTime{seconds:1747860201,subseconds_nanos:0}
-                            if let Some(start) = dt_str.find("seconds:") {
-                                let seconds_str = &dt_str[start + 8..]; // Skip "seconds:"
-                                if let Some(end) = seconds_str.find(',') {
-                                    // Extract just the seconds value
-                                    if let Ok(seconds) = seconds_str[..end].parse::<i64>() {
-                                        // Convert Unix timestamp to DateTime and format
-                                        if let Some(datetime) = Utc.timestamp_opt(seconds, 0).single() {
-                                            return datetime.format("%Y-%m-%d %H:%M:%S").to_string();
-                                        }
-                                    }
-                                }
-                            }
-                            
-                            // Fallback if parsing fails
-                            dt_str
-                        })
-                        .unwrap_or_else(|| "Unknown".to_string());
-                        
-                    
-                    // Check if this is a "directory" (prefix)
-                    if key.ends_with('/') {
-                        directories.insert(key.to_string());
-                        s3_objects.push(S3Object {
-                            key: key.to_string(),
-                            size: 0,
-                            last_modified,
-                            is_directory: true,
-                        });
-                    } else {
-                        // Check if this object is in a directory
-                        let mut path_parts = key.split('/').collect::<Vec<_>>();
-                        if path_parts.len() > 1 {
-                            path_parts.pop(); // Remove the filename
-                            let dir_path = path_parts.join("/") + "/";
-                            directories.insert(dir_path.clone());
-                        }
-                        
-                        s3_objects.push(S3Object {
-                            key: key.to_string(),
-                            size,
-                            last_modified,
-                            is_directory: false,
-                        });
-                    }
-                }
-                
-                // Add any directories that weren't already added
-                for dir in directories {
-                    if !s3_objects.iter().any(|obj| obj.key == dir) {
-                        s3_objects.push(S3Object {
-                            key: dir,
-                            size: 0,
-                            last_modified: "".to_string(),
-                            is_directory: true,
-                        });
-                    }
-                }
-                
-                // Sort objects: directories first, then by name
-                s3_objects.sort_by(|a, b| {
-                    match (a.is_directory, b.is_directory) {
-                        (true, false) => std::cmp::Ordering::Less,
-                        (false, true) => std::cmp::Ordering::Greater,
-                        _ => a.key.cmp(&b.key),
-                    }
-                });
-                
-                debug!("Found {} objects in bucket {}", s3_objects.len(), bucket);
-                self.objects = s3_objects.clone();
-                self.loading = false;
-                Ok(s3_objects)
-            },
-            Err(err) => {
-                let sdk_error = err.into_service_error();
-                let error_code = sdk_error.code().unwrap_or("Unknown");
-                let error_message = sdk_error.message().unwrap_or("No error message");
-                
-                let error = format!("Failed to list objects in bucket {}: {} - {}", bucket, error_code, error_message);
-                error!("{}", error);
-                self.loading = false;
-                Err(error)
-            }
-        }
+        let mut view = BucketView::default();
+        view.set_objects(vec![
+            object("report, final.txt", 2048),
+            object("say \"hi\".txt", 0),
+            object("notes.md", 10),
+        ], None);
+        
+        assert_eq!(view.visible_objects_csv().lines().collect::<Vec<_>>(), vec![
+            "key,size_bytes,size,last_modified,is_directory",
+            "\"report, final.txt\",2048,2.00 KB,2024-01-02 03:04:05,false",
+            "\"say \"\"hi\"\".txt\",0,0 B,2024-01-02 03:04:05,false",
+            "notes.md,10,10 B,2024-01-02 03:04:05,false",
+        ]);
+        
+        // Only objects matching the filter are exported
+        view.set_filter("NOTES".to_string());
+        assert_eq!(view.visible_objects_csv().lines().count(), 2);
     }
     
-    /// Get the location (region) of a bucket
-    async fn get_bucket_location(&self, client: &aws_sdk_s3::Client, bucket: &str) -> Result<String, String> {
-        match client.get_bucket_location().bucket(bucket).send().await {
-            Ok(resp) => {
-                // Extract the location constraint as a string
-                let location_str = match resp.location_constraint() {
-                    Some(constraint) => {
-                        // Convert the enum to a debug string and extract the value
-                        let debug_str = format!("{:?}", constraint);
-                        if debug_str.contains("\"\"") || debug_str == "Empty" {
-                            // Empty constraint means us-east-1
-                            "us-east-1".to_string()
-                        } else if debug_str.starts_with("Unknown(") {
-                            // Extract the value from Unknown("value")
-                            let start = debug_str.find('(').map(|i| i + 2).unwrap_or(0);
-                            let end = debug_str.rfind('"').unwrap_or(debug_str.len());
-                            if start < end {
-                                debug_str[start..end].to_string()
-                            } else {
-                                "us-east-1".to_string() // Default if parsing fails
-                            }
-                        } else {
-                            // For known enum variants, extract the region name
-                            let region_name = match debug_str.as_str() {
-                                "EuWest1" => "eu-west-1",
-                                "UsWest1" => "us-west-1",
-                                "UsWest2" => "us-west-2",
-                                "EuWest2" => "eu-west-2",
-                                "EuWest3" => "eu-west-3",
-                                "UsEast2" => "us-east-2",
-                                "ApSouth1" => "ap-south-1",
-                                "ApSoutheast1" => "ap-southeast-1",
-                                "ApSoutheast2" => "ap-southeast-2",
-                                "ApNortheast1" => "ap-northeast-1",
-                                "ApNortheast2" => "ap-northeast-2",
-                                "ApNortheast3" => "ap-northeast-3",
-                                "SaEast1" => "sa-east-1",
-                                "CnNorth1" => "cn-north-1",
-                                "CnNorthwest1" => "cn-northwest-1",
-                                "UsGovWest1" => "us-gov-west-1",
-                                "UsGovEast1" => "us-gov-east-1",
-                                "EuCentral1" => "eu-central-1",
-                                "EuNorth1" => "eu-north-1",
-                                "MeSouth1" => "me-south-1",
-                                "AfSouth1" => "af-south-1",
-                                "EuSouth1" => "eu-south-1",
-                                "ApEast1" => "ap-east-1",
-                                _ => "us-east-1", // Default for unknown regions
-                            };
-                            region_name.to_string()
-                        }
-                    },
-                    None => "us-east-1".to_string(), // Default if no constraint is specified
-                };
-                
-                Ok(location_str)
-            },
-            Err(err) => {
-                let sdk_error = err.into_service_error();
-                let error_code = sdk_error.code().unwrap_or("Unknown");
-                let error_message = sdk_error.message().unwrap_or("No error message");
-                
-                Err(format!("Failed to get bucket location: {} - {}", error_code, error_message))
-            }
-        }
+    #[test]
+    fn test_search_matches() {
+        let object = |key: &str| S3Object { key: key.to_string(), ..Default::default() };
+        
+        assert!(BucketView::key_matches("Photos/2023/Cat.JPG", "cat.jpg"));
+        assert!(BucketView::key_matches("photos/2023/cat.jpg", "2023/"));
+        assert!(!BucketView::key_matches("photos/2023/cat.jpg", "dog"));
+        
+        let mut view = BucketView::default();
+        assert!(view.start_search("photos").is_none());
+        
+        *view.search_query_mut() = " cat ".to_string();
+        let (query, first_cancel) = view.start_search("photos").unwrap();
+        assert_eq!(query, "cat");
+        view.add_search_matches("photos", "cat", vec![object("a/cat.jpg")], 1000);
+        
+        // A new search stops the first one, and its late pages are dropped
+        *view.search_query_mut() = "dog".to_string();
+        view.start_search("photos").unwrap();
+        assert!(first_cancel.load(Ordering::SeqCst));
+        view.add_search_matches("photos", "cat", vec![object("b/cat.jpg")], 2000);
+        view.add_search_matches("photos", "dog", vec![object("b/dog.jpg")], 500);
+        assert!(view.finish_search("photos", "cat").is_none());
+        
+        let search = view.finish_search("photos", "dog").unwrap();
+        assert!(!search.is_running());
+        assert_eq!(search.scanned, 500);
+        assert_eq!(search.matches.len(), 1);
+        assert_eq!(search.matches[0].key, "b/dog.jpg");
+        
+        view.clear_search();
+        assert!(view.search().is_none());
+    }
+    
+    #[test]
+    fn test_object_links() {
+        let mut view = BucketView::default();
+        view.bucket_regions.insert("east".to_string(), "us-east-1".to_string());
+        view.bucket_regions.insert("dublin".to_string(), "eu-west-1".to_string());
+        
+        assert_eq!(BucketView::object_uri("east", "photos/cat 1.jpg"), "s3://east/photos/cat 1.jpg");
+        assert_eq!(view.object_url("east", "photos/cat 1.jpg", "eu-west-1", None), "https://east.s3.amazonaws.com/photos/cat%201.jpg");
+        assert_eq!(view.object_url("dublin", "a+b/ü.txt", "us-east-1", None), "https://dublin.s3.eu-west-1.amazonaws.com/a%2Bb/%C3%BC.txt");
+        
+        // Buckets whose region hasn't been looked up use the configured one
+        assert_eq!(view.object_url("other", "a.txt", "ap-southeast-2", None), "https://other.s3.ap-southeast-2.amazonaws.com/a.txt");
+        
+        // S3-compatible services are addressed path-style on their own endpoint
+        assert_eq!(view.object_url("dublin", "a b.txt", "us-east-1", Some("http://localhost:9000/")), "http://localhost:9000/dublin/a%20b.txt");
     }
 }
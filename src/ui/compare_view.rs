@@ -0,0 +1,138 @@
+use eframe::egui;
+
+use crate::sync::diff::PrefixDiff;
+
+/// Direction to copy differences between the compared locations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyDirection {
+    AToB,
+    BToA,
+}
+
+/// Action requested from the compare view
+pub enum CompareAction {
+    Compare,
+    Copy(CopyDirection),
+    Close,
+}
+
+/// An S3 bucket and prefix being compared
+#[derive(Debug, Clone, Default)]
+pub struct S3Location {
+    pub bucket: String,
+    pub prefix: String,
+}
+
+/// Component for comparing two S3 buckets/prefixes
+#[derive(Default)]
+pub struct CompareView {
+    pub location_a: S3Location,
+    pub location_b: S3Location,
+    result: Option<PrefixDiff>,
+    running: bool,
+}
+
+impl CompareView {
+    /// Mark a comparison as started, clearing the previous report
+    pub fn set_running(&mut self) {
+        self.result = None;
+        self.running = true;
+    }
+    
+    /// Set the comparison report
+    pub fn set_result(&mut self, result: Option<PrefixDiff>) {
+        self.result = result;
+        self.running = false;
+    }
+    
+    /// Get the current comparison report
+    pub fn result(&self) -> Option<&PrefixDiff> {
+        self.result.as_ref()
+    }
+    
+    /// Render the compare UI and return the requested action, if any
+    pub fn ui(&mut self, ui: &mut egui::Ui, buckets: &[String]) -> Option<CompareAction> {
+        let mut action = None;
+        
+        ui.heading("Compare Buckets");
+        
+        egui::Grid::new("compare_grid")
+            .num_columns(3)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("");
+                ui.strong("Bucket");
+                ui.strong("Prefix");
+                ui.end_row();
+                
+                for (label, location) in [("A:", &mut self.location_a), ("B:", &mut self.location_b)] {
+                    ui.label(label);
+                    egui::ComboBox::from_id_source(label)
+                        .selected_text(&location.bucket)
+                        .show_ui(ui, |ui| {
+                            for bucket in buckets {
+                                ui.selectable_value(&mut location.bucket, bucket.clone(), bucket);
+                            }
+                        });
+                    ui.text_edit_singleline(&mut location.prefix);
+                    ui.end_row();
+                }
+            });
+        
+        ui.horizontal(|ui| {
+            let ready = !self.running && !self.location_a.bucket.is_empty() && !self.location_b.bucket.is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Compare")).clicked() {
+                action = Some(CompareAction::Compare);
+            }
+            
+            if ui.button("Back to Main").clicked() {
+                action = Some(CompareAction::Close);
+            }
+            
+            if self.running {
+                ui.add(egui::Spinner::new());
+                ui.label("Listing both locations...");
+            }
+        });
+        
+        ui.separator();
+        
+        if let Some(result) = &self.result {
+            ui.label(format!(
+                "{} only in A, {} only in B, {} differing, {} identical",
+                result.only_in_a.len(),
+                result.only_in_b.len(),
+                result.differing.len(),
+                result.identical
+            ));
+            
+            ui.horizontal(|ui| {
+                let a_to_b = result.only_in_a.len() + result.differing.len();
+                if ui.add_enabled(a_to_b > 0, egui::Button::new(format!("Copy {} differences A → B", a_to_b))).clicked() {
+                    action = Some(CompareAction::Copy(CopyDirection::AToB));
+                }
+                
+                let b_to_a = result.only_in_b.len() + result.differing.len();
+                if ui.add_enabled(b_to_a > 0, egui::Button::new(format!("Copy {} differences B → A", b_to_a))).clicked() {
+                    action = Some(CompareAction::Copy(CopyDirection::BToA));
+                }
+            });
+            
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (title, keys) in [
+                    ("Only in A", &result.only_in_a),
+                    ("Only in B", &result.only_in_b),
+                    ("Differing", &result.differing),
+                ] {
+                    ui.collapsing(format!("{} ({})", title, keys.len()), |ui| {
+                        for key in keys {
+                            ui.label(key);
+                        }
+                    });
+                }
+            });
+        }
+        
+        action
+    }
+}
@@ -0,0 +1,22 @@
+use eframe::egui;
+
+use crate::ui::app_state::{AppState, CurrentView};
+use crate::ui::aws_operations::AwsOperations;
+use crate::ui::compare_view::CompareAction;
+
+/// Renderer for the bucket compare view of the application
+pub struct CompareViewRenderer;
+
+impl CompareViewRenderer {
+    /// Render the compare view
+    pub fn render(app_state: &mut AppState, ui: &mut egui::Ui) {
+        let buckets = app_state.bucket_view.buckets().to_vec();
+        
+        match app_state.compare_view.ui(ui, &buckets) {
+            Some(CompareAction::Compare) => AwsOperations::compare_locations(app_state),
+            Some(CompareAction::Copy(direction)) => AwsOperations::copy_differences(app_state, direction),
+            Some(CompareAction::Close) => app_state.current_view = CurrentView::Main,
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use eframe::egui;
+
+use crate::ui::utils::error_color;
+
+/// Copy of an object confirmed in the copy dialog
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectCopy {
+    pub src_bucket: String,
+    pub src_key: String,
+    pub dst_bucket: String,
+    pub dst_key: String,
+    /// Delete the source once it has been copied, making this a move
+    pub remove_source: bool,
+    /// Replace an object already at the destination
+    pub overwrite: bool,
+}
+
+/// Whether the dialog renames an object in place or copies it elsewhere
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CopyMode {
+    Rename,
+    CopyTo,
+}
+
+/// Dialog for renaming an object, or copying or moving it to another bucket or prefix
+pub struct CopyObjectView {
+    bucket: String,
+    pub key: String,
+    mode: CopyMode,
+    /// Buckets the object can be copied to
+    buckets: Vec<String>,
+    dst_bucket: String,
+    /// New key when renaming, destination prefix when copying
+    destination: String,
+    /// Delete the source after copying it
+    move_object: bool,
+    /// Copy whose destination already holds an object, waiting for the overwrite to be confirmed
+    existing: Option<ObjectCopy>,
+    error: Option<String>,
+    working: bool,
+}
+
+impl CopyObjectView {
+    /// Create a dialog renaming an object within its bucket, starting from its current key
+    pub fn rename(bucket: String, key: String) -> Self {
+        Self {
+            dst_bucket: bucket.clone(),
+            destination: key.clone(),
+            bucket,
+            key,
+            mode: CopyMode::Rename,
+            buckets: Vec::new(),
+            move_object: true,
+            existing: None,
+            error: None,
+            working: false,
+        }
+    }
+    
+    /// Create a dialog copying an object to one of `buckets`, starting from its own bucket and `prefix`
+    pub fn copy_to(bucket: String, key: String, prefix: String, buckets: Vec<String>) -> Self {
+        Self {
+            dst_bucket: bucket.clone(),
+            destination: prefix,
+            bucket,
+            key,
+            mode: CopyMode::CopyTo,
+            buckets,
+            move_object: false,
+            existing: None,
+            error: None,
+            working: false,
+        }
+    }
+    
+    /// Record that the copy failed. The dialog stays open so the destination can be changed.
+    pub fn set_failed(&mut self, error: String) {
+        self.error = Some(error);
+        self.working = false;
+    }
+    
+    /// Record that the destination already holds an object, asking whether to overwrite it
+    pub fn set_destination_exists(&mut self, copy: ObjectCopy) {
+        self.existing = Some(copy);
+        self.working = false;
+    }
+    
+    /// Key the object is copied to. When copying, the object keeps its name under the destination prefix.
+    fn destination_key(&self) -> String {
+        let destination = self.destination.trim();
+        
+        match self.mode {
+            CopyMode::Rename => destination.to_string(),
+            CopyMode::CopyTo => {
+                let name = self.key.rsplit('/').next().unwrap_or(&self.key);
+                let prefix = destination.trim_matches('/');
+                if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", prefix, name)
+                }
+            }
+        }
+    }
+    
+    /// Check the destination, returning the copy to make
+    fn validate(&self) -> Result<ObjectCopy, String> {
+        let dst_key = self.destination_key();
+        
+        if dst_key.is_empty() {
+            return Err("Enter a key".to_string());
+        }
+        if dst_key.ends_with('/') {
+            return Err("Keys ending in / are folders".to_string());
+        }
+        if self.dst_bucket == self.bucket && dst_key == self.key {
+            return Err("The destination is the object itself".to_string());
+        }
+        
+        Ok(ObjectCopy {
+            src_bucket: self.bucket.clone(),
+            src_key: self.key.clone(),
+            dst_bucket: self.dst_bucket.clone(),
+            dst_key,
+            remove_source: self.move_object,
+            overwrite: false,
+        })
+    }
+    
+    /// Show the dialog as a window, returning whether it is still open and the copy once confirmed
+    pub fn show(&mut self, ctx: &egui::Context) -> (bool, Option<ObjectCopy>) {
+        let mut open = true;
+        let mut confirmed = None;
+        
+        let title = match self.mode {
+            CopyMode::Rename => "Rename Object",
+            CopyMode::CopyTo => "Copy Object",
+        };
+        
+        egui::Window::new(title)
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                confirmed = self.ui(ui);
+            });
+        
+        (open, confirmed)
+    }
+    
+    /// Render the destination fields, returning the copy once confirmed
+    fn ui(&mut self, ui: &mut egui::Ui) -> Option<ObjectCopy> {
+        let mut changed = false;
+        
+        ui.label(format!("s3://{}/{}", self.bucket, self.key));
+        ui.separator();
+        
+        egui::Grid::new("copy_object_grid")
+            .num_columns(2)
+            .spacing([20.0, 6.0])
+            .show(ui, |ui| {
+                match self.mode {
+                    CopyMode::Rename => {
+                        ui.label("New key:");
+                        changed |= ui.add(egui::TextEdit::singleline(&mut self.destination).desired_width(300.0)).changed();
+                        ui.end_row();
+                    },
+                    CopyMode::CopyTo => {
+                        ui.label("Bucket:");
+                        egui::ComboBox::from_id_source("copy_object_bucket")
+                            .selected_text(&self.dst_bucket)
+                            .show_ui(ui, |ui| {
+                                for bucket in &self.buckets {
+                                    changed |= ui.selectable_value(&mut self.dst_bucket, bucket.clone(), bucket).changed();
+                                }
+                            });
+                        ui.end_row();
+                        
+                        ui.label("Prefix:");
+                        changed |= ui.add(
+                            egui::TextEdit::singleline(&mut self.destination)
+                                .hint_text("folder/")
+                                .desired_width(300.0)
+                        ).changed();
+                        ui.end_row();
+                        
+                        ui.label("");
+                        changed |= ui.checkbox(&mut self.move_object, "Move (delete the original once copied)").changed();
+                        ui.end_row();
+                    },
+                }
+            });
+        
+        if changed {
+            self.error = None;
+            self.existing = None;
+        }
+        
+        let validation = self.validate();
+        match &validation {
+            Ok(copy) if self.mode == CopyMode::CopyTo => {
+                ui.label(egui::RichText::new(format!("To s3://{}/{}", copy.dst_bucket, copy.dst_key)).small());
+            },
+            Ok(_) => {},
+            Err(reason) => {
+                ui.colored_label(error_color(ui), reason);
+            }
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(error_color(ui), error);
+        }
+        if let Some(existing) = &self.existing {
+            ui.colored_label(error_color(ui), format!("s3://{}/{} already exists", existing.dst_bucket, existing.dst_key));
+        }
+        
+        ui.separator();
+        
+        let label = match (self.mode, self.move_object) {
+            (CopyMode::Rename, _) => "Rename",
+            (CopyMode::CopyTo, true) => "Move",
+            (CopyMode::CopyTo, false) => "Copy",
+        };
+        
+        let mut confirmed = None;
+        ui.horizontal(|ui| {
+            if self.existing.is_some() {
+                if ui.button("Overwrite").clicked() {
+                    self.working = true;
+                    confirmed = self.existing.take().map(|copy| ObjectCopy { overwrite: true, ..copy });
+                }
+            } else if ui.add_enabled(validation.is_ok() && !self.working, egui::Button::new(label)).clicked() {
+                self.working = true;
+                self.error = None;
+                confirmed = validation.ok();
+            }
+            
+            if self.working {
+                ui.add(egui::Spinner::new());
+            }
+        });
+        
+        confirmed
+    }
+}
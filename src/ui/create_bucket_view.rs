@@ -0,0 +1,115 @@
+use eframe::egui;
+
+use crate::aws::bucket::{validate_bucket_name, BUCKET_REGIONS};
+use crate::ui::utils::error_color;
+
+/// Dialog for creating a bucket: its name and the region it is created in
+pub struct CreateBucketView {
+    name: String,
+    region: String,
+    /// Why the bucket couldn't be created, shown until the name or region changes
+    error: Option<String>,
+    creating: bool,
+}
+
+impl CreateBucketView {
+    /// Create the dialog, offering the configured region first
+    pub fn new(region: String) -> Self {
+        Self {
+            name: String::new(),
+            region,
+            error: None,
+            creating: false,
+        }
+    }
+    
+    /// Name of the bucket being created
+    pub fn name(&self) -> &str {
+        self.name.trim()
+    }
+    
+    /// Record the result of the create request. The dialog stays open on failure so the name can be corrected.
+    pub fn set_failed(&mut self, error: String) {
+        self.error = Some(error);
+        self.creating = false;
+    }
+    
+    /// Show the dialog as a window, returning whether it is still open and the
+    /// bucket name and region once creation is confirmed
+    pub fn show(&mut self, ctx: &egui::Context) -> (bool, Option<(String, String)>) {
+        let mut open = true;
+        let mut confirmed = None;
+        
+        egui::Window::new("New Bucket")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                confirmed = self.ui(ui);
+            });
+        
+        (open, confirmed)
+    }
+    
+    /// Render the name and region fields, returning the bucket to create once confirmed
+    fn ui(&mut self, ui: &mut egui::Ui) -> Option<(String, String)> {
+        let mut changed = false;
+        
+        egui::Grid::new("create_bucket_grid")
+            .num_columns(2)
+            .spacing([20.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("Bucket name:");
+                changed |= ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("my-bucket-name")).changed();
+                ui.end_row();
+                
+                ui.label("Region:");
+                egui::ComboBox::from_id_source("create_bucket_region")
+                    .selected_text(&self.region)
+                    .show_ui(ui, |ui| {
+                        for region in BUCKET_REGIONS {
+                            changed |= ui.selectable_value(&mut self.region, region.to_string(), *region).changed();
+                        }
+                    });
+                ui.end_row();
+            });
+        
+        if changed {
+            self.error = None;
+        }
+        
+        // The name is checked as it is typed, before anything is sent to S3
+        let validation = if self.name().is_empty() {
+            Err(String::new())
+        } else {
+            validate_bucket_name(self.name())
+        };
+        
+        if let Err(reason) = &validation {
+            if !reason.is_empty() {
+                ui.colored_label(error_color(ui), reason);
+            }
+        } else if let Some(error) = &self.error {
+            ui.colored_label(error_color(ui), error);
+        }
+        
+        ui.separator();
+        
+        let mut confirmed = None;
+        ui.horizontal(|ui| {
+            let enabled = validation.is_ok() && !self.creating;
+            if ui.add_enabled(enabled, egui::Button::new("Create Bucket")).clicked() {
+                self.creating = true;
+                self.error = None;
+                confirmed = Some((self.name().to_string(), self.region.clone()));
+            }
+            
+            if self.creating {
+                ui.add(egui::Spinner::new());
+                ui.label("Creating bucket...");
+            }
+        });
+        
+        confirmed
+    }
+}
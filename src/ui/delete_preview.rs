@@ -0,0 +1,113 @@
+use eframe::egui;
+
+use crate::aws::transfer::ObjectSummary;
+use crate::ui::utils::{error_color, format_size, warning_color};
+
+/// Preview of the objects a delete will remove, shown before anything is deleted
+pub struct DeletePreviewView {
+    pub bucket: String,
+    objects: Vec<ObjectSummary>,
+    /// Whether the bucket keeps earlier versions, or None if that couldn't be checked
+    versioned: Option<bool>,
+    error: Option<String>,
+    loading: bool,
+}
+
+impl DeletePreviewView {
+    /// Create a preview for a bucket, waiting for the selected prefixes to be listed
+    pub fn new(bucket: String) -> Self {
+        Self {
+            bucket,
+            objects: Vec::new(),
+            versioned: None,
+            error: None,
+            loading: true,
+        }
+    }
+    
+    /// Set the full set of objects that will be deleted
+    pub fn set_objects(&mut self, objects: Result<Vec<ObjectSummary>, String>, versioned: Option<bool>) {
+        match objects {
+            Ok(objects) => self.objects = objects,
+            Err(e) => self.error = Some(e),
+        }
+        self.versioned = versioned;
+        self.loading = false;
+    }
+    
+    /// Keys of the objects that will be deleted
+    pub fn keys(&self) -> Vec<String> {
+        self.objects.iter().map(|object| object.key.clone()).collect()
+    }
+    
+    /// Show the preview as a window, returning whether it is still open and whether the delete was confirmed
+    pub fn show(&self, ctx: &egui::Context) -> (bool, bool) {
+        let mut open = true;
+        let mut confirmed = false;
+        
+        egui::Window::new(format!("Delete from {}", self.bucket))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([450.0, 400.0])
+            .show(ctx, |ui| {
+                confirmed = self.ui(ui);
+            });
+        
+        (open && !confirmed, confirmed)
+    }
+    
+    /// Render the key list and totals, returning whether the delete was confirmed
+    fn ui(&self, ui: &mut egui::Ui) -> bool {
+        if self.loading {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new());
+                ui.label("Listing objects to delete...");
+            });
+            return false;
+        }
+        
+        if let Some(error) = &self.error {
+            ui.colored_label(error_color(ui), error);
+            return false;
+        }
+        
+        if self.objects.is_empty() {
+            ui.label("Nothing to delete: the selected prefixes are empty.");
+            return false;
+        }
+        
+        let total_size: u64 = self.objects.iter().map(|object| object.size).sum();
+        ui.strong(format!("{} objects, {} will be deleted", self.objects.len(), format_size(total_size)));
+        
+        let versioning_note = match self.versioned {
+            Some(true) => "Versioning is enabled on this bucket: only the current version of each object is deleted. \
+                           S3 adds a delete marker and earlier versions are kept, so they can be restored from History.",
+            Some(false) => "Versioning is not enabled on this bucket: the objects are deleted permanently.",
+            None => "Could not check whether versioning is enabled on this bucket. \
+                     If it isn't, the objects are deleted permanently.",
+        };
+        ui.colored_label(warning_color(ui), versioning_note);
+        ui.separator();
+        
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        egui::ScrollArea::vertical()
+            .max_height(250.0)
+            .show_rows(ui, row_height, self.objects.len(), |ui, range| {
+                for object in &self.objects[range] {
+                    ui.horizontal(|ui| {
+                        ui.label(&object.key);
+                        ui.label(egui::RichText::new(format_size(object.size)).color(egui::Color32::GRAY));
+                    });
+                }
+            });
+        
+        ui.separator();
+        
+        let button = egui::Button::new(
+            egui::RichText::new(format!("Delete {} objects", self.objects.len())).color(egui::Color32::WHITE)
+        )
+        .fill(egui::Color32::from_rgb(180, 40, 40));
+        
+        ui.add(button).clicked()
+    }
+}
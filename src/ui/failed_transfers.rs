@@ -0,0 +1,90 @@
+use eframe::egui;
+use std::path::PathBuf;
+
+use crate::ui::progress::OperationType;
+use crate::ui::utils::{copy_button, error_color};
+
+/// A file that failed during a batch transfer, with enough detail to retry it
+#[derive(Debug, Clone)]
+pub struct FailedTransfer {
+    pub operation: OperationType,
+    pub bucket: String,
+    pub key: String,
+    pub local_path: PathBuf,
+    /// Short classified reason, e.g. "Access denied"
+    pub reason: String,
+    /// Full error message
+    pub error: String,
+}
+
+impl FailedTransfer {
+    /// Describe the failure for the clipboard
+    pub fn details(&self) -> String {
+        format!("{}: {}\n{}", self.key, self.reason, self.error)
+    }
+}
+
+/// Action requested from the failed transfers list
+pub enum FailedTransferAction {
+    Retry(usize),
+    Dismiss,
+}
+
+/// Expandable list of the files that failed in the last batch
+pub struct FailedTransfersRenderer;
+
+impl FailedTransfersRenderer {
+    /// Render the list, returning any requested action
+    pub fn ui(ui: &mut egui::Ui, failures: &[FailedTransfer]) -> Option<FailedTransferAction> {
+        let mut action = None;
+        
+        egui::CollapsingHeader::new(
+            egui::RichText::new(format!("{} files failed", failures.len())).color(error_color(ui))
+        )
+        .id_source("failed_transfers")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let all_details = failures.iter()
+                    .map(|failure| failure.details())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                copy_button(ui, "Copy All", &all_details);
+                
+                if ui.button("Dismiss").clicked() {
+                    action = Some(FailedTransferAction::Dismiss);
+                }
+            });
+            
+            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                egui::Grid::new("failed_transfers_grid")
+                    .num_columns(4)
+                    .spacing([10.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (index, failure) in failures.iter().enumerate() {
+                            let operation = match failure.operation {
+                                OperationType::Upload => "Upload",
+                                OperationType::Download => "Download",
+                                OperationType::Delete => "Delete",
+                                OperationType::Scan => "Scan",
+                            };
+                            ui.label(operation);
+                            ui.label(&failure.key);
+                            ui.label(&failure.reason).on_hover_text(&failure.error);
+                            
+                            ui.horizontal(|ui| {
+                                copy_button(ui, "Copy", &failure.details());
+                                
+                                if ui.button("Retry").clicked() {
+                                    action = Some(FailedTransferAction::Retry(index));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+        
+        action
+    }
+}
@@ -1,6 +1,8 @@
 use eframe::egui;
 use std::sync::{Arc, Mutex};
+use crate::config::app_config::FilterConfig;
 use crate::sync::filter::FileFilter;
+use crate::ui::utils::error_color;
 
 /// UI component for configuring file filters
 pub struct FilterView {
@@ -40,7 +42,7 @@ impl FilterView {
         
         // Show error message if any
         if let Some(error) = &self.error_message {
-            ui.colored_label(egui::Color32::RED, error);
+            ui.colored_label(error_color(ui), error);
             if ui.button("Clear Error").clicked() {
                 self.error_message = None;
             }
@@ -99,6 +101,31 @@ impl FilterView {
         self.changes_applied
     }
     
+    /// Create a filter view from a saved filter, applying it to the filter
+    pub fn from_config(filter: Arc<Mutex<FileFilter>>, config: &FilterConfig) -> Self {
+        let mut view = Self::new(filter);
+        view.include_patterns = config.include_patterns.clone();
+        view.exclude_patterns = config.exclude_patterns.clone();
+        view.include_extensions = config.include_extensions.clone();
+        view.exclude_extensions = config.exclude_extensions.clone();
+        view.min_size = config.min_size.clone();
+        view.max_size = config.max_size.clone();
+        view.apply_filters();
+        view
+    }
+    
+    /// Get the filter as entered, for saving
+    pub fn to_config(&self) -> FilterConfig {
+        FilterConfig {
+            include_patterns: self.include_patterns.clone(),
+            exclude_patterns: self.exclude_patterns.clone(),
+            include_extensions: self.include_extensions.clone(),
+            exclude_extensions: self.exclude_extensions.clone(),
+            min_size: self.min_size.clone(),
+            max_size: self.max_size.clone(),
+        }
+    }
+    
     /// Get the current filter
     pub fn get_filter(&self) -> Arc<Mutex<FileFilter>> {
         self.filter.clone()
@@ -149,7 +176,10 @@ impl FilterView {
         
         // Parse include extensions
         if !self.include_extensions.is_empty() {
-            new_filter.parse_extensions(&self.include_extensions).unwrap();
+            if let Err(e) = new_filter.parse_extensions(&self.include_extensions) {
+                self.error_message = Some(e.to_string());
+                return;
+            }
         }
         
         // Parse exclude extensions
@@ -170,7 +200,10 @@ impl FilterView {
                 .collect::<Vec<_>>()
                 .join(",");
             
-            new_filter.parse_extensions(&exclude_extensions).unwrap();
+            if let Err(e) = new_filter.parse_extensions(&exclude_extensions) {
+                self.error_message = Some(e.to_string());
+                return;
+            }
         }
         
         // Parse min size
@@ -258,3 +291,31 @@ fn parse_size(size_str: &str) -> Result<u64, String> {
         return Ok(num);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_invalid_extension() {
+        let filter = Arc::new(Mutex::new(FileFilter::new()));
+        let mut view = FilterView::new(filter.clone());
+        view.include_extensions = "txt,md".to_string();
+        view.apply_filters();
+        assert!(view.changes_applied());
+        assert!(view.error_message.is_none());
+        
+        // An unclosed bracket isn't a valid glob: the error is shown and the filter is left as it was
+        view.changes_applied = false;
+        view.exclude_extensions = "tmp,ba[k".to_string();
+        view.apply_filters();
+        assert!(!view.changes_applied());
+        assert!(view.error_message.as_deref().unwrap().contains("ba[k"));
+        
+        view.exclude_extensions.clear();
+        view.include_extensions = "j[pg".to_string();
+        view.apply_filters();
+        assert!(view.error_message.as_deref().unwrap().contains("j[pg"));
+        assert!(!view.changes_applied());
+    }
+}
@@ -23,6 +23,10 @@ impl FilterViewRenderer {
             app_state.folder_content.set_filter(filter_string.clone());
             app_state.bucket_view.set_filter(filter_string);
             
+            // Persist the filter
+            app_state.config.filter = filter_view.to_config();
+            app_state.save_config();
+            
             // Return to main view
             app_state.current_view = CurrentView::Main;
         }
@@ -1,9 +1,18 @@
 use eframe::egui;
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 use std::fs;
-use log::{debug, error};
+use log::{debug, error, warn};
 use chrono::{DateTime, Utc};
-use std::collections::HashSet;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the folder has to stop changing before it is reloaded, so a burst of
+/// events such as a large copy only reloads it once
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
 
 /// Component for displaying the contents of a local folder
 #[derive(Default)]
@@ -12,6 +21,28 @@ pub struct FolderContent {
     filter: String,
     selected_files: HashSet<PathBuf>,
     pub current_folder: Option<PathBuf>,
+    /// Contents of the subdirectories expanded in the tree, listed when first expanded
+    children: HashMap<PathBuf, Vec<FileEntry>>,
+    /// Watches the current folder so changes on disk show without a refresh. Replacing
+    /// it removes the previous folder's watch.
+    watcher: Option<RecommendedWatcher>,
+    /// When the watched folder last changed, until it is reloaded
+    pending_change: Arc<Mutex<Option<Instant>>>,
+    /// Why the current folder isn't watched, leaving only manual refresh
+    watch_error: Option<String>,
+    /// File count and size of the current folder and of each directory in it, once counted
+    totals: HashMap<PathBuf, FolderTotals>,
+    /// Receives the totals of a folder being counted in the background
+    totals_receiver: Option<mpsc::Receiver<(PathBuf, HashMap<PathBuf, FolderTotals>)>>,
+    /// Set to stop the background count once a newer one replaces it
+    totals_cancel: Arc<AtomicBool>,
+}
+
+/// Number and total size of the files in a directory tree
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FolderTotals {
+    pub files: usize,
+    pub bytes: u64,
 }
 
 /// Represents a file or directory in the folder
@@ -151,105 +182,331 @@ impl FolderContent {
         self.selected_files.contains(path)
     }
     
-    /// Toggle selection of a file
+    /// Check if a file is inside a selected directory, which selects its whole subtree
+    pub fn is_covered(&self, path: &Path) -> bool {
+        path.ancestors().skip(1).any(|ancestor| self.selected_files.contains(ancestor))
+    }
+    
+    /// Toggle selection of a file, or of a directory and everything in it
     pub fn toggle_file_selection(&mut self, path: &PathBuf) {
         if self.selected_files.contains(path) {
             self.selected_files.remove(path);
         } else {
             self.selected_files.insert(path.clone());
+            self.drop_covered();
+        }
+    }
+    
+    /// Unselect files inside selected directories, which are uploaded with the directory anyway
+    fn drop_covered(&mut self) {
+        let covered: Vec<PathBuf> = self.selected_files.iter()
+            .filter(|path| self.is_covered(path))
+            .cloned()
+            .collect();
+        for path in covered {
+            self.selected_files.remove(&path);
         }
     }
-    /// Set the current folder to display
-    pub fn set_folder(&mut self, path: PathBuf) {
+    /// Set the current folder to display and start watching it for changes
+    pub fn set_folder(&mut self, path: PathBuf, ctx: &egui::Context) {
         debug!("Setting folder to: {}", path.display());
         self.current_folder = Some(path.clone());
         self.selected_files.clear();
+        self.children.clear();
+        self.watch(&path, ctx);
         self.load_files(path);
     }
     
+    /// Stop showing and watching a folder, once none is selected
+    pub fn clear_folder(&mut self) {
+        self.current_folder = None;
+        self.files.clear();
+        self.children.clear();
+        self.selected_files.clear();
+        self.watcher = None;
+        self.watch_error = None;
+        self.totals.clear();
+        self.totals_receiver = None;
+        self.totals_cancel.store(true, Ordering::Relaxed);
+    }
+    
+    /// Watch a folder in place of the previous one. If the watch can't be set up the
+    /// folder is only reloaded when refreshed.
+    fn watch(&mut self, path: &Path, ctx: &egui::Context) {
+        // Dropping the old watcher removes its watch. Its change flag is replaced too, so
+        // events still in flight for the old folder are ignored.
+        self.watcher = None;
+        self.watch_error = None;
+        self.pending_change = Arc::new(Mutex::new(None));
+        
+        let pending_change = self.pending_change.clone();
+        let ctx = ctx.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            match result {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) => {
+                    *pending_change.lock().unwrap() = Some(Instant::now());
+                    ctx.request_repaint();
+                },
+                Ok(_) => {},
+                Err(e) => debug!("Folder watch error: {}", e),
+            }
+        });
+        
+        let result = watcher.and_then(|mut watcher| {
+            watcher.watch(path, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        
+        match result {
+            Ok(watcher) => self.watcher = Some(watcher),
+            Err(e) => {
+                warn!("Could not watch {} for changes: {}", path.display(), e);
+                self.watch_error = Some(e.to_string());
+            }
+        }
+    }
+    
+    /// Why the current folder isn't watched for changes, if it isn't
+    pub fn watch_error(&self) -> Option<&String> {
+        self.watch_error.as_ref()
+    }
+    
+    /// Reload the folder once changes on disk have settled
+    pub fn poll_changes(&mut self, ctx: &egui::Context) {
+        if self.poll_changes_at(Instant::now()) {
+            // Check again once the debounce has passed
+            ctx.request_repaint();
+        }
+    }
+    
+    /// Reload the folder if it last changed at least the debounce before `now`, returning
+    /// whether a change is still waiting
+    fn poll_changes_at(&mut self, now: Instant) -> bool {
+        let mut pending_change = self.pending_change.lock().unwrap();
+        let changed_at = *pending_change;
+        match changed_at {
+            Some(changed_at) if now.duration_since(changed_at) >= WATCH_DEBOUNCE => {
+                *pending_change = None;
+            },
+            Some(_) => return true,
+            None => return false,
+        }
+        drop(pending_change);
+        
+        if let Some(path) = self.current_folder.clone() {
+            debug!("Reloading {} after changes on disk", path.display());
+            self.load_files(path);
+            
+            // Forget selected files that were deleted
+            let listed: HashSet<PathBuf> = self.all_entries().map(|file| file.path.clone()).collect();
+            self.selected_files.retain(|path| listed.contains(path));
+        }
+        false
+    }
+    
     /// Get the list of files
     pub fn files(&self) -> Vec<FileEntry> {
         debug!("Returning {} files", self.files.len());
         self.files.clone()
     }
     
-    /// Load files from the specified path
+    /// Load files from the specified path, along with the subdirectories expanded in it
     pub fn load_files(&mut self, path: PathBuf) {
         debug!("Loading files from: {}", path.display());
-        self.files.clear();
         
-        match fs::read_dir(&path) {
+        match Self::read_entries(&path) {
             Ok(entries) => {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let file_path = entry.path();
-                        let file_name = file_path.file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string();
-                            
-                        let is_dir = file_path.is_dir();
-                        let size = if is_dir {
-                            0 // Directories show as 0 size
-                        } else {
-                            match fs::metadata(&file_path) {
-                                Ok(metadata) => metadata.len(),
-                                Err(e) => {
-                                    debug!("Failed to get metadata for {}: {}", file_path.display(), e);
-                                    0
-                                }
-                            }
-                        };
-                        
-                        let last_modified = fs::metadata(&file_path)
-                            .and_then(|m| m.modified())
-                            .map(|time| {
-                                let dt: DateTime<Utc> = time.into();
-                                dt.format("%Y-%m-%d %H:%M:%S").to_string()
-                            })
-                            .unwrap_or_else(|e| {
-                                debug!("Failed to get modified time for {}: {}", file_path.display(), e);
-                                "Unknown".to_string()
-                            });
-                            
-                        self.files.push(FileEntry {
-                            path: file_path,
-                            name: file_name,
-                            is_directory: is_dir,
-                            size,
-                            last_modified,
-                        });
-                    }
-                }
-                
-                // Sort files: directories first, then by name
-                self.files.sort_by(|a, b| {
-                    match (a.is_directory, b.is_directory) {
-                        (true, false) => std::cmp::Ordering::Less,
-                        (false, true) => std::cmp::Ordering::Greater,
-                        _ => a.name.cmp(&b.name),
-                    }
-                });
-                
+                self.files = entries;
                 debug!("Loaded {} files from {}", self.files.len(), path.display());
             },
             Err(e) => {
                 error!("Failed to read directory {}: {}", path.display(), e);
-                // Add a special entry to indicate the error
-                self.files.push(FileEntry {
-                    path: path.clone(),
-                    name: format!("Error: {}", e),
-                    is_directory: false,
-                    size: 0,
-                    last_modified: "".to_string(),
+                self.files = vec![Self::error_entry(&path, &e)];
+            }
+        }
+        
+        // Reload expanded subdirectories too, dropping ones that no longer exist
+        let expanded: Vec<PathBuf> = self.children.drain().map(|(dir, _)| dir).collect();
+        for dir in expanded {
+            if dir.starts_with(&path) && dir.is_dir() {
+                self.load_children(&dir);
+            }
+        }
+        
+        self.start_counting(path);
+    }
+    
+    /// Count the files in a folder on a background thread, stopping any count still
+    /// running for an earlier load so reloads don't pile up walks of the same tree
+    fn start_counting(&mut self, path: PathBuf) {
+        self.totals_cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.totals_cancel = cancel.clone();
+        
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            if let Some(totals) = Self::count_totals(&path, &cancel) {
+                let _ = tx.send((path, totals));
+            }
+        });
+        self.totals_receiver = Some(rx);
+    }
+    
+    /// Add up the files under a folder, for the folder and each directory in it, or None
+    /// if the count was cancelled
+    fn count_totals(root: &Path, cancel: &AtomicBool) -> Option<HashMap<PathBuf, FolderTotals>> {
+        let mut totals: HashMap<PathBuf, FolderTotals> = HashMap::new();
+        totals.insert(root.to_path_buf(), FolderTotals::default());
+        
+        for entry in walkdir::WalkDir::new(root).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            
+            if entry.file_type().is_dir() {
+                totals.entry(entry.path().to_path_buf()).or_default();
+                continue;
+            }
+            
+            let bytes = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            for dir in entry.path().ancestors().skip(1).take_while(|dir| dir.starts_with(root)) {
+                let dir_totals = totals.entry(dir.to_path_buf()).or_default();
+                dir_totals.files += 1;
+                dir_totals.bytes += bytes;
+            }
+        }
+        
+        Some(totals)
+    }
+    
+    /// Pick up the totals once the background count finishes, returning whether it is still running
+    pub fn poll_totals(&mut self) -> bool {
+        let (path, totals) = match &self.totals_receiver {
+            Some(rx) => match rx.try_recv() {
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.totals_receiver = None;
+                    return false;
+                }
+            },
+            None => return false,
+        };
+        self.totals_receiver = None;
+        
+        if self.current_folder.as_ref() == Some(&path) {
+            self.totals = totals;
+        }
+        false
+    }
+    
+    /// Get the file count and size of the whole current folder, once counted
+    pub fn folder_totals(&self) -> Option<FolderTotals> {
+        self.current_folder.as_ref().and_then(|folder| self.totals.get(folder).copied())
+    }
+    
+    /// Get the contents of a subdirectory, listing it the first time it is expanded
+    pub fn children(&mut self, dir: &Path) -> Vec<FileEntry> {
+        if !self.children.contains_key(dir) {
+            self.load_children(dir);
+        }
+        self.children.get(dir).cloned().unwrap_or_default()
+    }
+    
+    /// List a subdirectory and watch it for changes along with the current folder
+    fn load_children(&mut self, dir: &Path) {
+        let entries = match Self::read_entries(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed to read directory {}: {}", dir.display(), e);
+                vec![Self::error_entry(dir, &e)]
+            }
+        };
+        self.children.insert(dir.to_path_buf(), entries);
+        
+        if let Some(watcher) = self.watcher.as_mut() {
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                debug!("Could not watch {} for changes: {}", dir.display(), e);
+            }
+        }
+    }
+    
+    /// Read the entries of a directory: directories first, then by name
+    fn read_entries(path: &Path) -> std::io::Result<Vec<FileEntry>> {
+        let mut files = Vec::new();
+        
+        for entry in fs::read_dir(path)? {
+            if let Ok(entry) = entry {
+                let file_path = entry.path();
+                let file_name = file_path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                    
+                let is_dir = file_path.is_dir();
+                let size = if is_dir {
+                    0 // Directories show as 0 size
+                } else {
+                    match fs::metadata(&file_path) {
+                        Ok(metadata) => metadata.len(),
+                        Err(e) => {
+                            debug!("Failed to get metadata for {}: {}", file_path.display(), e);
+                            0
+                        }
+                    }
+                };
+                
+                let last_modified = fs::metadata(&file_path)
+                    .and_then(|m| m.modified())
+                    .map(|time| {
+                        let dt: DateTime<Utc> = time.into();
+                        dt.format("%Y-%m-%d %H:%M:%S").to_string()
+                    })
+                    .unwrap_or_else(|e| {
+                        debug!("Failed to get modified time for {}: {}", file_path.display(), e);
+                        "Unknown".to_string()
+                    });
+                    
+                files.push(FileEntry {
+                    path: file_path,
+                    name: file_name,
+                    is_directory: is_dir,
+                    size,
+                    last_modified,
                 });
             }
         }
+        
+        files.sort_by(|a, b| {
+            match (a.is_directory, b.is_directory) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a.name.cmp(&b.name),
+            }
+        });
+        
+        Ok(files)
     }
     
-    /// Get the selected files
+    /// Special entry shown in place of a directory's contents when it can't be read
+    fn error_entry(path: &Path, e: &std::io::Error) -> FileEntry {
+        FileEntry {
+            path: path.to_path_buf(),
+            name: format!("Error: {}", e),
+            is_directory: false,
+            size: 0,
+            last_modified: "".to_string(),
+        }
+    }
+    
+    /// Every listed entry, at the top level and in expanded subdirectories
+    fn all_entries(&self) -> impl Iterator<Item = &FileEntry> {
+        self.files.iter().chain(self.children.values().flatten())
+    }
+    
+    /// Get the selected files, including ones in expanded subdirectories
     pub fn selected_files(&self) -> Vec<&FileEntry> {
-        self.files.iter()
+        self.all_entries()
             .filter(|file| self.selected_files.contains(&file.path))
             .collect()
     }
@@ -277,6 +534,7 @@ impl FolderContent {
                 self.selected_files.insert(file.path.clone());
             }
         }
+        self.drop_covered();
     }
     
     /// Clear all selections
@@ -284,17 +542,24 @@ impl FolderContent {
         self.selected_files.clear();
     }
     
-    /// Get the number of selected files
+    /// Get the totals of a selected entry: the file itself, or everything in a directory
+    /// once the folder has been counted
+    fn entry_totals(&self, file: &FileEntry) -> FolderTotals {
+        if file.is_directory {
+            self.totals.get(&file.path).copied().unwrap_or_default()
+        } else {
+            FolderTotals { files: 1, bytes: file.size }
+        }
+    }
+    
+    /// Get the number of selected files, counting the files in selected directories
     pub fn selected_count(&self) -> usize {
-        self.selected_files.len()
+        self.selected_files().iter().map(|file| self.entry_totals(file).files).sum()
     }
     
-    /// Get the total size of selected files
+    /// Get the total size of selected files, including the files in selected directories
     pub fn selected_size(&self) -> u64 {
-        self.files.iter()
-            .filter(|file| self.selected_files.contains(&file.path))
-            .map(|file| file.size)
-            .sum()
+        self.selected_files().iter().map(|file| self.entry_totals(file).bytes).sum()
     }
 }
 
@@ -314,3 +579,104 @@ fn format_size(size: u64) -> String {
         format!("{:.2} GB", size as f64 / GB as f64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_poll_changes() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        
+        let mut content = FolderContent::default();
+        content.current_folder = Some(dir.path().to_path_buf());
+        content.load_files(dir.path().to_path_buf());
+        content.select_all_visible();
+        assert_eq!(content.files().len(), 2);
+        
+        fs::write(dir.path().join("c.txt"), "c").unwrap();
+        fs::remove_file(dir.path().join("b.txt")).unwrap();
+        let now = Instant::now();
+        *content.pending_change.lock().unwrap() = Some(now);
+        
+        // Nothing is reloaded until the changes settle
+        assert!(content.poll_changes_at(now));
+        assert_eq!(content.files().len(), 2);
+        
+        assert!(!content.poll_changes_at(now + WATCH_DEBOUNCE));
+        let names: Vec<String> = content.files().iter().map(|file| file.name.clone()).collect();
+        assert_eq!(names, vec!["a.txt", "c.txt"]);
+        assert_eq!(content.selected_count(), 1);
+        
+        assert!(!content.poll_changes_at(now + WATCH_DEBOUNCE));
+    }
+    
+    #[test]
+    fn test_subtree_selection() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub/deep")).unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("sub/b.txt"), "bb").unwrap();
+        fs::write(dir.path().join("sub/deep/c.txt"), "ccc").unwrap();
+        
+        let mut content = FolderContent::default();
+        content.current_folder = Some(dir.path().to_path_buf());
+        content.load_files(dir.path().to_path_buf());
+        
+        // Subdirectories are listed when expanded, directories first
+        let sub = dir.path().join("sub");
+        let names: Vec<String> = content.children(&sub).iter().map(|file| file.name.clone()).collect();
+        assert_eq!(names, vec!["deep", "b.txt"]);
+        
+        let nested = sub.join("b.txt");
+        content.toggle_file_selection(&nested);
+        assert_eq!(content.selected_size(), 2);
+        
+        // Selecting the directory covers everything in it
+        content.toggle_file_selection(&sub);
+        assert!(content.is_covered(&nested));
+        assert!(content.is_covered(&sub.join("deep/c.txt")));
+        assert!(!content.is_file_selected(&nested));
+        let selected: Vec<&PathBuf> = content.selected_files().iter().map(|file| &file.path).collect();
+        assert_eq!(selected, vec![&sub]);
+        
+        // Expanded subdirectories are listed again on reload
+        fs::write(sub.join("d.txt"), "d").unwrap();
+        content.load_files(dir.path().to_path_buf());
+        assert_eq!(content.children(&sub).len(), 3);
+    }
+    
+    #[test]
+    fn test_folder_totals() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub/deep")).unwrap();
+        fs::create_dir_all(dir.path().join("empty")).unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("sub/b.txt"), "bb").unwrap();
+        fs::write(dir.path().join("sub/deep/c.txt"), "ccc").unwrap();
+        
+        assert_eq!(FolderContent::count_totals(dir.path(), &AtomicBool::new(true)), None);
+        
+        let totals = FolderContent::count_totals(dir.path(), &AtomicBool::new(false)).unwrap();
+        assert_eq!(totals[dir.path()], FolderTotals { files: 3, bytes: 6 });
+        assert_eq!(totals[&dir.path().join("sub")], FolderTotals { files: 2, bytes: 5 });
+        assert_eq!(totals[&dir.path().join("empty")], FolderTotals::default());
+        
+        let mut content = FolderContent::default();
+        content.current_folder = Some(dir.path().to_path_buf());
+        content.load_files(dir.path().to_path_buf());
+        while content.poll_totals() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(content.folder_totals(), Some(FolderTotals { files: 3, bytes: 6 }));
+        
+        // Selected directories count everything in them
+        content.toggle_file_selection(&dir.path().join("sub"));
+        content.toggle_file_selection(&dir.path().join("a.txt"));
+        assert_eq!(content.selected_count(), 3);
+        assert_eq!(content.selected_size(), 6);
+    }
+}
@@ -1,19 +1,37 @@
 use eframe::egui;
 use std::path::PathBuf;
-// use std::sync::mpsc; // Unused
-// use std::thread; // Unused
+use std::sync::mpsc;
+use std::thread;
 use log::{debug, error};
 // use dirs; // Unused
 
+use crate::ui::utils::{error_color, success_color};
+
 /// Represents a folder to be synced
 #[derive(Clone, Debug)]
 pub struct SyncFolder {
     pub path: PathBuf,
     pub enabled: bool,
+    /// Bucket the folder is synced with, or None to use the bucket selected in the bucket panel
+    pub bucket: Option<String>,
+    /// Prefix the folder's keys are put under, or None for the top of the bucket
+    pub prefix: Option<String>,
     pub status: SyncStatus,
     pub last_synced: Option<chrono::DateTime<chrono::Local>>,
 }
 
+impl SyncFolder {
+    /// Prefix prepended to the keys of the folder's files: empty, or ending with a single '/'
+    pub fn key_prefix(&self) -> String {
+        let prefix = self.prefix.as_deref().unwrap_or_default().trim().trim_matches('/');
+        if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        }
+    }
+}
+
 /// Status of a sync folder
 #[derive(Clone, Debug, PartialEq)]
 pub enum SyncStatus {
@@ -28,12 +46,20 @@ pub enum SyncStatus {
 pub struct FolderList {
     pub folders: Vec<SyncFolder>,
     pub selected_index: Option<usize>,
-    folder_dialog_open: bool,
+    /// Receives the folder chosen in an open selection dialog, or None if it was cancelled
+    folder_dialog: Option<mpsc::Receiver<Option<PathBuf>>>,
 }
 
 impl FolderList {
-    /// Render the folder list UI
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    /// Render the folder list UI, returning true if a folder was added, enabled/disabled or
+    /// mapped to another bucket or prefix. `buckets` are offered for the selected folder's mapping.
+    pub fn ui(&mut self, ui: &mut egui::Ui, buckets: &[String]) -> bool {
+        let mut changed = self.poll_folder_dialog();
+        if self.folder_dialog.is_some() {
+            // Keep polling while the dialog is open
+            ui.ctx().request_repaint();
+        }
+        
         // Folder list
         egui::ScrollArea::vertical().show(ui, |ui| {
             if self.folders.is_empty() {
@@ -57,19 +83,24 @@ impl FolderList {
                             // Update the folder's enabled state
                             if i < self.folders.len() {
                                 self.folders[i].enabled = enabled;
+                                changed = true;
                             }
                         }
                         
-                        // Folder path
+                        // Folder path, with the bucket and prefix it is synced with on hover
                         let text = folder.path.to_string_lossy().to_string();
-                        if ui.selectable_label(is_selected, &text).clicked() {
+                        let target = match &folder.bucket {
+                            Some(bucket) => format!("Synced with s3://{}/{}", bucket, folder.key_prefix()),
+                            None => format!("Synced with the selected bucket under /{}", folder.key_prefix()),
+                        };
+                        if ui.selectable_label(is_selected, &text).on_hover_text(target).clicked() {
                             self.selected_index = Some(i);
                         }
                         
                         // Status indicator
                         match &folder.status {
                             SyncStatus::Synced => {
-                                ui.label(egui::RichText::new("✓").color(egui::Color32::GREEN));
+                                ui.label(egui::RichText::new("✓").color(success_color(ui)));
                             },
                             SyncStatus::Pending => {
                                 ui.label("⏱");
@@ -78,7 +109,7 @@ impl FolderList {
                                 ui.add(egui::Spinner::new());
                             },
                             SyncStatus::Error(msg) => {
-                                ui.label(egui::RichText::new("✗").color(egui::Color32::RED))
+                                ui.label(egui::RichText::new("✗").color(error_color(ui)))
                                     .on_hover_text(msg);
                             },
                         }
@@ -93,99 +124,99 @@ impl FolderList {
                 }
             }
         });
-    }
-    
-    /// Show a folder selection dialog
-    pub fn show_folder_dialog(&mut self) {
-        if self.folder_dialog_open {
-            return;
-        }
         
-        self.folder_dialog_open = true;
+        changed |= self.mapping_ui(ui, buckets);
         
-        // Use PowerShell to open a folder selection dialog
-        debug!("Opening Windows folder selection dialog");
+        changed
+    }
+    
+    /// Render the bucket and prefix of the selected folder, returning true if either was changed
+    fn mapping_ui(&mut self, ui: &mut egui::Ui, buckets: &[String]) -> bool {
+        let folder = match self.selected_index.and_then(|index| self.folders.get_mut(index)) {
+            Some(folder) => folder,
+            None => return false,
+        };
+        let mut changed = false;
         
-        // Create a PowerShell script that will open a folder browser dialog
-        let temp_dir = std::env::temp_dir();
-        let script_path = temp_dir.join("select_folder.ps1");
-        let result_path = temp_dir.join("selected_folder.txt");
+        ui.separator();
+        egui::Grid::new("folder_mapping_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Bucket:");
+                let selected_text = folder.bucket.clone().unwrap_or_else(|| "Selected bucket".to_string());
+                egui::ComboBox::from_id_source("folder_mapping_bucket")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        changed |= ui.selectable_value(&mut folder.bucket, None, "Selected bucket").changed();
+                        for bucket in buckets {
+                            changed |= ui.selectable_value(&mut folder.bucket, Some(bucket.clone()), bucket).changed();
+                        }
+                    });
+                ui.end_row();
+                
+                ui.label("Prefix:");
+                let mut prefix = folder.prefix.clone().unwrap_or_default();
+                let response = ui.add(egui::TextEdit::singleline(&mut prefix).hint_text("none"));
+                if response.changed() {
+                    folder.prefix = Some(prefix).filter(|prefix| !prefix.trim().is_empty());
+                }
+                // Saved once editing finishes rather than on every keystroke
+                changed |= response.lost_focus();
+                ui.end_row();
+            });
         
-        // Delete the result file if it exists
-        if result_path.exists() {
-            let _ = std::fs::remove_file(&result_path);
+        changed
+    }
+    
+    /// Show a folder selection dialog. The native dialog runs on its own thread and the
+    /// chosen folder is added once it closes, so the UI keeps repainting in the meantime.
+    pub fn show_folder_dialog(&mut self) {
+        if self.folder_dialog.is_some() {
+            return;
         }
         
-        // Create the PowerShell script
-        let script_content = format!(
-            r#"
-            Add-Type -AssemblyName System.Windows.Forms
-            $folderBrowser = New-Object System.Windows.Forms.FolderBrowserDialog
-            $folderBrowser.Description = "Select a folder to add to S3Sync"
-            $folderBrowser.ShowNewFolderButton = $true
-            
-            # Show the dialog
-            if ($folderBrowser.ShowDialog() -eq 'OK') {{
-                $selectedPath = $folderBrowser.SelectedPath
-                # Write the selected path to a file
-                $selectedPath | Out-File -FilePath "{}" -Encoding utf8
-            }}
-            "#,
-            result_path.to_string_lossy().replace("\\", "\\\\")
-        );
+        debug!("Opening folder selection dialog");
         
-        // Write the script to a file
-        if let Err(e) = std::fs::write(&script_path, script_content) {
-            error!("Failed to write PowerShell script: {}", e);
-            self.folder_dialog_open = false;
-            return;
-        }
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let selected = futures::executor::block_on(
+                rfd::AsyncFileDialog::new()
+                    .set_title("Select a folder to add to S3Sync")
+                    .pick_folder()
+            );
+            let _ = tx.send(selected.map(|handle| handle.path().to_path_buf()));
+        });
         
-        // Execute the PowerShell script
-        let script_path_str = script_path.to_string_lossy().to_string();
-        let output = std::process::Command::new("powershell.exe")
-            .arg("-ExecutionPolicy")
-            .arg("Bypass")
-            .arg("-File")
-            .arg(&script_path_str)
-            .output();
+        self.folder_dialog = Some(rx);
+    }
+    
+    /// Add the folder chosen in the selection dialog once it has closed, returning true if one was added
+    fn poll_folder_dialog(&mut self) -> bool {
+        let selected = match &self.folder_dialog {
+            Some(rx) => match rx.try_recv() {
+                Ok(selected) => selected,
+                Err(mpsc::TryRecvError::Empty) => return false,
+                Err(mpsc::TryRecvError::Disconnected) => None,
+            },
+            None => return false,
+        };
+        self.folder_dialog = None;
         
-        match output {
-            Ok(_) => {
-                // Check if the result file exists
-                if result_path.exists() {
-                    // Read the selected path from the file
-                    match std::fs::read_to_string(&result_path) {
-                        Ok(content) => {
-                            let selected_path = content.trim().trim_start_matches('\u{feff}');
-                            if !selected_path.is_empty() {
-                                let path = PathBuf::from(selected_path);
-                                if path.exists() && path.is_dir() {
-                                    debug!("Selected folder: {}", path.display());
-                                    self.add_folder(path);
-                                } else {
-                                    error!("Selected path is not a valid directory: {}", selected_path);
-                                }
-                            }
-                        },
-                        Err(e) => {
-                            error!("Failed to read selected path: {}", e);
-                        }
-                    }
-                    
-                    // Clean up the result file
-                    let _ = std::fs::remove_file(&result_path);
-                }
+        match selected {
+            Some(path) if path.exists() && path.is_dir() => {
+                debug!("Selected folder: {}", path.display());
+                self.add_folder(path);
+                true
+            },
+            Some(path) => {
+                error!("Selected path is not a valid directory: {}", path.display());
+                false
+            },
+            None => {
+                debug!("Folder selection cancelled");
+                false
             },
-            Err(e) => {
-                error!("Failed to execute PowerShell script: {}", e);
-            }
         }
-        
-        // Clean up the script file
-        let _ = std::fs::remove_file(&script_path);
-        
-        self.folder_dialog_open = false;
     }
     
     /// Render the folder selection dialog - no longer needed with native dialog
@@ -200,6 +231,8 @@ impl FolderList {
         self.folders.push(SyncFolder {
             path,
             enabled: true,
+            bucket: None,
+            prefix: None,
             status: SyncStatus::Pending,
             last_synced: None,
         });
@@ -223,6 +256,11 @@ impl FolderList {
         }
     }
     
+    /// Get the currently selected folder with its bucket and prefix mapping
+    pub fn selected(&self) -> Option<&SyncFolder> {
+        self.selected_index.and_then(|index| self.folders.get(index))
+    }
+    
     /// Get the currently selected folder
     pub fn selected_folder(&self) -> Option<&PathBuf> {
         self.selected_index.and_then(|index| {
@@ -234,10 +272,14 @@ impl FolderList {
         })
     }
     
-    /// Remove the currently selected folder
-    pub fn remove_selected(&mut self) {
-        if let Some(index) = self.selected_index {
-            self.remove_folder(index);
+    /// Remove the currently selected folder, returning true if one was removed
+    pub fn remove_selected(&mut self) -> bool {
+        match self.selected_index.filter(|index| *index < self.folders.len()) {
+            Some(index) => {
+                self.remove_folder(index);
+                true
+            },
+            None => false,
         }
     }
     
@@ -262,3 +304,25 @@ impl FolderList {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_key_prefix() {
+        let folder = |prefix: Option<&str>| SyncFolder {
+            path: PathBuf::from("/home/user/docs"),
+            enabled: true,
+            bucket: None,
+            prefix: prefix.map(|prefix| prefix.to_string()),
+            status: SyncStatus::Pending,
+            last_synced: None,
+        };
+        
+        assert_eq!(folder(None).key_prefix(), "");
+        assert_eq!(folder(Some(" / ")).key_prefix(), "");
+        assert_eq!(folder(Some("backup")).key_prefix(), "backup/");
+        assert_eq!(folder(Some("/backup/docs/")).key_prefix(), "backup/docs/");
+    }
+}
@@ -1,9 +1,23 @@
 use eframe::egui;
 use log::debug;
+use std::fs;
+use std::thread;
+use std::time::Duration;
 
-use crate::ui::app_state::AppState;
+use crate::ui::app_state::{AppState, StatusMessage};
 use crate::ui::aws_operations::AwsOperations;
-use crate::ui::utils::format_size;
+use crate::ui::bucket_view::BucketView;
+use crate::ui::copy_object_view::CopyObjectView;
+use crate::ui::create_bucket_view::CreateBucketView;
+use crate::ui::folder_content::FileEntry;
+use crate::ui::utils::{clipboard_available, format_size};
+
+/// Expiry choices for presigned share links
+const SHARE_LINK_EXPIRIES: [(&str, Duration); 3] = [
+    ("15 minutes", Duration::from_secs(15 * 60)),
+    ("1 hour", Duration::from_secs(60 * 60)),
+    ("24 hours", Duration::from_secs(24 * 60 * 60)),
+];
 
 /// Renderer for the main view of the application
 pub struct MainViewRenderer;
@@ -27,7 +41,11 @@ impl MainViewRenderer {
             // Local folders section
             ui.group(|ui| {
                 ui.heading("Local Folders");
-                app_state.folder_list.ui(ui);
+                // The folder list is saved as soon as it changes, so it is restored on the next launch
+                let buckets = app_state.bucket_view.buckets().to_vec();
+                if app_state.folder_list.ui(ui, &buckets) {
+                    app_state.save_config();
+                }
                 
                 ui.separator();
                 
@@ -36,8 +54,8 @@ impl MainViewRenderer {
                         app_state.folder_list.show_folder_dialog();
                     }
                     
-                    if ui.button("Remove").clicked() {
-                        app_state.folder_list.remove_selected();
+                    if ui.button("Remove").clicked() && app_state.folder_list.remove_selected() {
+                        app_state.save_config();
                     }
                 });
             });
@@ -47,7 +65,7 @@ impl MainViewRenderer {
             // S3 buckets section
             ui.group(|ui| {
                 ui.heading("S3 Buckets");
-                if app_state.bucket_view.ui(ui) {
+                if app_state.bucket_view.ui(ui) && !app_state.offline {
                     // Bucket selection changed, load objects
                     if let Some(bucket) = app_state.bucket_view.selected_bucket() {
                         AwsOperations::load_bucket_objects(app_state, &bucket);
@@ -56,9 +74,15 @@ impl MainViewRenderer {
                 
                 ui.separator();
                 
-                if ui.button("Connect to AWS").clicked() {
-                    AwsOperations::connect_to_aws(app_state);
-                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!app_state.offline, egui::Button::new("Connect to AWS")).clicked() {
+                        AwsOperations::connect_to_aws(app_state);
+                    }
+                    
+                    if ui.add_enabled(!app_state.offline, egui::Button::new("New Bucket")).clicked() {
+                        app_state.create_bucket = Some(CreateBucketView::new(app_state.settings_view.aws_region()));
+                    }
+                });
             });
         });
     }
@@ -74,19 +98,117 @@ impl MainViewRenderer {
                     ui.heading(&format!("Bucket: {}", bucket));
                     
                     ui.with_layout(egui::Layout::right_to_left(), |ui| {
-                        if ui.button("Upload").clicked() {
+                        let online = !app_state.offline;
+                        
+                        if ui.add_enabled(online, egui::Button::new("Upload")).clicked() {
                             AwsOperations::upload_selected(app_state);
                         }
                         
-                        if ui.button("Download").clicked() {
+                        let destination = format!("s3://{}/{}", bucket, app_state.bucket_view.prefix());
+                        ui.checkbox(&mut app_state.upload_to_prefix, "Here")
+                            .on_hover_text(format!("Upload into {}, keeping the folder structure below it", destination));
+                        
+                        ui.add(
+                            egui::TextEdit::singleline(&mut app_state.upload_key_template)
+                                .hint_text("{relpath}")
+                                .desired_width(160.0)
+                        )
+                        .on_hover_text("Key template for uploads, e.g. backups/{date}/{relpath}. Placeholders: {date}, {folder}, {relpath}, {filename}");
+                        
+                        // Only the checked objects are downloaded, so there is nothing to do without a selection
+                        let has_selection = !app_state.bucket_view.selected_objects().is_empty();
+                        if ui.add_enabled(online && has_selection, egui::Button::new("Download"))
+                            .on_disabled_hover_text("Check the objects or prefixes to download")
+                            .clicked()
+                        {
                             AwsOperations::download_selected(app_state);
                         }
                         
-                        if ui.button("Refresh").clicked() {
+                        ui.checkbox(&mut app_state.flatten_downloads, "Flatten")
+                            .on_hover_text("Download into the local folder without recreating key prefixes");
+                        
+                        if ui.add_enabled(online && has_selection, egui::Button::new("Delete"))
+                            .on_hover_text("Preview and delete the selected objects and prefixes")
+                            .clicked()
+                        {
+                            AwsOperations::preview_delete_selected(app_state);
+                        }
+                        
+                        // History is shown for a single selected file
+                        let history_key = match app_state.bucket_view.selected_objects().as_slice() {
+                            [object] if !object.is_directory => Some(object.key.clone()),
+                            _ => None,
+                        };
+                        if ui.add_enabled(online && history_key.is_some(), egui::Button::new("History")).clicked() {
+                            if let Some(key) = &history_key {
+                                AwsOperations::show_object_history(app_state, key);
+                            }
+                        }
+                        
+                        if ui.add_enabled(online && history_key.is_some(), egui::Button::new("Properties")).clicked() {
+                            if let Some(key) = &history_key {
+                                AwsOperations::show_object_properties(app_state, key);
+                            }
+                        }
+                        
+                        if ui.add_enabled(online && history_key.is_some(), egui::Button::new("Copy To...")).clicked() {
+                            if let Some(key) = &history_key {
+                                Self::open_copy_to(app_state, key);
+                            }
+                        }
+                        
+                        if ui.add_enabled(online && history_key.is_some(), egui::Button::new("Rename")).clicked() {
+                            if let Some(key) = &history_key {
+                                Self::open_rename(app_state, key);
+                            }
+                        }
+                        
+                        // Share links are created for a single selected file too
+                        let share_enabled = online && history_key.is_some() && clipboard_available();
+                        ui.add_enabled_ui(share_enabled, |ui| {
+                            ui.menu_button("Copy Share Link", |ui| {
+                                for (label, ttl) in SHARE_LINK_EXPIRIES {
+                                    if ui.button(label).clicked() {
+                                        if let Some(key) = &history_key {
+                                            AwsOperations::copy_share_link(app_state, key, ttl, false);
+                                        }
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        })
+                        .response
+                        .on_hover_text("Copy a presigned download link that works without AWS credentials until it expires");
+                        
+                        ui.add_enabled_ui(share_enabled, |ui| {
+                            ui.menu_button("Copy Upload Link", |ui| {
+                                for (label, ttl) in SHARE_LINK_EXPIRIES {
+                                    if ui.button(label).clicked() {
+                                        if let Some(key) = &history_key {
+                                            AwsOperations::copy_share_link(app_state, key, ttl, true);
+                                        }
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        })
+                        .response
+                        .on_hover_text("Copy a presigned link that lets anyone holding it replace the object with a PUT request until it expires");
+                        
+                        if ui.add_enabled(online, egui::Button::new("Refresh")).clicked() {
                             if let Some(bucket) = app_state.bucket_view.selected_bucket() {
                                 AwsOperations::load_bucket_objects(app_state, &bucket);
                             }
                         }
+                        
+                        if ui.add_enabled(online, egui::Button::new("Configuration"))
+                            .on_hover_text("View the bucket's lifecycle and CORS rules")
+                            .clicked()
+                        {
+                            if let Some(bucket) = app_state.bucket_view.selected_bucket() {
+                                AwsOperations::show_bucket_configuration(app_state, &bucket);
+                            }
+                        }
                     });
                 } else {
                     ui.heading("No S3 bucket selected");
@@ -96,94 +218,343 @@ impl MainViewRenderer {
             ui.separator();
             
             // Split the view into two panels
-            egui::TopBottomPanel::top("bucket_content_panel")
+            let bucket_panel = egui::TopBottomPanel::top("bucket_content_panel")
                 .resizable(true)
                 .default_height(300.0)
                 .show_inside(ui, |ui| {
                     Self::render_bucket_content(app_state, ui);
                 });
             
+            // Files dragged from the file manager are uploaded when dropped
+            Self::render_drop_overlay(app_state, ui, bucket_panel.response.rect);
+            
             // Local folder content in the bottom panel
             ui.separator();
             Self::render_folder_content(app_state, ui);
         });
     }
 
+    /// Open the rename dialog for an object in the selected bucket
+    fn open_rename(app_state: &mut AppState, key: &str) {
+        if let Some(bucket) = app_state.bucket_view.selected_bucket() {
+            app_state.copy_object = Some(CopyObjectView::rename(bucket, key.to_string()));
+        }
+    }
+    
+    /// Open the copy dialog for an object in the selected bucket, starting at the prefix being browsed
+    fn open_copy_to(app_state: &mut AppState, key: &str) {
+        if let Some(bucket) = app_state.bucket_view.selected_bucket() {
+            let prefix = app_state.bucket_view.prefix().to_string();
+            let buckets = app_state.bucket_view.buckets().to_vec();
+            app_state.copy_object = Some(CopyObjectView::copy_to(bucket, key.to_string(), prefix, buckets));
+        }
+    }
+    
+    /// Add context menu buttons copying the S3 URI and HTTPS URL of an object
+    fn copy_link_buttons(app_state: &mut AppState, ui: &mut egui::Ui, bucket: &str, key: &str) {
+        let enabled = clipboard_available();
+        let mut link = None;
+        
+        if ui.add_enabled(enabled, egui::Button::new("Copy S3 URI"))
+            .on_disabled_hover_text("Clipboard is not available in this session")
+            .clicked()
+        {
+            link = Some(BucketView::object_uri(bucket, key));
+        }
+        if ui.add_enabled(enabled, egui::Button::new("Copy HTTPS URL"))
+            .on_disabled_hover_text("Clipboard is not available in this session")
+            .clicked()
+        {
+            let endpoint_url = app_state.settings_view.endpoint_url();
+            link = Some(app_state.bucket_view.object_url(bucket, key, &app_state.settings_view.aws_region(), endpoint_url.as_deref()));
+        }
+        
+        if let Some(link) = link {
+            app_state.set_status_info(&format!("Copied! {}", link));
+            ui.output().copied_text = link;
+            ui.close_menu();
+        }
+    }
+    
+    /// Export the visible objects to a CSV file chosen in a save dialog. The native dialog
+    /// runs on its own thread, which reports the result through the status channel.
+    fn export_csv(app_state: &mut AppState, bucket: &str) {
+        let csv = app_state.bucket_view.visible_objects_csv();
+        let count = app_state.bucket_view.visible_objects().len();
+        let file_name = format!("{}.csv", bucket);
+        let tx = app_state.status_tx.clone();
+        
+        thread::spawn(move || {
+            let selected = futures::executor::block_on(
+                rfd::AsyncFileDialog::new()
+                    .set_title("Export object listing")
+                    .set_file_name(&file_name)
+                    .add_filter("CSV", &["csv"])
+                    .save_file()
+            );
+            let path = match selected {
+                Some(handle) => handle.path().to_path_buf(),
+                None => return,
+            };
+            
+            let message = match fs::write(&path, csv) {
+                Ok(()) => StatusMessage::Info(format!("Exported {} objects to {}", count, path.display())),
+                Err(e) => StatusMessage::Error(format!("Failed to export objects to {}: {}", path.display(), e)),
+            };
+            let _ = tx.send(message);
+        });
+    }
+    
+    /// Cover the bucket panel with a drop target while files are dragged over the window
+    fn render_drop_overlay(app_state: &AppState, ui: &egui::Ui, rect: egui::Rect) {
+        if ui.ctx().input().raw.hovered_files.is_empty() {
+            return;
+        }
+        
+        let text = match app_state.bucket_view.selected_bucket() {
+            Some(bucket) => format!("Drop here to upload to s3://{}/{}", bucket, app_state.bucket_view.prefix()),
+            None => "Select a bucket to upload dropped files to".to_string(),
+        };
+        
+        let painter = ui.ctx().layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_overlay")));
+        painter.rect_filled(rect, 4.0, egui::Color32::from_black_alpha(180));
+        painter.rect_stroke(rect, 4.0, ui.visuals().selection.stroke);
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            text,
+            egui::TextStyle::Heading.resolve(ui.style()),
+            egui::Color32::WHITE,
+        );
+    }
+    
     /// Render the bucket content section
     fn render_bucket_content(app_state: &mut AppState, ui: &mut egui::Ui) {
         // S3 bucket contents (if a bucket is selected)
-        if let Some(_) = app_state.bucket_view.selected_bucket() {
-            // Create a table header for bucket contents
+        if let Some(bucket) = app_state.bucket_view.selected_bucket() {
+            let columns = app_state.bucket_view.columns();
+            // Prefix to browse to, applied once the table has been drawn
+            let mut navigate_to: Option<String> = None;
+            // Object picked from a context menu to show the properties of
+            let mut properties_of: Option<String> = None;
+            
+            // Preview a single selected file beside the table
+            let previewed = match app_state.bucket_view.selected_objects().as_slice() {
+                [object] if !object.is_directory => Some((object.key.clone(), object.size)),
+                _ => None,
+            };
+            match previewed {
+                Some((key, size)) => {
+                    if app_state.preview.key() != Some(key.as_str()) {
+                        AwsOperations::load_preview(app_state, &key, size);
+                    }
+                    
+                    egui::SidePanel::right("preview_panel")
+                        .resizable(true)
+                        .default_width(300.0)
+                        .show_inside(ui, |ui| {
+                            app_state.preview.ui(ui);
+                        });
+                },
+                None => app_state.preview.clear(),
+            }
+            
+            // Search of every key in the bucket, whatever prefix it is under
+            ui.horizontal(|ui| {
+                ui.label("Search:");
+                let response = ui.add(
+                    egui::TextEdit::singleline(app_state.bucket_view.search_query_mut())
+                        .hint_text("part of any key")
+                        .desired_width(200.0)
+                );
+                let submitted = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                
+                if ui.add_enabled(!app_state.offline, egui::Button::new("Search"))
+                    .on_hover_text("List the whole bucket for keys containing the text, ignoring case")
+                    .clicked() || submitted
+                {
+                    AwsOperations::search_objects(app_state);
+                }
+                
+                let running = app_state.bucket_view.search().map(|search| search.is_running());
+                match running {
+                    Some(true) => {
+                        ui.add(egui::Spinner::new());
+                        if ui.button("Cancel").clicked() {
+                            app_state.bucket_view.cancel_search();
+                        }
+                    },
+                    Some(false) => {
+                        if ui.button("Clear").clicked() {
+                            app_state.bucket_view.clear_search();
+                        }
+                    },
+                    None => {},
+                }
+            });
+            
+            // Search results replace the prefix listing until the search is cleared
+            if app_state.bucket_view.search().is_some() {
+                Self::render_search_results(app_state, ui, &bucket);
+                return;
+            }
+            
+            // Breadcrumb trail of the prefix being browsed
             ui.horizontal(|ui| {
-                ui.style_mut().spacing.item_spacing.x = 10.0;
-                ui.strong("Select");
-                ui.strong("Type");
-                ui.strong("Name");
-                ui.add_space(200.0);
-                ui.strong("Size");
-                ui.add_space(50.0);
-                ui.strong("Last Modified");
+                let at_top = app_state.bucket_view.prefix().is_empty();
+                if ui.add_enabled(!at_top, egui::Button::new("⬆ Up")).clicked() {
+                    navigate_to = Some(app_state.bucket_view.parent_prefix());
+                }
+                
+                if ui.link(&bucket).clicked() {
+                    navigate_to = Some(String::new());
+                }
+                for (name, prefix) in app_state.bucket_view.breadcrumbs() {
+                    ui.label("/");
+                    if ui.link(&name).clicked() {
+                        navigate_to = Some(prefix);
+                    }
+                }
+                
+                ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                    app_state.bucket_view.columns_mut().ui(ui);
+                });
             });
             
             ui.separator();
             
-            // Display bucket objects in a scrollable area
-            egui::ScrollArea::vertical()
+            // Display bucket objects in a scrollable table
+            egui::ScrollArea::both()
                 .id_source("bucket_contents_scroll")
                 .show(ui, |ui| {
                     let objects = app_state.bucket_view.objects().to_vec(); // Clone to avoid borrow issues
                     
                     if objects.is_empty() {
-                        ui.label("No objects in this bucket");
-                    } else {
-                        // Add each object as a row in the table
-                        for object in &objects {
-                            // Use a container for each row
-                            egui::containers::Frame::none()
-                                .show(ui, |ui| {
-                                    ui.horizontal(|ui| {
-                                        ui.style_mut().spacing.item_spacing.x = 10.0;
-                                        
-                                        // Selection checkbox
-                                        let is_selected = app_state.bucket_view.is_object_selected(&object.key);
-                                        let mut selected = is_selected;
-                                        if ui.checkbox(&mut selected, "").changed() {
-                                            app_state.bucket_view.toggle_object_selection(&object.key);
+                        if app_state.bucket_view.prefix().is_empty() {
+                            ui.label("No objects in this bucket");
+                        } else {
+                            ui.label("No objects under this prefix");
+                        }
+                        return;
+                    }
+                    
+                    egui::Grid::new("bucket_contents_grid")
+                        .num_columns(3 + columns.count())
+                        .spacing([10.0, 4.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            // Header
+                            ui.strong("Select");
+                            ui.strong("Type");
+                            ui.strong("Name");
+                            if columns.size {
+                                ui.strong("Size");
+                            }
+                            if columns.last_modified {
+                                ui.strong("Last Modified");
+                            }
+                            if columns.storage_class {
+                                ui.strong("Storage Class");
+                            }
+                            if columns.e_tag {
+                                ui.strong("ETag");
+                            }
+                            if columns.owner {
+                                ui.strong("Owner");
+                            }
+                            ui.end_row();
+                            
+                            for object in &objects {
+                                // Selection checkbox
+                                let is_selected = app_state.bucket_view.is_object_selected(&object.key);
+                                let mut selected = is_selected;
+                                if ui.checkbox(&mut selected, "").changed() {
+                                    app_state.bucket_view.toggle_object_selection(&object.key);
+                                }
+                                
+                                // Type icon
+                                let icon = if object.is_directory { "📁" } else { "📄" };
+                                ui.label(icon);
+                                
+                                // Name, relative to the prefix being browsed. Clicking a directory opens it.
+                                let text = egui::RichText::new(app_state.bucket_view.display_name(&object.key));
+                                let text = if is_selected { text.strong() } else { text };
+                                let response = ui.selectable_label(is_selected, text);
+                                if response.clicked() {
+                                    if object.is_directory {
+                                        navigate_to = Some(BucketView::directory_prefix(&object.key));
+                                    } else {
+                                        app_state.bucket_view.toggle_object_selection(&object.key);
+                                    }
+                                }
+                                if !object.is_directory {
+                                    response.context_menu(|ui| {
+                                        if ui.button("Rename...").clicked() {
+                                            Self::open_rename(app_state, &object.key);
+                                            ui.close_menu();
+                                        }
+                                        if ui.button("Copy To...").clicked() {
+                                            Self::open_copy_to(app_state, &object.key);
+                                            ui.close_menu();
                                         }
-                                        
-                                        // Type icon
-                                        let icon = if object.is_directory { "📁" } else { "📄" };
-                                        ui.label(icon);
-                                        
-                                        // Name
-                                        let name_len = object.key.len();
-                                        let text = egui::RichText::new(&object.key);
-                                        let text = if is_selected { text.strong() } else { text };
-                                        if ui.selectable_label(is_selected, text).clicked() {
-                                            app_state.bucket_view.toggle_object_selection(&object.key);
+                                        Self::copy_link_buttons(app_state, ui, &bucket, &object.key);
+                                        if ui.button("Properties").clicked() {
+                                            properties_of = Some(object.key.clone());
+                                            ui.close_menu();
                                         }
-                                        ui.add_space(200.0 - name_len as f32 * 7.0); // Approximate spacing
-                                        
-                                        // Size
-                                        let size_text = if object.is_directory {
-                                            "-".to_string()
-                                        } else {
-                                            format_size(object.size)
-                                        };
-                                        ui.label(&size_text);
-                                        ui.add_space(50.0);
-                                        
-                                        // Last Modified
-                                        ui.label(&object.last_modified);
                                     });
-                                });
-                            
-                            // Add some spacing between rows
-                            ui.add_space(2.0);
-                        }
+                                }
+                                
+                                if columns.size {
+                                    let size_text = if object.is_directory {
+                                        "-".to_string()
+                                    } else {
+                                        format_size(object.size)
+                                    };
+                                    ui.label(&size_text);
+                                }
+                                if columns.last_modified {
+                                    ui.label(&object.last_modified);
+                                }
+                                if columns.storage_class {
+                                    ui.label(&object.storage_class);
+                                }
+                                if columns.e_tag {
+                                    ui.label(egui::RichText::new(&object.e_tag).monospace());
+                                }
+                                if columns.owner {
+                                    ui.label(&object.owner);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    
+                    // Only the first page is listed up front so large buckets show straight away
+                    if app_state.bucket_view.has_more_objects() {
+                        ui.horizontal(|ui| {
+                            let loading_more = app_state.bucket_view.is_loading_more();
+                            if ui.add_enabled(!loading_more && !app_state.offline, egui::Button::new("Load more"))
+                                .on_hover_text("List the next 1000 objects under this prefix")
+                                .clicked()
+                            {
+                                AwsOperations::load_more_objects(app_state);
+                            }
+                            if loading_more {
+                                ui.add(egui::Spinner::new());
+                            }
+                        });
                     }
                 });
                 
+                if let Some(prefix) = navigate_to {
+                    app_state.bucket_view.set_prefix(&prefix);
+                    AwsOperations::load_bucket_objects(app_state, &bucket);
+                }
+                
+                if let Some(key) = properties_of {
+                    AwsOperations::show_object_properties(app_state, &key);
+                }
+                
                 // Add selection controls
                 ui.separator();
                 ui.horizontal(|ui| {
@@ -195,6 +566,13 @@ impl MainViewRenderer {
                         app_state.bucket_view.clear_selection();
                     }
                     
+                    if ui.button("Export CSV")
+                        .on_hover_text("Save the listed objects (matching the filter) to a CSV file")
+                        .clicked()
+                    {
+                        Self::export_csv(app_state, &bucket);
+                    }
+                    
                     ui.with_layout(egui::Layout::right_to_left(), |ui| {
                         let selected_count = app_state.bucket_view.selected_objects().len();
                         ui.label(format!("{} objects selected", selected_count));
@@ -207,6 +585,136 @@ impl MainViewRenderer {
         }
     }
 
+    /// Render the matches of a bucket search as a flat list of full keys. Clicking a match
+    /// opens the prefix it is under.
+    fn render_search_results(app_state: &mut AppState, ui: &mut egui::Ui, bucket: &str) {
+        let (matches, summary) = match app_state.bucket_view.search() {
+            Some(search) => {
+                let summary = if search.is_running() {
+                    format!("{} matches for '{}' so far, {} keys searched", search.matches.len(), search.query, search.scanned)
+                } else {
+                    format!("{} matches for '{}' in {} keys", search.matches.len(), search.query, search.scanned)
+                };
+                (search.matches.clone(), summary)
+            },
+            None => return,
+        };
+        
+        ui.label(summary);
+        ui.separator();
+        
+        let mut open_prefix: Option<String> = None;
+        let mut properties_of: Option<String> = None;
+        
+        egui::ScrollArea::both()
+            .id_source("search_results_scroll")
+            .show(ui, |ui| {
+                egui::Grid::new("search_results_grid")
+                    .num_columns(3)
+                    .spacing([10.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Key");
+                        ui.strong("Size");
+                        ui.strong("Last Modified");
+                        ui.end_row();
+                        
+                        for object in &matches {
+                            let response = ui.selectable_label(false, &object.key)
+                                .on_hover_text("Open the prefix this object is under");
+                            if response.clicked() {
+                                let prefix = match object.key.trim_end_matches('/').rfind('/') {
+                                    Some(index) => object.key[..index + 1].to_string(),
+                                    None => String::new(),
+                                };
+                                open_prefix = Some(prefix);
+                            }
+                            if !object.is_directory {
+                                response.context_menu(|ui| {
+                                    Self::copy_link_buttons(app_state, ui, bucket, &object.key);
+                                    if ui.button("Properties").clicked() {
+                                        properties_of = Some(object.key.clone());
+                                        ui.close_menu();
+                                    }
+                                });
+                            }
+                            
+                            if object.is_directory {
+                                ui.label("-");
+                            } else {
+                                ui.label(format_size(object.size));
+                            }
+                            ui.label(&object.last_modified);
+                            ui.end_row();
+                        }
+                    });
+            });
+        
+        if let Some(prefix) = open_prefix {
+            app_state.bucket_view.clear_search();
+            app_state.bucket_view.set_prefix(&prefix);
+            AwsOperations::load_bucket_objects(app_state, bucket);
+        }
+        
+        if let Some(key) = properties_of {
+            AwsOperations::show_object_properties(app_state, &key);
+        }
+    }
+    
+    /// Render local folder entries as grid rows. Each subdirectory expands into a grid of
+    /// its own, listed the first time it is opened. `covered` is set inside a checked
+    /// directory, whose whole subtree is already selected.
+    fn render_folder_entries(app_state: &mut AppState, ui: &mut egui::Ui, entries: &[FileEntry], covered: bool) {
+        for file in entries {
+            // Selection checkbox
+            let is_selected = covered || app_state.folder_content.is_file_selected(&file.path);
+            let mut selected = is_selected;
+            if ui.add_enabled(!covered, egui::Checkbox::new(&mut selected, "")).changed() {
+                app_state.folder_content.toggle_file_selection(&file.path);
+            }
+            
+            // Type icon
+            let icon = if file.is_directory { "📁" } else { "📄" };
+            ui.label(icon);
+            
+            // Name. Directories expand to show their contents.
+            let text = egui::RichText::new(&file.name);
+            let text = if is_selected { text.strong() } else { text };
+            if file.is_directory {
+                egui::CollapsingHeader::new(text)
+                    .id_source(&file.path)
+                    .show(ui, |ui| {
+                        let children = app_state.folder_content.children(&file.path);
+                        if children.is_empty() {
+                            ui.label("Empty folder");
+                            return;
+                        }
+                        
+                        egui::Grid::new(("local_folder_grid", &file.path))
+                            .num_columns(5)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                Self::render_folder_entries(app_state, ui, &children, is_selected);
+                            });
+                    });
+            } else if ui.add_enabled(!covered, egui::SelectableLabel::new(is_selected, text)).clicked() {
+                app_state.folder_content.toggle_file_selection(&file.path);
+            }
+            
+            // Size
+            let size_text = if file.is_directory {
+                "--".to_string()
+            } else {
+                format_size(file.size)
+            };
+            ui.label(&size_text);
+            
+            // Last Modified
+            ui.label(&file.last_modified);
+            ui.end_row();
+        }
+    }
+    
     /// Render the folder content section
     fn render_folder_content(app_state: &mut AppState, ui: &mut egui::Ui) {
         // Local folder contents (if a folder is selected)
@@ -221,6 +729,11 @@ impl MainViewRenderer {
                             app_state.folder_content.load_files(path_clone);
                         }
                     }
+                    
+                    if let Some(error) = app_state.folder_content.watch_error() {
+                        ui.label("Not watching for changes")
+                            .on_hover_text(format!("Refresh to see changes made on disk. {}", error));
+                    }
                 });
             } else {
                 ui.heading("No local folder selected");
@@ -233,29 +746,20 @@ impl MainViewRenderer {
             // Set the folder in the folder_content component and ensure files are loaded
             if app_state.folder_content.current_folder.as_ref() != Some(folder_path) {
                 debug!("Loading folder contents for: {}", folder_path.display());
-                app_state.folder_content.set_folder(folder_path.clone());
+                app_state.folder_content.set_folder(folder_path.clone(), ui.ctx());
+            }
+            app_state.folder_content.poll_changes(ui.ctx());
+            if app_state.folder_content.poll_totals() {
+                // Keep polling while the folder is being counted
+                ui.ctx().request_repaint();
             }
             
             // Display files directly here for debugging
             let files = app_state.folder_content.files().to_vec(); // Clone the files to avoid borrow issues
             debug!("Found {} files in folder", files.len());
             
-            // Table header
-            ui.horizontal(|ui| {
-                ui.style_mut().spacing.item_spacing.x = 10.0;
-                ui.strong("Select");
-                ui.strong("Type");
-                ui.strong("Name");
-                ui.add_space(200.0);
-                ui.strong("Size");
-                ui.add_space(50.0);
-                ui.strong("Modified");
-            });
-            
-            ui.separator();
-            
-            // Display files in a scrollable area
-            egui::ScrollArea::vertical().id_source("local_folder_scroll").show(ui, |ui| {
+            // Display files in a scrollable table
+            egui::ScrollArea::both().id_source("local_folder_scroll").show(ui, |ui| {
                 if files.is_empty() {
                     ui.label("No files in this folder or unable to access folder contents");
                     
@@ -266,44 +770,24 @@ impl MainViewRenderer {
                             app_state.folder_content.load_files(path_clone);
                         }
                     }
-                } else {
-                    for file in &files {
-                        ui.horizontal(|ui| {
-                            ui.style_mut().spacing.item_spacing.x = 10.0;
-                            
-                            // Selection checkbox
-                            let is_selected = app_state.folder_content.is_file_selected(&file.path);
-                            let mut selected = is_selected;
-                            if ui.checkbox(&mut selected, "").changed() {
-                                app_state.folder_content.toggle_file_selection(&file.path);
-                            }
-                            
-                            // Type icon
-                            let icon = if file.is_directory { "📁" } else { "📄" };
-                            ui.label(icon);
-                            
-                            // Name
-                            let text = egui::RichText::new(&file.name);
-                            let text = if is_selected { text.strong() } else { text };
-                            if ui.selectable_label(is_selected, text).clicked() {
-                                app_state.folder_content.toggle_file_selection(&file.path);
-                            }
-                            ui.add_space(200.0 - file.name.len() as f32 * 7.0);
-                            
-                            // Size
-                            let size_text = if file.is_directory {
-                                "--".to_string()
-                            } else {
-                                format_size(file.size)
-                            };
-                            ui.label(&size_text);
-                            ui.add_space(50.0);
-                            
-                            // Last Modified
-                            ui.label(&file.last_modified);
-                        });
-                    }
+                    return;
                 }
+                
+                egui::Grid::new("local_folder_grid")
+                    .num_columns(5)
+                    .spacing([10.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        // Header
+                        ui.strong("Select");
+                        ui.strong("Type");
+                        ui.strong("Name");
+                        ui.strong("Size");
+                        ui.strong("Modified");
+                        ui.end_row();
+                        
+                        Self::render_folder_entries(app_state, ui, &files, false);
+                    });
             });
             
             // Add selection controls
@@ -320,10 +804,18 @@ impl MainViewRenderer {
                 ui.with_layout(egui::Layout::right_to_left(), |ui| {
                     let selected_count = app_state.folder_content.selected_count();
                     let selected_size = app_state.folder_content.selected_size();
-                    ui.label(format!("{} files selected ({} total)", selected_count, format_size(selected_size)));
+                    let total = match app_state.folder_content.folder_totals() {
+                        Some(totals) => format!("{} total files ({})", totals.files, format_size(totals.bytes)),
+                        None => "counting total files...".to_string(),
+                    };
+                    ui.label(format!("{} files selected ({}), {}", selected_count, format_size(selected_size), total));
                 });
             });
         } else {
+            if app_state.folder_content.current_folder.is_some() {
+                app_state.folder_content.clear_folder();
+            }
+            
             ui.centered_and_justified(|ui| {
                 ui.label("Please select a folder from the list on the left");
             });
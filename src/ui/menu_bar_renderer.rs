@@ -2,6 +2,7 @@ use eframe::egui;
 
 use crate::ui::app_state::{AppState, CurrentView};
 use crate::ui::aws_operations::AwsOperations;
+use crate::ui::transfer_history_view_renderer::TransferHistoryViewRenderer;
 
 /// Renderer for the menu bar of the application
 pub struct MenuBarRenderer;
@@ -9,6 +10,8 @@ pub struct MenuBarRenderer;
 impl MenuBarRenderer {
     /// Render the menu bar
     pub fn render(app_state: &mut AppState, ctx: &egui::Context) {
+        let online = !app_state.offline;
+        
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
@@ -28,32 +31,45 @@ impl MenuBarRenderer {
                         ui.close_menu();
                     }
                     
-                    if ui.button("Refresh").clicked() {
+                    if ui.button("Compare Buckets").clicked() {
+                        app_state.current_view = CurrentView::Compare;
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("Transfer History").clicked() {
+                        TransferHistoryViewRenderer::open(app_state);
+                        ui.close_menu();
+                    }
+                    
+                    if ui.add_enabled(online, egui::Button::new("Refresh")).clicked() {
                         AwsOperations::refresh_buckets(app_state);
                         ui.close_menu();
                     }
                 });
                 
                 ui.menu_button("Actions", |ui| {
-                    if ui.button("Upload").clicked() {
+                    if ui.add_enabled(online, egui::Button::new("Upload")).clicked() {
                         AwsOperations::upload_selected(app_state);
                         ui.close_menu();
                     }
                     
-                    if ui.button("Download").clicked() {
+                    if ui.add_enabled(online, egui::Button::new("Download")).clicked() {
                         AwsOperations::download_selected(app_state);
                         ui.close_menu();
                     }
                     
-                    if ui.button("Sync").clicked() {
+                    if ui.add_enabled(online, egui::Button::new("Sync")).clicked() {
                         AwsOperations::sync_selected(app_state);
                         ui.close_menu();
                     }
+                    
+                    ui.checkbox(&mut app_state.sync_dry_run, "Dry run")
+                        .on_hover_text("Show what Sync would change without changing anything");
                 });
                 
                 ui.menu_button("Help", |ui| {
                     if ui.button("About").clicked() {
-                        // Show about dialog
+                        app_state.show_about = true;
                         ui.close_menu();
                     }
                 });
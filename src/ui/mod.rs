@@ -1,16 +1,34 @@
+pub mod about_view;
 pub mod app;
 pub mod app_impl;
 pub mod app_state;
 pub mod aws_operations;
+pub mod bucket_config_view;
 pub mod bucket_view;
+pub mod compare_view;
+pub mod compare_view_renderer;
+pub mod copy_object_view;
+pub mod create_bucket_view;
+pub mod delete_preview;
+pub mod failed_transfers;
 pub mod filter_view;
 pub mod filter_view_renderer;
 pub mod folder_content;
 pub mod folder_list;
 pub mod main_view_renderer;
 pub mod menu_bar_renderer;
+pub mod notification;
+pub mod object_history;
+pub mod object_properties;
+pub mod preview;
 pub mod progress;
 pub mod settings;
 pub mod settings_view_renderer;
+pub mod session_token_view;
+pub mod shortcuts;
 pub mod status_bar_renderer;
+pub mod status_log;
+pub mod sync_preview;
+pub mod transfer_history_view;
+pub mod transfer_history_view_renderer;
 pub mod utils;
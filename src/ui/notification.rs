@@ -0,0 +1,14 @@
+use log::error;
+use notify_rust::Notification;
+
+/// Show a desktop notification. Failures are only logged, as notifications are best effort.
+pub fn show(summary: &str, body: &str) {
+    if let Err(e) = Notification::new()
+        .appname("S3 Sync")
+        .summary(summary)
+        .body(body)
+        .show()
+    {
+        error!("Failed to show notification: {}", e);
+    }
+}
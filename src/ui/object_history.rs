@@ -0,0 +1,153 @@
+use chrono::{TimeZone, Utc};
+use eframe::egui;
+
+use crate::aws::transfer::{ObjectExpiration, ObjectVersionInfo};
+use crate::ui::utils::{copy_button, error_color, format_size, success_color, warning_color};
+
+/// Action requested from the object history window
+pub enum HistoryAction {
+    Download(String),
+    Restore(ObjectVersionInfo),
+}
+
+/// Timeline of an object's versions and delete markers
+pub struct ObjectHistoryView {
+    pub bucket: String,
+    pub key: String,
+    versions: Vec<ObjectVersionInfo>,
+    /// When a lifecycle rule will expire the current version
+    expiration: Option<ObjectExpiration>,
+    loading: bool,
+}
+
+impl ObjectHistoryView {
+    /// Create a history view for an object, waiting for its versions to load
+    pub fn new(bucket: String, key: String) -> Self {
+        Self {
+            bucket,
+            key,
+            versions: Vec::new(),
+            expiration: None,
+            loading: true,
+        }
+    }
+    
+    /// Set the loaded versions, newest first
+    pub fn set_versions(&mut self, versions: Vec<ObjectVersionInfo>) {
+        self.versions = versions;
+        self.loading = false;
+    }
+    
+    /// Set the lifecycle expiration of the current version
+    pub fn set_expiration(&mut self, expiration: Option<ObjectExpiration>) {
+        self.expiration = expiration;
+    }
+    
+    /// Show the history as a window, returning whether it is still open and any requested action
+    pub fn show(&self, ctx: &egui::Context) -> (bool, Option<HistoryAction>) {
+        let mut open = true;
+        let mut action = None;
+        
+        egui::Window::new(format!("History: {}", self.key))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([450.0, 400.0])
+            .show(ctx, |ui| {
+                action = self.ui(ui);
+            });
+        
+        (open, action)
+    }
+    
+    /// Render the timeline, newest version at the top
+    fn ui(&self, ui: &mut egui::Ui) -> Option<HistoryAction> {
+        let mut action = None;
+        
+        let uri = format!("s3://{}/{}", self.bucket, self.key);
+        ui.horizontal(|ui| {
+            ui.label(&uri);
+            copy_button(ui, "Copy", &uri);
+        });
+        
+        if let Some(expiration) = &self.expiration {
+            let text = if expiration.rule_id.is_empty() {
+                format!("Expires {}", expiration.expiry_date)
+            } else {
+                format!("Expires {} (lifecycle rule \"{}\")", expiration.expiry_date, expiration.rule_id)
+            };
+            ui.colored_label(warning_color(ui), text);
+        }
+        ui.separator();
+        
+        if self.loading {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new());
+                ui.label("Loading versions...");
+            });
+            return None;
+        }
+        
+        if self.versions.is_empty() {
+            ui.label("No versions found. Versioning may not be enabled for this bucket.");
+            return None;
+        }
+        
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let count = self.versions.len();
+            
+            for (index, version) in self.versions.iter().enumerate() {
+                let timestamp = Utc.timestamp_opt(version.last_modified, 0)
+                    .single()
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                
+                ui.horizontal(|ui| {
+                    // Timeline marker
+                    let (marker, color) = if version.is_delete_marker {
+                        ("✗", error_color(ui))
+                    } else if version.is_latest {
+                        ("●", success_color(ui))
+                    } else {
+                        ("○", egui::Color32::GRAY)
+                    };
+                    ui.label(egui::RichText::new(marker).color(color));
+                    
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.strong(&timestamp);
+                            if version.is_latest {
+                                ui.label("(current)");
+                            }
+                        });
+                        
+                        if version.is_delete_marker {
+                            ui.label("Deleted");
+                        } else {
+                            ui.label(format_size(version.size));
+                        }
+                        
+                        ui.label(egui::RichText::new(&version.version_id).small().color(egui::Color32::GRAY));
+                        
+                        ui.horizontal(|ui| {
+                            if !version.is_delete_marker && ui.button("Download").clicked() {
+                                action = Some(HistoryAction::Download(version.version_id.clone()));
+                            }
+                            
+                            // A delete marker is restored by removing it; an older version by copying it back
+                            let can_restore = if version.is_delete_marker { version.is_latest } else { !version.is_latest };
+                            if can_restore && ui.button("Restore").clicked() {
+                                action = Some(HistoryAction::Restore(version.clone()));
+                            }
+                        });
+                    });
+                });
+                
+                if index + 1 < count {
+                    ui.label(egui::RichText::new("│").color(egui::Color32::GRAY));
+                }
+            }
+        });
+        
+        action
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{TimeZone, Utc};
+use eframe::egui;
+
+use crate::aws::transfer::ObjectMetadata;
+use crate::ui::utils::{copy_button, error_color, format_size};
+
+/// Window showing an object's metadata from a HEAD request
+pub struct ObjectPropertiesView {
+    pub bucket: String,
+    pub key: String,
+    /// Metadata once loaded, or why it couldn't be
+    metadata: Option<Result<ObjectMetadata, String>>,
+}
+
+impl ObjectPropertiesView {
+    /// Create a properties view for an object, waiting for its metadata to load
+    pub fn new(bucket: String, key: String) -> Self {
+        Self {
+            bucket,
+            key,
+            metadata: None,
+        }
+    }
+    
+    /// Set the loaded metadata, or the error that stopped it loading
+    pub fn set_metadata(&mut self, metadata: Result<ObjectMetadata, String>) {
+        self.metadata = Some(metadata);
+    }
+    
+    /// Show the properties as a window, returning whether it is still open
+    pub fn show(&self, ctx: &egui::Context) -> bool {
+        let mut open = true;
+        
+        egui::Window::new(format!("Properties: {}", self.key))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([450.0, 350.0])
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+        
+        open
+    }
+    
+    /// Render the object's properties followed by its user metadata
+    fn ui(&self, ui: &mut egui::Ui) {
+        let uri = format!("s3://{}/{}", self.bucket, self.key);
+        ui.horizontal(|ui| {
+            ui.label(&uri);
+            copy_button(ui, "Copy URI", &uri);
+        });
+        ui.separator();
+        
+        let metadata = match &self.metadata {
+            Some(Ok(metadata)) => metadata,
+            Some(Err(e)) => {
+                ui.colored_label(error_color(ui), e);
+                return;
+            },
+            None => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Spinner::new());
+                    ui.label("Loading properties...");
+                });
+                return;
+            }
+        };
+        
+        let last_modified = Utc.timestamp_opt(metadata.last_modified, 0)
+            .single()
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        
+        egui::Grid::new("object_properties_grid")
+            .num_columns(2)
+            .spacing([20.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Size:");
+                ui.label(format!("{} ({} bytes)", format_size(metadata.size), metadata.size));
+                ui.end_row();
+                
+                ui.label("Last modified:");
+                ui.label(last_modified);
+                ui.end_row();
+                
+                ui.label("Content type:");
+                ui.label(metadata.content_type.as_deref().unwrap_or("-"));
+                ui.end_row();
+                
+                if let Some(content_encoding) = &metadata.content_encoding {
+                    ui.label("Content encoding:");
+                    ui.label(content_encoding);
+                    ui.end_row();
+                }
+                
+                ui.label("Storage class:");
+                ui.label(&metadata.storage_class);
+                ui.end_row();
+                
+                ui.label("ETag:");
+                ui.horizontal(|ui| {
+                    match &metadata.e_tag {
+                        Some(e_tag) => {
+                            ui.label(egui::RichText::new(e_tag).monospace());
+                            copy_button(ui, "Copy", e_tag);
+                        },
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                });
+                ui.end_row();
+                
+                ui.label("Encryption:");
+                match (&metadata.encryption, &metadata.kms_key_id) {
+                    (Some(encryption), Some(kms_key_id)) => ui.label(format!("{} (key {})", encryption, kms_key_id)),
+                    (Some(encryption), None) => ui.label(encryption),
+                    (None, _) => ui.label("None"),
+                };
+                ui.end_row();
+                
+                if let Some(version_id) = &metadata.version_id {
+                    ui.label("Version ID:");
+                    ui.label(egui::RichText::new(version_id).monospace());
+                    ui.end_row();
+                }
+            });
+        
+        ui.separator();
+        ui.strong("User metadata");
+        
+        if metadata.user_metadata.is_empty() {
+            ui.label("No user metadata");
+            return;
+        }
+        
+        egui::Grid::new("object_user_metadata_grid")
+            .num_columns(2)
+            .spacing([20.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in &metadata.user_metadata {
+                    ui.label(format!("x-amz-meta-{}", name));
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+    }
+}
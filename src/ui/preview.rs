@@ -0,0 +1,208 @@
+use eframe::egui;
+use std::io::Cursor;
+
+use crate::ui::utils::error_color;
+
+/// Largest object that is previewed, since it is downloaded into memory
+pub const PREVIEW_SIZE_LIMIT: u64 = 5 * 1024 * 1024;
+
+/// Largest width or height of a previewed image; bigger images are scaled down to fit
+const PREVIEW_MAX_DIMENSION: u32 = 2048;
+
+/// Largest number of pixels an image may have to be decoded at all. A small compressed
+/// file can expand to a huge image, which would exhaust memory before it could be scaled.
+const PREVIEW_MAX_PIXELS: u64 = 100_000_000;
+
+/// How an object's content can be previewed, decided from its content type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewKind {
+    Image,
+    Text,
+}
+
+impl PreviewKind {
+    /// Get the preview kind for a content type, or None if it can't be previewed
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        // Drop parameters such as "; charset=utf-8"
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        
+        match mime.as_str() {
+            "image/png" | "image/jpeg" | "image/gif" | "image/bmp" | "image/webp" => Some(PreviewKind::Image),
+            "application/json" | "application/xml" | "application/javascript" | "application/x-yaml"
+                | "application/yaml" | "application/x-sh" | "application/toml" => Some(PreviewKind::Text),
+            _ if mime.starts_with("text/") || mime.ends_with("+json") || mime.ends_with("+xml") => Some(PreviewKind::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Content of a previewed object
+pub enum PreviewContent {
+    Image(egui::ColorImage),
+    Text(String),
+    /// The object can't be previewed, and why
+    Unavailable(String),
+    /// The object couldn't be fetched
+    Failed(String),
+}
+
+impl PreviewContent {
+    /// Decode downloaded bytes for display
+    pub fn decode(kind: PreviewKind, bytes: &[u8]) -> Self {
+        match kind {
+            PreviewKind::Image => match Self::decode_image(bytes) {
+                Ok(image) => {
+                    let image = image.to_rgba8();
+                    let size = [image.width() as usize, image.height() as usize];
+                    PreviewContent::Image(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+                },
+                Err(e) => PreviewContent::Unavailable(format!("The image could not be decoded: {}", e)),
+            },
+            PreviewKind::Text => PreviewContent::Text(String::from_utf8_lossy(bytes).into_owned()),
+        }
+    }
+    
+    /// Decode an image, checking its dimensions first and scaling it down to the preview size
+    fn decode_image(bytes: &[u8]) -> Result<image::DynamicImage, String> {
+        let reader = || image::io::Reader::new(Cursor::new(bytes)).with_guessed_format().map_err(|e| e.to_string());
+        
+        let (width, height) = reader()?.into_dimensions().map_err(|e| e.to_string())?;
+        if width as u64 * height as u64 > PREVIEW_MAX_PIXELS {
+            return Err(format!("it is too large to preview ({} × {})", width, height));
+        }
+        
+        let image = reader()?.decode().map_err(|e| e.to_string())?;
+        if width > PREVIEW_MAX_DIMENSION || height > PREVIEW_MAX_DIMENSION {
+            Ok(image.thumbnail(PREVIEW_MAX_DIMENSION, PREVIEW_MAX_DIMENSION))
+        } else {
+            Ok(image)
+        }
+    }
+}
+
+/// Pane previewing the selected object's content
+#[derive(Default)]
+pub struct PreviewPane {
+    /// Object being previewed
+    key: Option<String>,
+    /// Content once loaded; None while it is downloading
+    content: Option<PreviewContent>,
+    /// Texture for an image preview, created when it is first drawn
+    texture: Option<egui::TextureHandle>,
+}
+
+impl PreviewPane {
+    /// Get the key of the object being previewed
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+    
+    /// Start previewing an object, showing a spinner until its content is set
+    pub fn start(&mut self, key: &str) {
+        self.key = Some(key.to_string());
+        self.content = None;
+        self.texture = None;
+    }
+    
+    /// Set the content of the object being previewed. Content for another object,
+    /// which arrived after the selection changed, is ignored.
+    pub fn set_content(&mut self, key: &str, content: PreviewContent) {
+        if self.key.as_deref() == Some(key) {
+            self.content = Some(content);
+            self.texture = None;
+        }
+    }
+    
+    /// Stop previewing once nothing previewable is selected
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+    
+    /// Render the preview
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return,
+        };
+        
+        ui.strong("Preview");
+        ui.label(egui::RichText::new(key).small());
+        ui.separator();
+        
+        match &self.content {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Spinner::new());
+                    ui.label("Loading preview...");
+                });
+            },
+            Some(PreviewContent::Unavailable(reason)) => {
+                ui.label("No preview available");
+                ui.label(egui::RichText::new(reason).small().color(egui::Color32::GRAY));
+            },
+            Some(PreviewContent::Failed(error)) => {
+                ui.colored_label(error_color(ui), error);
+            },
+            Some(PreviewContent::Text(text)) => {
+                egui::ScrollArea::both().id_source("preview_text_scroll").show(ui, |ui| {
+                    // Read-only: edits to the borrowed text are discarded
+                    let mut text = text.as_str();
+                    ui.add(egui::TextEdit::multiline(&mut text).code_editor().desired_width(f32::INFINITY));
+                });
+            },
+            Some(PreviewContent::Image(image)) => {
+                let texture = self.texture.get_or_insert_with(|| ui.ctx().load_texture("object_preview", image.clone()));
+                
+                // Scale the image down to the pane, never up
+                let size = texture.size_vec2();
+                let scale = (ui.available_width() / size.x).min(1.0);
+                ui.label(format!("{} × {}", image.size[0], image.size[1]));
+                egui::ScrollArea::vertical().id_source("preview_image_scroll").show(ui, |ui| {
+                    ui.image(&*texture, size * scale);
+                });
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_preview_kind_from_content_type() {
+        assert_eq!(PreviewKind::from_content_type("image/png"), Some(PreviewKind::Image));
+        assert_eq!(PreviewKind::from_content_type("IMAGE/JPEG"), Some(PreviewKind::Image));
+        assert_eq!(PreviewKind::from_content_type("text/plain; charset=utf-8"), Some(PreviewKind::Text));
+        assert_eq!(PreviewKind::from_content_type("application/json"), Some(PreviewKind::Text));
+        assert_eq!(PreviewKind::from_content_type("application/ld+json"), Some(PreviewKind::Text));
+        assert_eq!(PreviewKind::from_content_type("application/octet-stream"), None);
+        assert_eq!(PreviewKind::from_content_type("image/tiff"), None);
+        assert_eq!(PreviewKind::from_content_type(""), None);
+    }
+    
+    #[test]
+    fn test_decode_text_preview() {
+        match PreviewContent::decode(PreviewKind::Text, b"hello\nworld") {
+            PreviewContent::Text(text) => assert_eq!(text, "hello\nworld"),
+            _ => panic!("expected a text preview"),
+        }
+        
+        match PreviewContent::decode(PreviewKind::Image, b"not an image") {
+            PreviewContent::Unavailable(_) => {},
+            _ => panic!("expected an undecodable image to be unavailable"),
+        }
+    }
+    
+    #[test]
+    fn test_decode_large_image_preview() {
+        let mut png = Cursor::new(Vec::new());
+        image::DynamicImage::new_rgba8(4096, 100).write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+        
+        // Scaled down to fit, keeping the aspect ratio
+        match PreviewContent::decode(PreviewKind::Image, png.get_ref()) {
+            PreviewContent::Image(image) => assert_eq!(image.size, [2048, 50]),
+            _ => panic!("expected an image preview"),
+        }
+    }
+}
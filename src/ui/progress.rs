@@ -1,19 +1,45 @@
 use eframe::egui;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use log::debug;
 
+use crate::aws::s3::S3ErrorHelper;
+use crate::config::transfer_history::{TransferHistory, TransferRecord};
+use crate::ui::failed_transfers::FailedTransfer;
+use crate::ui::utils::{error_color, success_color, warning_color};
+
+/// How often the aggregate transferred bytes are sampled for the throughput graph
+const THROUGHPUT_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How much throughput history the graph shows
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60);
+
+/// How far back the current rate and the time remaining look, so they follow changes
+/// in speed instead of averaging over the whole batch
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Minimum spacing of the samples the current rate is worked out from
+const RATE_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Component for displaying progress information
 #[derive(Default, Clone)]
 pub struct ProgressView {
     tracker: Arc<Mutex<ProgressTracker>>,
+    /// Set by the Cancel button; the running batch checks it between files and chunks
+    cancel: Arc<AtomicBool>,
 }
 
 /// Progress information for a file operation
 #[derive(Debug, Clone)]
 pub struct ProgressInfo {
+    /// Name shown in the list
     pub file_name: String,
+    /// Bucket and key the operation transfers, which identify the entry
+    pub bucket: String,
+    pub s3_key: String,
     pub operation_type: OperationType,
     pub bytes_transferred: u64,
     pub total_bytes: u64,
@@ -21,6 +47,13 @@ pub struct ProgressInfo {
     pub status: ProgressStatus,
     pub message: String,
     pub timestamp: Instant,
+    /// Local file the operation reads or writes, so it can be retried; empty for deletes
+    pub local_path: PathBuf,
+}
+
+/// Action requested from the progress view
+pub enum ProgressAction {
+    RetryFailed,
 }
 
 /// Type of operation being performed
@@ -39,20 +72,108 @@ pub enum ProgressStatus {
     InProgress,
     Completed,
     Failed(String),
+    /// Stopped or never started because the batch was cancelled or stopped
+    Cancelled,
+}
+
+/// Identifies a progress entry by its bucket and key, which are unique within a batch
+/// even where file names aren't
+type EntryKey = (String, String);
+
+fn entry_key(bucket: &str, s3_key: &str) -> EntryKey {
+    (bucket.to_string(), s3_key.to_string())
 }
 
 /// Tracker for progress information
 #[derive(Default, Clone)]
 pub struct ProgressTracker {
-    entries: HashMap<String, ProgressInfo>,
+    entries: HashMap<EntryKey, ProgressInfo>,
     total_operations: usize,
     completed_operations: usize,
     total_bytes: u64,
     transferred_bytes: u64,
     start_time: Option<Instant>,
+    skipped: Vec<(String, String)>,
+    throughput_samples: VecDeque<(Instant, u64)>,
+    /// Transferred bytes over the last few seconds as (time, bytes), oldest first
+    rate_samples: VecDeque<(Instant, u64)>,
+    scanning: bool,
+    scanned_files: usize,
+    scanned_bytes: u64,
+    /// Where finished transfers are recorded, if anywhere
+    history: Option<Arc<TransferHistory>>,
+    /// Finished transfers waiting to be written to the history
+    unwritten_history: Vec<TransferRecord>,
 }
 
 impl ProgressTracker {
+    /// Start the scan phase, which enumerates and sizes everything before transferring
+    pub fn start_scan(&mut self) {
+        debug!("Starting scan phase");
+        
+        self.start_sync(0, 0);
+        self.scanning = true;
+        self.add_entry(ProgressInfo {
+            file_name: "Scanning...".to_string(),
+            bucket: String::new(),
+            s3_key: String::new(),
+            operation_type: OperationType::Scan,
+            bytes_transferred: 0,
+            total_bytes: 0,
+            percentage: 0.0,
+            status: ProgressStatus::InProgress,
+            message: String::new(),
+            timestamp: Instant::now(),
+            local_path: PathBuf::new(),
+        });
+    }
+    
+    /// Record a file found during the scan phase
+    pub fn add_scanned(&mut self, size: u64) {
+        self.scanned_files += 1;
+        self.scanned_bytes += size;
+    }
+    
+    /// Show a step of the scan phase, such as listing the bucket, as a scan entry of its
+    /// own in place of the general one. Earlier steps are marked completed.
+    pub fn set_scan_step(&mut self, step: &str, description: String) {
+        self.entries.remove(&entry_key("", ""));
+        
+        for (key, entry) in self.entries.iter_mut() {
+            if entry.operation_type == OperationType::Scan && key.1 != step {
+                entry.percentage = 100.0;
+                entry.status = ProgressStatus::Completed;
+            }
+        }
+        
+        let entry = self.entries.entry(entry_key("", step)).or_insert_with(|| ProgressInfo {
+            file_name: String::new(),
+            bucket: String::new(),
+            s3_key: step.to_string(),
+            operation_type: OperationType::Scan,
+            bytes_transferred: 0,
+            total_bytes: 0,
+            percentage: 0.0,
+            status: ProgressStatus::InProgress,
+            message: String::new(),
+            timestamp: Instant::now(),
+            local_path: PathBuf::new(),
+        });
+        entry.file_name = description;
+    }
+    
+    /// End the scan phase, removing its entries. Starting the transfers does this too,
+    /// but a scan that fails never gets that far.
+    pub fn finish_scan(&mut self) {
+        self.entries.retain(|_, entry| entry.operation_type != OperationType::Scan);
+        self.scanning = false;
+    }
+    
+    /// Check whether the scan phase is running
+    pub fn is_scanning(&self) -> bool {
+        self.scanning
+    }
+    
     /// Start a new sync operation
     pub fn start_sync(&mut self, total_operations: usize, total_bytes: u64) {
         debug!("Starting sync operation with {} operations, {} bytes", total_operations, total_bytes);
@@ -63,12 +184,18 @@ impl ProgressTracker {
         self.transferred_bytes = 0;
         self.start_time = Some(Instant::now());
         self.entries.clear();
+        self.skipped.clear();
+        self.throughput_samples.clear();
+        self.rate_samples.clear();
+        self.scanning = false;
+        self.scanned_files = 0;
+        self.scanned_bytes = 0;
     }
     
     /// Add a new progress entry
     pub fn add_entry(&mut self, entry: ProgressInfo) {
-        debug!("Adding progress entry for {}: {:?}", entry.file_name, entry.operation_type);
-        self.entries.insert(entry.file_name.clone(), entry);
+        debug!("Adding progress entry for s3://{}/{}: {:?}", entry.bucket, entry.s3_key, entry.operation_type);
+        self.entries.insert(entry_key(&entry.bucket, &entry.s3_key), entry);
     }
     
     /// Get all progress entries
@@ -76,6 +203,84 @@ impl ProgressTracker {
         self.entries.values().cloned().collect()
     }
     
+    /// Set the local file of an entry once it is known
+    pub fn set_local_path(&mut self, bucket: &str, s3_key: &str, local_path: &Path) {
+        if let Some(entry) = self.entries.get_mut(&entry_key(bucket, s3_key)) {
+            entry.local_path = local_path.to_path_buf();
+        }
+    }
+    
+    /// Remove completed and cancelled entries, taking them out of the totals too so the
+    /// counts and percentage only cover what is left
+    pub fn clear_completed(&mut self) {
+        let finished: Vec<EntryKey> = self.entries.iter()
+            .filter(|(_, entry)| entry.operation_type != OperationType::Scan)
+            .filter(|(_, entry)| matches!(entry.status, ProgressStatus::Completed | ProgressStatus::Cancelled))
+            .map(|(key, _)| key.clone())
+            .collect();
+        
+        for key in &finished {
+            if let Some(entry) = self.entries.remove(key) {
+                self.total_bytes = self.total_bytes.saturating_sub(entry.total_bytes);
+                self.transferred_bytes = self.transferred_bytes.saturating_sub(entry.bytes_transferred);
+            }
+        }
+        
+        debug!("Cleared {} finished operations", finished.len());
+        self.total_operations = self.total_operations.saturating_sub(finished.len());
+        self.completed_operations = self.completed_operations.saturating_sub(finished.len());
+    }
+    
+    /// Check whether any operation has failed
+    pub fn has_failed(&self) -> bool {
+        self.entries.values().any(|entry| matches!(entry.status, ProgressStatus::Failed(_)))
+    }
+    
+    /// Get the failed operations with what is needed to retry them
+    pub fn failed_transfers(&self) -> Vec<FailedTransfer> {
+        self.entries.values()
+            .filter(|entry| entry.operation_type != OperationType::Scan)
+            .filter_map(|entry| match &entry.status {
+                ProgressStatus::Failed(message) => Some(FailedTransfer {
+                    operation: entry.operation_type.clone(),
+                    bucket: entry.bucket.clone(),
+                    key: entry.s3_key.clone(),
+                    local_path: entry.local_path.clone(),
+                    reason: S3ErrorHelper::failure_reason(message),
+                    error: message.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    /// Put a finished operation back in progress to retry it, returning its size. The
+    /// bytes it transferred before are taken out of the total.
+    pub fn restart_operation(&mut self, bucket: &str, s3_key: &str) -> Option<u64> {
+        let entry = self.entries.get_mut(&entry_key(bucket, s3_key))?;
+        
+        if matches!(entry.status, ProgressStatus::Completed | ProgressStatus::Failed(_) | ProgressStatus::Cancelled) {
+            self.completed_operations = self.completed_operations.saturating_sub(1);
+        }
+        Self::apply_delta(&mut self.transferred_bytes, entry.bytes_transferred, 0);
+        
+        entry.bytes_transferred = 0;
+        entry.percentage = 0.0;
+        entry.status = ProgressStatus::InProgress;
+        Some(entry.total_bytes)
+    }
+    
+    /// Record a file that was skipped and the reason why
+    pub fn add_skipped(&mut self, file_name: &str, reason: &str) {
+        debug!("Skipping {}: {}", file_name, reason);
+        self.skipped.push((file_name.to_string(), reason.to_string()));
+    }
+    
+    /// Get the skipped files and their reasons
+    pub fn skipped(&self) -> &[(String, String)] {
+        &self.skipped
+    }
+    
     /// Get the total number of operations
     pub fn total_operations(&self) -> usize {
         self.total_operations
@@ -105,28 +310,45 @@ impl ProgressTracker {
         (self.transferred_bytes as f32 / self.total_bytes as f32) * 100.0
     }
     
+    /// Mark an operation as started. Several can be in progress at once.
+    pub fn start_operation(&mut self, bucket: &str, s3_key: &str) {
+        if let Some(entry) = self.entries.get_mut(&entry_key(bucket, s3_key)) {
+            entry.status = ProgressStatus::InProgress;
+        }
+    }
+    
     /// Update a progress entry
-    pub fn update_entry(&mut self, file_name: &str, bytes_transferred: u64, percentage: f32) {
-        if let Some(entry) = self.entries.get_mut(file_name) {
-            // Calculate the delta in bytes transferred
-            let delta = bytes_transferred - entry.bytes_transferred;
+    pub fn update_entry(&mut self, bucket: &str, s3_key: &str, bytes_transferred: u64, percentage: f32) {
+        if let Some(entry) = self.entries.get_mut(&entry_key(bucket, s3_key)) {
+            let previous = entry.bytes_transferred;
             
             // Update the entry
             entry.bytes_transferred = bytes_transferred;
             entry.percentage = percentage;
             
             // Update the overall transferred bytes
-            self.transferred_bytes += delta;
+            Self::apply_delta(&mut self.transferred_bytes, previous, bytes_transferred);
+            self.record_rate_sample(Instant::now());
+        }
+    }
+    
+    /// Move the aggregate byte count from an entry's previous to its new value. Updates
+    /// can arrive out of order, and a retried transfer restarts at 0, so the new value
+    /// may be smaller than the previous one.
+    fn apply_delta(total: &mut u64, previous: u64, current: u64) {
+        if current >= previous {
+            *total = total.saturating_add(current - previous);
+        } else {
+            *total = total.saturating_sub(previous - current);
         }
     }
     
     /// Mark an operation as complete
-    pub fn complete_operation(&mut self, file_name: &str, bytes_transferred: u64) {
-        debug!("Completing operation for {}", file_name);
+    pub fn complete_operation(&mut self, bucket: &str, s3_key: &str, bytes_transferred: u64) {
+        debug!("Completing operation for s3://{}/{}", bucket, s3_key);
         
-        if let Some(entry) = self.entries.get_mut(file_name) {
-            // Calculate the delta in bytes transferred
-            let delta = bytes_transferred - entry.bytes_transferred;
+        if let Some(entry) = self.entries.get_mut(&entry_key(bucket, s3_key)) {
+            let previous = entry.bytes_transferred;
             
             // Update the entry
             entry.bytes_transferred = bytes_transferred;
@@ -134,24 +356,95 @@ impl ProgressTracker {
             entry.status = ProgressStatus::Completed;
             
             // Update the overall transferred bytes
-            self.transferred_bytes += delta;
+            Self::apply_delta(&mut self.transferred_bytes, previous, bytes_transferred);
+            self.record_rate_sample(Instant::now());
             
             // Update the completed operations count
             self.completed_operations += 1;
         }
+        
+        self.record_history(bucket, s3_key);
     }
     
     /// Mark an operation as failed
-    pub fn fail_operation(&mut self, file_name: &str, message: &str) {
-        debug!("Operation failed for {}: {}", file_name, message);
+    pub fn fail_operation(&mut self, bucket: &str, s3_key: &str, message: &str) {
+        debug!("Operation failed for s3://{}/{}: {}", bucket, s3_key, message);
         
-        if let Some(entry) = self.entries.get_mut(file_name) {
+        if let Some(entry) = self.entries.get_mut(&entry_key(bucket, s3_key)) {
             // Update the entry
             entry.status = ProgressStatus::Failed(message.to_string());
             
             // Update the completed operations count
             self.completed_operations += 1;
         }
+        
+        self.record_history(bucket, s3_key);
+    }
+    
+    /// Set where finished transfers are recorded
+    pub fn set_history(&mut self, history: Option<Arc<TransferHistory>>) {
+        self.history = history;
+    }
+    
+    /// Queue a finished operation for the transfer history
+    fn record_history(&mut self, bucket: &str, s3_key: &str) {
+        let entry = match (&self.history, self.entries.get(&entry_key(bucket, s3_key))) {
+            (Some(_), Some(entry)) => entry,
+            _ => return,
+        };
+        
+        let status = match &entry.status {
+            ProgressStatus::Completed => "Completed".to_string(),
+            ProgressStatus::Failed(message) => format!("Failed: {}", message),
+            _ => return,
+        };
+        
+        let operation = match entry.operation_type {
+            OperationType::Upload => "Upload",
+            OperationType::Download => "Download",
+            OperationType::Delete => "Delete",
+            OperationType::Scan => return,
+        };
+        
+        let record = TransferRecord::new(operation, &entry.bucket, &entry.s3_key, entry.bytes_transferred, status);
+        self.unwritten_history.push(record);
+    }
+    
+    /// Take the finished operations still to be written, along with the history they go to.
+    /// Writing touches the disk, so callers do it once they've let go of the tracker.
+    pub fn take_unwritten_history(&mut self) -> Option<(Arc<TransferHistory>, Vec<TransferRecord>)> {
+        if self.unwritten_history.is_empty() {
+            return None;
+        }
+        
+        let records = std::mem::take(&mut self.unwritten_history);
+        self.history.clone().map(|history| (history, records))
+    }
+    
+    /// Mark an operation that was stopped part way as cancelled
+    pub fn cancel_operation(&mut self, bucket: &str, s3_key: &str) {
+        debug!("Operation cancelled for s3://{}/{}", bucket, s3_key);
+        
+        if let Some(entry) = self.entries.get_mut(&entry_key(bucket, s3_key)) {
+            entry.status = ProgressStatus::Cancelled;
+            self.completed_operations += 1;
+        }
+    }
+    
+    /// Cancel every operation that hasn't started, returning how many were cancelled
+    pub fn cancel_pending(&mut self) -> usize {
+        let mut cancelled = 0;
+        
+        for entry in self.entries.values_mut() {
+            if entry.status == ProgressStatus::Pending {
+                entry.status = ProgressStatus::Cancelled;
+                cancelled += 1;
+            }
+        }
+        
+        debug!("Cancelled {} pending operations", cancelled);
+        self.completed_operations += cancelled;
+        cancelled
     }
     
     /// Get the elapsed time since the sync started
@@ -192,8 +485,8 @@ impl ProgressTracker {
         }
     }
     
-    /// Calculate the transfer rate in bytes per second
-    fn transfer_rate(&self) -> Option<u64> {
+    /// Calculate the average transfer rate since the batch started in bytes per second
+    fn average_rate(&self) -> Option<u64> {
         let elapsed = self.elapsed_time()?;
         
         if elapsed.as_secs() == 0 {
@@ -203,18 +496,55 @@ impl ProgressTracker {
         Some(self.transferred_bytes / elapsed.as_secs())
     }
     
-    /// Format the transfer rate as a human-readable string
-    fn format_transfer_rate(&self) -> String {
-        if let Some(rate) = self.transfer_rate() {
+    /// Record the transferred bytes for the current rate, dropping samples older than the rate window
+    fn record_rate_sample(&mut self, now: Instant) {
+        let due = self.rate_samples
+            .back()
+            .map_or(true, |(time, _)| now.duration_since(*time) >= RATE_SAMPLE_INTERVAL);
+        if due {
+            self.rate_samples.push_back((now, self.transferred_bytes));
+        }
+        
+        while let Some((time, _)) = self.rate_samples.front() {
+            if now.duration_since(*time) > RATE_WINDOW {
+                self.rate_samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+    
+    /// Calculate the transfer rate over the last few seconds in bytes per second. It is 0
+    /// once nothing has moved for the whole window, and the average over the first second.
+    fn recent_rate(&self, now: Instant) -> Option<u64> {
+        let (time, bytes) = match self.rate_samples.front() {
+            Some(sample) => *sample,
+            None => return self.average_rate(),
+        };
+        
+        let elapsed = now.duration_since(time);
+        if elapsed > RATE_WINDOW {
+            return Some(0);
+        }
+        if elapsed < Duration::from_secs(1) {
+            return self.average_rate();
+        }
+        
+        Some((self.transferred_bytes.saturating_sub(bytes) as f64 / elapsed.as_secs_f64()) as u64)
+    }
+    
+    /// Format a transfer rate as a human-readable string
+    fn format_rate(rate: Option<u64>) -> String {
+        if let Some(rate) = rate {
             format!("{}/s", Self::format_size(rate))
         } else {
             "N/A".to_string()
         }
     }
     
-    /// Estimate the time remaining
+    /// Estimate the time remaining from the current rate
     fn estimate_time_remaining(&self) -> Option<std::time::Duration> {
-        let rate = self.transfer_rate()?;
+        let rate = self.recent_rate(Instant::now())?;
         
         if rate == 0 || self.transferred_bytes >= self.total_bytes {
             return None;
@@ -239,6 +569,54 @@ impl ProgressTracker {
     pub fn is_complete(&self) -> bool {
         self.completed_operations >= self.total_operations && self.total_operations > 0
     }
+    
+    /// Check whether an upload or download batch is scanning or still has operations to finish
+    fn is_transferring(&self) -> bool {
+        let transfers = self.entries.values()
+            .any(|entry| matches!(entry.operation_type, OperationType::Upload | OperationType::Download));
+        
+        self.scanning || (transfers && !self.is_complete())
+    }
+    
+    /// Record the aggregate transferred bytes if the sample interval has passed,
+    /// dropping samples older than the throughput window
+    fn sample_throughput(&mut self) {
+        let now = Instant::now();
+        
+        let due = self.throughput_samples
+            .back()
+            .map_or(true, |(time, _)| now.duration_since(*time) >= THROUGHPUT_SAMPLE_INTERVAL);
+        if due {
+            self.throughput_samples.push_back((now, self.transferred_bytes));
+        }
+        
+        while let Some((time, _)) = self.throughput_samples.front() {
+            if now.duration_since(*time) > THROUGHPUT_WINDOW {
+                self.throughput_samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+    
+    /// Throughput between consecutive samples as (seconds ago, bytes per second) points
+    fn throughput_points(&self) -> Vec<(f64, f64)> {
+        let now = Instant::now();
+        
+        self.throughput_samples
+            .iter()
+            .zip(self.throughput_samples.iter().skip(1))
+            .filter_map(|((prev_time, prev_bytes), (time, bytes))| {
+                let seconds = time.duration_since(*prev_time).as_secs_f64();
+                if seconds <= 0.0 {
+                    return None;
+                }
+                
+                let rate = bytes.saturating_sub(*prev_bytes) as f64 / seconds;
+                Some((-now.duration_since(*time).as_secs_f64(), rate))
+            })
+            .collect()
+    }
 }
 
 impl ProgressView {
@@ -246,15 +624,19 @@ impl ProgressView {
     pub fn new() -> Self {
         Self {
             tracker: Arc::new(Mutex::new(ProgressTracker::default())),
+            cancel: Arc::new(AtomicBool::new(false)),
         }
     }
     
-    /// Render the progress view UI
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    /// Render the progress view UI, returning any requested action
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<ProgressAction> {
+        let mut action = None;
+        
         ui.heading("Transfer Progress");
         
         // Get a lock on the tracker
-        let tracker = self.tracker.lock().unwrap();
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.sample_throughput();
         
         // Overall progress
         let total_ops = tracker.total_operations();
@@ -272,15 +654,77 @@ impl ProgressView {
                 ui.separator();
             }
             
-            ui.label(format!("Rate: {}", tracker.format_transfer_rate()));
+            ui.label(format!("Rate: {}", ProgressTracker::format_rate(tracker.recent_rate(Instant::now()))))
+                .on_hover_text("Over the last few seconds");
+            ui.separator();
+            ui.label(format!("Average: {}", ProgressTracker::format_rate(tracker.average_rate())));
             ui.separator();
             ui.label(format!("Remaining: {}", tracker.format_time_remaining()));
+            
+            // Deletes run as a single request, so only transfers can be cancelled
+            let running = tracker.is_transferring();
+            if self.is_cancelled() {
+                if running {
+                    ui.separator();
+                    ui.label("Cancelling...");
+                }
+            } else if running {
+                ui.separator();
+                if ui.button("Cancel").on_hover_text("Stop the transfers in progress and skip the remaining files").clicked() {
+                    self.cancel();
+                }
+            }
         });
         
+        ui.horizontal(|ui| {
+            if ui.button("Clear Completed").on_hover_text("Remove completed and cancelled files from the list").clicked() {
+                tracker.clear_completed();
+            }
+            
+            let can_retry = tracker.has_failed() && !tracker.is_transferring();
+            if ui.add_enabled(can_retry, egui::Button::new("Retry Failed")).clicked() {
+                action = Some(ProgressAction::RetryFailed);
+            }
+        });
+        
+        // Totals aren't known until the scan phase finishes
+        if tracker.is_scanning() {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new());
+                // Syncs count what they find in their scan entries instead
+                if tracker.scanned_files == 0 {
+                    ui.label("Scanning...");
+                } else {
+                    ui.label(format!(
+                        "Scanning... {} files ({}) found",
+                        tracker.scanned_files,
+                        ProgressTracker::format_size(tracker.scanned_bytes)
+                    ));
+                }
+            });
+        }
+        
         // Progress bar
         let progress = percentage / 100.0;
         ui.add(egui::ProgressBar::new(progress).show_percentage());
         
+        // Throughput over the last minute
+        let points = tracker.throughput_points();
+        let line = egui::plot::Line::new(egui::plot::Values::from_values_iter(
+            points.into_iter().map(|(x, y)| egui::plot::Value::new(x, y)),
+        ));
+        egui::plot::Plot::new("throughput_plot")
+            .height(80.0)
+            .include_x(-THROUGHPUT_WINDOW.as_secs_f64())
+            .include_x(0.0)
+            .include_y(0.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_boxed_zoom(false)
+            .x_axis_formatter(|x, _| format!("{:.0}s", x))
+            .y_axis_formatter(|y, _| format!("{}/s", ProgressTracker::format_size(y.max(0.0) as u64)))
+            .show(ui, |plot_ui| plot_ui.line(line));
+        
         ui.separator();
         
         // File list
@@ -328,20 +772,64 @@ impl ProgressView {
                         match entry.status {
                             ProgressStatus::Pending => ui.label("Pending"),
                             ProgressStatus::InProgress => ui.label("In Progress"),
-                            ProgressStatus::Completed => ui.label(egui::RichText::new("Completed").color(egui::Color32::GREEN)),
-                            ProgressStatus::Failed(ref msg) => ui.label(egui::RichText::new(format!("Failed: {}", msg)).color(egui::Color32::RED)),
+                            ProgressStatus::Completed => ui.label(egui::RichText::new("Completed").color(success_color(ui))),
+                            ProgressStatus::Failed(ref msg) => ui.label(egui::RichText::new(format!("Failed: {}", msg)).color(error_color(ui))),
+                            ProgressStatus::Cancelled => ui.label(egui::RichText::new("Cancelled").color(warning_color(ui))),
                         };
                     });
                     
                     ui.separator();
                 }
             }
+            
+            // Skipped files, kept separate from failures
+            let skipped = tracker.skipped();
+            if !skipped.is_empty() {
+                ui.collapsing(format!("Skipped ({})", skipped.len()), |ui| {
+                    for (file_name, reason) in skipped {
+                        ui.horizontal(|ui| {
+                            ui.label(file_name);
+                            ui.label(egui::RichText::new(reason).color(egui::Color32::GRAY));
+                        });
+                    }
+                });
+            }
         });
+        
+        action
     }
     
-    /// Start a new sync operation
+    /// Start the scan phase of a new batch
+    pub fn start_scan(&self) {
+        let mut tracker = self.tracker.lock().unwrap();
+        self.cancel.store(false, Ordering::SeqCst);
+        tracker.start_scan();
+    }
+    
+    /// Record a file found during the scan phase
+    pub fn add_scanned(&self, size: u64) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.add_scanned(size);
+    }
+    
+    /// Show a step of the scan phase as a scan entry of its own
+    pub fn set_scan_step(&self, step: &str, description: String) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.set_scan_step(step, description);
+    }
+    
+    /// End the scan phase, removing its entries
+    pub fn finish_scan(&self) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.finish_scan();
+    }
+    
+    /// Start a new sync operation. A batch that was scanned keeps any cancel requested during the scan.
     pub fn start_sync(&self, total_operations: usize, total_bytes: u64) {
         let mut tracker = self.tracker.lock().unwrap();
+        if !tracker.is_scanning() {
+            self.cancel.store(false, Ordering::SeqCst);
+        }
         tracker.start_sync(total_operations, total_bytes);
     }
     
@@ -351,22 +839,103 @@ impl ProgressView {
         tracker.add_entry(entry);
     }
     
+    /// Set the local file of an entry once it is known
+    pub fn set_local_path(&self, bucket: &str, s3_key: &str, local_path: &Path) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.set_local_path(bucket, s3_key, local_path);
+    }
+    
+    /// Get the failed operations with what is needed to retry them
+    pub fn failed_transfers(&self) -> Vec<FailedTransfer> {
+        let tracker = self.tracker.lock().unwrap();
+        tracker.failed_transfers()
+    }
+    
+    /// Put a finished operation back in progress to retry it, returning its size
+    pub fn restart_operation(&self, bucket: &str, s3_key: &str) -> Option<u64> {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.restart_operation(bucket, s3_key)
+    }
+    
+    /// Record a skipped file
+    pub fn add_skipped(&self, file_name: &str, reason: &str) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.add_skipped(file_name, reason);
+    }
+    
+    /// Mark an operation as started
+    pub fn start_operation(&self, bucket: &str, s3_key: &str) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.start_operation(bucket, s3_key);
+    }
+    
     /// Update a progress entry
-    pub fn update_entry(&self, file_name: &str, bytes_transferred: u64, percentage: f32) {
+    pub fn update_entry(&self, bucket: &str, s3_key: &str, bytes_transferred: u64, percentage: f32) {
         let mut tracker = self.tracker.lock().unwrap();
-        tracker.update_entry(file_name, bytes_transferred, percentage);
+        tracker.update_entry(bucket, s3_key, bytes_transferred, percentage);
     }
     
     /// Mark an operation as complete
-    pub fn complete_operation(&self, file_name: &str, bytes_transferred: u64) {
-        let mut tracker = self.tracker.lock().unwrap();
-        tracker.complete_operation(file_name, bytes_transferred);
+    pub fn complete_operation(&self, bucket: &str, s3_key: &str, bytes_transferred: u64) {
+        let unwritten = {
+            let mut tracker = self.tracker.lock().unwrap();
+            tracker.complete_operation(bucket, s3_key, bytes_transferred);
+            tracker.take_unwritten_history()
+        };
+        Self::write_history(unwritten);
     }
     
     /// Mark an operation as failed
-    pub fn fail_operation(&self, file_name: &str, message: &str) {
+    pub fn fail_operation(&self, bucket: &str, s3_key: &str, message: &str) {
+        let unwritten = {
+            let mut tracker = self.tracker.lock().unwrap();
+            tracker.fail_operation(bucket, s3_key, message);
+            tracker.take_unwritten_history()
+        };
+        Self::write_history(unwritten);
+    }
+    
+    /// Append finished operations to the history, without holding the tracker lock
+    fn write_history(unwritten: Option<(Arc<TransferHistory>, Vec<TransferRecord>)>) {
+        if let Some((history, records)) = unwritten {
+            for record in &records {
+                history.append(record);
+            }
+        }
+    }
+    
+    /// Record finished transfers in a history
+    pub fn set_history(&self, history: Option<Arc<TransferHistory>>) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.set_history(history);
+    }
+    
+    /// Mark an operation that was stopped part way as cancelled
+    pub fn cancel_operation(&self, bucket: &str, s3_key: &str) {
+        let mut tracker = self.tracker.lock().unwrap();
+        tracker.cancel_operation(bucket, s3_key);
+    }
+    
+    /// Request that the running batch stops
+    pub fn cancel(&self) {
+        debug!("Cancel requested");
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+    
+    /// Check whether the running batch has been asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+    
+    /// Get the token transfers check to see whether the batch has been cancelled
+    pub fn cancel_token(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+    
+    /// Cancel every operation that hasn't started
+    pub fn cancel_pending(&self) -> usize {
         let mut tracker = self.tracker.lock().unwrap();
-        tracker.fail_operation(file_name, message);
+        tracker.cancel_pending()
     }
     
     /// Check if all operations are complete
@@ -379,70 +948,324 @@ impl ProgressView {
     pub fn clone(&self) -> Self {
         Self {
             tracker: self.tracker.clone(),
+            cancel: self.cancel.clone(),
         }
     }
     
-    /// Add a file to track
-    pub fn add_file(&self, file_name: &str, size: u64) {
-        let entry = ProgressInfo {
+    /// Update progress for a file in a bucket. Transfers report their key as the file name.
+    pub fn update_progress(&self, bucket: &str, progress: crate::aws::transfer::TransferProgress) {
+        self.update_entry(
+            bucket,
+            &progress.file_name,
+            progress.bytes_transferred,
+            progress.percentage
+        );
+    }
+    
+    /// Show the progress view as a modal overlay, returning whether it is still open
+    /// and any requested action
+    pub fn show(&self, ctx: &egui::Context) -> (bool, Option<ProgressAction>) {
+        let mut open = true;
+        let mut action = None;
+        
+        egui::Window::new("Transfer Progress")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                let mut view = self.clone();
+                action = view.ui(ui);
+            });
+        
+        (open, action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn entry(file_name: &str, total_bytes: u64) -> ProgressInfo {
+        ProgressInfo {
             file_name: file_name.to_string(),
-            operation_type: OperationType::Upload, // Default to upload
+            bucket: "bucket".to_string(),
+            s3_key: file_name.to_string(),
+            operation_type: OperationType::Upload,
             bytes_transferred: 0,
-            total_bytes: size,
+            total_bytes,
             percentage: 0.0,
             status: ProgressStatus::Pending,
             message: String::new(),
             timestamp: Instant::now(),
-        };
+            local_path: PathBuf::from(format!("/tmp/{}", file_name)),
+        }
+    }
+    
+    #[test]
+    fn test_cancel_pending() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(3, 300);
+        tracker.add_entry(entry("a", 100));
+        tracker.add_entry(entry("b", 100));
+        tracker.add_entry(entry("c", 100));
+        
+        tracker.fail_operation("bucket", "a", "AccessDenied");
+        assert!(!tracker.is_complete());
         
-        self.add_entry(entry);
+        assert_eq!(tracker.cancel_pending(), 2);
+        assert!(tracker.is_complete());
+        
+        let entries = tracker.entries();
+        let status = |name: &str| entries.iter().find(|e| e.file_name == name).unwrap().status.clone();
+        assert_eq!(status("a"), ProgressStatus::Failed("AccessDenied".to_string()));
+        assert_eq!(status("b"), ProgressStatus::Cancelled);
+        assert_eq!(status("c"), ProgressStatus::Cancelled);
     }
     
-    /// Mark a file as complete
-    pub fn complete_file(&self, file_name: &str) {
-        let mut tracker = self.tracker.lock().unwrap();
-        if let Some(entry) = tracker.entries.get_mut(file_name) {
-            // Update the entry directly
-            entry.bytes_transferred = entry.total_bytes;
-            entry.percentage = 100.0;
-            entry.status = ProgressStatus::Completed;
-            
-            // Update the completed operations count
-            tracker.completed_operations += 1;
-        }
+    #[test]
+    fn test_concurrent_operations() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(3, 300);
+        tracker.add_entry(entry("a", 100));
+        tracker.add_entry(entry("b", 100));
+        tracker.add_entry(entry("c", 100));
+        
+        // Two files run at once and the first to start finishes last
+        tracker.start_operation("bucket", "a");
+        tracker.start_operation("bucket", "b");
+        tracker.update_entry("bucket", "a", 50, 50.0);
+        tracker.update_entry("bucket", "b", 100, 100.0);
+        tracker.complete_operation("bucket", "b", 100);
+        assert_eq!(tracker.transferred_bytes(), 150);
+        
+        // The batch stops: the file in flight is kept, the one not started is cancelled
+        assert_eq!(tracker.cancel_pending(), 1);
+        assert!(!tracker.is_complete());
+        tracker.complete_operation("bucket", "a", 100);
+        assert!(tracker.is_complete());
+        
+        let entries = tracker.entries();
+        let status = |name: &str| entries.iter().find(|e| e.file_name == name).unwrap().status.clone();
+        assert_eq!(status("a"), ProgressStatus::Completed);
+        assert_eq!(status("c"), ProgressStatus::Cancelled);
     }
     
-    /// Mark a file as failed
-    pub fn fail_file(&self, file_name: &str) {
-        self.fail_operation(file_name, "Transfer failed");
+    #[test]
+    fn test_cancel_operation() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(2, 200);
+        tracker.add_entry(entry("a", 100));
+        tracker.add_entry(entry("b", 100));
+        
+        tracker.start_operation("bucket", "a");
+        tracker.update_entry("bucket", "a", 40, 40.0);
+        tracker.cancel_operation("bucket", "a");
+        tracker.complete_operation("bucket", "b", 100);
+        
+        // Cancelled operations count as finished
+        assert!(tracker.is_complete());
+        assert_eq!(tracker.completed_operations(), 2);
+        
+        let entries = tracker.entries();
+        assert_eq!(entries.iter().find(|e| e.file_name == "a").unwrap().status, ProgressStatus::Cancelled);
     }
     
-    /// Mark the sync operation as complete
-    pub fn complete_sync(&self) {
-        // Nothing to do here, just a placeholder for the API
+    #[test]
+    fn test_records_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = Arc::new(TransferHistory::open_in(dir.path()));
+        
+        let mut tracker = ProgressTracker::default();
+        tracker.set_history(Some(history.clone()));
+        tracker.start_sync(3, 300);
+        let photo = |name: &str| ProgressInfo { bucket: "photos".to_string(), ..entry(name, 100) };
+        tracker.add_entry(photo("a"));
+        tracker.add_entry(photo("b"));
+        tracker.add_entry(photo("c"));
+        
+        tracker.complete_operation("photos", "a", 100);
+        tracker.fail_operation("photos", "b", "AccessDenied");
+        tracker.cancel_operation("photos", "c");
+        
+        // Nothing touches the disk until the records are taken out of the tracker
+        assert!(history.load().unwrap().is_empty());
+        ProgressView::write_history(tracker.take_unwritten_history());
+        assert!(tracker.take_unwritten_history().is_none());
+        
+        // Cancelled operations aren't finished transfers
+        let records = history.load().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].bucket.as_str(), records[0].key.as_str(), records[0].bytes), ("photos", "a", 100));
+        assert_eq!(records[0].operation, "Upload");
+        assert_eq!(records[1].status, "Failed: AccessDenied");
     }
     
-    /// Update progress for a file
-    pub fn update_progress(&self, progress: crate::aws::transfer::TransferProgress) {
-        self.update_entry(
-            &progress.file_name,
-            progress.bytes_transferred,
-            progress.percentage
-        );
+    #[test]
+    fn test_out_of_order_updates() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(2, 200);
+        tracker.add_entry(entry("a", 100));
+        tracker.add_entry(entry("b", 100));
+        
+        tracker.update_entry("bucket", "a", 60, 60.0);
+        tracker.update_entry("bucket", "b", 30, 30.0);
+        assert_eq!(tracker.transferred_bytes(), 90);
+        
+        // A stale update arrives after a newer one
+        tracker.update_entry("bucket", "a", 40, 40.0);
+        assert_eq!(tracker.transferred_bytes(), 70);
+        
+        // A retry restarts the file from zero
+        tracker.update_entry("bucket", "b", 0, 0.0);
+        assert_eq!(tracker.transferred_bytes(), 40);
+        
+        tracker.complete_operation("bucket", "a", 100);
+        tracker.complete_operation("bucket", "b", 100);
+        assert_eq!(tracker.transferred_bytes(), 200);
+        assert!(tracker.is_complete());
     }
     
-    /// Show the progress view as a modal overlay
-    pub fn show(&self, ctx: &egui::Context) {
-        let mut open = true;
+    #[test]
+    fn test_cancel_batch() {
+        let view = ProgressView::new();
+        view.start_scan();
         
-        egui::Window::new("Transfer Progress")
-            .open(&mut open)
-            .collapsible(false)
-            .resizable(true)
-            .default_size([600.0, 400.0])
-            .show(ctx, |ui| {
-                let mut view = self.clone();
-                view.ui(ui);
-            });
+        // A cancel during the scan carries over to the transfers
+        view.cancel();
+        view.start_sync(2, 200);
+        assert!(view.is_cancelled());
+        assert!(view.cancel_token().load(Ordering::SeqCst));
+        
+        view.add_entry(entry("a", 100));
+        view.add_entry(entry("b", 100));
+        view.start_operation("bucket", "a");
+        view.cancel_operation("bucket", "a");
+        assert_eq!(view.cancel_pending(), 1);
+        assert!(view.is_complete());
+        
+        // The next batch starts afresh
+        view.start_scan();
+        assert!(!view.is_cancelled());
+    }
+    
+    #[test]
+    fn test_scan_phase() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_scan();
+        assert!(tracker.is_scanning());
+        assert_eq!(tracker.entries().len(), 1);
+        
+        tracker.add_scanned(100);
+        tracker.add_scanned(50);
+        
+        // Transfers start with the scanned totals and the scan entry is gone
+        tracker.start_sync(2, 150);
+        assert!(!tracker.is_scanning());
+        assert!(tracker.entries().is_empty());
+        assert_eq!(tracker.total_bytes(), 150);
+    }
+    
+    #[test]
+    fn test_scan_steps() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_scan();
+        
+        // Steps replace the general scan entry, one entry each
+        tracker.set_scan_step("local", "Scanning local folder... 0 files found".to_string());
+        tracker.set_scan_step("local", "Scanning local folder... 100 files found".to_string());
+        assert_eq!(tracker.entries().len(), 1);
+        assert_eq!(tracker.entries()[0].file_name, "Scanning local folder... 100 files found");
+        
+        tracker.set_scan_step("remote", "Listing s3://bucket/... 0 objects found".to_string());
+        let entries = tracker.entries();
+        assert_eq!(entries.len(), 2);
+        let local = entries.iter().find(|entry| entry.s3_key == "local").unwrap();
+        assert_eq!(local.status, ProgressStatus::Completed);
+        
+        // Clearing completed entries leaves the scan and the totals alone
+        tracker.clear_completed();
+        assert_eq!(tracker.entries().len(), 2);
+        
+        // A scan that fails still removes its entries
+        tracker.finish_scan();
+        assert!(!tracker.is_scanning());
+        assert!(tracker.entries().is_empty());
+    }
+    
+    #[test]
+    fn test_clear_completed() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(3, 300);
+        tracker.add_entry(entry("a", 100));
+        tracker.add_entry(entry("b", 100));
+        tracker.add_entry(entry("c", 100));
+        
+        tracker.complete_operation("bucket", "a", 100);
+        tracker.update_entry("bucket", "b", 40, 40.0);
+        tracker.fail_operation("bucket", "b", "AWS S3 error - Type: AccessDenied, Raw: service error");
+        tracker.cancel_operation("bucket", "c");
+        
+        // Only the failure is left, and the totals only count it
+        tracker.clear_completed();
+        assert_eq!(tracker.entries().len(), 1);
+        assert_eq!((tracker.total_operations(), tracker.completed_operations()), (1, 1));
+        assert_eq!((tracker.total_bytes(), tracker.transferred_bytes()), (100, 40));
+        
+        let failed = tracker.failed_transfers();
+        assert_eq!(failed.len(), 1);
+        assert_eq!((failed[0].bucket.as_str(), failed[0].key.as_str()), ("bucket", "b"));
+        assert_eq!(failed[0].local_path, PathBuf::from("/tmp/b"));
+        assert_eq!(failed[0].reason, "Access denied");
+        
+        // Retrying starts the file again from zero
+        assert_eq!(tracker.restart_operation("bucket", "b"), Some(100));
+        assert!(!tracker.has_failed());
+        assert_eq!((tracker.completed_operations(), tracker.transferred_bytes()), (0, 0));
+        tracker.complete_operation("bucket", "b", 100);
+        assert!(tracker.is_complete());
+        assert_eq!(tracker.restart_operation("bucket", "missing"), None);
+    }
+    
+    #[test]
+    fn test_entries_keyed_by_bucket_and_key() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(2, 200);
+        
+        // The same key in two buckets, and two entries showing the same name
+        tracker.add_entry(ProgressInfo { bucket: "one".to_string(), ..entry("a/report.txt", 100) });
+        tracker.add_entry(ProgressInfo { bucket: "two".to_string(), ..entry("a/report.txt", 100) });
+        assert_eq!(tracker.entries().len(), 2);
+        
+        tracker.complete_operation("one", "a/report.txt", 100);
+        assert_eq!(tracker.completed_operations(), 1);
+        let entries = tracker.entries();
+        let status = |bucket: &str| entries.iter().find(|e| e.bucket == bucket).unwrap().status.clone();
+        assert_eq!(status("one"), ProgressStatus::Completed);
+        assert_eq!(status("two"), ProgressStatus::Pending);
+    }
+    
+    #[test]
+    fn test_recent_rate() {
+        let mut tracker = ProgressTracker::default();
+        tracker.start_sync(1, 100_000);
+        assert_eq!(tracker.recent_rate(Instant::now()), None);
+        
+        // A fast start
+        let start = Instant::now();
+        tracker.record_rate_sample(start);
+        tracker.transferred_bytes = 50_000;
+        tracker.record_rate_sample(start + Duration::from_secs(1));
+        
+        // ...followed by a slow network, which is all the rate reflects
+        tracker.transferred_bytes = 51_000;
+        tracker.record_rate_sample(start + Duration::from_secs(10));
+        tracker.transferred_bytes = 53_000;
+        tracker.record_rate_sample(start + Duration::from_secs(12));
+        assert_eq!(tracker.rate_samples.len(), 2);
+        assert_eq!(tracker.recent_rate(start + Duration::from_secs(12)), Some(1000));
+        
+        // Nothing moving for the whole window
+        assert_eq!(tracker.recent_rate(start + Duration::from_secs(20)), Some(0));
     }
 }
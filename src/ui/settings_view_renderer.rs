@@ -1,9 +1,13 @@
 use eframe::egui;
 use log::error;
+use std::time::Duration;
 // use std::sync::Arc; // Unused
 
+use crate::aws::auth::AwsAuth;
 use crate::config::credentials::CredentialManager;
-use crate::ui::app_state::{AppState, CurrentView};
+use crate::ui::app_state::{AppState, CurrentView, StatusMessage};
+use crate::ui::aws_operations::AwsOperations;
+use crate::ui::settings::SettingsTransfer;
 
 /// Renderer for the settings view of the application
 pub struct SettingsViewRenderer;
@@ -16,31 +20,145 @@ impl SettingsViewRenderer {
             // Settings applied
             let settings = app_state.settings_view.get_settings();
             
-            // Save credentials if requested
+            // Save credentials if requested: the secret key goes to the keyring and the
+            // access key ID and region are saved with the rest of the configuration.
+            // Otherwise nothing is kept, including credentials saved before.
             if settings.save_credentials {
-                CredentialManager::save_credentials(
-                    &settings.aws_access_key,
-                    &settings.aws_secret_key,
-                    &settings.aws_region,
-                ).unwrap_or_else(|e| {
-                    error!("Failed to save credentials: {}", e);
-                    app_state.set_status_error(&format!("Failed to save credentials: {}", e));
-                });
+                app_state.save_credentials(&settings.aws_access_key, &settings.aws_secret_key);
+            } else if CredentialManager::has_credentials() || !app_state.config.access_key_id.is_empty() {
+                if let Err(e) = CredentialManager::clear_credentials() {
+                    error!("Failed to clear credentials: {}", e);
+                    app_state.set_status_error(&format!("Failed to clear credentials: {}", e));
+                }
+                app_state.config.access_key_id.clear();
             }
+            app_state.save_config();
             
             // Update AWS auth
-            let aws_auth = app_state.aws_auth.clone();
             let access_key = settings.aws_access_key.clone();
             let secret_key = settings.aws_secret_key.clone();
+            let session_token = Some(settings.aws_session_token.clone());
             let region = settings.aws_region.clone();
+            let endpoint_url = app_state.settings_view.endpoint_url();
+            let profile = app_state.settings_view.aws_profile();
+            let refresh_margin = Duration::from_secs(settings.credential_refresh_minutes as u64 * 60);
+            let app_retries = app_state.settings_view.retry_policy().max_retries > 0;
             
-            app_state.rt.spawn(async move {
-                let mut auth = aws_auth.lock().await;
-                auth.set_credentials(access_key, secret_key, region);
+            AwsOperations::update_auth(app_state, "applying settings", move |auth| {
+                auth.set_credentials(access_key, secret_key, session_token, region);
+                auth.set_endpoint_url(endpoint_url);
+                auth.set_profile(profile);
+                auth.set_refresh_margin(refresh_margin);
+                auth.set_app_retries(app_retries);
             });
             
+            let tx = app_state.status_tx.clone();
+            app_state.sync_scheduler.set_interval(&app_state.rt, settings.sync_interval, tx);
+            app_state.rate_limiter.set_limit(settings.bandwidth_limit);
+            
             // Return to main view
             app_state.current_view = CurrentView::Main;
         }
+        
+        if let Some(transfer) = app_state.settings_view.take_transfer() {
+            Self::handle_transfer(app_state, transfer);
+        }
+        
+        if app_state.settings_view.take_clear_credentials() {
+            Self::clear_credentials(app_state);
+        }
+        
+        if app_state.settings_view.take_credential_test() {
+            Self::test_credentials(app_state);
+        }
+    }
+    
+    /// Test the credentials entered in the settings with their own client, leaving the
+    /// connection in use untouched until the settings are applied
+    fn test_credentials(app_state: &mut AppState) {
+        let settings = app_state.settings_view.get_settings();
+        let mut auth = AwsAuth::new();
+        auth.set_credentials(
+            settings.aws_access_key.trim().to_string(),
+            settings.aws_secret_key.trim().to_string(),
+            Some(settings.aws_session_token.clone()),
+            settings.aws_region.clone(),
+        );
+        auth.set_endpoint_url(app_state.settings_view.endpoint_url());
+        auth.set_profile(app_state.settings_view.aws_profile());
+        
+        let tx = app_state.status_tx.clone();
+        app_state.set_status_info("Testing credentials...");
+        
+        app_state.rt.spawn(async move {
+            let message = match auth.test_credentials().await {
+                Ok(()) => StatusMessage::Info("Credentials are valid".to_string()),
+                Err(e) => StatusMessage::Error(e.to_string()),
+            };
+            let _ = tx.send(message);
+        });
+    }
+    
+    /// Remove the saved credentials from the keyring and the configuration, and sign out
+    fn clear_credentials(app_state: &mut AppState) {
+        if let Err(e) = CredentialManager::clear_credentials() {
+            error!("Failed to clear credentials: {}", e);
+            app_state.set_status_error(&format!("Failed to clear credentials: {}", e));
+            return;
+        }
+        
+        app_state.config.access_key_id.clear();
+        app_state.save_config();
+        app_state.settings_view.clear_credential_fields();
+        app_state.credentials_expiry = None;
+        
+        // Drop the credentials and the clients built with them
+        AwsOperations::update_auth(app_state, "clearing credentials", |auth| auth.clear_credentials());
+        
+        app_state.set_status_info("Saved credentials cleared. Enter new credentials or choose a profile to reconnect.");
+    }
+    
+    /// Export or import the settings file
+    fn handle_transfer(app_state: &mut AppState, transfer: SettingsTransfer) {
+        let mut settings = app_state.config.settings.clone();
+        app_state.settings_view.apply_to(&mut settings);
+        settings.update_from_sync_folders(&app_state.folder_list.folders);
+        
+        match transfer {
+            SettingsTransfer::Export(path) => match settings.export_to(&path) {
+                Ok(()) => app_state.set_status_info(&format!("Exported settings to {}", path.display())),
+                Err(e) => {
+                    error!("Failed to export settings: {}", e);
+                    app_state.set_status_error(&format!("Failed to export settings: {}", e));
+                }
+            },
+            SettingsTransfer::Import(path) => match settings.import_from(&path) {
+                Ok(()) => {
+                    app_state.settings_view.load_from(&settings);
+                    
+                    // Add imported folders that aren't in the list yet
+                    for folder in settings.to_sync_folders() {
+                        match app_state.folder_list.folders.iter_mut().find(|f| f.path == folder.path) {
+                            Some(existing) => {
+                                existing.enabled = folder.enabled;
+                                existing.bucket = folder.bucket;
+                                existing.prefix = folder.prefix;
+                            },
+                            None => app_state.folder_list.folders.push(folder),
+                        }
+                    }
+                    
+                    // Keep the imported bucket and prefix mappings
+                    app_state.config.settings.folders = settings.folders;
+                    app_state.save_config();
+                    
+                    app_state.set_status_info(&format!("Imported settings from {}", path.display()));
+                },
+                Err(e) => {
+                    error!("Failed to import settings: {}", e);
+                    app_state.set_status_error(&format!("Failed to import settings: {}", e));
+                }
+            },
+        }
     }
 }
@@ -0,0 +1,80 @@
+use eframe::egui;
+
+/// Action triggered by a keyboard shortcut in the main view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shortcut {
+    Refresh,
+    SelectAll,
+    Delete,
+    Upload,
+    Download,
+}
+
+/// The shortcuts and what they do, as listed in the About dialog.
+/// egui has no function keys, so refresh is Ctrl+R rather than F5.
+pub const SHORTCUTS: [(Shortcut, &str, &str); 5] = [
+    (Shortcut::Refresh, "Ctrl+R", "Refresh the bucket's objects, or the bucket list"),
+    (Shortcut::SelectAll, "Ctrl+A", "Select all visible objects"),
+    (Shortcut::Delete, "Delete", "Delete the selected objects, after confirming"),
+    (Shortcut::Upload, "Ctrl+U", "Upload the checked files"),
+    (Shortcut::Download, "Ctrl+D", "Download the selected objects"),
+];
+
+impl Shortcut {
+    /// Get the shortcut a key press triggers, if any. Ctrl is Cmd on macOS.
+    pub fn from_key(key: egui::Key, modifiers: egui::Modifiers) -> Option<Self> {
+        if modifiers.alt || modifiers.shift {
+            return None;
+        }
+        
+        match (key, modifiers.command) {
+            (egui::Key::R, true) => Some(Shortcut::Refresh),
+            (egui::Key::A, true) => Some(Shortcut::SelectAll),
+            (egui::Key::U, true) => Some(Shortcut::Upload),
+            (egui::Key::D, true) => Some(Shortcut::Download),
+            (egui::Key::Delete, false) => Some(Shortcut::Delete),
+            _ => None,
+        }
+    }
+    
+    /// Get the shortcuts pressed this frame. Nothing fires while a text field has focus,
+    /// so typing and text editing keys keep working there.
+    pub fn pressed(ctx: &egui::Context) -> Vec<Self> {
+        if ctx.wants_keyboard_input() {
+            return Vec::new();
+        }
+        
+        ctx.input().events.iter()
+            .filter_map(|event| match event {
+                egui::Event::Key { key, pressed: true, modifiers } => Self::from_key(*key, *modifiers),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_from_key() {
+        assert_eq!(Shortcut::from_key(egui::Key::A, egui::Modifiers::COMMAND), Some(Shortcut::SelectAll));
+        assert_eq!(Shortcut::from_key(egui::Key::U, egui::Modifiers::COMMAND), Some(Shortcut::Upload));
+        assert_eq!(Shortcut::from_key(egui::Key::Delete, egui::Modifiers::NONE), Some(Shortcut::Delete));
+        
+        // Plain letters and other modifier combinations do nothing
+        assert_eq!(Shortcut::from_key(egui::Key::A, egui::Modifiers::NONE), None);
+        assert_eq!(Shortcut::from_key(egui::Key::Delete, egui::Modifiers::COMMAND), None);
+        let command_shift = egui::Modifiers { shift: true, ..egui::Modifiers::COMMAND };
+        assert_eq!(Shortcut::from_key(egui::Key::D, command_shift), None);
+        
+        // Every listed shortcut is reachable
+        for (shortcut, _, _) in SHORTCUTS {
+            assert!([egui::Key::R, egui::Key::A, egui::Key::Delete, egui::Key::U, egui::Key::D].iter().any(|key| {
+                Shortcut::from_key(*key, egui::Modifiers::COMMAND) == Some(shortcut)
+                    || Shortcut::from_key(*key, egui::Modifiers::NONE) == Some(shortcut)
+            }));
+        }
+    }
+}
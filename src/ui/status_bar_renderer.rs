@@ -1,6 +1,12 @@
 use eframe::egui;
+use std::time::{Instant, SystemTime};
 
-use crate::ui::app_state::AppState;
+use crate::aws::s3::S3ErrorHelper;
+use crate::ui::app_state::{AppState, ConnectionState};
+use crate::ui::aws_operations::AwsOperations;
+use crate::ui::failed_transfers::{FailedTransferAction, FailedTransfersRenderer};
+use crate::ui::status_log::StatusSeverity;
+use crate::ui::utils::{error_color, success_color};
 
 /// Renderer for the status bar of the application
 pub struct StatusBarRenderer;
@@ -9,13 +15,131 @@ impl StatusBarRenderer {
     /// Render the status bar
     pub fn render(app_state: &mut AppState, ctx: &egui::Context) {
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
+            // Per-file details of the last batch's failures
+            if !app_state.failed_transfers.is_empty() {
+                match FailedTransfersRenderer::ui(ui, &app_state.failed_transfers) {
+                    Some(FailedTransferAction::Retry(index)) => {
+                        let failure = app_state.failed_transfers.remove(index);
+                        AwsOperations::retry_transfers(app_state, vec![failure]);
+                    },
+                    Some(FailedTransferAction::Dismiss) => {
+                        let failures = std::mem::take(&mut app_state.failed_transfers);
+                        AwsOperations::discard_interrupted_uploads(app_state, failures);
+                    },
+                    None => {}
+                }
+                ui.separator();
+            }
+            
             ui.horizontal(|ui| {
-                if app_state.status_is_error {
-                    ui.colored_label(egui::Color32::RED, &app_state.status_message);
-                } else {
-                    ui.label(&app_state.status_message);
+                let now = Instant::now();
+                
+                Self::render_connection(app_state, ui);
+                
+                match app_state.status.current(now) {
+                    Some(entry) if entry.severity == StatusSeverity::Error => {
+                        ui.colored_label(error_color(ui), &entry.text);
+                    },
+                    Some(entry) => {
+                        ui.label(&entry.text);
+                        
+                        // Keep repainting so the info message clears on time
+                        ctx.request_repaint();
+                    },
+                    None => {}
+                }
+                
+                if app_state.status.has_error(now) {
+                    // Explain which permission a denied request needed
+                    if let Some(context) = &app_state.access_denied {
+                        if ui.small_button("Why?").clicked() {
+                            let explanation = S3ErrorHelper::explain_access_denied(context);
+                            app_state.status.push_error(&explanation);
+                            app_state.access_denied = None;
+                        }
+                    }
+                    
+                    if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                        app_state.acknowledge_status_error();
+                    }
+                }
+                
+                if ui.small_button("Log").on_hover_text("Show earlier status messages").clicked() {
+                    app_state.show_status_log = !app_state.show_status_log;
+                }
+                
+                // Time left on temporary credentials
+                if let Some(expiry) = app_state.credentials_expiry {
+                    ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                        match expiry.duration_since(SystemTime::now()) {
+                            Ok(remaining) => {
+                                let minutes = remaining.as_secs() / 60;
+                                ui.label(format!("Credentials expire in {}h {}m", minutes / 60, minutes % 60));
+                            },
+                            Err(_) => {
+                                ui.colored_label(error_color(ui), "Credentials expired");
+                            }
+                        }
+                    });
                 }
             });
         });
+        
+        if app_state.show_status_log {
+            Self::render_log(app_state, ctx);
+        }
+    }
+    
+    /// Render a dot showing whether the last AWS call got through, with a Reconnect
+    /// button once one has failed because AWS couldn't be reached
+    fn render_connection(app_state: &mut AppState, ui: &mut egui::Ui) {
+        match app_state.connection {
+            ConnectionState::Unknown => {
+                ui.colored_label(egui::Color32::GRAY, "●").on_hover_text("Not connected to AWS yet");
+            },
+            ConnectionState::Connected => {
+                ui.colored_label(success_color(ui), "●").on_hover_text("Connected: the last AWS request succeeded");
+            },
+            ConnectionState::Disconnected => {
+                ui.colored_label(error_color(ui), "●").on_hover_text("Disconnected: the last AWS request couldn't reach AWS");
+                if ui.add_enabled(!app_state.offline, egui::Button::new("Reconnect").small()).clicked() {
+                    AwsOperations::connect_to_aws(app_state);
+                }
+            },
+        }
+    }
+    
+    /// Render the status message history as a window, newest first
+    fn render_log(app_state: &mut AppState, ctx: &egui::Context) {
+        let mut open = true;
+        let mut clear = false;
+        
+        egui::Window::new("Status Log")
+            .open(&mut open)
+            .resizable(true)
+            .default_size([500.0, 300.0])
+            .show(ctx, |ui| {
+                if ui.button("Clear").clicked() {
+                    clear = true;
+                }
+                ui.separator();
+                
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for entry in app_state.status.history().iter().rev() {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(&entry.time).color(egui::Color32::GRAY));
+                            match entry.severity {
+                                StatusSeverity::Error => ui.colored_label(error_color(ui), &entry.text),
+                                StatusSeverity::Info => ui.label(&entry.text),
+                            };
+                        });
+                    }
+                });
+            });
+        
+        if clear {
+            app_state.status.clear_history();
+        }
+        app_state.show_status_log = open;
     }
 }
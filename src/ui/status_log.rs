@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long an info message stays in the status bar
+const INFO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long an unacknowledged error stays in the status bar
+const ERROR_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Number of past messages kept for the status log
+const HISTORY_LIMIT: usize = 200;
+
+/// Severity of a status message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusSeverity {
+    /// Routine progress, cleared after a few seconds
+    Info,
+    /// Sticks until acknowledged or timed out, and isn't replaced by info messages
+    Error,
+}
+
+/// A status message and when it was posted
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    pub text: String,
+    pub severity: StatusSeverity,
+    /// Local wall-clock time, for the log
+    pub time: String,
+    posted: Instant,
+}
+
+/// Status bar messages with their history. An error stays visible until it is
+/// acknowledged or times out, even if newer info messages arrive in the meantime.
+#[derive(Debug, Default)]
+pub struct StatusLog {
+    info: Option<StatusEntry>,
+    error: Option<StatusEntry>,
+    history: VecDeque<StatusEntry>,
+}
+
+impl StatusLog {
+    /// Post a transient info message
+    pub fn push_info(&mut self, text: &str) {
+        let entry = Self::entry(text, StatusSeverity::Info);
+        self.info = Some(entry.clone());
+        self.record(entry);
+    }
+    
+    /// Post a sticky error message, replacing any earlier error
+    pub fn push_error(&mut self, text: &str) {
+        let entry = Self::entry(text, StatusSeverity::Error);
+        self.error = Some(entry.clone());
+        self.record(entry);
+    }
+    
+    /// Dismiss the current error
+    pub fn acknowledge(&mut self) {
+        self.error = None;
+    }
+    
+    /// The message to show in the status bar at `now`: an unacknowledged error
+    /// takes precedence over info, and expired messages aren't shown
+    pub fn current(&self, now: Instant) -> Option<&StatusEntry> {
+        let live = |entry: &&StatusEntry, timeout: Duration| now.duration_since(entry.posted) < timeout;
+        
+        self.error.as_ref()
+            .filter(|entry| live(entry, ERROR_TIMEOUT))
+            .or_else(|| self.info.as_ref().filter(|entry| live(entry, INFO_TIMEOUT)))
+    }
+    
+    /// Check whether an unacknowledged error is showing at `now`
+    pub fn has_error(&self, now: Instant) -> bool {
+        self.current(now).map_or(false, |entry| entry.severity == StatusSeverity::Error)
+    }
+    
+    /// Past messages, oldest first
+    pub fn history(&self) -> &VecDeque<StatusEntry> {
+        &self.history
+    }
+    
+    /// Forget past messages
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+    
+    fn entry(text: &str, severity: StatusSeverity) -> StatusEntry {
+        StatusEntry {
+            text: text.to_string(),
+            severity,
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            posted: Instant::now(),
+        }
+    }
+    
+    fn record(&mut self, entry: StatusEntry) {
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_info_does_not_replace_error() {
+        let mut log = StatusLog::default();
+        
+        log.push_error("Upload failed");
+        log.push_info("Refreshing bucket contents...");
+        let now = Instant::now();
+        assert_eq!(log.current(now).unwrap().text, "Upload failed");
+        assert!(log.has_error(now));
+        
+        // Once acknowledged, the latest info shows again
+        log.acknowledge();
+        assert_eq!(log.current(now).unwrap().text, "Refreshing bucket contents...");
+        assert!(!log.has_error(now));
+        
+        assert_eq!(log.history().len(), 2);
+        assert_eq!(log.history()[0].severity, StatusSeverity::Error);
+    }
+    
+    #[test]
+    fn test_messages_expire() {
+        let mut log = StatusLog::default();
+        
+        log.push_info("Loaded 3 objects");
+        let now = Instant::now();
+        assert!(log.current(now).is_some());
+        assert!(log.current(now + INFO_TIMEOUT + Duration::from_secs(1)).is_none());
+        
+        log.push_error("Access denied");
+        let now = Instant::now();
+        assert!(log.current(now + INFO_TIMEOUT + Duration::from_secs(1)).is_some());
+        assert!(log.current(now + ERROR_TIMEOUT + Duration::from_secs(1)).is_none());
+        
+        // Expired messages stay in the history
+        assert_eq!(log.history().len(), 2);
+    }
+    
+    #[test]
+    fn test_history_limit() {
+        let mut log = StatusLog::default();
+        
+        for i in 0..HISTORY_LIMIT + 10 {
+            log.push_info(&format!("message {}", i));
+        }
+        
+        assert_eq!(log.history().len(), HISTORY_LIMIT);
+        assert_eq!(log.history()[0].text, "message 10");
+    }
+}
@@ -0,0 +1,177 @@
+use eframe::egui;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::sync::engine::SyncOperation;
+use crate::ui::utils::{error_color, format_size, warning_color};
+
+/// Preview of the changes a sync will make, shown before a sync that may delete objects,
+/// or on its own as a dry run
+pub struct SyncPreviewView {
+    pub folder: PathBuf,
+    pub bucket: String,
+    pub key_prefix: String,
+    /// Only show the changes, without offering to make them
+    dry_run: bool,
+    changes: Vec<(String, SyncOperation, u64)>,
+    error: Option<String>,
+    loading: bool,
+}
+
+impl SyncPreviewView {
+    /// Create a preview for a folder and bucket, waiting for the changes to be worked out
+    pub fn new(folder: PathBuf, bucket: String, key_prefix: String, dry_run: bool) -> Self {
+        Self {
+            folder,
+            bucket,
+            key_prefix,
+            dry_run,
+            changes: Vec::new(),
+            error: None,
+            loading: true,
+        }
+    }
+    
+    /// Set the changes the sync will make, or why they couldn't be worked out
+    pub fn set_plan(&mut self, plan: Result<Vec<(String, SyncOperation, u64)>, String>) {
+        match plan {
+            Ok(changes) => self.changes = changes,
+            Err(e) => self.error = Some(e),
+        }
+        self.loading = false;
+    }
+    
+    /// Number of changes of one kind
+    fn count(&self, operation: SyncOperation) -> usize {
+        self.changes.iter().filter(|(_, op, _)| *op == operation).count()
+    }
+    
+    /// Keys the sync will delete from the bucket
+    pub fn delete_keys(&self) -> HashSet<String> {
+        self.changes.iter()
+            .filter(|(_, operation, _)| *operation == SyncOperation::Delete)
+            .map(|(key, _, _)| key.clone())
+            .collect()
+    }
+    
+    /// Show the preview as a window, returning whether it is still open and whether the sync was confirmed
+    pub fn show(&self, ctx: &egui::Context) -> (bool, bool) {
+        let mut open = true;
+        let mut confirmed = false;
+        
+        let title = if self.dry_run { "Sync Dry Run" } else { "Confirm Sync" };
+        egui::Window::new(title)
+            .open(&mut open)
+            .resizable(true)
+            .default_size([450.0, 400.0])
+            .show(ctx, |ui| {
+                confirmed = self.ui(ui);
+            });
+        
+        (open && !confirmed, confirmed)
+    }
+    
+    /// Render the change counts and the keys to delete, returning whether the sync was confirmed
+    fn ui(&self, ui: &mut egui::Ui) -> bool {
+        ui.label(format!("{} with s3://{}/{}", self.folder.display(), self.bucket, self.key_prefix));
+        
+        if self.loading {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new());
+                ui.label("Comparing the folder with the bucket...");
+            });
+            return false;
+        }
+        
+        if let Some(error) = &self.error {
+            ui.colored_label(error_color(ui), error);
+            return false;
+        }
+        
+        if self.changes.is_empty() {
+            ui.label("Nothing to sync: the folder and the bucket already match.");
+            return false;
+        }
+        
+        let transfer_size: u64 = self.changes.iter()
+            .filter(|(_, operation, _)| *operation != SyncOperation::Delete)
+            .map(|(_, _, size)| *size)
+            .sum();
+        ui.strong(format!(
+            "{} uploads, {} downloads ({}), {} deletes",
+            self.count(SyncOperation::Upload),
+            self.count(SyncOperation::Download),
+            format_size(transfer_size),
+            self.count(SyncOperation::Delete),
+        ));
+        
+        let mut deletes: Vec<&(String, SyncOperation, u64)> = self.changes.iter()
+            .filter(|(_, operation, _)| *operation == SyncOperation::Delete)
+            .collect();
+        deletes.sort_by(|a, b| a.0.cmp(&b.0));
+        
+        if !deletes.is_empty() {
+            ui.colored_label(warning_color(ui), "These objects are no longer in the folder and will be deleted from the bucket:");
+            ui.separator();
+            
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            egui::ScrollArea::vertical()
+                .max_height(250.0)
+                .show_rows(ui, row_height, deletes.len(), |ui, range| {
+                    for (key, _, size) in &deletes[range] {
+                        ui.horizontal(|ui| {
+                            ui.label(key);
+                            ui.label(egui::RichText::new(format_size(*size)).color(egui::Color32::GRAY));
+                        });
+                    }
+                });
+        }
+        
+        ui.separator();
+        
+        if self.dry_run {
+            ui.label("Dry run: nothing has been changed.");
+            return false;
+        }
+        
+        let label = if deletes.is_empty() {
+            "Sync".to_string()
+        } else {
+            format!("Sync and delete {} objects", deletes.len())
+        };
+        let mut button = egui::Button::new(egui::RichText::new(label).color(egui::Color32::WHITE));
+        if !deletes.is_empty() {
+            button = button.fill(egui::Color32::from_rgb(180, 40, 40));
+        }
+        
+        ui.add(button).clicked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_set_plan() {
+        let mut preview = SyncPreviewView::new(PathBuf::from("/photos"), "bucket".to_string(), String::new(), false);
+        preview.set_plan(Ok(vec![
+            ("new.jpg".to_string(), SyncOperation::Upload, 100),
+            ("remote.jpg".to_string(), SyncOperation::Download, 50),
+            ("gone.jpg".to_string(), SyncOperation::Delete, 10),
+            ("also-gone.jpg".to_string(), SyncOperation::Delete, 20),
+        ]));
+        
+        assert!(!preview.loading);
+        assert_eq!(preview.count(SyncOperation::Upload), 1);
+        assert_eq!(preview.count(SyncOperation::Download), 1);
+        
+        let deletes = preview.delete_keys();
+        assert_eq!(deletes.len(), 2);
+        assert!(deletes.contains("gone.jpg"));
+        assert!(!deletes.contains("new.jpg"));
+        
+        preview.set_plan(Err("Access denied".to_string()));
+        assert_eq!(preview.error.as_deref(), Some("Access denied"));
+    }
+}
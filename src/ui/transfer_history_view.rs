@@ -0,0 +1,236 @@
+use eframe::egui;
+use std::cmp::Ordering;
+
+use crate::config::transfer_history::TransferRecord;
+use crate::ui::utils::{error_color, format_size, success_color};
+
+/// Action requested from the transfer history view
+pub enum TransferHistoryAction {
+    Refresh,
+    Clear,
+    Close,
+}
+
+/// Column the history table is sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryColumn {
+    Time,
+    Operation,
+    Bucket,
+    Key,
+    Size,
+    Status,
+}
+
+impl HistoryColumn {
+    const ALL: [HistoryColumn; 6] = [
+        HistoryColumn::Time,
+        HistoryColumn::Operation,
+        HistoryColumn::Bucket,
+        HistoryColumn::Key,
+        HistoryColumn::Size,
+        HistoryColumn::Status,
+    ];
+    
+    fn label(&self) -> &'static str {
+        match self {
+            HistoryColumn::Time => "Time",
+            HistoryColumn::Operation => "Operation",
+            HistoryColumn::Bucket => "Bucket",
+            HistoryColumn::Key => "Key",
+            HistoryColumn::Size => "Size",
+            HistoryColumn::Status => "Status",
+        }
+    }
+    
+    /// Compare two records by this column
+    fn compare(&self, a: &TransferRecord, b: &TransferRecord) -> Ordering {
+        match self {
+            HistoryColumn::Time => a.timestamp.cmp(&b.timestamp),
+            HistoryColumn::Operation => a.operation.cmp(&b.operation),
+            HistoryColumn::Bucket => a.bucket.cmp(&b.bucket),
+            HistoryColumn::Key => a.key.cmp(&b.key),
+            HistoryColumn::Size => a.bytes.cmp(&b.bytes),
+            HistoryColumn::Status => a.status.cmp(&b.status),
+        }
+    }
+}
+
+/// Component listing past transfers from the transfer history
+pub struct TransferHistoryView {
+    records: Vec<TransferRecord>,
+    sort_column: HistoryColumn,
+    ascending: bool,
+    /// Why the history couldn't be loaded or cleared
+    error: Option<String>,
+}
+
+impl Default for TransferHistoryView {
+    fn default() -> Self {
+        // Most recent first
+        Self {
+            records: Vec::new(),
+            sort_column: HistoryColumn::Time,
+            ascending: false,
+            error: None,
+        }
+    }
+}
+
+impl TransferHistoryView {
+    /// Set the records shown, or the error loading them
+    pub fn set_records(&mut self, records: Result<Vec<TransferRecord>, String>) {
+        match records {
+            Ok(records) => {
+                self.records = records;
+                self.error = None;
+                self.sort();
+            },
+            Err(e) => {
+                self.records.clear();
+                self.error = Some(e);
+            },
+        }
+    }
+    
+    /// Set the error shown above the table
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+    
+    /// Sort by a column, reversing the order if it is already the sort column
+    pub fn sort_by(&mut self, column: HistoryColumn) {
+        if self.sort_column == column {
+            self.ascending = !self.ascending;
+        } else {
+            self.sort_column = column;
+            self.ascending = true;
+        }
+        self.sort();
+    }
+    
+    fn sort(&mut self) {
+        let column = self.sort_column;
+        let ascending = self.ascending;
+        self.records.sort_by(|a, b| {
+            let ordering = column.compare(a, b);
+            if ascending { ordering } else { ordering.reverse() }
+        });
+    }
+    
+    /// Render the history UI and return the requested action, if any
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<TransferHistoryAction> {
+        let mut action = None;
+        
+        ui.heading("Transfer History");
+        
+        ui.horizontal(|ui| {
+            if ui.button("Refresh").clicked() {
+                action = Some(TransferHistoryAction::Refresh);
+            }
+            
+            if ui.add_enabled(!self.records.is_empty(), egui::Button::new("Clear History")).clicked() {
+                action = Some(TransferHistoryAction::Clear);
+            }
+            
+            if ui.button("Back to Main").clicked() {
+                action = Some(TransferHistoryAction::Close);
+            }
+            
+            let failed = self.records.iter().filter(|record| record.is_failed()).count();
+            ui.label(format!("{} transfers, {} failed", self.records.len(), failed));
+        });
+        
+        if let Some(error) = &self.error {
+            ui.colored_label(error_color(ui), error);
+        }
+        
+        ui.separator();
+        
+        if self.records.is_empty() {
+            ui.label("No transfers recorded yet");
+            return action;
+        }
+        
+        let mut clicked_column = None;
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("transfer_history_grid")
+                .num_columns(HistoryColumn::ALL.len())
+                .spacing([20.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    for column in HistoryColumn::ALL {
+                        let arrow = match (column == self.sort_column, self.ascending) {
+                            (true, true) => " ▲",
+                            (true, false) => " ▼",
+                            (false, _) => "",
+                        };
+                        if ui.selectable_label(column == self.sort_column, format!("{}{}", column.label(), arrow)).clicked() {
+                            clicked_column = Some(column);
+                        }
+                    }
+                    ui.end_row();
+                    
+                    for record in &self.records {
+                        ui.label(&record.timestamp);
+                        ui.label(&record.operation);
+                        ui.label(&record.bucket);
+                        ui.label(&record.key);
+                        ui.label(format_size(record.bytes));
+                        if record.is_failed() {
+                            ui.colored_label(error_color(ui), &record.status);
+                        } else {
+                            ui.colored_label(success_color(ui), &record.status);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        
+        if let Some(column) = clicked_column {
+            self.sort_by(column);
+        }
+        
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn record(timestamp: &str, key: &str, bytes: u64) -> TransferRecord {
+        TransferRecord {
+            timestamp: timestamp.to_string(),
+            operation: "Upload".to_string(),
+            bucket: "bucket".to_string(),
+            key: key.to_string(),
+            bytes,
+            status: "Completed".to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_sort_by() {
+        let mut view = TransferHistoryView::default();
+        view.set_records(Ok(vec![
+            record("2024-01-01T10:00:00+00:00", "b.txt", 5),
+            record("2024-01-02T10:00:00+00:00", "a.txt", 20),
+            record("2024-01-03T10:00:00+00:00", "c.txt", 1),
+        ]));
+        
+        // Newest first by default
+        let keys = |view: &TransferHistoryView| view.records.iter().map(|r| r.key.clone()).collect::<Vec<_>>();
+        assert_eq!(keys(&view), ["c.txt", "a.txt", "b.txt"]);
+        
+        view.sort_by(HistoryColumn::Key);
+        assert_eq!(keys(&view), ["a.txt", "b.txt", "c.txt"]);
+        
+        view.sort_by(HistoryColumn::Key);
+        assert_eq!(keys(&view), ["c.txt", "b.txt", "a.txt"]);
+        
+        view.sort_by(HistoryColumn::Size);
+        assert_eq!(keys(&view), ["c.txt", "b.txt", "a.txt"]);
+        assert_eq!(view.records[2].bytes, 20);
+    }
+}
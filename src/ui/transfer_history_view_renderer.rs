@@ -0,0 +1,45 @@
+use eframe::egui;
+
+use crate::ui::app_state::{AppState, CurrentView};
+use crate::ui::transfer_history_view::TransferHistoryAction;
+
+/// Renderer for the transfer history view of the application
+pub struct TransferHistoryViewRenderer;
+
+impl TransferHistoryViewRenderer {
+    /// Switch to the history view, loading the latest history
+    pub fn open(app_state: &mut AppState) {
+        Self::reload(app_state);
+        app_state.current_view = CurrentView::History;
+    }
+    
+    /// Render the transfer history view
+    pub fn render(app_state: &mut AppState, ui: &mut egui::Ui) {
+        match app_state.history_view.ui(ui) {
+            Some(TransferHistoryAction::Refresh) => Self::reload(app_state),
+            Some(TransferHistoryAction::Clear) => {
+                let result = match &app_state.transfer_history {
+                    Some(history) => history.clear(),
+                    None => Ok(()),
+                };
+                
+                Self::reload(app_state);
+                match result {
+                    Ok(()) => app_state.set_status_info("Transfer history cleared"),
+                    Err(e) => app_state.history_view.set_error(format!("Could not clear the history: {}", e)),
+                }
+            },
+            Some(TransferHistoryAction::Close) => app_state.current_view = CurrentView::Main,
+            None => {}
+        }
+    }
+    
+    /// Load the history file into the view
+    fn reload(app_state: &mut AppState) {
+        let records = match &app_state.transfer_history {
+            Some(history) => history.load().map_err(|e| e.to_string()),
+            None => Err("Transfer history is unavailable: the config directory could not be found".to_string()),
+        };
+        app_state.history_view.set_records(records);
+    }
+}
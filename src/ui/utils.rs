@@ -1,3 +1,7 @@
+use eframe::egui;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
 /// Format a file size in bytes to a human-readable string
 pub fn format_size(size: u64) -> String {
     const KB: u64 = 1024;
@@ -17,3 +21,273 @@ pub fn format_size(size: u64) -> String {
         format!("{:.2} TB", size as f64 / TB as f64)
     }
 }
+
+/// Color for errors and failures, readable on both light and dark backgrounds
+pub fn error_color(ui: &egui::Ui) -> egui::Color32 {
+    if ui.visuals().dark_mode {
+        egui::Color32::RED
+    } else {
+        egui::Color32::from_rgb(190, 20, 20)
+    }
+}
+
+/// Color for success and enabled states, readable on both light and dark backgrounds
+pub fn success_color(ui: &egui::Ui) -> egui::Color32 {
+    if ui.visuals().dark_mode {
+        egui::Color32::GREEN
+    } else {
+        egui::Color32::from_rgb(0, 130, 0)
+    }
+}
+
+/// Color for warnings, readable on both light and dark backgrounds
+pub fn warning_color(ui: &egui::Ui) -> egui::Color32 {
+    if ui.visuals().dark_mode {
+        egui::Color32::from_rgb(230, 160, 0)
+    } else {
+        egui::Color32::from_rgb(170, 100, 0)
+    }
+}
+
+/// Check whether a system clipboard is likely to be reachable. Headless and some
+/// remote sessions on Linux have no display server, so copying would silently fail.
+pub fn clipboard_available() -> bool {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return true;
+    }
+    
+    std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Show a button that copies text to the clipboard, disabled with an explanation
+/// when no clipboard is available
+pub fn copy_button(ui: &mut egui::Ui, label: &str, text: &str) {
+    if clipboard_available() {
+        if ui.button(label).clicked() {
+            ui.output().copied_text = text.to_string();
+        }
+    } else {
+        ui.add_enabled(false, egui::Button::new(label))
+            .on_disabled_hover_text("Clipboard is not available in this session");
+    }
+}
+
+/// Build an S3 key from an upload key template such as `backups/{date}/{relpath}`.
+///
+/// `{date}` is the upload date, `{folder}` the name of the local folder, `{relpath}` the
+/// file's path relative to that folder and `{filename}` its name. An empty template
+/// uses the relative path. Empty path segments are dropped.
+pub fn resolve_key_template(template: &str, folder: &str, relpath: &str, date: &str) -> Result<String, String> {
+    if template.trim().is_empty() {
+        return Ok(relpath.to_string());
+    }
+    
+    let filename = relpath.rsplit('/').next().unwrap_or(relpath);
+    let mut key = String::new();
+    let mut rest = template.trim();
+    
+    while let Some(start) = rest.find('{') {
+        key.push_str(&rest[..start]);
+        
+        let end = rest[start..].find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in key template \"{}\"", template))?;
+        let value = match &rest[start + 1..start + end] {
+            "date" => date,
+            "folder" => folder,
+            "relpath" => relpath,
+            "filename" => filename,
+            other => return Err(format!("Unknown placeholder {{{}}} in key template", other)),
+        };
+        key.push_str(value);
+        rest = &rest[start + end + 1..];
+    }
+    key.push_str(rest);
+    
+    // Without the file's path or name every file would get the same key
+    if !template.contains("{relpath}") && !template.contains("{filename}") {
+        return Err("Key template must include {relpath} or {filename}".to_string());
+    }
+    
+    Ok(key.split('/').filter(|segment| !segment.is_empty()).collect::<Vec<_>>().join("/"))
+}
+
+/// Check a key prefix that uploads are placed under: empty for the top of the bucket,
+/// otherwise ending with `/` and without empty segments, so it is a folder in S3's view
+pub fn validate_key_prefix(prefix: &str) -> Result<(), String> {
+    if prefix.is_empty() {
+        return Ok(());
+    }
+    
+    if !prefix.ends_with('/') {
+        return Err(format!("Upload prefix \"{}\" must end with /", prefix));
+    }
+    
+    if prefix.starts_with('/') || prefix.contains("//") {
+        return Err(format!("Upload prefix \"{}\" has an empty path segment", prefix));
+    }
+    
+    Ok(())
+}
+
+/// Split a listing into real files and the zero-byte "folder" marker objects the
+/// S3 console creates, whose keys end in `/`
+pub fn split_directory_markers(listing: Vec<(String, u64)>) -> (Vec<(String, u64)>, Vec<String>) {
+    let mut files = Vec::new();
+    let mut markers = Vec::new();
+    
+    for (key, size) in listing {
+        if key.ends_with('/') {
+            markers.push(key);
+        } else {
+            files.push((key, size));
+        }
+    }
+    
+    (files, markers)
+}
+
+/// Get the deepest folder prefix, empty or ending in `/`, that every key is under
+pub fn common_key_prefix<'a>(keys: impl IntoIterator<Item = &'a str>) -> String {
+    let mut keys = keys.into_iter();
+    let first = match keys.next() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    
+    let mut common = first.len();
+    for key in keys {
+        common = first.bytes().zip(key.bytes()).take(common).take_while(|(a, b)| a == b).count();
+    }
+    
+    match first.as_bytes()[..common].iter().rposition(|&byte| byte == b'/') {
+        Some(end) => first[..=end].to_string(),
+        None => String::new(),
+    }
+}
+
+/// Build a local path for an object using only its basename, appending a counter
+/// when the name has already been used by another object in the same download
+pub fn flat_download_path(folder: &Path, key: &str, used: &mut HashSet<PathBuf>) -> PathBuf {
+    let name = key.rsplit('/').next().unwrap_or(key);
+    unique_download_path(folder.join(name), used)
+}
+
+/// Reserve a local download path, appending a counter when it collides with a path
+/// already used by this download. Paths are compared case-insensitively because S3
+/// keys like `photo.JPG` and `photo.jpg` map to the same file on Windows and macOS.
+pub fn unique_download_path(candidate: PathBuf, used: &mut HashSet<PathBuf>) -> PathBuf {
+    if used.insert(fold_case(&candidate)) {
+        return candidate;
+    }
+    
+    let name = candidate.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    
+    // Split into stem and extension so the counter goes before the extension
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 => (&name[..index], &name[index..]),
+        _ => (name.as_str(), ""),
+    };
+    
+    let mut counter = 1;
+    loop {
+        let renamed = candidate.with_file_name(format!("{} ({}){}", stem, counter, extension));
+        if used.insert(fold_case(&renamed)) {
+            return renamed;
+        }
+        counter += 1;
+    }
+}
+
+/// Lowercase a path for case-insensitive comparisons
+fn fold_case(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_flat_download_path() {
+        let folder = PathBuf::from("downloads");
+        let mut used = HashSet::new();
+        
+        assert_eq!(flat_download_path(&folder, "a/b/c/photo.jpg", &mut used), folder.join("photo.jpg"));
+        assert_eq!(flat_download_path(&folder, "x/photo.jpg", &mut used), folder.join("photo (1).jpg"));
+        assert_eq!(flat_download_path(&folder, "y/z/photo.jpg", &mut used), folder.join("photo (2).jpg"));
+        assert_eq!(flat_download_path(&folder, "readme", &mut used), folder.join("readme"));
+        assert_eq!(flat_download_path(&folder, "docs/readme", &mut used), folder.join("readme (1)"));
+        assert_eq!(flat_download_path(&folder, ".env", &mut used), folder.join(".env"));
+        assert_eq!(flat_download_path(&folder, "config/.env", &mut used), folder.join(".env (1)"));
+    }
+    
+    #[test]
+    fn test_resolve_key_template() {
+        let resolve = |template| resolve_key_template(template, "Photos", "2023/trip/a.jpg", "2024-05-01");
+        
+        assert_eq!(resolve(""), Ok("2023/trip/a.jpg".to_string()));
+        assert_eq!(resolve("backups/{date}/{relpath}"), Ok("backups/2024-05-01/2023/trip/a.jpg".to_string()));
+        assert_eq!(resolve("{folder}/{filename}"), Ok("Photos/a.jpg".to_string()));
+        assert_eq!(resolve("/backups//{date}/{relpath}"), Ok("backups/2024-05-01/2023/trip/a.jpg".to_string()));
+        
+        assert!(resolve("{date}/{name}").is_err());
+        assert!(resolve("{date}/{relpath").is_err());
+        assert!(resolve("backups/{date}").is_err());
+    }
+    
+    #[test]
+    fn test_validate_key_prefix() {
+        assert!(validate_key_prefix("").is_ok());
+        assert!(validate_key_prefix("backup/").is_ok());
+        assert!(validate_key_prefix("backup/photos/").is_ok());
+        
+        assert!(validate_key_prefix("backup").is_err());
+        assert!(validate_key_prefix("/backup/").is_err());
+        assert!(validate_key_prefix("backup//photos/").is_err());
+    }
+    
+    #[test]
+    fn test_split_directory_markers() {
+        let listing = vec![
+            ("photos/".to_string(), 0),
+            ("photos/a.jpg".to_string(), 100),
+            ("photos/2023/".to_string(), 0),
+            ("photos/2023/b.jpg".to_string(), 200),
+            ("empty.txt".to_string(), 0),
+        ];
+        
+        let (files, markers) = split_directory_markers(listing);
+        
+        assert_eq!(files, vec![
+            ("photos/a.jpg".to_string(), 100),
+            ("photos/2023/b.jpg".to_string(), 200),
+            ("empty.txt".to_string(), 0),
+        ]);
+        assert_eq!(markers, vec!["photos/", "photos/2023/"]);
+    }
+    
+    #[test]
+    fn test_common_key_prefix() {
+        assert_eq!(common_key_prefix(["backup/photos/a.jpg", "backup/photos/2023/b.jpg"]), "backup/photos/");
+        assert_eq!(common_key_prefix(["backup/photos/a.jpg", "backup/photos2/b.jpg"]), "backup/");
+        assert_eq!(common_key_prefix(["backup/empty/"]), "backup/empty/");
+        assert_eq!(common_key_prefix(["a.jpg", "b.jpg"]), "");
+        assert_eq!(common_key_prefix(["é/a", "é/b", "è/c"]), "");
+        assert_eq!(common_key_prefix(Vec::<&str>::new()), "");
+    }
+    
+    #[test]
+    fn test_unique_download_path_ignores_case() {
+        let folder = PathBuf::from("downloads");
+        let mut used = HashSet::new();
+        
+        assert_eq!(unique_download_path(folder.join("photo.jpg"), &mut used), folder.join("photo.jpg"));
+        assert_eq!(unique_download_path(folder.join("photo.JPG"), &mut used), folder.join("photo (1).JPG"));
+        assert_eq!(unique_download_path(folder.join("Photo.jpg"), &mut used), folder.join("Photo (2).jpg"));
+        assert_eq!(unique_download_path(folder.join("Docs").join("a.txt"), &mut used), folder.join("Docs").join("a.txt"));
+        assert_eq!(unique_download_path(folder.join("docs").join("A.txt"), &mut used), folder.join("docs").join("A (1).txt"));
+        assert_eq!(flat_download_path(&folder, "x/PHOTO.jpg", &mut used), folder.join("PHOTO (3).jpg"));
+    }
+}